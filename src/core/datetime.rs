@@ -0,0 +1,260 @@
+use std::sync::OnceLock;
+
+use serde::{Deserialize, Serialize};
+use time::{macros::format_description, Month, OffsetDateTime, UtcOffset, Weekday};
+
+/// How timestamps are displayed in the list view, grid view, footer and preview pane.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DateFormat {
+    /// Compact local date and time, e.g. `Mar 5, 2024, 14:03`.
+    #[default]
+    Short,
+    /// Weekday, full month name and seconds, e.g. `Tuesday, March 5, 2024 at 14:03:07`.
+    Long,
+    /// ISO-8601 with the local UTC offset, e.g. `2024-03-05T14:03:07+01:00`.
+    Iso8601,
+    /// Distance from now, e.g. `3 minutes ago`.
+    Relative,
+}
+
+impl DateFormat {
+    pub fn all() -> [DateFormat; 4] {
+        [DateFormat::Short, DateFormat::Long, DateFormat::Iso8601, DateFormat::Relative]
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DateFormat::Short => "short",
+            DateFormat::Long => "long",
+            DateFormat::Iso8601 => "iso8601",
+            DateFormat::Relative => "relative",
+        }
+    }
+
+    pub fn from_str(s: &str) -> Self {
+        Self::all().into_iter().find(|f| f.as_str() == s).unwrap_or_default()
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            DateFormat::Short => "Short",
+            DateFormat::Long => "Long",
+            DateFormat::Iso8601 => "ISO-8601",
+            DateFormat::Relative => "Relative",
+        }
+    }
+}
+
+static LOCAL_OFFSET: OnceLock<UtcOffset> = OnceLock::new();
+
+/// Captures the local UTC offset.
+///
+/// On Unix the offset can only be determined soundly while the process is single-threaded, so this
+/// must run at the very start of `main`, before the UI spawns its threads. Later calls are no-ops.
+pub fn init_local_offset() {
+    LOCAL_OFFSET.get_or_init(|| UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC));
+}
+
+/// The local UTC offset captured by [`init_local_offset`], or UTC if it could not be determined.
+pub fn local_offset() -> UtcOffset {
+    *LOCAL_OFFSET.get_or_init(|| UtcOffset::current_local_offset().unwrap_or(UtcOffset::UTC))
+}
+
+/// Formats a Unix timestamp for display using the current UI locale.
+///
+/// A timestamp of `0` means "unknown" throughout the listing code and is shown as `-`.
+pub fn format_unix(secs: i64, nanos: u32, format: DateFormat) -> String {
+    format_unix_at(secs, nanos, format, &rust_i18n::locale(), OffsetDateTime::now_utc())
+}
+
+/// Like [`format_unix`] with an explicit locale and notion of "now".
+pub fn format_unix_at(
+    secs: i64,
+    nanos: u32,
+    format: DateFormat,
+    locale: &str,
+    now: OffsetDateTime,
+) -> String {
+    if secs == 0 && nanos == 0 {
+        return "-".to_string();
+    }
+    let Ok(dt) = OffsetDateTime::from_unix_timestamp(secs) else {
+        return "-".to_string();
+    };
+    let dt = dt.replace_nanosecond(nanos).unwrap_or(dt).to_offset(local_offset());
+    let chinese = locale.starts_with("zh");
+
+    match format {
+        DateFormat::Short if chinese => format!(
+            "{:04}/{:02}/{:02} {:02}:{:02}",
+            dt.year(),
+            u8::from(dt.month()),
+            dt.day(),
+            dt.hour(),
+            dt.minute()
+        ),
+        DateFormat::Short => format!(
+            "{} {}, {}, {:02}:{:02}",
+            month_name(dt.month(), false),
+            dt.day(),
+            dt.year(),
+            dt.hour(),
+            dt.minute()
+        ),
+        DateFormat::Long if chinese => format!(
+            "{}年{}月{}日 {} {:02}:{:02}:{:02}",
+            dt.year(),
+            u8::from(dt.month()),
+            dt.day(),
+            weekday_name_zh(dt.weekday()),
+            dt.hour(),
+            dt.minute(),
+            dt.second()
+        ),
+        DateFormat::Long => format!(
+            "{}, {} {}, {} at {:02}:{:02}:{:02}",
+            dt.weekday(),
+            month_name(dt.month(), true),
+            dt.day(),
+            dt.year(),
+            dt.hour(),
+            dt.minute(),
+            dt.second()
+        ),
+        DateFormat::Iso8601 => dt
+            .format(format_description!(
+                "[year]-[month]-[day]T[hour]:[minute]:[second][offset_hour \
+                 sign:mandatory]:[offset_minute]"
+            ))
+            .unwrap_or_else(|_| "-".to_string()),
+        DateFormat::Relative => relative(dt, now.to_offset(dt.offset()), chinese),
+    }
+}
+
+fn relative(dt: OffsetDateTime, now: OffsetDateTime, chinese: bool) -> String {
+    let delta = now - dt;
+    let future = delta.is_negative();
+    let seconds = delta.whole_seconds().unsigned_abs();
+
+    if seconds < 60 {
+        return if chinese { "刚刚" } else { "just now" }.to_string();
+    }
+
+    // Whole calendar days between the two dates, so "yesterday" means the previous date rather
+    // than "24 to 48 hours ago".
+    let days = (now.date() - dt.date()).whole_days().unsigned_abs();
+    let (count, unit_en, unit_zh) = if seconds < 3600 {
+        (seconds / 60, "minute", "分钟")
+    } else if seconds < 86400 && days == 0 {
+        (seconds / 3600, "hour", "小时")
+    } else if days <= 1 {
+        return match (future, chinese) {
+            (false, false) => "yesterday",
+            (true, false) => "tomorrow",
+            (false, true) => "昨天",
+            (true, true) => "明天",
+        }
+        .to_string();
+    } else if days < 30 {
+        (days, "day", "天")
+    } else if days < 365 {
+        (days / 30, "month", "个月")
+    } else {
+        (days / 365, "year", "年")
+    };
+
+    match (future, chinese) {
+        (false, true) => format!("{count} {unit_zh}前"),
+        (true, true) => format!("{count} {unit_zh}后"),
+        (false, false) if count == 1 => format!("1 {unit_en} ago"),
+        (false, false) => format!("{count} {unit_en}s ago"),
+        (true, false) if count == 1 => format!("in 1 {unit_en}"),
+        (true, false) => format!("in {count} {unit_en}s"),
+    }
+}
+
+fn month_name(month: Month, long: bool) -> String {
+    let name = month.to_string();
+    if long {
+        name
+    } else {
+        name[..3].to_string()
+    }
+}
+
+fn weekday_name_zh(weekday: Weekday) -> &'static str {
+    match weekday {
+        Weekday::Monday => "星期一",
+        Weekday::Tuesday => "星期二",
+        Weekday::Wednesday => "星期三",
+        Weekday::Thursday => "星期四",
+        Weekday::Friday => "星期五",
+        Weekday::Saturday => "星期六",
+        Weekday::Sunday => "星期日",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    const NOW: OffsetDateTime = datetime!(2024-03-05 14:03:07 UTC);
+
+    /// Formats `seconds_ago` before [`NOW`], in UTC whatever the machine's zone.
+    fn format(seconds_ago: i64, format: DateFormat, locale: &str) -> String {
+        assert_eq!(*LOCAL_OFFSET.get_or_init(|| UtcOffset::UTC), UtcOffset::UTC);
+        format_unix_at(NOW.unix_timestamp() - seconds_ago, 0, format, locale, NOW)
+    }
+
+    #[test]
+    fn absolute_formats() {
+        assert_eq!(format(0, DateFormat::Short, "en"), "Mar 5, 2024, 14:03");
+        assert_eq!(format(0, DateFormat::Short, "zh-CN"), "2024/03/05 14:03");
+        assert_eq!(format(0, DateFormat::Long, "en"), "Tuesday, March 5, 2024 at 14:03:07");
+        assert_eq!(format(0, DateFormat::Long, "zh-CN"), "2024年3月5日 星期二 14:03:07");
+        assert_eq!(format(0, DateFormat::Iso8601, "en"), "2024-03-05T14:03:07+00:00");
+    }
+
+    #[test]
+    fn unknown_and_out_of_range_times() {
+        for format in DateFormat::all() {
+            assert_eq!(format_unix_at(0, 0, format, "en", NOW), "-");
+            assert_eq!(format_unix_at(i64::MAX, 0, format, "en", NOW), "-");
+        }
+        // Only an exact zero means unknown.
+        assert_ne!(format_unix_at(0, 1, DateFormat::Short, "en", NOW), "-");
+    }
+
+    #[test]
+    fn relative_format() {
+        let cases = [
+            (30, "just now", "刚刚"),
+            (60, "1 minute ago", "1 分钟前"),
+            (3599, "59 minutes ago", "59 分钟前"),
+            (4000, "1 hour ago", "1 小时前"),
+            (14 * 3600, "14 hours ago", "14 小时前"),
+            // 23:59 the day before is "yesterday", not "14 hours ago".
+            (14 * 3600 + 4 * 60, "yesterday", "昨天"),
+            (3 * 86400, "3 days ago", "3 天前"),
+            (70 * 86400, "2 months ago", "2 个月前"),
+            (800 * 86400, "2 years ago", "2 年前"),
+            (-400, "in 6 minutes", "6 分钟后"),
+            (-86400, "tomorrow", "明天"),
+        ];
+        for (seconds_ago, en, zh) in cases {
+            assert_eq!(format(seconds_ago, DateFormat::Relative, "en"), en, "{seconds_ago}");
+            assert_eq!(format(seconds_ago, DateFormat::Relative, "zh-CN"), zh, "{seconds_ago}");
+        }
+    }
+
+    #[test]
+    fn setting_names_round_trip() {
+        for format in DateFormat::all() {
+            assert_eq!(DateFormat::from_str(format.as_str()), format);
+        }
+        assert_eq!(DateFormat::from_str("fancy"), DateFormat::Short);
+    }
+}
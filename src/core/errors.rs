@@ -8,6 +8,22 @@ pub enum Error {
     NotImplemented(&'static str),
     #[error("io error: {0}")]
     Io(#[from] std::io::Error),
+    #[error("invalid cursor: {0}")]
+    InvalidCursor(String),
+    #[error("stale cursor: {0}")]
+    StaleCursor(String),
+    #[error("already exists: {0}")]
+    AlreadyExists(String),
+    #[error("invalid name: {0}")]
+    InvalidName(String),
+    /// The filesystem no longer looks the way a recorded operation left it.
+    #[error("no longer matches: {0}")]
+    Diverged(String),
+    /// A file the app reads (settings, keymap) is not in a format it understands.
+    #[error("can't parse {0}")]
+    Parse(String),
+    #[error("operation cancelled")]
+    Cancelled,
     #[error("other error: {0}")]
     Other(String),
 }
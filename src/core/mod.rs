@@ -1,2 +1,6 @@
+pub mod datetime;
 pub mod errors;
+pub mod paths;
 pub mod telemetry;
+#[cfg(test)]
+pub mod testing;
@@ -0,0 +1,60 @@
+use std::{env, path::PathBuf};
+
+const APP_DIR: &str = "nohrs";
+
+/// Per-user folder for settings and other files meant to be edited or backed up:
+/// `$XDG_CONFIG_HOME/nohrs` (or `~/.config/nohrs`) on Linux, `~/Library/Application Support/nohrs`
+/// on macOS and `%APPDATA%\nohrs` on Windows.
+pub fn config_dir() -> Option<PathBuf> {
+    #[cfg(target_os = "windows")]
+    let base = env::var_os("APPDATA").map(PathBuf::from);
+    #[cfg(target_os = "macos")]
+    let base = home_dir().map(|home| home.join("Library/Application Support"));
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let base = xdg_dir("XDG_CONFIG_HOME", ".config");
+
+    base.map(|base| base.join(APP_DIR))
+}
+
+/// Per-user folder for state the app maintains itself (history, caches that should survive a
+/// restart): `$XDG_DATA_HOME/nohrs` (or `~/.local/share/nohrs`) on Linux,
+/// `~/Library/Application Support/nohrs` on macOS and `%LOCALAPPDATA%\nohrs` on Windows.
+pub fn data_dir() -> Option<PathBuf> {
+    #[cfg(target_os = "windows")]
+    let base = env::var_os("LOCALAPPDATA").map(PathBuf::from);
+    #[cfg(target_os = "macos")]
+    let base = home_dir().map(|home| home.join("Library/Application Support"));
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let base = xdg_dir("XDG_DATA_HOME", ".local/share");
+
+    base.map(|base| base.join(APP_DIR))
+}
+
+/// Per-user folder for caches that can be rebuilt at any time (thumbnails):
+/// `$XDG_CACHE_HOME/nohrs` (or `~/.cache/nohrs`) on Linux, `~/Library/Caches/nohrs` on macOS and
+/// `%LOCALAPPDATA%\nohrs\cache` on Windows.
+pub fn cache_dir() -> Option<PathBuf> {
+    #[cfg(target_os = "windows")]
+    return env::var_os("LOCALAPPDATA").map(|base| PathBuf::from(base).join(APP_DIR).join("cache"));
+    #[cfg(target_os = "macos")]
+    let base = home_dir().map(|home| home.join("Library/Caches"));
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    let base = xdg_dir("XDG_CACHE_HOME", ".cache");
+
+    #[cfg(not(target_os = "windows"))]
+    base.map(|base| base.join(APP_DIR))
+}
+
+#[cfg(not(target_os = "windows"))]
+fn home_dir() -> Option<PathBuf> {
+    env::var_os("HOME").filter(|home| !home.is_empty()).map(PathBuf::from)
+}
+
+/// An XDG base directory; relative values are invalid per the spec and ignored.
+#[cfg(not(any(target_os = "windows", target_os = "macos")))]
+fn xdg_dir(var: &str, fallback: &str) -> Option<PathBuf> {
+    env::var_os(var)
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| home_dir().map(|home| home.join(fallback)))
+}
@@ -3,20 +3,21 @@
 use gpui::Application;
 use gpui_component_assets::Assets;
 use nohrs::{
-    core::telemetry::logging::init_logging,
+    core::{datetime::init_local_offset, telemetry::logging::init_logging},
     ui,
-    ui::{create_new_window, NohrsApp},
 };
 
 fn main() {
     // Placeholder entry point for gpui-based app.
     // TODO: Replace with actual gpui app initialization and run loop once gpui is pinned.
+    // Must run before any other thread exists, see `init_local_offset`.
+    init_local_offset();
     init_logging();
     let app = Application::new().with_assets(Assets);
     app.run(move |cx| {
         ui::init(cx);
         cx.activate(true);
 
-        create_new_window("Nohrs", NohrsApp::view, cx);
+        ui::session_store::open_windows(cx);
     });
 }
@@ -1,3 +1,7 @@
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
 #[derive(Debug, Clone)]
 pub struct FileEntry {
     pub name: String,
@@ -6,9 +10,98 @@ pub struct FileEntry {
     pub size: u64,
 }
 
-#[derive(Debug, Clone)]
+/// What a directory entry is, as reported by `lstat` (symlinks are not followed).
+///
+/// Serializes to the same lowercase strings the listing API has always used ("file", "dir",
+/// "symlink", ...), so JSON consumers keep working.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum FileKind {
     File,
     Dir,
-    Link,
+    Symlink,
+    Fifo,
+    Socket,
+    BlockDevice,
+    CharDevice,
+    Unknown,
+}
+
+impl FileKind {
+    pub fn from_file_type(file_type: std::fs::FileType) -> Self {
+        if file_type.is_dir() {
+            return FileKind::Dir;
+        }
+        if file_type.is_file() {
+            return FileKind::File;
+        }
+        if file_type.is_symlink() {
+            return FileKind::Symlink;
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::FileTypeExt;
+            if file_type.is_fifo() {
+                return FileKind::Fifo;
+            }
+            if file_type.is_socket() {
+                return FileKind::Socket;
+            }
+            if file_type.is_block_device() {
+                return FileKind::BlockDevice;
+            }
+            if file_type.is_char_device() {
+                return FileKind::CharDevice;
+            }
+        }
+        FileKind::Unknown
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FileKind::File => "file",
+            FileKind::Dir => "dir",
+            FileKind::Symlink => "symlink",
+            FileKind::Fifo => "fifo",
+            FileKind::Socket => "socket",
+            FileKind::BlockDevice => "block_device",
+            FileKind::CharDevice => "char_device",
+            FileKind::Unknown => "unknown",
+        }
+    }
+
+    /// Human readable name used in the Type column.
+    pub fn label(&self) -> &'static str {
+        match self {
+            FileKind::File => "File",
+            FileKind::Dir => "Folder",
+            FileKind::Symlink => "Link",
+            FileKind::Fifo => "Pipe",
+            FileKind::Socket => "Socket",
+            FileKind::BlockDevice => "Block Device",
+            FileKind::CharDevice => "Char Device",
+            FileKind::Unknown => "Unknown",
+        }
+    }
+}
+
+impl fmt::Display for FileKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Where a symlink points.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LinkTarget {
+    /// The link contents as stored on disk (may be relative to the link's directory).
+    pub path: String,
+    /// Kind of the fully resolved target, or `None` when the link is broken.
+    pub kind: Option<FileKind>,
+}
+
+impl LinkTarget {
+    pub fn is_broken(&self) -> bool {
+        self.kind.is_none()
+    }
 }
@@ -1 +1,2 @@
 pub mod file_entry;
+pub mod selection;
@@ -0,0 +1,213 @@
+use std::collections::HashSet;
+
+/// A set of selected entries, keyed by path so it survives re-sorting, filtering and live
+/// updates of the listing it belongs to.
+///
+/// Besides the set itself it tracks the *anchor*, where shift-range selection starts, and the
+/// *lead*, the entry most recently clicked or moved to, which is what single-entry actions such
+/// as rename and the preview use.
+#[derive(Debug, Clone, Default)]
+pub struct Selection {
+    paths: HashSet<String>,
+    anchor: Option<String>,
+    lead: Option<String>,
+}
+
+impl Selection {
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.paths.len()
+    }
+
+    pub fn contains(&self, path: &str) -> bool {
+        self.paths.contains(path)
+    }
+
+    pub fn lead(&self) -> Option<&str> {
+        self.lead.as_deref()
+    }
+
+    pub fn clear(&mut self) {
+        self.paths.clear();
+        self.anchor = None;
+        self.lead = None;
+    }
+
+    /// A plain click: `path` becomes the whole selection and the new anchor.
+    pub fn select_only(&mut self, path: &str) {
+        self.paths.clear();
+        self.paths.insert(path.to_string());
+        self.anchor = Some(path.to_string());
+        self.lead = Some(path.to_string());
+    }
+
+    /// A ctrl/cmd-click: adds or removes `path` and moves the anchor there.
+    pub fn toggle(&mut self, path: &str) {
+        if !self.paths.remove(path) {
+            self.paths.insert(path.to_string());
+        }
+        self.anchor = Some(path.to_string());
+        self.lead = Some(path.to_string());
+    }
+
+    /// A shift-click: selects everything between the anchor and `path` in `order` (the listing as
+    /// displayed). With `extend` (ctrl/cmd+shift) the range is added to the current selection
+    /// instead of replacing it. Without a visible anchor this behaves like [`select_only`].
+    ///
+    /// [`select_only`]: Selection::select_only
+    pub fn select_range<'a>(
+        &mut self,
+        order: impl IntoIterator<Item = &'a str>,
+        path: &str,
+        extend: bool,
+    ) {
+        let order: Vec<&str> = order.into_iter().collect();
+        let anchor = self.anchor.as_deref().and_then(|a| order.iter().position(|p| *p == a));
+        let (Some(anchor), Some(target)) = (anchor, order.iter().position(|p| *p == path)) else {
+            self.select_only(path);
+            return;
+        };
+
+        if !extend {
+            self.paths.clear();
+        }
+        let (start, end) = (anchor.min(target), anchor.max(target));
+        self.paths.extend(order[start..=end].iter().map(|p| p.to_string()));
+        self.lead = Some(path.to_string());
+    }
+
+    /// Selects every path in `visible`, keeping the lead if it is among them.
+    pub fn select_all<'a>(&mut self, visible: impl IntoIterator<Item = &'a str>) {
+        self.paths.extend(visible.into_iter().map(str::to_string));
+    }
+
+    /// Selects exactly the paths in `visible` that were not selected. Selected paths outside
+    /// `visible` (hidden by a filter) are dropped.
+    pub fn invert<'a>(&mut self, visible: impl IntoIterator<Item = &'a str>) {
+        let previous = std::mem::take(&mut self.paths);
+        self.paths =
+            visible.into_iter().filter(|p| !previous.contains(*p)).map(str::to_string).collect();
+        if self.lead.as_ref().is_some_and(|lead| !self.paths.contains(lead)) {
+            self.lead = None;
+        }
+        if self.anchor.as_ref().is_some_and(|anchor| !self.paths.contains(anchor)) {
+            self.anchor = self.lead.clone();
+        }
+    }
+
+    /// Replaces the selection with `paths` (a rubber band sweep), keeping the anchor.
+    pub fn set<'a>(&mut self, paths: impl IntoIterator<Item = &'a str>) {
+        self.paths = paths.into_iter().map(str::to_string).collect();
+        if self.lead.as_ref().is_some_and(|lead| !self.paths.contains(lead)) {
+            self.lead = None;
+        }
+    }
+
+    /// Forgets paths for which `keep` returns false, e.g. entries that were deleted.
+    pub fn retain(&mut self, mut keep: impl FnMut(&str) -> bool) {
+        self.paths.retain(|p| keep(p));
+        if self.lead.as_deref().is_some_and(|p| !self.paths.contains(p)) {
+            self.lead = None;
+        }
+        if self.anchor.as_deref().is_some_and(|p| !keep(p)) {
+            self.anchor = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ORDER: [&str; 5] = ["/a", "/b", "/c", "/d", "/e"];
+
+    fn selected(selection: &Selection) -> Vec<&str> {
+        ORDER.into_iter().filter(|p| selection.contains(p)).collect()
+    }
+
+    #[test]
+    fn clicks_and_toggles() {
+        let mut selection = Selection::default();
+        assert!(selection.is_empty());
+        selection.select_only("/b");
+        selection.toggle("/d");
+        assert_eq!(selected(&selection), ["/b", "/d"]);
+        assert_eq!(selection.lead(), Some("/d"));
+
+        selection.toggle("/b");
+        assert_eq!(selected(&selection), ["/d"]);
+        assert_eq!(selection.lead(), Some("/b"));
+
+        selection.clear();
+        assert_eq!(selection.len(), 0);
+        assert_eq!(selection.lead(), None);
+    }
+
+    #[test]
+    fn ranges_start_at_the_anchor() {
+        let mut selection = Selection::default();
+        selection.select_only("/d");
+        selection.select_range(ORDER, "/b", false);
+        assert_eq!(selected(&selection), ["/b", "/c", "/d"]);
+        assert_eq!(selection.lead(), Some("/b"));
+
+        // The anchor stays put, so a second shift-click re-spans from it.
+        selection.select_range(ORDER, "/e", false);
+        assert_eq!(selected(&selection), ["/d", "/e"]);
+
+        selection.toggle("/a");
+        selection.select_range(ORDER, "/b", true);
+        assert_eq!(selected(&selection), ["/a", "/b", "/d", "/e"]);
+    }
+
+    #[test]
+    fn range_without_a_visible_anchor_selects_only_the_target() {
+        let mut selection = Selection::default();
+        selection.select_range(ORDER, "/c", false);
+        assert_eq!(selected(&selection), ["/c"]);
+
+        selection.select_only("/filtered-out");
+        selection.select_range(ORDER, "/e", false);
+        assert_eq!(selected(&selection), ["/e"]);
+    }
+
+    #[test]
+    fn select_all_and_invert() {
+        let mut selection = Selection::default();
+        selection.select_only("/b");
+        selection.select_all(ORDER[..3].iter().copied());
+        assert_eq!(selected(&selection), ["/a", "/b", "/c"]);
+        assert_eq!(selection.lead(), Some("/b"));
+
+        selection.invert(ORDER);
+        assert_eq!(selected(&selection), ["/d", "/e"]);
+        assert_eq!(selection.lead(), None);
+
+        // Paths hidden by a filter are dropped rather than inverted.
+        selection.select_only("/hidden");
+        selection.invert(["/a", "/b"]);
+        assert_eq!(selected(&selection), ["/a", "/b"]);
+        assert!(!selection.contains("/hidden"));
+    }
+
+    #[test]
+    fn sweeps_and_removals() {
+        let mut selection = Selection::default();
+        selection.select_only("/a");
+        selection.set(["/c", "/d"]);
+        assert_eq!(selected(&selection), ["/c", "/d"]);
+        assert_eq!(selection.lead(), None);
+        // The anchor survives the sweep.
+        selection.select_range(ORDER, "/b", false);
+        assert_eq!(selected(&selection), ["/a", "/b"]);
+
+        selection.retain(|path| path != "/a");
+        assert_eq!(selected(&selection), ["/b"]);
+        assert_eq!(selection.lead(), Some("/b"));
+        selection.select_range(ORDER, "/d", false);
+        assert_eq!(selected(&selection), ["/d"]);
+    }
+}
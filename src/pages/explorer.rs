@@ -1,38 +1,210 @@
 use std::{
+    cell::{Cell, RefCell},
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    ops::ControlFlow,
+    path::{Path, PathBuf},
     rc::Rc,
     time::{Duration, Instant},
 };
 
 use gpui::{
-    div, prelude::*, px, size, AnyElement, App, Context, Entity, FocusHandle, Focusable,
-    IntoElement, Render, Window,
+    actions, anchored, deferred, div, img, prelude::*, px, size, Action, AnyElement, App, Bounds,
+    Context, Entity, EventEmitter, FocusHandle, Focusable, Hsla, IntoElement, KeyBinding,
+    Modifiers, ObjectFit, Pixels, Point, Render, ScrollHandle, ScrollStrategy, SharedString,
+    StyledImage, Task, Window,
 };
 use gpui_component::{
     breadcrumb::{Breadcrumb, BreadcrumbItem},
-    input::{Input, InputState},
+    button::{Button, ButtonVariants as _},
+    h_flex,
+    input::{Input, InputEvent, InputState},
     list::{ListEvent, ListState},
-    resizable::{h_resizable, resizable_panel},
-    v_virtual_list, ActiveTheme, Icon, IconName, VirtualListScrollHandle,
+    menu::ContextMenuExt,
+    notification::Notification,
+    resizable::{h_resizable, resizable_panel, ResizablePanelEvent, ResizableState},
+    v_flex, v_virtual_list, ActiveTheme, Disableable as _, Icon, IconName, VirtualListScrollHandle,
+    WindowExt as _,
 };
 
 use crate::{
-    services::fs::listing::{list_dir_sync, FileEntryDto, ListParams},
-    ui::components::file_list::FileListDelegate,
+    models::{file_entry::FileKind, selection::Selection},
+    pages::settings::AppSettings,
+    services::{
+        fs::{
+            compare::Difference,
+            dir_size::{invalidate_cached_sizes, spawn_dir_sizes, SizeEvent, SizeJob, SizeOptions},
+            listing::{
+                list_dir_stream, stat_entry, FileEntryDto, ListOptions, ListStream, SortDirection,
+                SortKey, StreamParams, DEFAULT_STREAM_BATCH_SIZE,
+            },
+            ops::{
+                create_dir, create_file, create_symlink, find_conflicts, rename_entry, unique_name,
+                Conflict, ConflictPolicy, TransferOptions,
+            },
+            trash::{delete_permanently, list_trash, purge, restore, trash_entries, TrashedItem},
+            watcher::{watch_dir, DirWatcher, DEFAULT_DEBOUNCE},
+        },
+        jobs::{JobKind, JobSpec},
+        journal::{Created, Operation},
+        preview::{
+            image_info::{can_decode, is_image},
+            large_text::{detect_encoding, Encoding},
+            magic::{looks_binary, read_header},
+            thumbnails::{spawn_thumbnails, ThumbnailEvent, ThumbnailJob, ThumbnailRequest},
+        },
+        session::{ColumnWidths, ExplorerSession, ViewMode},
+    },
+    ui::{
+        channel::forward_channel,
+        components::{
+            code_preview::{CodePreview, CodeText},
+            file_list::{human_bytes, FileListDelegate},
+            hex_preview::HexPreview,
+            history::{History, RedoOperation, UndoOperation},
+            image_preview::ImagePreview,
+            markdown_preview::{MarkdownPreview, MarkdownText, OpenLinkedPath},
+            text_preview::LargeTextPreview,
+            transfers::Transfers,
+        },
+    },
 };
 
-#[derive(Clone, Copy, PartialEq, Eq)]
-#[allow(dead_code)]
-enum SortKey {
-    Name,
-    Size,
-    Modified,
-    Type,
-}
-
-#[derive(Clone, Copy, PartialEq, Eq)]
-enum ViewMode {
-    List,
-    Grid,
+actions!(explorer, [
+    CopyEntries,
+    CutEntries,
+    PasteEntries,
+    PasteAsLink,
+    DuplicateEntries,
+    RenameEntry,
+    NewFolder,
+    NewFile,
+    TrashEntries,
+    DeletePermanently,
+    SelectAll,
+    InvertSelection,
+    ClearSelection,
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    SelectUp,
+    SelectDown,
+    SelectLeft,
+    SelectRight,
+    MoveToFirst,
+    MoveToLast,
+    MovePageUp,
+    MovePageDown,
+    OpenEntry,
+    OpenInNewTab,
+    GoToParent,
+    FindInFolder,
+    CloseSearch,
+    FocusListing
+]);
+
+/// Sorts the listing by a column, as picked from the context menu.
+#[derive(Action, Clone, PartialEq)]
+#[action(namespace = explorer, no_json)]
+pub struct SortBy(SortKey);
+
+/// The sort orders offered in the context menu.
+const SORT_KEYS: [(&str, SortKey); 5] = [
+    ("Name", SortKey::Name),
+    ("Name, Numbers by Value", SortKey::Natural),
+    ("Type", SortKey::Type),
+    ("Size", SortKey::Size),
+    ("Date Modified", SortKey::Modified),
+];
+
+const CONTEXT: &str = "Explorer";
+/// Key context of the preview panel, inside [`CONTEXT`].
+const PREVIEW_CONTEXT: &str = "Preview";
+/// Key context of the search box, inside [`CONTEXT`].
+const SEARCH_CONTEXT: &str = "Search";
+
+pub fn init(cx: &mut App) {
+    cx.bind_keys([
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-c", CopyEntries, Some(CONTEXT)),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("ctrl-c", CopyEntries, Some(CONTEXT)),
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-x", CutEntries, Some(CONTEXT)),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("ctrl-x", CutEntries, Some(CONTEXT)),
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-v", PasteEntries, Some(CONTEXT)),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("ctrl-v", PasteEntries, Some(CONTEXT)),
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-d", DuplicateEntries, Some(CONTEXT)),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("ctrl-d", DuplicateEntries, Some(CONTEXT)),
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-shift-n", NewFolder, Some(CONTEXT)),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("ctrl-shift-n", NewFolder, Some(CONTEXT)),
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-alt-n", NewFile, Some(CONTEXT)),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("ctrl-alt-n", NewFile, Some(CONTEXT)),
+        KeyBinding::new("f2", RenameEntry, Some(CONTEXT)),
+        KeyBinding::new("delete", TrashEntries, Some(CONTEXT)),
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-backspace", TrashEntries, Some(CONTEXT)),
+        KeyBinding::new("shift-delete", DeletePermanently, Some(CONTEXT)),
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-alt-backspace", DeletePermanently, Some(CONTEXT)),
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-a", SelectAll, Some(CONTEXT)),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("ctrl-a", SelectAll, Some(CONTEXT)),
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-shift-i", InvertSelection, Some(CONTEXT)),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("ctrl-shift-i", InvertSelection, Some(CONTEXT)),
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-z", UndoOperation, Some(CONTEXT)),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("ctrl-z", UndoOperation, Some(CONTEXT)),
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-shift-z", RedoOperation, Some(CONTEXT)),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("ctrl-shift-z", RedoOperation, Some(CONTEXT)),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("ctrl-y", RedoOperation, Some(CONTEXT)),
+        KeyBinding::new("up", MoveUp, Some(CONTEXT)),
+        KeyBinding::new("down", MoveDown, Some(CONTEXT)),
+        KeyBinding::new("left", MoveLeft, Some(CONTEXT)),
+        KeyBinding::new("right", MoveRight, Some(CONTEXT)),
+        KeyBinding::new("shift-up", SelectUp, Some(CONTEXT)),
+        KeyBinding::new("shift-down", SelectDown, Some(CONTEXT)),
+        KeyBinding::new("shift-left", SelectLeft, Some(CONTEXT)),
+        KeyBinding::new("shift-right", SelectRight, Some(CONTEXT)),
+        KeyBinding::new("home", MoveToFirst, Some(CONTEXT)),
+        KeyBinding::new("end", MoveToLast, Some(CONTEXT)),
+        KeyBinding::new("pageup", MovePageUp, Some(CONTEXT)),
+        KeyBinding::new("pagedown", MovePageDown, Some(CONTEXT)),
+        KeyBinding::new("enter", OpenEntry, Some(CONTEXT)),
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-down", OpenEntry, Some(CONTEXT)),
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-enter", OpenInNewTab, Some(CONTEXT)),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("ctrl-enter", OpenInNewTab, Some(CONTEXT)),
+        KeyBinding::new("backspace", GoToParent, Some(CONTEXT)),
+        KeyBinding::new("alt-up", GoToParent, Some(CONTEXT)),
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-up", GoToParent, Some(CONTEXT)),
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-f", FindInFolder, Some(CONTEXT)),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("ctrl-f", FindInFolder, Some(CONTEXT)),
+        KeyBinding::new("escape", CloseSearch, Some(SEARCH_CONTEXT)),
+        KeyBinding::new("escape", FocusListing, Some(PREVIEW_CONTEXT)),
+    ]);
 }
 
 pub struct ExplorerPage {
@@ -40,17 +212,40 @@ pub struct ExplorerPage {
     history: Vec<String>,
     history_index: usize,
     entries: Vec<FileEntryDto>,
+    /// Bumped whenever `entries` changes, so a comparison can tell a new listing from a repaint.
+    listing_generation: u64,
     filtered_entries: Vec<FileEntryDto>,
+    // Streaming listing state: `loaded` is set once a listing of `cwd` has been started, `loading`
+    // stays true until the last batch has arrived.
+    loaded: bool,
+    loading: bool,
+    listing_task: Option<Task<()>>,
+    // Recursive folder sizes by path; the flag is false while the value is still a partial count.
+    dir_sizes: HashMap<String, (u64, bool)>,
+    dir_size_task: Option<Task<()>>,
+    // Cached grid thumbnails by image path, made in the background while the grid is shown.
+    thumbnails: HashMap<String, PathBuf>,
+    // Images already handed to the thumbnail worker, whether or not a thumbnail came back.
+    thumbnails_requested: HashSet<String>,
+    thumbnail_task: Option<Task<()>>,
+    // Live updates for `cwd`. Changes that arrive while a listing is still streaming are held back
+    // until it completes, so they cannot race with (and duplicate) entries from later batches.
+    watch_task: Option<Task<()>>,
+    pending_changes: Vec<PathBuf>,
     sort_key: SortKey,
     sort_asc: bool,
+    show_hidden: bool,
     search_query: String,
     search_visible: bool,
     search_input: Entity<InputState>,
     list: Option<Entity<ListState<FileListDelegate>>>,
     subs: Vec<gpui::Subscription>,
     preview_path: Option<String>,
-    preview_text: Option<String>,
-    selected_index: Option<usize>,
+    preview: Option<PreviewContent>,
+    preview_entry: Option<FileEntryDto>,
+    /// Reads the file being previewed; replaced, and so cancelled, when another is selected.
+    preview_task: Option<Task<()>>,
+    selection: Selection,
     virtual_scroll_handle: VirtualListScrollHandle,
     item_sizes: Rc<Vec<gpui::Size<gpui::Pixels>>>,
     // Column widths (resizable)
@@ -61,11 +256,39 @@ pub struct ExplorerPage {
     col_action_width: f32,
     // Resize state
     resizing_column: Option<ResizingColumn>,
+    // Sidebar, listing and preview panel sizes; the widths are the ones to start with.
+    panel_state: Entity<ResizableState>,
+    sidebar_width: f32,
+    preview_width: f32,
     focus_handle: FocusHandle,
+    preview_focus_handle: FocusHandle,
     last_click_info: Option<LastClickInfo>,
     view_mode: ViewMode,
+    // Window bounds of the grid items as last painted, in listing order, for rubber-band hits.
+    grid_item_bounds: Rc<RefCell<Vec<Bounds<Pixels>>>>,
+    grid_scroll_handle: ScrollHandle,
+    // Window bounds of the listing area as last painted, for page-wise movement.
+    listing_bounds: Rc<Cell<Bounds<Pixels>>>,
+    rubber_band: Option<RubberBand>,
+    type_ahead: TypeAhead,
+    clipboard: Option<FileClipboard>,
+    // The trash replaces the listing while this is set.
+    trash_view: Option<TrashView>,
+    trash_task: Option<Task<()>>,
+    // Names that differ from the folder this one is being compared with; empty when not comparing.
+    compare_marks: HashMap<String, Difference>,
+}
+
+/// Requests for whatever hosts the explorer.
+pub enum ExplorerEvent {
+    /// Open the folder in a tab of its own.
+    OpenInNewTab(String),
+    /// The listing was scrolled by the user.
+    Scrolled,
 }
 
+impl EventEmitter<ExplorerEvent> for ExplorerPage {}
+
 impl Focusable for ExplorerPage {
     fn focus_handle(&self, _cx: &gpui::App) -> FocusHandle {
         self.focus_handle.clone()
@@ -79,77 +302,524 @@ struct ResizingColumn {
     start_x: gpui::Point<gpui::Pixels>,
 }
 
+/// Letters typed in quick succession while the listing has focus, matched against entry names.
+#[derive(Default)]
+struct TypeAhead {
+    query: String,
+    last_key: Option<Instant>,
+}
+
+/// Typing pauses longer than this start a new type-ahead query.
+const TYPE_AHEAD_TIMEOUT: Duration = Duration::from_millis(1000);
+
+/// What the preview panel shows for the file in [`ExplorerPage::preview_path`].
+enum PreviewContent {
+    Message(SharedString),
+    Code(Entity<CodePreview>),
+    Markdown(Entity<MarkdownPreview>),
+    Image(Entity<ImagePreview>),
+    Hex(Entity<HexPreview>),
+    Text(Entity<LargeTextPreview>),
+}
+
+/// What a file is previewed as, worked out on a background thread. Small UTF-8 files come read
+/// and prepared; the other previews read the file themselves, a page at a time.
+enum PreviewSource {
+    Code(CodeText),
+    Markdown(MarkdownText),
+    Text,
+    /// The length of the file.
+    Hex(u64),
+    Unavailable,
+}
+
+fn load_preview(file: &Path) -> PreviewSource {
+    let Ok(md) = std::fs::metadata(file) else {
+        return PreviewSource::Unavailable;
+    };
+    if !md.is_file() {
+        return PreviewSource::Unavailable;
+    }
+    // UTF-16 is full of NUL bytes and mostly-ASCII UTF-16 is valid UTF-8 too, so the encoding is
+    // settled before a small file is taken for UTF-8 source.
+    let header = read_header(file).unwrap_or_default();
+    let (encoding, _) = detect_encoding(&header);
+    if encoding == Encoding::Utf8 && md.len() <= 1024 * 1024 * 2 {
+        if let Ok(Ok(text)) = std::fs::read(file).map(String::from_utf8) {
+            return if MarkdownPreview::is_markdown(file) {
+                PreviewSource::Markdown(MarkdownText::new(file, &text))
+            } else {
+                PreviewSource::Code(CodeText::new(file, &text))
+            };
+        }
+    }
+    // Large text files and other encodings are read a page at a time, binary files get a hex
+    // dump.
+    if encoding.is_utf16() || !looks_binary(&header) {
+        PreviewSource::Text
+    } else {
+        PreviewSource::Hex(md.len())
+    }
+}
+
+/// A rubber-band sweep over the grid, from where the mouse went down to where it is now.
+struct RubberBand {
+    origin: Point<Pixels>,
+    current: Point<Pixels>,
+    /// The selection before the sweep, kept when ctrl/cmd was held and replaced otherwise.
+    base: Option<Selection>,
+}
+
+impl RubberBand {
+    fn bounds(&self) -> Bounds<Pixels> {
+        let origin =
+            Point::new(self.origin.x.min(self.current.x), self.origin.y.min(self.current.y));
+        let end = Point::new(self.origin.x.max(self.current.x), self.origin.y.max(self.current.y));
+        Bounds::from_corners(origin, end)
+    }
+}
+
 struct LastClickInfo {
     row: usize,
     timestamp: Instant,
     click_count: usize,
 }
 
+/// Entries copied or cut inside the explorer, waiting to be pasted.
+struct FileClipboard {
+    paths: Vec<PathBuf>,
+    cut: bool,
+}
+
+struct TrashView {
+    items: Vec<TrashedItem>,
+    selected: Option<usize>,
+    loading: bool,
+    error: Option<String>,
+}
+
+#[derive(Clone)]
+enum TrashOp {
+    Trash(Vec<PathBuf>),
+    Delete(Vec<PathBuf>),
+    Restore(TrashedItem),
+    Purge(TrashedItem),
+}
+
+/// What the name dialog does with the entered name.
+#[derive(Clone)]
+enum NamePrompt {
+    Rename(PathBuf),
+    NewFolder,
+    NewFile,
+}
+
+impl NamePrompt {
+    fn title(&self) -> &'static str {
+        match self {
+            NamePrompt::Rename(_) => "Rename",
+            NamePrompt::NewFolder => "New Folder",
+            NamePrompt::NewFile => "New File",
+        }
+    }
+
+    fn confirm_label(&self) -> &'static str {
+        match self {
+            NamePrompt::Rename(_) => "Rename",
+            NamePrompt::NewFolder | NamePrompt::NewFile => "Create",
+        }
+    }
+}
+
 const CONFIRM_SUPPRESS_WINDOW: Duration = Duration::from_millis(300);
 
 impl ExplorerPage {
-    pub fn new(search_input: Entity<InputState>, focus_handle: FocusHandle) -> Self {
+    /// An explorer in the working folder, or showing what `restored` saved.
+    pub fn new(
+        search_input: Entity<InputState>,
+        restored: Option<ExplorerSession>,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let restored = restored.unwrap_or_default();
+        let cwd = restored
+            .existing_cwd()
+            .or_else(|| std::env::current_dir().ok())
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_else(|| ".".into());
+        // The saved history only makes sense around the folder it led to.
+        let (history, history_index) = if restored.history.get(restored.history_index) == Some(&cwd)
+        {
+            (restored.history, restored.history_index)
+        } else {
+            (Vec::new(), 0)
+        };
+        let panel_state = cx.new(|_| ResizableState::default());
+        cx.subscribe(&panel_state, |_, _, _: &ResizablePanelEvent, cx| cx.notify()).detach();
+        let columns = restored.columns;
+
         Self {
-            cwd: std::env::current_dir()
-                .map(|p| p.to_string_lossy().to_string())
-                .unwrap_or_else(|_| ".".into()),
-            history: Vec::new(),
-            history_index: 0,
+            cwd,
+            history,
+            history_index,
             entries: Vec::new(),
+            listing_generation: 0,
             filtered_entries: Vec::new(),
-            sort_key: SortKey::Name,
-            sort_asc: true,
+            loaded: false,
+            loading: false,
+            listing_task: None,
+            dir_sizes: HashMap::new(),
+            dir_size_task: None,
+            thumbnails: HashMap::new(),
+            thumbnails_requested: HashSet::new(),
+            thumbnail_task: None,
+            watch_task: None,
+            pending_changes: Vec::new(),
+            sort_key: restored.sort_key,
+            sort_asc: restored.sort_direction == SortDirection::Ascending,
+            show_hidden: true,
             search_query: String::new(),
             search_visible: false,
             search_input,
             list: None,
             subs: Vec::new(),
             preview_path: None,
-            preview_text: None,
-            selected_index: None,
+            preview: None,
+            preview_entry: None,
+            preview_task: None,
+            selection: Selection::default(),
             virtual_scroll_handle: VirtualListScrollHandle::new(),
             item_sizes: Rc::new(Vec::new()),
-            // Initial column widths
-            col_name_width: 400.0,
-            col_type_width: 120.0,
-            col_size_width: 120.0,
-            col_modified_width: 180.0,
+            col_name_width: columns.name,
+            col_type_width: columns.kind,
+            col_size_width: columns.size,
+            col_modified_width: columns.modified,
             col_action_width: 60.0,
             resizing_column: None,
-            focus_handle,
+            panel_state,
+            sidebar_width: restored.sidebar_width,
+            preview_width: restored.preview_width,
+            focus_handle: cx.focus_handle(),
+            preview_focus_handle: cx.focus_handle(),
             last_click_info: None,
-            view_mode: ViewMode::List,
+            view_mode: restored.view_mode,
+            grid_item_bounds: Rc::new(RefCell::new(Vec::new())),
+            grid_scroll_handle: ScrollHandle::new(),
+            listing_bounds: Rc::new(Cell::new(Bounds::default())),
+            rubber_band: None,
+            type_ahead: TypeAhead::default(),
+            clipboard: None,
+            trash_view: None,
+            trash_task: None,
+            compare_marks: HashMap::new(),
         }
     }
 
-    fn ensure_loaded(&mut self) {
-        if self.entries.is_empty() {
-            self.reload();
+    /// Where this explorer is and how it is laid out, for the session file.
+    pub fn session(&self, cx: &App) -> ExplorerSession {
+        let sizes = self.panel_state.read(cx).sizes();
+        let (sidebar_width, preview_width) = match sizes.as_slice() {
+            [sidebar, _, preview] => (f32::from(*sidebar), f32::from(*preview)),
+            _ => (self.sidebar_width, self.preview_width),
+        };
+        ExplorerSession {
+            cwd: Some(self.cwd.clone()),
+            history: self.history.clone(),
+            history_index: self.history_index,
+            view_mode: self.view_mode,
+            sort_key: self.sort_key,
+            sort_direction: if self.sort_asc {
+                SortDirection::Ascending
+            } else {
+                SortDirection::Descending
+            },
+            columns: ColumnWidths {
+                name: self.col_name_width,
+                kind: self.col_type_width,
+                size: self.col_size_width,
+                modified: self.col_modified_width,
+            },
+            sidebar_width,
+            preview_width,
         }
     }
 
-    fn reload(&mut self) {
-        if let Ok(res) = list_dir_sync(ListParams { path: &self.cwd, limit: 1000, cursor: None }) {
-            let mut e = res.entries;
-            self.sort_entries(&mut e);
-            self.entries = e;
-            self.apply_filter();
-            self.update_item_sizes();
-            self.preview_text = None;
-            self.preview_path = None;
+    fn ensure_loaded(&mut self, cx: &mut Context<Self>) {
+        if !self.loaded {
+            self.reload(cx);
         }
     }
 
-    fn update_item_sizes(&mut self) {
-        let total_width = self.col_name_width
-            + self.col_type_width
-            + self.col_size_width
-            + self.col_modified_width
-            + self.col_action_width
-            + 48.0;
+    fn reload(&mut self, cx: &mut Context<Self>) {
+        self.loaded = true;
+        self.loading = true;
+        self.entries.clear();
+        self.listing_generation += 1;
+        self.apply_filter();
+        self.preview = None;
+        self.preview_path = None;
+        self.preview_entry = None;
+        self.preview_task = None;
+        self.dir_sizes.clear();
+        self.dir_size_task = None;
+        self.thumbnails.clear();
+        self.thumbnails_requested.clear();
+        self.thumbnail_task = None;
+        self.pending_changes.clear();
+        // Subscribe before listing so nothing that changes while the listing streams is missed.
+        self.start_watcher(cx);
+
+        let stream = list_dir_stream(StreamParams {
+            path: &self.cwd,
+            batch_size: DEFAULT_STREAM_BATCH_SIZE,
+            options: self.list_options(),
+        });
+
+        // Replacing the task drops the previous listing, which cancels its worker thread.
+        self.listing_task = Some(cx.spawn(async move |this, cx| {
+            let done = forward_channel(stream, ListStream::recv, &this, cx, |this, batch, cx| {
+                match batch {
+                    Ok(entries) => this.append_entries(entries),
+                    Err(err) => {
+                        tracing::warn!("Failed to list {}: {}", this.cwd, err);
+                        show_error(format!("Can't list \"{}\": {}", this.cwd, err), cx);
+                    },
+                }
+                cx.notify();
+                ControlFlow::Continue(())
+            })
+            .await;
+            if !done {
+                return;
+            }
+
+            let _ = this.update(cx, |this, cx| {
+                this.loading = false;
+                let pending = std::mem::take(&mut this.pending_changes);
+                if !pending.is_empty() {
+                    this.apply_changes(pending);
+                }
+                this.prune_selection();
+                this.start_dir_sizes(cx);
+                this.start_thumbnails(cx);
+                cx.notify();
+            });
+        }));
+    }
+
+    /// Watches `cwd` and applies create/modify/remove/rename events to the listing as they happen.
+    fn start_watcher(&mut self, cx: &mut Context<Self>) {
+        // Drop the old watcher first so two are never active at once.
+        self.watch_task = None;
+        let watcher = match watch_dir(Path::new(&self.cwd), DEFAULT_DEBOUNCE) {
+            Ok(watcher) => watcher,
+            Err(err) => {
+                tracing::warn!("Not watching {}: {}", self.cwd, err);
+                return;
+            },
+        };
 
-        let sizes = self.filtered_entries.iter().map(|_| size(px(total_width), px(32.0))).collect();
-        self.item_sizes = Rc::new(sizes);
+        self.watch_task = Some(cx.spawn(async move |this, cx| {
+            forward_channel(watcher, DirWatcher::recv, &this, cx, |this, batch, cx| {
+                cx.notify();
+                // Cached folder sizes are stale for every folder above a change.
+                if batch.rescan {
+                    invalidate_cached_sizes(&[PathBuf::from(&this.cwd)]);
+                    // This replaces the task with a fresh watcher.
+                    this.reload(cx);
+                    return ControlFlow::Break(());
+                }
+                invalidate_cached_sizes(&batch.changed);
+                if this.loading {
+                    this.pending_changes.extend(batch.changed);
+                } else {
+                    this.apply_changes(batch.changed);
+                    this.start_thumbnails(cx);
+                }
+                ControlFlow::Continue(())
+            })
+            .await;
+        }));
+    }
+
+    /// Re-reads the changed paths and patches them into the listing in place. The selection
+    /// follows its entries by path and the scroll offset is left untouched.
+    fn apply_changes(&mut self, paths: Vec<PathBuf>) {
+        let cwd = Path::new(&self.cwd);
+        let changed: HashSet<String> = paths
+            .iter()
+            .filter(|p| p.parent() == Some(cwd))
+            .map(|p| p.to_string_lossy().to_string())
+            .collect();
+        if changed.is_empty() {
+            return;
+        }
+
+        let mut fresh: Vec<FileEntryDto> =
+            changed.iter().filter_map(|p| stat_entry(Path::new(p), true)).collect();
+        self.entries.retain(|e| !changed.contains(&e.path));
+        self.dir_sizes
+            .retain(|path, _| !changed.contains(path) || fresh.iter().any(|e| &e.path == path));
+        // A changed image has a new cache key, so it is asked for again.
+        self.thumbnails.retain(|path, _| !changed.contains(path));
+        self.thumbnails_requested.retain(|path| !changed.contains(path));
+
+        let preview = self.preview_entry.as_ref().map(|e| e.path.clone());
+        if let Some(preview) = preview.filter(|p| changed.contains(p)) {
+            match fresh.iter().find(|e| e.path == preview) {
+                Some(entry) => self.preview_entry = Some(entry.clone()),
+                None => {
+                    self.preview_entry = None;
+                    self.preview_path = None;
+                    self.preview = None;
+                    self.preview_task = None;
+                },
+            }
+        }
+
+        self.sort_entries(&mut fresh);
+        self.list_options().merge_sorted(&mut self.entries, fresh);
+        self.listing_generation += 1;
+        self.apply_filter();
+        self.prune_selection();
+    }
+
+    /// Drops selected paths that are no longer part of the listing.
+    fn prune_selection(&mut self) {
+        if self.selection.is_empty() {
+            return;
+        }
+        let listed: HashSet<&str> = self.entries.iter().map(|e| e.path.as_str()).collect();
+        self.selection.retain(|path| listed.contains(path));
+    }
+
+    /// Computes recursive sizes for the folders of the current listing in the background.
+    fn start_dir_sizes(&mut self, cx: &mut Context<Self>) {
+        let paths: Vec<PathBuf> =
+            self.entries.iter().filter(|e| e.is_dir()).map(|e| PathBuf::from(&e.path)).collect();
+        if paths.is_empty() {
+            return;
+        }
+        let job = spawn_dir_sizes(paths, SizeOptions::default());
+
+        self.dir_size_task = Some(cx.spawn(async move |this, cx| {
+            forward_channel(job, SizeJob::recv, &this, cx, |this, event, cx| {
+                match event {
+                    SizeEvent::Progress { path, size } => {
+                        let path = path.to_string_lossy().to_string();
+                        this.dir_sizes.insert(path, (size.bytes, false));
+                    },
+                    SizeEvent::Done { path, result } => {
+                        let path = path.to_string_lossy().to_string();
+                        match result {
+                            Ok(size) => {
+                                this.dir_sizes.insert(path, (size.bytes, true));
+                            },
+                            Err(err) => {
+                                tracing::debug!("Folder size of {} failed: {}", path, err);
+                                this.dir_sizes.remove(&path);
+                            },
+                        }
+                    },
+                }
+                cx.notify();
+                ControlFlow::Continue(())
+            })
+            .await;
+        }));
+    }
+
+    /// Makes (or finds cached) thumbnails for the images of the current listing, while the grid is
+    /// shown. Switching to the grid later starts it then. Images that show up while a batch runs
+    /// are picked up by the next one, started when it finishes.
+    fn start_thumbnails(&mut self, cx: &mut Context<Self>) {
+        if self.view_mode != ViewMode::Grid || self.loading || self.thumbnail_task.is_some() {
+            return;
+        }
+        let requests: Vec<ThumbnailRequest> = self
+            .entries
+            .iter()
+            .filter(|e| {
+                !e.is_dir()
+                    && !self.thumbnails_requested.contains(&e.path)
+                    && can_decode(Path::new(&e.path))
+            })
+            .map(|e| ThumbnailRequest {
+                path: PathBuf::from(&e.path),
+                size: e.size,
+                modified: e.modified,
+            })
+            .collect();
+        if requests.is_empty() {
+            return;
+        }
+        self.thumbnails_requested
+            .extend(requests.iter().map(|r| r.path.to_string_lossy().to_string()));
+        let job = spawn_thumbnails(requests);
+
+        self.thumbnail_task = Some(cx.spawn(async move |this, cx| {
+            let done = forward_channel(job, ThumbnailJob::recv, &this, cx, |this, event, cx| {
+                let ThumbnailEvent { path, result } = event;
+                let path = path.to_string_lossy().to_string();
+                match result {
+                    Ok(thumbnail) => {
+                        this.thumbnails.insert(path, thumbnail);
+                        cx.notify();
+                    },
+                    Err(err) => tracing::debug!("Thumbnail of {} failed: {}", path, err),
+                }
+                ControlFlow::Continue(())
+            })
+            .await;
+            if done {
+                let _ = this.update(cx, |this, cx| {
+                    this.thumbnail_task = None;
+                    this.start_thumbnails(cx);
+                });
+            }
+        }));
+    }
+
+    /// Size column text; folders show their recursive size once (or while) it is computed.
+    fn size_text(&self, item: &FileEntryDto) -> String {
+        use crate::ui::components::file_list::size_label;
+
+        if item.is_dir() {
+            match self.dir_sizes.get(&item.path) {
+                Some((bytes, true)) => human_bytes(*bytes),
+                Some((bytes, false)) => format!("{}…", human_bytes(*bytes)),
+                None => "-".to_string(),
+            }
+        } else {
+            size_label(item)
+        }
+    }
+
+    /// Merges a batch from the listing stream, which arrives sorted, into the sorted entry list.
+    /// Only the new entries are filtered and merged into the filtered view, so a long listing
+    /// costs one linear pass per batch.
+    fn append_entries(&mut self, mut batch: Vec<FileEntryDto>) {
+        let options = self.list_options();
+        // The stream sorts by the options it was started with; the sort may have changed since.
+        if !batch.is_sorted_by(|a, b| options.compare(a, b) != Ordering::Greater) {
+            self.sort_entries(&mut batch);
+        }
+        let visible = batch.iter().filter(|e| options.matches_entry(e)).cloned().collect();
+        options.merge_sorted(&mut self.entries, batch);
+        self.listing_generation += 1;
+        options.merge_sorted(&mut self.filtered_entries, visible);
+        self.update_item_sizes();
+    }
+
+    /// Keeps one row size per filtered entry. All rows share a size, so unless the columns were
+    /// resized only the rows added or removed at the end are touched.
+    fn update_item_sizes(&mut self) {
+        let row = size(px(self.total_table_width()), px(32.0));
+        let len = self.filtered_entries.len();
+        if self.item_sizes.first().is_some_and(|first| *first != row) {
+            self.item_sizes = Rc::new(vec![row; len]);
+        } else if self.item_sizes.len() != len {
+            Rc::make_mut(&mut self.item_sizes).resize(len, row);
+        }
     }
 
     fn total_table_width(&self) -> f32 {
@@ -161,18 +831,26 @@ impl ExplorerPage {
             + 48.0
     }
 
-    fn apply_filter(&mut self) {
-        if self.search_query.is_empty() {
-            self.filtered_entries = self.entries.clone();
-        } else {
-            let query = self.search_query.to_lowercase();
-            self.filtered_entries = self
-                .entries
-                .iter()
-                .filter(|e| e.name.to_lowercase().contains(&query))
-                .cloned()
-                .collect();
+    /// Sort and filter options matching the current view, shared with the listing service.
+    fn list_options(&self) -> ListOptions {
+        ListOptions {
+            sort: self.sort_key,
+            direction: if self.sort_asc {
+                SortDirection::Ascending
+            } else {
+                SortDirection::Descending
+            },
+            dirs_first: true,
+            show_hidden: self.show_hidden,
+            filter: Some(self.search_query.clone()).filter(|q| !q.is_empty()),
+            detailed: true,
         }
+    }
+
+    fn apply_filter(&mut self) {
+        let options = self.list_options();
+        self.filtered_entries =
+            self.entries.iter().filter(|e| options.matches_entry(e)).cloned().collect();
         self.update_item_sizes();
     }
 
@@ -183,42 +861,27 @@ impl ExplorerPage {
             self.sort_key = key;
             self.sort_asc = true;
         }
-        let mut e = self.entries.clone();
+        let mut e = std::mem::take(&mut self.entries);
         self.sort_entries(&mut e);
         self.entries = e;
         self.apply_filter();
     }
 
+    fn set_show_hidden(&mut self, show_hidden: bool, cx: &mut Context<Self>) {
+        if self.show_hidden != show_hidden {
+            self.show_hidden = show_hidden;
+            self.apply_filter();
+            cx.notify();
+        }
+    }
+
     fn sort_entries(&self, entries: &mut [FileEntryDto]) {
-        entries.sort_by(|a, b| {
-            // Directories before files
-            let is_dir_a = a.kind == "dir";
-            let is_dir_b = b.kind == "dir";
-
-            match is_dir_b.cmp(&is_dir_a) {
-                std::cmp::Ordering::Equal => {
-                    let order = match self.sort_key {
-                        SortKey::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
-                        SortKey::Size => a.size.cmp(&b.size),
-                        SortKey::Modified => a.modified.cmp(&b.modified),
-                        SortKey::Type => {
-                            let ext_a = get_extension(&a.name, &a.kind);
-                            let ext_b = get_extension(&b.name, &b.kind);
-                            ext_a.cmp(&ext_b)
-                        },
-                    };
-                    if self.sort_asc {
-                        order
-                    } else {
-                        order.reverse()
-                    }
-                },
-                kind_order => kind_order,
-            }
-        });
+        let options = self.list_options();
+        entries.sort_by(|a, b| options.compare(a, b));
     }
 
     fn change_dir(&mut self, path: String, window: &mut Window, cx: &mut Context<Self>) {
+        self.close_trash();
         if path == self.cwd {
             return;
         }
@@ -233,8 +896,8 @@ impl ExplorerPage {
         self.history.push(path.clone());
         self.history_index += 1;
         self.cwd = path;
-        self.entries.clear();
-        self.reload();
+        self.selection.clear();
+        self.reload(cx);
     }
 
     fn go_back(&mut self, window: &mut Window, cx: &mut Context<Self>) {
@@ -242,9 +905,10 @@ impl ExplorerPage {
             self.history_index -= 1;
             if let Some(p) = self.history.get(self.history_index).cloned() {
                 self.cwd = p;
-                self.entries.clear();
+                self.selection.clear();
+                self.close_trash();
                 self.close_search(window, cx);
-                self.reload();
+                self.reload(cx);
             }
         }
     }
@@ -254,9 +918,10 @@ impl ExplorerPage {
             self.history_index += 1;
             if let Some(p) = self.history.get(self.history_index).cloned() {
                 self.cwd = p;
-                self.entries.clear();
+                self.selection.clear();
+                self.close_trash();
                 self.close_search(window, cx);
-                self.reload();
+                self.reload(cx);
             }
         }
     }
@@ -298,6 +963,7 @@ impl ExplorerPage {
     fn set_view_mode(&mut self, mode: ViewMode, cx: &mut Context<Self>) {
         if self.view_mode != mode {
             self.view_mode = mode;
+            self.start_thumbnails(cx);
             cx.notify();
         }
     }
@@ -307,10 +973,10 @@ impl ExplorerPage {
     }
 
     fn activate_entry(&mut self, item: FileEntryDto, window: &mut Window, cx: &mut Context<Self>) {
-        if item.kind == "dir" {
+        if item.opens_as_dir() {
             self.change_dir(item.path, window, cx);
         } else {
-            self.open_preview(item.path);
+            self.open_preview(&item, cx);
         }
     }
 
@@ -323,10 +989,10 @@ impl ExplorerPage {
                 cx.subscribe_in(&list, window, |this, _list, event: &ListEvent, window, cx| {
                     match event {
                         ListEvent::Select(ix) => {
-                            this.selected_index = Some(ix.row);
                             if let Some(item) = this.filtered_entries.get(ix.row).cloned() {
-                                if item.kind == "file" {
-                                    this.open_preview(item.path);
+                                this.selection.select_only(&item.path);
+                                if item.opens_as_file() {
+                                    this.open_preview(&item, cx);
                                 }
                             }
                         },
@@ -341,8 +1007,8 @@ impl ExplorerPage {
                                 }
                             }
                             this.last_click_info = None;
-                            this.selected_index = Some(ix.row);
                             if let Some(item) = this.filtered_entries.get(ix.row).cloned() {
+                                this.selection.select_only(&item.path);
                                 this.activate_entry(item, window, cx);
                             }
                         },
@@ -353,8 +1019,11 @@ impl ExplorerPage {
             self.list = Some(list);
         } else if let Some(list) = &self.list {
             let items = self.filtered_entries.clone();
+            let selection = self.selection.clone();
             list.update(cx, |l, _cx| {
-                l.delegate_mut().set_items(items);
+                let delegate = l.delegate_mut();
+                delegate.set_items(items);
+                delegate.selection = selection;
             });
         }
     }
@@ -380,60 +1049,924 @@ impl ExplorerPage {
         self.search_input.update(cx, |input, cx| {
             input.set_value("", window, cx);
         });
+        self.focus_handle.focus(window, cx);
         cx.notify();
     }
 
-    fn toggle_search(&mut self, window: &mut Window, cx: &mut Context<Self>) {
-        if self.search_visible {
-            self.close_search(window, cx);
+    fn toggle_search(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if self.search_visible {
+            self.close_search(window, cx);
+        } else {
+            self.open_search(window, cx);
+        }
+    }
+
+    fn open_preview(&mut self, item: &FileEntryDto, cx: &mut Context<Self>) {
+        let path = item.path.clone();
+        let file = PathBuf::from(&path);
+        self.preview_entry = Some(item.clone());
+        self.preview_path = Some(path);
+        if is_image(&file) {
+            self.preview_task = None;
+            self.preview = Some(PreviewContent::Image(cx.new(|cx| ImagePreview::new(&file, cx))));
+            return;
+        }
+
+        self.preview = Some(PreviewContent::Message("Loading…".into()));
+        self.preview_task = Some(cx.spawn(async move |this, cx| {
+            let source = file.clone();
+            let loaded = cx.background_executor().spawn(async move { load_preview(&source) }).await;
+            let _ = this.update(cx, |this, cx| {
+                let file = file.as_path();
+                this.preview = Some(match loaded {
+                    PreviewSource::Code(text) => {
+                        PreviewContent::Code(cx.new(|cx| CodePreview::new(text, cx)))
+                    },
+                    PreviewSource::Markdown(text) => {
+                        PreviewContent::Markdown(cx.new(|cx| MarkdownPreview::new(text, cx)))
+                    },
+                    PreviewSource::Text => {
+                        PreviewContent::Text(cx.new(|cx| LargeTextPreview::new(file, cx)))
+                    },
+                    PreviewSource::Hex(len) => {
+                        PreviewContent::Hex(cx.new(|cx| HexPreview::new(file, len, cx)))
+                    },
+                    PreviewSource::Unavailable => {
+                        PreviewContent::Message("(Preview not available for this file)".into())
+                    },
+                });
+                cx.notify();
+            });
+        }));
+    }
+
+    /// Goes to what a link in the preview points to: into a folder, or to a file's folder with
+    /// the file selected and previewed.
+    fn on_open_linked_path(
+        &mut self,
+        action: &OpenLinkedPath,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let path = &action.0;
+        let Some(entry) = stat_entry(path, true) else {
+            let message = format!("{} doesn't exist", path.display());
+            window.push_notification(Notification::error(message), cx);
+            return;
+        };
+        if entry.opens_as_dir() {
+            self.change_dir(entry.path, window, cx);
+            return;
+        }
+        if let Some(parent) = path.parent() {
+            self.change_dir(parent.to_string_lossy().to_string(), window, cx);
+        }
+        self.selection.select_only(&entry.path);
+        self.open_preview(&entry, cx);
+        cx.notify();
+    }
+
+    fn shortcuts(&self) -> Vec<(String, String)> {
+        let mut v = Vec::new();
+        let home = std::env::var("HOME").ok();
+        #[cfg(target_os = "windows")]
+        let home = home.or_else(|| std::env::var("USERPROFILE").ok());
+        if let Some(h) = home {
+            let p = |s: &str| std::path::Path::new(&h).join(s).to_string_lossy().to_string();
+            v.push(("Home".into(), h.clone()));
+            for (label, sub) in [
+                ("Desktop", "Desktop"),
+                ("Downloads", "Downloads"),
+                ("Documents", "Documents"),
+                ("Pictures", "Pictures"),
+            ] {
+                let path = p(sub);
+                if std::path::Path::new(&path).exists() {
+                    v.push((label.into(), path));
+                }
+            }
+        }
+        v
+    }
+}
+
+// File operations: clipboard, paste, duplicate, rename and create.
+impl ExplorerPage {
+    /// Paths the file operations act on: the visible selected entries in listing order.
+    fn selected_paths(&self) -> Vec<PathBuf> {
+        self.selected_entries().map(|e| PathBuf::from(&e.path)).collect()
+    }
+
+    fn select_path(&mut self, path: &Path) {
+        let path = path.to_string_lossy();
+        if self.filtered_entries.iter().any(|e| e.path == path) {
+            self.selection.select_only(&path);
+        }
+    }
+
+    /// Shows the effect of an operation right away instead of waiting for the watcher, which will
+    /// report the same paths again (re-applying them is harmless).
+    fn refresh_paths(&mut self, paths: Vec<PathBuf>) {
+        if self.loading {
+            self.pending_changes.extend(paths);
+        } else {
+            self.apply_changes(paths);
+        }
+    }
+
+    fn on_copy_entries(&mut self, _: &CopyEntries, _: &mut Window, cx: &mut Context<Self>) {
+        self.set_clipboard(false, cx);
+    }
+
+    fn on_cut_entries(&mut self, _: &CutEntries, _: &mut Window, cx: &mut Context<Self>) {
+        self.set_clipboard(true, cx);
+    }
+
+    fn set_clipboard(&mut self, cut: bool, cx: &mut Context<Self>) {
+        let paths = self.selected_paths();
+        if !paths.is_empty() {
+            self.clipboard = Some(FileClipboard { paths, cut });
+            cx.notify();
+        }
+    }
+
+    fn on_paste_entries(&mut self, _: &PasteEntries, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(clipboard) = self.clipboard.as_ref() else {
+            return;
+        };
+        let kind = if clipboard.cut { JobKind::Move } else { JobKind::Copy };
+        let sources = clipboard.paths.clone();
+        self.start_transfer(sources, PathBuf::from(&self.cwd), kind, window, cx);
+    }
+
+    fn on_duplicate_entries(
+        &mut self,
+        _: &DuplicateEntries,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        // Copying into the entry's own folder always keeps both.
+        let sources = self.selected_paths();
+        self.start_transfer(sources, PathBuf::from(&self.cwd), JobKind::Copy, window, cx);
+    }
+
+    fn on_paste_as_link(&mut self, _: &PasteAsLink, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(clipboard) = self.clipboard.as_ref() else {
+            return;
+        };
+        let cwd = PathBuf::from(&self.cwd);
+        let mut created = Vec::new();
+        for source in &clipboard.paths {
+            let Some(name) = source.file_name() else {
+                continue;
+            };
+            let link_name = path_name(&unique_name(&cwd.join(name)).to_string_lossy());
+            match create_symlink(&cwd, &link_name, source) {
+                Ok(path) => created.push(Created::Symlink { path, target: source.clone() }),
+                Err(err) => {
+                    window.push_notification(Notification::error(err.to_string()), cx);
+                    break;
+                },
+            }
+        }
+
+        let paths: Vec<PathBuf> = created.iter().map(|c| c.path().to_path_buf()).collect();
+        self.refresh_paths(paths.clone());
+        if let Some(last) = paths.last() {
+            self.select_path(last);
+        }
+        if !created.is_empty() {
+            let op = Operation::Create { items: created };
+            History::global(cx).update(cx, |history, cx| history.record(op, cx));
+        }
+        cx.notify();
+    }
+
+    /// Copies or moves `sources` into `dest`, asking first if any of them already exist there.
+    fn start_transfer(
+        &mut self,
+        sources: Vec<PathBuf>,
+        dest: PathBuf,
+        kind: JobKind,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if sources.is_empty() {
+            return;
+        }
+        let conflicts = find_conflicts(&sources, &dest);
+        if conflicts.is_empty() {
+            // Without a top-level conflict nothing gets merged, so no nested conflicts can arise.
+            self.run_transfer(sources, dest, kind, ConflictPolicy::Skip, cx);
+        } else {
+            self.ask_conflict_policy(sources, dest, kind, &conflicts, window, cx);
+        }
+    }
+
+    fn ask_conflict_policy(
+        &mut self,
+        sources: Vec<PathBuf>,
+        dest: PathBuf,
+        kind: JobKind,
+        conflicts: &[Conflict],
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let message = match conflicts {
+            [conflict] => format!(
+                "\"{}\" already exists in this folder.",
+                path_name(&conflict.target.to_string_lossy())
+            ),
+            _ => format!("{} items already exist in this folder.", conflicts.len()),
+        };
+        let this = cx.entity().downgrade();
+
+        window.open_dialog(cx, move |dialog, _window, _cx| {
+            let choice = |id: &'static str, label: &'static str, policy: ConflictPolicy| {
+                let this = this.clone();
+                let sources = sources.clone();
+                let dest = dest.clone();
+                Button::new(id).label(label).on_click(move |_, window, cx| {
+                    window.close_dialog(cx);
+                    let (sources, dest) = (sources.clone(), dest.clone());
+                    let _ = this.update(cx, |this, cx| {
+                        this.run_transfer(sources, dest, kind, policy, cx);
+                    });
+                })
+            };
+
+            dialog.title("Replace existing items?").child(
+                v_flex().gap_4().child(message.clone()).child(
+                    h_flex()
+                        .gap_2()
+                        .justify_end()
+                        .child(choice("conflict-skip", "Skip", ConflictPolicy::Skip))
+                        .child(choice("conflict-replace", "Replace", ConflictPolicy::Overwrite))
+                        .child(
+                            choice("conflict-keep-both", "Keep Both", ConflictPolicy::KeepBoth)
+                                .primary(),
+                        ),
+                ),
+            )
+        });
+    }
+
+    /// Hands the transfer to the app-wide job queue; the watcher picks up the result.
+    fn run_transfer(
+        &mut self,
+        sources: Vec<PathBuf>,
+        dest: PathBuf,
+        kind: JobKind,
+        policy: ConflictPolicy,
+        cx: &mut Context<Self>,
+    ) {
+        // Cut entries are leaving their old place, so pasting them again would only fail.
+        if kind == JobKind::Move {
+            self.clipboard = None;
+        }
+        let options = TransferOptions { conflict: policy, ..Default::default() };
+        Transfers::global(cx).read(cx).enqueue(JobSpec { kind, sources, dest, options });
+        cx.notify();
+    }
+
+    fn on_rename_entry(&mut self, _: &RenameEntry, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(entry) = self.selected_entry().cloned() else {
+            return;
+        };
+        self.prompt_name(NamePrompt::Rename(PathBuf::from(&entry.path)), entry.name, window, cx);
+    }
+
+    fn on_new_folder(&mut self, _: &NewFolder, window: &mut Window, cx: &mut Context<Self>) {
+        let name = self.free_name("New Folder");
+        self.prompt_name(NamePrompt::NewFolder, name, window, cx);
+    }
+
+    fn on_new_file(&mut self, _: &NewFile, window: &mut Window, cx: &mut Context<Self>) {
+        let name = self.free_name("New File");
+        self.prompt_name(NamePrompt::NewFile, name, window, cx);
+    }
+
+    /// `base`, or `base (n)` if that is taken in the current folder.
+    fn free_name(&self, base: &str) -> String {
+        path_name(&unique_name(&Path::new(&self.cwd).join(base)).to_string_lossy())
+    }
+
+    fn prompt_name(
+        &mut self,
+        prompt: NamePrompt,
+        initial: String,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let input = cx.new(|cx| InputState::new(window, cx).default_value(initial));
+        cx.subscribe_in(&input, window, {
+            let prompt = prompt.clone();
+            move |this, input, event: &InputEvent, window, cx| {
+                if let InputEvent::PressEnter { .. } = event {
+                    this.submit_name(&prompt, input, window, cx);
+                }
+            }
+        })
+        .detach();
+
+        let this = cx.entity().downgrade();
+        let dialog_input = input.clone();
+        window.open_dialog(cx, move |dialog, _window, _cx| {
+            let this = this.clone();
+            let prompt = prompt.clone();
+            let input = dialog_input.clone();
+            dialog.title(prompt.title()).child(
+                v_flex().gap_4().child(Input::new(&dialog_input)).child(
+                    h_flex().justify_end().child(
+                        Button::new("name-dialog-confirm")
+                            .primary()
+                            .label(prompt.confirm_label())
+                            .on_click(move |_, window, cx| {
+                                let _ = this.update(cx, |this, cx| {
+                                    this.submit_name(&prompt, &input, window, cx);
+                                });
+                            }),
+                    ),
+                ),
+            )
+        });
+        input.update(cx, |input, cx| input.focus(window, cx));
+    }
+
+    fn submit_name(
+        &mut self,
+        prompt: &NamePrompt,
+        input: &Entity<InputState>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let name = input.read(cx).text().to_string();
+        let name = name.trim();
+        let cwd = PathBuf::from(&self.cwd);
+        let result = match prompt {
+            NamePrompt::Rename(path) => rename_entry(path, name).map(|new| {
+                let op = Operation::Rename { from: path.clone(), to: new.clone() };
+                (vec![path.clone(), new.clone()], new.clone(), (new != *path).then_some(op))
+            }),
+            NamePrompt::NewFolder => create_dir(&cwd, name).map(|new| {
+                let op = Operation::Create { items: vec![Created::Dir { path: new.clone() }] };
+                (vec![new.clone()], new, Some(op))
+            }),
+            NamePrompt::NewFile => create_file(&cwd, name).map(|new| {
+                let op = Operation::Create { items: vec![Created::File { path: new.clone() }] };
+                (vec![new.clone()], new, Some(op))
+            }),
+        };
+
+        match result {
+            Ok((changed, new, op)) => {
+                window.close_dialog(cx);
+                self.refresh_paths(changed);
+                self.select_path(&new);
+                if let Some(op) = op {
+                    History::global(cx).update(cx, |history, cx| history.record(op, cx));
+                }
+                cx.notify();
+            },
+            Err(err) => window.push_notification(Notification::error(err.to_string()), cx),
+        }
+    }
+}
+
+// Selection: clicks with modifiers, select all/invert and the grid's rubber band.
+impl ExplorerPage {
+    /// Applies a left click on `item`: shift selects a range from the anchor, ctrl/cmd toggles,
+    /// ctrl/cmd+shift adds a range, and a plain click selects just the item.
+    fn click_entry(&mut self, item: &FileEntryDto, modifiers: Modifiers, cx: &mut Context<Self>) {
+        if modifiers.shift {
+            let order = self.filtered_entries.iter().map(|e| e.path.as_str());
+            self.selection.select_range(order, &item.path, modifiers.secondary());
+        } else if modifiers.secondary() {
+            self.selection.toggle(&item.path);
+        } else {
+            self.selection.select_only(&item.path);
+        }
+        if item.opens_as_file() && self.selection.lead() == Some(item.path.as_str()) {
+            self.open_preview(item, cx);
+        }
+    }
+
+    /// A right click keeps a selection it lands in, so the context menu acts on all of it.
+    fn select_for_context_menu(&mut self, path: &str) {
+        if !self.selection.contains(path) {
+            self.selection.select_only(path);
+        }
+    }
+
+    fn on_select_all(&mut self, _: &SelectAll, _: &mut Window, cx: &mut Context<Self>) {
+        self.selection.select_all(self.filtered_entries.iter().map(|e| e.path.as_str()));
+        cx.notify();
+    }
+
+    fn on_invert_selection(&mut self, _: &InvertSelection, _: &mut Window, cx: &mut Context<Self>) {
+        self.selection.invert(self.filtered_entries.iter().map(|e| e.path.as_str()));
+        cx.notify();
+    }
+
+    fn on_clear_selection(&mut self, _: &ClearSelection, _: &mut Window, cx: &mut Context<Self>) {
+        self.selection.clear();
+        cx.notify();
+    }
+
+    fn start_rubber_band(&mut self, position: Point<Pixels>, modifiers: Modifiers) {
+        let base = modifiers.secondary().then(|| self.selection.clone());
+        if base.is_none() {
+            self.selection.clear();
+        }
+        self.rubber_band = Some(RubberBand { origin: position, current: position, base });
+    }
+
+    /// Selects the grid items the band overlaps, on top of the base selection if there is one.
+    fn update_rubber_band(&mut self, position: Point<Pixels>) {
+        let Some(band) = self.rubber_band.as_mut() else {
+            return;
+        };
+        band.current = position;
+        let area = band.bounds();
+        let item_bounds = self.grid_item_bounds.borrow();
+        let hits = self
+            .filtered_entries
+            .iter()
+            .zip(item_bounds.iter())
+            .filter(|(_, bounds)| bounds.intersects(&area))
+            .map(|(entry, _)| entry.path.as_str());
+        match &band.base {
+            Some(base) => {
+                let mut selection = base.clone();
+                selection.select_all(hits);
+                self.selection = selection;
+            },
+            None => self.selection.set(hits),
+        }
+    }
+}
+
+/// Where a keyboard movement takes the cursor.
+#[derive(Clone, Copy)]
+enum Step {
+    Up,
+    Down,
+    Left,
+    Right,
+    First,
+    Last,
+    PageUp,
+    PageDown,
+}
+
+// Keyboard navigation: cursor movement, opening entries and type-ahead.
+impl ExplorerPage {
+    /// Index of the keyboard cursor in the listing: the selection's lead, or the first selected
+    /// entry.
+    fn cursor_index(&self) -> Option<usize> {
+        let entry = self.selected_entry()?;
+        self.filtered_entries.iter().position(|e| e.path == entry.path)
+    }
+
+    /// Grid items per row, read from where the items were last painted.
+    fn grid_columns(&self) -> usize {
+        let bounds = self.grid_item_bounds.borrow();
+        match bounds.first() {
+            Some(first) => bounds.iter().take_while(|b| b.origin.y == first.origin.y).count(),
+            None => 1,
+        }
+    }
+
+    /// Entries that fit in the visible part of the listing.
+    fn page_size(&self) -> usize {
+        let height = self.listing_bounds.get().size.height;
+        match self.view_mode {
+            ViewMode::List => (f32::from(height - px(48.0)) / 32.0).floor().max(1.0) as usize,
+            ViewMode::Grid => {
+                let row_height = self
+                    .grid_item_bounds
+                    .borrow()
+                    .first()
+                    .map_or(px(1.0), |b| b.size.height + px(16.0));
+                let rows = (f32::from(height) / f32::from(row_height)).floor().max(1.0) as usize;
+                rows * self.grid_columns().max(1)
+            },
+        }
+    }
+
+    /// Moves the cursor one step. With `extend` the selection grows from the anchor to the new
+    /// position; otherwise the new entry becomes the whole selection. Left and right only move in
+    /// the grid, where up and down go a row at a time.
+    fn move_cursor(&mut self, step: Step, extend: bool, cx: &mut Context<Self>) {
+        let len = self.filtered_entries.len();
+        if len == 0 || self.trash_view.is_some() {
+            return;
+        }
+        let grid = self.view_mode == ViewMode::Grid;
+        let row = if grid { self.grid_columns().max(1) } else { 1 };
+        let target = match (self.cursor_index(), step) {
+            (None, Step::Last) => len - 1,
+            (None, _) => 0,
+            (Some(ix), Step::Up) if ix >= row => ix - row,
+            (Some(ix), Step::Up) => ix,
+            (Some(ix), Step::Down) if ix + row < len => ix + row,
+            (Some(ix), Step::Down) => ix,
+            (Some(ix), Step::Left) if grid => ix.saturating_sub(1),
+            (Some(ix), Step::Right) if grid => (ix + 1).min(len - 1),
+            (Some(ix), Step::Left | Step::Right) => ix,
+            (Some(_), Step::First) => 0,
+            (Some(_), Step::Last) => len - 1,
+            (Some(ix), Step::PageUp) => ix.saturating_sub(self.page_size()),
+            (Some(ix), Step::PageDown) => (ix + self.page_size()).min(len - 1),
+        };
+        self.move_cursor_to(target, extend, cx);
+        cx.notify();
+    }
+
+    fn move_cursor_to(&mut self, ix: usize, extend: bool, cx: &mut Context<Self>) {
+        let Some(item) = self.filtered_entries.get(ix).cloned() else {
+            return;
+        };
+        if extend {
+            let order = self.filtered_entries.iter().map(|e| e.path.as_str());
+            self.selection.select_range(order, &item.path, false);
+        } else {
+            self.selection.select_only(&item.path);
+        }
+        if item.opens_as_file() {
+            self.open_preview(&item, cx);
+        }
+        self.scroll_to_entry(ix);
+    }
+
+    fn scroll_to_entry(&self, ix: usize) {
+        match self.view_mode {
+            // Row 0 of the virtual list is the column header.
+            ViewMode::List => {
+                self.virtual_scroll_handle.scroll_to_item(ix + 1, ScrollStrategy::Top)
+            },
+            ViewMode::Grid => {
+                let Some(item) = self.grid_item_bounds.borrow().get(ix).copied() else {
+                    return;
+                };
+                let view = self.grid_scroll_handle.bounds();
+                let mut offset = self.grid_scroll_handle.offset();
+                if item.top() < view.top() {
+                    offset.y += view.top() - item.top();
+                } else if item.bottom() > view.bottom() {
+                    offset.y -= item.bottom() - view.bottom();
+                }
+                self.grid_scroll_handle.set_offset(offset);
+            },
+        }
+    }
+
+    fn on_open_entry(&mut self, _: &OpenEntry, window: &mut Window, cx: &mut Context<Self>) {
+        if self.trash_view.is_some() {
+            return;
+        }
+        if let Some(item) = self.selected_entry().cloned() {
+            self.activate_entry(item, window, cx);
+            cx.notify();
+        }
+    }
+
+    fn on_open_in_new_tab(&mut self, _: &OpenInNewTab, _: &mut Window, cx: &mut Context<Self>) {
+        if self.trash_view.is_some() {
+            return;
+        }
+        let folders: Vec<String> =
+            self.selected_entries().filter(|e| e.opens_as_dir()).map(|e| e.path.clone()).collect();
+        for path in folders {
+            cx.emit(ExplorerEvent::OpenInNewTab(path));
+        }
+    }
+
+    fn on_go_to_parent(&mut self, _: &GoToParent, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(parent) = Path::new(&self.cwd).parent() else {
+            return;
+        };
+        let child = self.cwd.clone();
+        self.change_dir(parent.to_string_lossy().to_string(), window, cx);
+        // Land on the folder we came from once it is listed.
+        self.selection.select_only(&child);
+        cx.notify();
+    }
+
+    /// Jumps to the first entry whose name starts with the letters typed so far. Returns whether
+    /// the key was used.
+    fn type_ahead(&mut self, event: &gpui::KeyDownEvent, cx: &mut Context<Self>) -> bool {
+        let modifiers = event.keystroke.modifiers;
+        if modifiers.control || modifiers.platform || modifiers.alt || self.trash_view.is_some() {
+            return false;
+        }
+        let Some(text) = event.keystroke.key_char.as_deref() else {
+            return false;
+        };
+        if text.chars().any(char::is_control) {
+            return false;
+        }
+
+        let now = Instant::now();
+        if self.type_ahead.last_key.is_none_or(|last| now - last > TYPE_AHEAD_TIMEOUT) {
+            self.type_ahead.query.clear();
+        }
+        self.type_ahead.last_key = Some(now);
+        if self.type_ahead.query.is_empty() && text.trim().is_empty() {
+            return false;
+        }
+        self.type_ahead.query.push_str(&text.to_lowercase());
+
+        // A repeated single letter cycles through the entries starting with it; a longer query
+        // keeps matching from the current entry.
+        let query = &self.type_ahead.query;
+        let mut chars = query.chars();
+        let repeated = chars.next().is_some_and(|first| chars.all(|c| c == first));
+        let (query, skip_current) = if repeated {
+            (&query[..query.chars().next().map_or(0, char::len_utf8)], true)
+        } else {
+            (query.as_str(), false)
+        };
+
+        let len = self.filtered_entries.len();
+        let start = self.cursor_index().map_or(0, |ix| if skip_current { ix + 1 } else { ix });
+        let found = (0..len)
+            .map(|offset| (start + offset) % len)
+            .find(|&ix| self.filtered_entries[ix].name.to_lowercase().starts_with(query));
+        if let Some(ix) = found {
+            self.move_cursor_to(ix, false, cx);
+            cx.notify();
+        }
+        true
+    }
+}
+
+// Trash: deleting to and restoring from the OS trash, and the trash view in the listing area.
+impl ExplorerPage {
+    fn on_trash_entries(&mut self, _: &TrashEntries, window: &mut Window, cx: &mut Context<Self>) {
+        if self.trash_view.is_some() {
+            return;
+        }
+        let paths = self.selected_paths();
+        if !paths.is_empty() {
+            self.run_trash_op(TrashOp::Trash(paths), window, cx);
+        }
+    }
+
+    fn on_delete_permanently(
+        &mut self,
+        _: &DeletePermanently,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let (op, what) = match &self.trash_view {
+            Some(_) => match self.selected_trash_item() {
+                Some(item) => {
+                    let what = format!("\"{}\"", item.name);
+                    (TrashOp::Purge(item), what)
+                },
+                None => return,
+            },
+            None => {
+                let paths = self.selected_paths();
+                let what = match paths.as_slice() {
+                    [] => return,
+                    [path] => format!("\"{}\"", path_name(&path.to_string_lossy())),
+                    paths => format!("{} items", paths.len()),
+                };
+                (TrashOp::Delete(paths), what)
+            },
+        };
+        self.confirm_permanent_delete(op, what, window, cx);
+    }
+
+    /// Permanent deletion always goes through this confirmation; there is no way back. `what` is
+    /// the quoted name of a single item, or the item count for several.
+    fn confirm_permanent_delete(
+        &mut self,
+        op: TrashOp,
+        what: String,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let message = format!("{what} will be deleted immediately. This cannot be undone.");
+        let this = cx.entity().downgrade();
+
+        window.open_dialog(cx, move |dialog, _window, _cx| {
+            let this = this.clone();
+            let op = op.clone();
+            dialog.title("Delete permanently?").child(
+                v_flex().gap_4().child(message.clone()).child(h_flex().justify_end().child(
+                    Button::new("delete-permanently-confirm").danger().label("Delete").on_click(
+                        move |_, window, cx| {
+                            window.close_dialog(cx);
+                            let op = op.clone();
+                            let _ = this.update(cx, |this, cx| this.run_trash_op(op, window, cx));
+                        },
+                    ),
+                )),
+            )
+        });
+    }
+
+    fn run_trash_op(&mut self, op: TrashOp, window: &mut Window, cx: &mut Context<Self>) {
+        // Permanent deletes cannot be undone, and restores happen from the trash view.
+        let journal_op = match &op {
+            TrashOp::Trash(paths) => Some(Operation::Trash { paths: paths.clone() }),
+            _ => None,
+        };
+        let work = cx.background_executor().spawn(async move {
+            match op {
+                TrashOp::Trash(paths) => trash_entries(&paths).map(|()| paths),
+                TrashOp::Delete(paths) => delete_permanently(&paths).map(|()| paths),
+                TrashOp::Restore(item) => {
+                    restore(std::slice::from_ref(&item)).map(|()| vec![item.original_path])
+                },
+                TrashOp::Purge(item) => purge(std::slice::from_ref(&item)).map(|()| Vec::new()),
+            }
+        });
+
+        cx.spawn_in(window, async move |this, cx| {
+            let result = work.await;
+            let _ = this.update_in(cx, |this, window, cx| {
+                match result {
+                    Ok(changed) => {
+                        this.refresh_paths(changed);
+                        if this.trash_view.is_some() {
+                            this.open_trash(cx);
+                        }
+                        if let Some(op) = journal_op {
+                            History::global(cx).update(cx, |history, cx| history.record(op, cx));
+                        }
+                    },
+                    Err(err) => window.push_notification(Notification::error(err.to_string()), cx),
+                }
+                cx.notify();
+            });
+        })
+        .detach();
+    }
+
+    /// Shows the trash in place of the listing and (re)loads its contents.
+    fn open_trash(&mut self, cx: &mut Context<Self>) {
+        let items = self.trash_view.take().map(|view| view.items).unwrap_or_default();
+        self.trash_view = Some(TrashView { items, selected: None, loading: true, error: None });
+
+        let work = cx.background_executor().spawn(async move { list_trash() });
+        self.trash_task = Some(cx.spawn(async move |this, cx| {
+            let result = work.await;
+            let _ = this.update(cx, |this, cx| {
+                if let Some(view) = this.trash_view.as_mut() {
+                    view.loading = false;
+                    match result {
+                        Ok(items) => view.items = items,
+                        Err(err) => view.error = Some(err.to_string()),
+                    }
+                }
+                cx.notify();
+            });
+        }));
+        cx.notify();
+    }
+
+    fn close_trash(&mut self) {
+        self.trash_view = None;
+        self.trash_task = None;
+    }
+
+    fn selected_trash_item(&self) -> Option<TrashedItem> {
+        let view = self.trash_view.as_ref()?;
+        view.selected.and_then(|ix| view.items.get(ix)).cloned()
+    }
+
+    fn restore_selected_trash(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if let Some(item) = self.selected_trash_item() {
+            self.run_trash_op(TrashOp::Restore(item), window, cx);
+        }
+    }
+
+    fn purge_selected_trash(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if let Some(item) = self.selected_trash_item() {
+            let what = format!("\"{}\"", item.name);
+            self.confirm_permanent_delete(TrashOp::Purge(item), what, window, cx);
+        }
+    }
+
+    fn render_trash_view(&self, cx: &mut Context<Self>) -> AnyElement {
+        use crate::ui::components::file_list::format_date;
+
+        let Some(view) = self.trash_view.as_ref() else {
+            return div().into_any_element();
+        };
+        let no_selection = view.selected.is_none();
+        let date_format = AppSettings::date_format(cx);
+
+        let toolbar = h_flex()
+            .gap_2()
+            .px(px(24.0))
+            .py(px(12.0))
+            .border_b_1()
+            .border_color(cx.theme().border)
+            .child(
+                div()
+                    .flex_1()
+                    .text_sm()
+                    .font_weight(gpui::FontWeight::SEMIBOLD)
+                    .child(format!("Trash ({})", view.items.len())),
+            )
+            .child(
+                Button::new("trash-refresh")
+                    .label("Refresh")
+                    .on_click(cx.listener(|this, _, _, cx| this.open_trash(cx))),
+            )
+            .child(Button::new("trash-restore").label("Restore").disabled(no_selection).on_click(
+                cx.listener(|this, _, window, cx| this.restore_selected_trash(window, cx)),
+            ))
+            .child(
+                Button::new("trash-purge")
+                    .danger()
+                    .label("Delete Permanently")
+                    .disabled(no_selection)
+                    .on_click(
+                        cx.listener(|this, _, window, cx| this.purge_selected_trash(window, cx)),
+                    ),
+            );
+
+        let status = if view.loading && view.items.is_empty() {
+            Some("Loading…".to_string())
+        } else if let Some(err) = &view.error {
+            Some(err.clone())
+        } else if view.items.is_empty() {
+            Some("Trash is empty".to_string())
         } else {
-            self.open_search(window, cx);
-        }
-    }
+            None
+        };
 
-    fn open_preview(&mut self, path: String) {
-        if let Ok(md) = std::fs::metadata(&path) {
-            if md.is_file() && md.len() <= 1024 * 1024 * 2 {
-                if let Ok(bytes) = std::fs::read(&path) {
-                    if let Ok(text) = String::from_utf8(bytes) {
-                        self.preview_path = Some(path);
-                        self.preview_text = Some(text);
-                        return;
-                    }
-                }
-            }
+        let mut rows = div().id("trash-items").flex_1().min_h(px(0.0)).overflow_y_scroll();
+        if let Some(status) = status {
+            rows = rows.child(
+                div().p(px(24.0)).text_sm().text_color(cx.theme().muted_foreground).child(status),
+            );
         }
-        self.preview_path = Some(path);
-        self.preview_text = Some("(Preview not available for this file)".into());
-    }
-
-    fn shortcuts(&self) -> Vec<(String, String)> {
-        let mut v = Vec::new();
-        let home = std::env::var("HOME").ok();
-        #[cfg(target_os = "windows")]
-        let home = home.or_else(|| std::env::var("USERPROFILE").ok());
-        if let Some(h) = home {
-            let p = |s: &str| std::path::Path::new(&h).join(s).to_string_lossy().to_string();
-            v.push(("Home".into(), h.clone()));
-            for (label, sub) in [
-                ("Desktop", "Desktop"),
-                ("Downloads", "Downloads"),
-                ("Documents", "Documents"),
-                ("Pictures", "Pictures"),
-            ] {
-                let path = p(sub);
-                if std::path::Path::new(&path).exists() {
-                    v.push((label.into(), path));
-                }
-            }
+        for (ix, item) in view.items.iter().enumerate() {
+            let icon = if item.kind == FileKind::Dir { IconName::Folder } else { IconName::File };
+            let location = item
+                .original_path
+                .parent()
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_default();
+            rows = rows.child(
+                gpui_component::list::ListItem::new(("trash-row", ix))
+                    .selected(view.selected == Some(ix))
+                    .h(px(32.0))
+                    .px(px(24.0))
+                    .on_click(cx.listener(move |this, _, _, cx| {
+                        if let Some(view) = this.trash_view.as_mut() {
+                            view.selected = Some(ix);
+                        }
+                        cx.notify();
+                    }))
+                    .child(
+                        h_flex()
+                            .w_full()
+                            .gap_3()
+                            .child(Icon::new(icon).size_4().text_color(cx.theme().primary))
+                            .child(
+                                div()
+                                    .flex_1()
+                                    .text_sm()
+                                    .overflow_hidden()
+                                    .text_ellipsis()
+                                    .whitespace_nowrap()
+                                    .child(item.name.clone()),
+                            )
+                            .child(
+                                div()
+                                    .w(px(320.0))
+                                    .text_sm()
+                                    .text_color(cx.theme().muted_foreground)
+                                    .overflow_hidden()
+                                    .text_ellipsis()
+                                    .whitespace_nowrap()
+                                    .child(location),
+                            )
+                            .child(
+                                div()
+                                    .w(px(180.0))
+                                    .text_sm()
+                                    .child(format_date(item.deleted.max(0) as u64, date_format)),
+                            ),
+                    ),
+            );
         }
-        v
+
+        div().size_full().flex().flex_col().child(toolbar).child(rows).into_any_element()
     }
 }
 
 impl Render for ExplorerPage {
     fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
-        self.ensure_loaded();
+        self.ensure_loaded(cx);
 
         div()
             .size_full()
@@ -441,15 +1974,78 @@ impl Render for ExplorerPage {
             .flex_col()
             .bg(cx.theme().background)
             .relative()
+            .key_context(CONTEXT)
             .track_focus(&self.focus_handle)
+            .on_action(cx.listener(Self::on_open_linked_path))
+            .on_action(cx.listener(Self::on_copy_entries))
+            .on_action(cx.listener(Self::on_cut_entries))
+            .on_action(cx.listener(Self::on_paste_entries))
+            .on_action(cx.listener(Self::on_paste_as_link))
+            .on_action(cx.listener(Self::on_duplicate_entries))
+            .on_action(cx.listener(Self::on_rename_entry))
+            .on_action(cx.listener(Self::on_new_folder))
+            .on_action(cx.listener(Self::on_new_file))
+            .on_action(cx.listener(Self::on_trash_entries))
+            .on_action(cx.listener(Self::on_delete_permanently))
+            .on_action(cx.listener(Self::on_select_all))
+            .on_action(cx.listener(Self::on_invert_selection))
+            .on_action(cx.listener(Self::on_clear_selection))
+            .on_action(cx.listener(|this, SortBy(key): &SortBy, _, cx| {
+                this.set_sort_key(*key);
+                cx.notify();
+            }))
+            .on_action(cx.listener(|this, _: &MoveUp, _, cx| this.move_cursor(Step::Up, false, cx)))
+            .on_action(
+                cx.listener(|this, _: &MoveDown, _, cx| this.move_cursor(Step::Down, false, cx)),
+            )
+            .on_action(
+                cx.listener(|this, _: &MoveLeft, _, cx| this.move_cursor(Step::Left, false, cx)),
+            )
+            .on_action(
+                cx.listener(|this, _: &MoveRight, _, cx| this.move_cursor(Step::Right, false, cx)),
+            )
+            .on_action(
+                cx.listener(|this, _: &SelectUp, _, cx| this.move_cursor(Step::Up, true, cx)),
+            )
+            .on_action(
+                cx.listener(|this, _: &SelectDown, _, cx| this.move_cursor(Step::Down, true, cx)),
+            )
+            .on_action(
+                cx.listener(|this, _: &SelectLeft, _, cx| this.move_cursor(Step::Left, true, cx)),
+            )
+            .on_action(
+                cx.listener(|this, _: &SelectRight, _, cx| this.move_cursor(Step::Right, true, cx)),
+            )
+            .on_action(
+                cx.listener(|this, _: &MoveToFirst, _, cx| {
+                    this.move_cursor(Step::First, false, cx)
+                }),
+            )
+            .on_action(
+                cx.listener(|this, _: &MoveToLast, _, cx| this.move_cursor(Step::Last, false, cx)),
+            )
+            .on_action(
+                cx.listener(|this, _: &MovePageUp, _, cx| {
+                    this.move_cursor(Step::PageUp, false, cx)
+                }),
+            )
+            .on_action(cx.listener(|this, _: &MovePageDown, _, cx| {
+                this.move_cursor(Step::PageDown, false, cx)
+            }))
+            .on_action(cx.listener(Self::on_open_entry))
+            .on_action(cx.listener(Self::on_open_in_new_tab))
+            .on_action(cx.listener(Self::on_go_to_parent))
+            .on_action(cx.listener(|this, _: &FindInFolder, window, cx| {
+                this.toggle_search(window, cx);
+            }))
+            .on_action(cx.listener(|this, _: &CloseSearch, window, cx| {
+                this.close_search(window, cx);
+            }))
+            .on_action(cx.listener(|this, _: &FocusListing, window, cx| {
+                this.focus_handle.focus(window, cx);
+            }))
             .on_key_down(cx.listener(|this, event: &gpui::KeyDownEvent, window, cx| {
-                let key_lc = event.keystroke.key.to_lowercase();
-                let is_f = key_lc == "f" || event.keystroke.key == "KeyF";
-                if (is_f
-                    && (event.keystroke.modifiers.platform || event.keystroke.modifiers.control))
-                    || (key_lc == "escape" && this.search_visible)
-                {
-                    this.toggle_search(window, cx);
+                if this.focus_handle.is_focused(window) && this.type_ahead(event, cx) {
                     cx.stop_propagation();
                 }
             }))
@@ -458,6 +2054,10 @@ impl Render for ExplorerPage {
                     this.update_column_resize(event.position);
                     cx.notify();
                 }
+                if this.rubber_band.is_some() {
+                    this.update_rubber_band(event.position);
+                    cx.notify();
+                }
             }))
             .on_mouse_up(
                 gpui::MouseButton::Left,
@@ -466,15 +2066,19 @@ impl Render for ExplorerPage {
                         this.stop_column_resize();
                         cx.notify();
                     }
+                    if this.rubber_band.take().is_some() {
+                        cx.notify();
+                    }
                 }),
             )
             .child(self.render_header(window, cx))
             .child(
                 div().flex().flex_row().flex_grow().min_h(px(0.0)).child(
                     h_resizable("file-explorer")
+                        .with_state(&self.panel_state)
                         .child(
                             resizable_panel()
-                                .size(px(180.0))
+                                .size(px(self.sidebar_width))
                                 .size_range(px(180.0)..px(360.0))
                                 .child(
                                     div()
@@ -498,7 +2102,7 @@ impl Render for ExplorerPage {
                         )
                         .child(
                             resizable_panel()
-                                .size(px(240.0))
+                                .size(px(self.preview_width))
                                 .size_range(px(240.0)..px(600.0))
                                 .child(
                                     div()
@@ -506,6 +2110,13 @@ impl Render for ExplorerPage {
                                         .overflow_hidden()
                                         .border_l_1()
                                         .border_color(cx.theme().border)
+                                        .key_context(PREVIEW_CONTEXT)
+                                        .track_focus(&self.preview_focus_handle)
+                                        .capture_any_mouse_down(cx.listener(
+                                            |this, _, window, cx| {
+                                                this.preview_focus_handle.focus(window, cx);
+                                            },
+                                        ))
                                         .child(self.render_preview(cx)),
                                 ),
                         )
@@ -624,13 +2235,38 @@ impl ExplorerPage {
                     .gap_2()
                     .flex_shrink_0()
                     .child(
-                        div()
-                            .text_xs()
-                            .text_color(cx.theme().primary)
-                            .whitespace_nowrap()
-                            .child(format!("{} items", self.filtered_entries.len())),
+                        div().text_xs().text_color(cx.theme().primary).whitespace_nowrap().child(
+                            if self.loading {
+                                format!("{} items…", self.filtered_entries.len())
+                            } else {
+                                format!("{} items", self.filtered_entries.len())
+                            },
+                        ),
                     )
                     .child(self.render_view_mode_toggle(cx))
+                    .child({
+                        let show_hidden = self.show_hidden;
+                        gpui_component::list::ListItem::new("hidden-toggle")
+                            .px(px(8.0))
+                            .py(px(6.0))
+                            .rounded(px(6.0))
+                            .on_click(cx.listener(move |view, _, _, cx| {
+                                view.set_show_hidden(!show_hidden, cx);
+                            }))
+                            .child(
+                                Icon::new(if show_hidden {
+                                    IconName::Eye
+                                } else {
+                                    IconName::EyeOff
+                                })
+                                .size_4()
+                                .text_color(if show_hidden {
+                                    cx.theme().primary
+                                } else {
+                                    cx.theme().muted
+                                }),
+                            )
+                    })
                     .child(
                         gpui_component::list::ListItem::new("search-toggle")
                             .px(px(8.0))
@@ -724,8 +2360,7 @@ impl ExplorerPage {
                     .px(px(8.0))
                     .child(self.sidebar_item(IconName::Folder, "Home", true, cx))
                     .child(self.sidebar_item(IconName::Star, "Favorites", false, cx))
-                    .child(self.sidebar_item(IconName::File, "Recent", false, cx))
-                    .child(self.sidebar_item(IconName::Folder, "Trash", false, cx)),
+                    .child(self.sidebar_item(IconName::File, "Recent", false, cx)),
             )
             .child(
                 div()
@@ -804,15 +2439,90 @@ impl ExplorerPage {
             );
         }
 
-        shortcuts_el
+        shortcuts_el.child(
+            gpui_component::list::ListItem::new("shortcut-trash")
+                .selected(self.trash_view.is_some())
+                .on_click(cx.listener(|this, _, _, cx| this.open_trash(cx)))
+                .child(
+                    div()
+                        .flex()
+                        .items_center()
+                        .gap_2()
+                        .child(Icon::new(IconName::Delete).size_4().text_color(cx.theme().primary))
+                        .child(div().text_sm().text_color(cx.theme().primary).child("Trash")),
+                ),
+        )
     }
 
     fn render_listing(&mut self, window: &mut Window, cx: &mut Context<Self>) -> AnyElement {
+        if self.trash_view.is_some() {
+            return self.render_trash_view(cx);
+        }
         self.ensure_list_initialized(window, cx);
-        match self.view_mode {
+        let body = match self.view_mode {
             ViewMode::List => self.render_list_view(cx),
             ViewMode::Grid => self.render_grid_view(window, cx),
-        }
+        };
+
+        let explorer = cx.entity();
+        let focus_handle = self.focus_handle.clone();
+        let listing_bounds = self.listing_bounds.clone();
+        div()
+            .id("explorer-listing")
+            .size_full()
+            .flex()
+            .flex_col()
+            .min_h(px(0.0))
+            // Clicking into the listing gives it the keyboard, ahead of the items' own handlers.
+            .capture_any_mouse_down(cx.listener(|this, _, window, cx| {
+                this.focus_handle.focus(window, cx);
+            }))
+            .on_children_prepainted(move |children, _window, _cx| {
+                if let Some(bounds) = children.first() {
+                    listing_bounds.set(*bounds);
+                }
+            })
+            .on_scroll_wheel(cx.listener(|_, _, _, cx| cx.emit(ExplorerEvent::Scrolled)))
+            .child(body)
+            .context_menu(move |menu, _window, cx| {
+                let this = explorer.read(cx);
+                let no_selection = this.selected_count() == 0;
+                let no_entries = this.filtered_entries.is_empty();
+                let no_clipboard = this.clipboard.is_none();
+                let no_folder = !this.selected_entries().any(FileEntryDto::opens_as_dir);
+                let sort_key = this.sort_key;
+                let menu = menu
+                    .action_context(focus_handle.clone())
+                    .menu_with_disabled("Open in New Tab", Box::new(OpenInNewTab), no_folder)
+                    .separator()
+                    .menu_with_disabled("Copy", Box::new(CopyEntries), no_selection)
+                    .menu_with_disabled("Cut", Box::new(CutEntries), no_selection)
+                    .menu_with_disabled("Paste", Box::new(PasteEntries), no_clipboard)
+                    .menu_with_disabled("Paste as Link", Box::new(PasteAsLink), no_clipboard)
+                    .separator()
+                    .menu_with_disabled("Duplicate", Box::new(DuplicateEntries), no_selection)
+                    .menu_with_disabled("Rename…", Box::new(RenameEntry), no_selection)
+                    .separator()
+                    .menu_with_disabled("Move to Trash", Box::new(TrashEntries), no_selection)
+                    .menu_with_disabled(
+                        "Delete Permanently…",
+                        Box::new(DeletePermanently),
+                        no_selection,
+                    )
+                    .separator()
+                    .menu_with_disabled("Select All", Box::new(SelectAll), no_entries)
+                    .menu_with_disabled("Invert Selection", Box::new(InvertSelection), no_entries)
+                    .menu_with_disabled("Select None", Box::new(ClearSelection), no_selection)
+                    .separator()
+                    .menu("New Folder…", Box::new(NewFolder))
+                    .menu("New File…", Box::new(NewFile))
+                    .separator()
+                    .label("Sort By");
+                SORT_KEYS.into_iter().fold(menu, |menu, (label, key)| {
+                    menu.menu_with_check(label, sort_key == key, Box::new(SortBy(key)))
+                })
+            })
+            .into_any_element()
     }
 
     fn render_list_view(&mut self, cx: &mut Context<Self>) -> AnyElement {
@@ -830,20 +2540,48 @@ impl ExplorerPage {
 
     fn render_grid_view(&mut self, window: &mut Window, cx: &mut Context<Self>) -> AnyElement {
         let items = self.filtered_entries.clone();
-        let mut grid = div().flex().flex_wrap().gap_4().items_start().min_h(px(0.0));
+        let bounds = self.grid_item_bounds.clone();
+        let mut grid =
+            div().flex().flex_wrap().gap_4().items_start().min_h(px(0.0)).on_children_prepainted(
+                move |children, _window, _cx| {
+                    *bounds.borrow_mut() = children;
+                },
+            );
 
         for (ix, item) in items.into_iter().enumerate() {
-            let selected = self.selected_index == Some(ix);
+            let selected = self.selection.contains(&item.path);
             grid = grid.child(self.render_grid_item(item, ix, selected, window, cx));
         }
 
+        let band = self.rubber_band.as_ref().map(RubberBand::bounds);
         div()
             .id("grid-scroll")
             .flex_1()
             .overflow_scroll()
+            .track_scroll(&self.grid_scroll_handle)
             .px(px(24.0))
             .py(px(16.0))
+            // Items stop propagation, so this only sees presses on the empty space around them.
+            .on_mouse_down(
+                gpui::MouseButton::Left,
+                cx.listener(|this, event: &gpui::MouseDownEvent, _window, cx| {
+                    this.start_rubber_band(event.position, event.modifiers);
+                    cx.notify();
+                }),
+            )
             .child(grid)
+            .when_some(band, |this, band| {
+                this.child(deferred(
+                    anchored().position(band.origin).child(
+                        div()
+                            .w(band.size.width)
+                            .h(band.size.height)
+                            .border_1()
+                            .border_color(cx.theme().accent)
+                            .bg(cx.theme().accent.opacity(0.15)),
+                    ),
+                ))
+            })
             .into_any_element()
     }
 
@@ -855,23 +2593,18 @@ impl ExplorerPage {
         _window: &mut Window,
         cx: &mut Context<Self>,
     ) -> AnyElement {
-        use crate::ui::components::file_list::{format_date, get_file_type, human_bytes};
+        use crate::ui::components::file_list::{format_date, get_file_type, kind_icon};
 
-        let icon_name = match item.kind.as_str() {
-            "dir" => IconName::Folder,
-            _ => IconName::File,
-        };
+        let icon_name = kind_icon(&item);
+        let thumbnail = self.thumbnails.get(&item.path).cloned();
 
         let name = truncate_middle(&item.name, 28);
-        let file_type = get_file_type(&item.name, &item.kind);
-        let size_text = match item.kind.as_str() {
-            "file" => human_bytes(item.size),
-            "dir" => file_type.clone(),
-            _ => file_type.clone(),
-        };
-        let modified_text = format_date(&item.modified);
+        let file_type = get_file_type(&item.name, item.kind);
+        let size_text = self.size_text(&item);
+        let modified_text = format_date(item.modified, AppSettings::date_format(cx));
         let activation_item = item.clone();
         let preview_item = item.clone();
+        let path = item.path.clone();
 
         let bg_color = if selected { cx.theme().accordion_hover } else { cx.theme().background };
 
@@ -894,22 +2627,43 @@ impl ExplorerPage {
             .on_mouse_down(
                 gpui::MouseButton::Left,
                 cx.listener(move |this, event: &gpui::MouseDownEvent, window, cx| {
+                    cx.stop_propagation();
                     this.record_click(ix, event.click_count);
-                    this.selected_index = Some(ix);
-                    if preview_item.kind == "file" {
-                        this.open_preview(preview_item.path.clone());
-                    }
+                    this.click_entry(&preview_item, event.modifiers, cx);
                     if event.click_count >= 2 {
                         this.activate_entry(activation_item.clone(), window, cx);
                     }
+                    cx.notify();
+                }),
+            )
+            .on_mouse_down(
+                gpui::MouseButton::Right,
+                cx.listener(move |this, _, _, cx| {
+                    this.select_for_context_menu(&path);
+                    cx.notify();
                 }),
             )
-            .child(Icon::new(icon_name).size_6().text_color(cx.theme().primary))
+            .when(item.opens_as_dir(), |this| {
+                let path = item.path.clone();
+                this.on_mouse_down(
+                    gpui::MouseButton::Middle,
+                    cx.listener(move |_, _, _, cx| {
+                        cx.stop_propagation();
+                        cx.emit(ExplorerEvent::OpenInNewTab(path.clone()));
+                    }),
+                )
+            })
+            .map(|this| match &thumbnail {
+                Some(thumbnail) => this.child(
+                    img(thumbnail.clone()).w_full().h(px(96.0)).object_fit(ObjectFit::Contain),
+                ),
+                None => this.child(Icon::new(icon_name).size_6().text_color(cx.theme().primary)),
+            })
             .child(
                 div()
                     .text_sm()
                     .font_weight(gpui::FontWeight::MEDIUM)
-                    .text_color(cx.theme().primary)
+                    .text_color(self.compare_color(&item.name, cx).unwrap_or(cx.theme().primary))
                     .overflow_hidden()
                     .text_ellipsis()
                     .whitespace_nowrap()
@@ -936,6 +2690,7 @@ impl ExplorerPage {
         let match_count = self.filtered_entries.len();
 
         div()
+            .key_context(SEARCH_CONTEXT)
             .absolute()
             .top(px(12.0))
             .right(px(24.0))
@@ -1133,14 +2888,11 @@ impl ExplorerPage {
     ) -> impl IntoElement {
         use gpui_component::list::ListItem;
 
-        use crate::ui::components::file_list::{format_date, get_file_type, human_bytes};
+        use crate::ui::components::file_list::{format_date, get_file_type, kind_icon};
 
-        let icon_name = match item.kind.as_str() {
-            "dir" => IconName::Folder,
-            _ => IconName::File,
-        };
+        let icon_name = kind_icon(item);
 
-        let file_type = get_file_type(&item.name, &item.kind);
+        let file_type = get_file_type(&item.name, item.kind);
 
         let max_chars = (self.col_name_width / 8.0) as usize;
         let display_name = truncate_middle(&item.name, max_chars.max(20));
@@ -1148,25 +2900,40 @@ impl ExplorerPage {
         let total_width = self.total_table_width();
         let item_for_preview = item.clone();
         let item_for_activate = item.clone();
+        let path = item.path.clone();
 
         ListItem::new(("file-row", ix))
             .w(px(total_width))
             .h(px(32.0))
             .px(px(24.0))
+            .selected(self.selection.contains(&item.path))
+            .on_mouse_down(
+                gpui::MouseButton::Right,
+                cx.listener(move |this, _, _, cx| {
+                    this.select_for_context_menu(&path);
+                    cx.notify();
+                }),
+            )
+            .when(item.opens_as_dir(), |this| {
+                let path = item.path.clone();
+                this.on_mouse_down(
+                    gpui::MouseButton::Middle,
+                    cx.listener(move |_, _, _, cx| {
+                        cx.emit(ExplorerEvent::OpenInNewTab(path.clone()));
+                    }),
+                )
+            })
             .on_click(cx.listener(move |this, event: &gpui::ClickEvent, window, cx| {
                 if let gpui::ClickEvent::Mouse(mouse) = event {
                     if mouse.up.button == gpui::MouseButton::Left {
                         this.record_click(ix, mouse.up.click_count);
-                        this.selected_index = Some(ix);
-                        if item_for_preview.kind == "file" {
-                            this.open_preview(item_for_preview.path.clone());
-                        }
+                        this.click_entry(&item_for_preview, mouse.up.modifiers, cx);
                         if mouse.up.click_count >= 2 {
                             this.activate_entry(item_for_activate.clone(), window, cx);
                         }
                     }
                 } else if let gpui::ClickEvent::Keyboard(_) = event {
-                    this.selected_index = Some(ix);
+                    this.selection.select_only(&item_for_activate.path);
                     this.activate_entry(item_for_activate.clone(), window, cx);
                 }
             }))
@@ -1191,6 +2958,9 @@ impl ExplorerPage {
                                     .overflow_hidden()
                                     .text_ellipsis()
                                     .whitespace_nowrap()
+                                    .when_some(self.compare_color(&item.name, cx), |this, color| {
+                                        this.text_color(color)
+                                    })
                                     .child(display_name),
                             ),
                     )
@@ -1204,13 +2974,13 @@ impl ExplorerPage {
                             .whitespace_nowrap()
                             .child(file_type),
                     )
-                    .child(div().w(px(self.col_size_width)).flex_shrink_0().text_sm().child(
-                        match item.kind.as_str() {
-                            "file" => human_bytes(item.size),
-                            "dir" => "-".to_string(),
-                            other => other.to_string(),
-                        },
-                    ))
+                    .child(
+                        div()
+                            .w(px(self.col_size_width))
+                            .flex_shrink_0()
+                            .text_sm()
+                            .child(self.size_text(item)),
+                    )
                     .child(
                         div()
                             .w(px(self.col_modified_width))
@@ -1219,7 +2989,7 @@ impl ExplorerPage {
                             .overflow_hidden()
                             .text_ellipsis()
                             .whitespace_nowrap()
-                            .child(format_date(&item.modified)),
+                            .child(format_date(item.modified, AppSettings::date_format(cx))),
                     )
                     .child(
                         div()
@@ -1240,7 +3010,10 @@ impl ExplorerPage {
         flex: bool,
         cx: &mut Context<Self>,
     ) -> gpui::Div {
-        let is_active = self.sort_key == key;
+        // The Name column also stands for the natural name order.
+        let is_active =
+            self.sort_key == key || (key == SortKey::Name && self.sort_key == SortKey::Natural);
+        let click_key = if is_active { self.sort_key } else { key };
         let label_str = label.to_string();
         let sort_icon =
             if is_active { Some(if self.sort_asc { "↑" } else { "↓" }) } else { None };
@@ -1254,7 +3027,7 @@ impl ExplorerPage {
         wrapper.child(
             gpui_component::list::ListItem::new(("sort-header", key_idx))
                 .on_click(cx.listener(move |this, _, _, _| {
-                    this.set_sort_key(key);
+                    this.set_sort_key(click_key);
                 }))
                 .child(
                     div()
@@ -1291,8 +3064,30 @@ impl ExplorerPage {
             .map(|p| path_name(p))
             .unwrap_or_else(|| "Preview".to_string());
 
-        let body: String =
-            self.preview_text.clone().unwrap_or_else(|| "Select a file to see a preview".into());
+        let subtitle = match &self.preview {
+            Some(PreviewContent::Code(code)) => {
+                let code = code.read(cx);
+                let language = code.language().unwrap_or("Plain text");
+                Some(format!("{} · {} lines", language, code.line_count()))
+            },
+            Some(PreviewContent::Image(image)) => image.read(cx).summary(),
+            Some(PreviewContent::Hex(hex)) => Some(hex.read(cx).file_type().to_string()),
+            Some(PreviewContent::Text(text)) => text.read(cx).summary(),
+            _ => None,
+        };
+        let body = match &self.preview {
+            Some(PreviewContent::Code(code)) => code.clone().into_any_element(),
+            Some(PreviewContent::Markdown(markdown)) => markdown.clone().into_any_element(),
+            Some(PreviewContent::Image(image)) => image.clone().into_any_element(),
+            Some(PreviewContent::Hex(hex)) => hex.clone().into_any_element(),
+            Some(PreviewContent::Text(text)) => text.clone().into_any_element(),
+            Some(PreviewContent::Message(message)) => {
+                self.render_preview_message(message.clone(), cx).into_any_element()
+            },
+            None => self
+                .render_preview_message("Select a file to see a preview".into(), cx)
+                .into_any_element(),
+        };
 
         div()
             .size_full()
@@ -1300,26 +3095,152 @@ impl ExplorerPage {
             .flex_col()
             .bg(cx.theme().background)
             .child(
-                div().px(px(16.0)).py(px(12.0)).border_b_1().border_color(cx.theme().border).child(
-                    div()
-                        .text_sm()
-                        .font_weight(gpui::FontWeight::SEMIBOLD)
-                        .text_color(cx.theme().primary)
-                        .child(title),
-                ),
+                div()
+                    .px(px(16.0))
+                    .py(px(12.0))
+                    .border_b_1()
+                    .border_color(cx.theme().border)
+                    .child(
+                        div()
+                            .text_sm()
+                            .font_weight(gpui::FontWeight::SEMIBOLD)
+                            .text_color(cx.theme().primary)
+                            .child(title),
+                    )
+                    .children(subtitle.map(|subtitle| {
+                        div().text_xs().text_color(cx.theme().muted_foreground).child(subtitle)
+                    })),
             )
-            .child(div().flex_1().overflow_hidden().px(px(16.0)).py(px(16.0)).child(
-                div().text_sm().text_color(cx.theme().foreground).line_height(px(20.0)).child(body),
-            ))
+            .when_some(self.preview_entry.clone(), |this, entry| {
+                this.child(self.render_preview_details(&entry, cx))
+            })
+            .child(
+                div()
+                    .flex_1()
+                    .min_h(px(0.0))
+                    .overflow_hidden()
+                    .px(px(16.0))
+                    .py(px(16.0))
+                    .child(body),
+            )
+    }
+
+    fn render_preview_message(&self, message: SharedString, cx: &App) -> impl IntoElement {
+        div().text_sm().text_color(cx.theme().foreground).line_height(px(20.0)).child(message)
+    }
+
+    fn render_preview_details(&self, entry: &FileEntryDto, cx: &App) -> impl IntoElement {
+        use crate::ui::components::file_list::{format_timestamp, get_file_type};
+
+        let date_format = AppSettings::date_format(cx);
+        let mut rows: Vec<(&'static str, String)> =
+            vec![("Kind", get_file_type(&entry.name, entry.kind)), ("Size", self.size_text(entry))];
+        if let Some(link) = &entry.link {
+            let target = if link.is_broken() {
+                format!("{} (broken)", link.path)
+            } else {
+                link.path.clone()
+            };
+            rows.push(("Target", target));
+        }
+        if let Some(md) = &entry.metadata {
+            rows.push(("Permissions", format!("{} ({:04o})", md.permissions, md.mode & 0o7777)));
+            if let Some(uid) = md.uid {
+                rows.push(("Owner", md.owner.clone().unwrap_or_else(|| uid.to_string())));
+            }
+            if let Some(gid) = md.gid {
+                rows.push(("Group", md.group.clone().unwrap_or_else(|| gid.to_string())));
+            }
+            for (label, ts) in [
+                ("Created", md.created),
+                ("Modified", md.modified),
+                ("Accessed", md.accessed),
+                ("Changed", md.changed),
+            ] {
+                if let Some(ts) = ts {
+                    rows.push((label, format_timestamp(&ts, date_format)));
+                }
+            }
+            if let Some(nlink) = md.nlink {
+                rows.push(("Links", nlink.to_string()));
+            }
+            if let (Some(ino), Some(dev)) = (md.ino, md.dev) {
+                rows.push(("Inode", format!("{ino} on device {dev}")));
+            }
+        }
+        if entry.hidden {
+            rows.push(("Hidden", "Yes".into()));
+        }
+
+        div()
+            .flex()
+            .flex_col()
+            .gap_1()
+            .px(px(16.0))
+            .py(px(12.0))
+            .border_b_1()
+            .border_color(cx.theme().border)
+            .children(rows.into_iter().map(|(label, value)| {
+                div()
+                    .flex()
+                    .gap_2()
+                    .text_xs()
+                    .child(
+                        div().w(px(80.0)).flex_shrink_0().text_color(cx.theme().muted).child(label),
+                    )
+                    .child(
+                        div()
+                            .flex_1()
+                            .overflow_hidden()
+                            .text_ellipsis()
+                            .whitespace_nowrap()
+                            .text_color(cx.theme().foreground)
+                            .child(value),
+                    )
+            }))
     }
 
     // Public getters for footer data
+    /// Number of selected entries that are currently visible.
     pub fn selected_count(&self) -> usize {
-        if self.selected_index.is_some() {
-            1
-        } else {
-            0
-        }
+        self.selected_entries().count()
+    }
+
+    /// The visible selected entries in listing order. Entries hidden by the filter stay selected
+    /// but are left out, so nothing is acted on that cannot be seen.
+    pub fn selected_entries(&self) -> impl Iterator<Item = &FileEntryDto> {
+        self.filtered_entries.iter().filter(|e| self.selection.contains(&e.path))
+    }
+
+    /// The entry single-entry actions apply to: the lead of the selection, or else the first
+    /// selected entry.
+    pub fn selected_entry(&self) -> Option<&FileEntryDto> {
+        let lead = self.selection.lead();
+        self.filtered_entries
+            .iter()
+            .find(|e| lead == Some(e.path.as_str()) && self.selection.contains(&e.path))
+            .or_else(|| self.selected_entries().next())
+    }
+
+    /// Combined size of the visible selected entries, with computed folder sizes.
+    pub fn selected_size(&self) -> u64 {
+        self.selected_entries()
+            .map(|e| match self.dir_sizes.get(&e.path) {
+                Some((bytes, _)) => *bytes,
+                None => e.size,
+            })
+            .sum()
+    }
+
+    /// Combined size of the listed files plus every folder size computed so far.
+    pub fn total_size(&self) -> u64 {
+        self.entries
+            .iter()
+            .map(|e| match self.dir_sizes.get(&e.path) {
+                Some((bytes, _)) => *bytes,
+                None => e.size,
+            })
+            .sum()
     }
 
     pub fn total_count(&self) -> usize {
@@ -1329,6 +3250,70 @@ impl ExplorerPage {
     pub fn current_path(&self) -> &str {
         &self.cwd
     }
+
+    /// Navigates to `path` as if the user had opened it, so it goes into the history.
+    pub fn open_folder(&mut self, path: String, window: &mut Window, cx: &mut Context<Self>) {
+        self.change_dir(path, window, cx);
+    }
+
+    /// Every listed entry, before the search filter.
+    pub fn entries(&self) -> &[FileEntryDto] {
+        &self.entries
+    }
+
+    /// Changes whenever [`entries`](Self::entries) does.
+    pub fn listing_generation(&self) -> u64 {
+        self.listing_generation
+    }
+
+    /// Marks the entries that differ from the other side of a comparison, by name.
+    pub fn set_compare_marks(
+        &mut self,
+        marks: HashMap<String, Difference>,
+        cx: &mut Context<Self>,
+    ) {
+        if self.compare_marks != marks {
+            self.compare_marks = marks;
+            cx.notify();
+        }
+    }
+
+    fn compare_color(&self, name: &str, cx: &App) -> Option<Hsla> {
+        match self.compare_marks.get(name)? {
+            Difference::LeftOnly | Difference::RightOnly => Some(cx.theme().success),
+            Difference::Different => Some(cx.theme().warning),
+        }
+    }
+
+    pub fn scroll_offset(&self) -> Point<Pixels> {
+        match self.view_mode {
+            ViewMode::List => self.virtual_scroll_handle.offset(),
+            ViewMode::Grid => self.grid_scroll_handle.offset(),
+        }
+    }
+
+    pub fn set_scroll_offset(&mut self, offset: Point<Pixels>, cx: &mut Context<Self>) {
+        match self.view_mode {
+            ViewMode::List => self.virtual_scroll_handle.set_offset(offset),
+            ViewMode::Grid => self.grid_scroll_handle.set_offset(offset),
+        }
+        cx.notify();
+    }
+
+    /// Copies or moves the selected entries into `dest`, as pasting them there would.
+    pub fn transfer_selection(
+        &mut self,
+        dest: PathBuf,
+        kind: JobKind,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self.trash_view.is_some() {
+            return;
+        }
+        let sources = self.selected_paths();
+        self.start_transfer(sources, dest, kind, window, cx);
+    }
 }
 
 impl crate::pages::Page for ExplorerPage {
@@ -1337,6 +3322,17 @@ impl crate::pages::Page for ExplorerPage {
     }
 }
 
+/// Shows an error in the active window, for background work that has no window at hand.
+fn show_error(message: String, cx: &mut App) {
+    if let Some(window) = cx.active_window() {
+        cx.defer(move |cx| {
+            let _ = window.update(cx, |_, window, cx| {
+                window.push_notification(Notification::error(message), cx);
+            });
+        });
+    }
+}
+
 fn path_name(p: &str) -> String {
     std::path::Path::new(p)
         .file_name()
@@ -1390,15 +3386,3 @@ fn truncate_middle(text: &str, max_len: usize) -> String {
         format!("{}...{}", start_part, end_part)
     }
 }
-
-fn get_extension(name: &str, kind: &str) -> String {
-    match kind {
-        "dir" => "0_dir".to_string(),
-        "file" => std::path::Path::new(name)
-            .extension()
-            .and_then(|e| e.to_str())
-            .map(|s| s.to_lowercase())
-            .unwrap_or_else(|| "zzz_noext".to_string()),
-        other => other.to_string(),
-    }
-}
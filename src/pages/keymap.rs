@@ -1,41 +1,317 @@
-use gpui::{div, prelude::*, px, AnyElement, Context, Render, Window};
-use gpui_component::ActiveTheme;
-pub struct KeymapPage;
+use gpui::{
+    div, prelude::*, px, AnyElement, Context, Entity, FocusHandle, IntoElement, KeyDownEvent,
+    Render, SharedString, Window,
+};
+use gpui_component::{
+    button::{Button, ButtonVariants as _},
+    h_flex,
+    input::{Input, InputEvent, InputState},
+    notification::Notification,
+    v_flex, ActiveTheme, Disableable as _, Sizable as _, WindowExt as _,
+};
 
-impl Default for KeymapPage {
-    fn default() -> Self {
-        Self::new()
-    }
+use crate::ui::keymap::{BindingInfo, BindingSource, Keymap, CONTEXTS};
+
+/// Longest chord that can be recorded.
+const MAX_CHORD_LEN: usize = 4;
+
+/// A chord being recorded for an action.
+struct Recording {
+    action: &'static str,
+    keystrokes: Vec<String>,
+    /// Index into [`CONTEXTS`].
+    context: usize,
+}
+
+/// Every registered action with its key bindings, and recording of new ones into the user keymap.
+pub struct KeymapPage {
+    search: Entity<InputState>,
+    focus_handle: FocusHandle,
+    recording: Option<Recording>,
 }
 
 impl KeymapPage {
-    pub fn new() -> Self {
-        Self
+    pub fn new(window: &mut Window, cx: &mut Context<Self>) -> Self {
+        let search = cx.new(|cx| InputState::new(window, cx).placeholder("Search actions or keys"));
+        cx.subscribe(&search, |_, _, _: &InputEvent, cx| cx.notify()).detach();
+        cx.observe(&Keymap::global(cx), |_, _, cx| cx.notify()).detach();
+
+        Self { search, focus_handle: cx.focus_handle(), recording: None }
+    }
+
+    fn start_recording(
+        &mut self,
+        action: &'static str,
+        bindings: &[BindingInfo],
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        // Suggest the context the action is already bound in.
+        let context = bindings
+            .iter()
+            .find_map(|b| CONTEXTS.iter().position(|(name, _)| b.context.as_ref() == *name))
+            .unwrap_or(0);
+        self.recording = Some(Recording { action, keystrokes: Vec::new(), context });
+        Keymap::global(cx).update(cx, |keymap, cx| keymap.set_recording(true, cx));
+        self.focus_handle.focus(window, cx);
+        cx.notify();
+    }
+
+    /// Drops an unfinished recording and restores the key bindings.
+    pub fn stop_recording(&mut self, cx: &mut Context<Self>) {
+        if self.recording.take().is_some() {
+            Keymap::global(cx).update(cx, |keymap, cx| keymap.set_recording(false, cx));
+            cx.notify();
+        }
+    }
+
+    fn save_recording(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(recording) = self.recording.take() else {
+            return;
+        };
+        let keystrokes = recording.keystrokes.join(" ");
+        let (context, _) = CONTEXTS[recording.context];
+        let result = Keymap::global(cx).update(cx, |keymap, cx| {
+            keymap.set_recording(false, cx);
+            keymap.add_binding(&keystrokes, context, recording.action, cx)
+        });
+        if let Err(err) = result {
+            window.push_notification(
+                Notification::error(format!("Can't save the binding: {err:#}")),
+                cx,
+            );
+        }
+        cx.notify();
+    }
+
+    fn on_key_down(&mut self, event: &KeyDownEvent, _window: &mut Window, cx: &mut Context<Self>) {
+        let Some(recording) = self.recording.as_mut() else {
+            return;
+        };
+        if recording.keystrokes.len() == MAX_CHORD_LEN {
+            recording.keystrokes.clear();
+        }
+        recording.keystrokes.push(event.keystroke.unparse());
+        cx.stop_propagation();
+        cx.notify();
+    }
+
+    fn render_recording(
+        &self,
+        recording: &Recording,
+        bindings: &[BindingInfo],
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let keystrokes = recording.keystrokes.join(" ");
+        let (context, _) = CONTEXTS[recording.context];
+        let current = bindings.iter().find(|b| {
+            !keystrokes.is_empty()
+                && b.keystrokes.as_ref() == keystrokes
+                && b.context.as_ref() == context
+                && b.action != Some(recording.action)
+                && !b.overridden
+        });
+
+        let contexts =
+            CONTEXTS.iter().enumerate().fold(h_flex().gap_1(), |row, (ix, (name, _))| {
+                row.child(
+                    Button::new(("keymap-context", ix))
+                        .small()
+                        .label(*name)
+                        .when(ix == recording.context, |button| button.primary())
+                        .on_click(cx.listener(move |this, _, _, cx| {
+                            if let Some(recording) = this.recording.as_mut() {
+                                recording.context = ix;
+                                cx.notify();
+                            }
+                        })),
+                )
+            });
+
+        v_flex()
+            .gap_2()
+            .p(px(12.0))
+            .rounded(px(6.0))
+            .bg(cx.theme().muted)
+            .child(div().text_sm().child(if keystrokes.is_empty() {
+                "Press the keys to bind…".to_string()
+            } else {
+                keystrokes.clone()
+            }))
+            .when_some(current, |this, current| {
+                this.child(div().text_xs().text_color(cx.theme().warning).child(format!(
+                    "Currently runs {} in {}; saving replaces it",
+                    current.action.unwrap_or("nothing"),
+                    context
+                )))
+            })
+            .child(
+                h_flex()
+                    .gap_2()
+                    .child(contexts)
+                    .child(div().flex_1())
+                    .child(
+                        Button::new("keymap-cancel")
+                            .small()
+                            .label("Cancel")
+                            .on_click(cx.listener(|this, _, _, cx| this.stop_recording(cx))),
+                    )
+                    .child(
+                        Button::new("keymap-save")
+                            .small()
+                            .primary()
+                            .label("Save")
+                            .disabled(keystrokes.is_empty())
+                            .on_click(
+                                cx.listener(|this, _, window, cx| this.save_recording(window, cx)),
+                            ),
+                    ),
+            )
+    }
+
+    fn render_binding_row(
+        &self,
+        action: &'static str,
+        binding: Option<&BindingInfo>,
+        cx: &mut Context<Self>,
+    ) -> impl IntoElement {
+        let muted = cx.theme().muted_foreground;
+        let overridden = binding.is_some_and(|b| b.overridden);
+
+        h_flex()
+            .gap_3()
+            .py(px(6.0))
+            .text_sm()
+            .when(overridden, |this| this.text_color(muted).line_through())
+            .child(div().flex_1().min_w(px(0.0)).overflow_hidden().text_ellipsis().child(action))
+            .child(
+                div()
+                    .w(px(180.0))
+                    .font_family("monospace")
+                    .children(binding.map(|b| b.keystrokes.clone())),
+            )
+            .child(
+                div().w(px(100.0)).text_color(muted).children(binding.map(|b| b.context.clone())),
+            )
+            .child(div().w(px(70.0)).text_xs().text_color(muted).children(binding.map(|b| {
+                match b.source {
+                    BindingSource::Default => "Default",
+                    BindingSource::User => "User",
+                }
+            })))
     }
 }
 
 impl Render for KeymapPage {
     fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
-        div()
+        let keymap = Keymap::global(cx);
+        let keymap = keymap.read(cx);
+        let bindings = keymap.bindings();
+        let path: Option<SharedString> =
+            keymap.path().map(|path| format!("User bindings: {}", path.display()).into());
+        let problems = keymap.problems().to_vec();
+        let conflicts: Vec<String> = keymap
+            .conflicts()
+            .iter()
+            .map(|c| format!("{} ({}): {}", c.keystrokes, c.context, c.message))
+            .collect();
+
+        let query = self.search.read(cx).text().to_string().to_lowercase();
+        let mut actions: Vec<&'static str> = cx.all_action_names().to_vec();
+        actions.sort_unstable();
+        actions.dedup();
+
+        let mut rows = Vec::new();
+        for (ix, action) in actions.into_iter().enumerate() {
+            let action_bindings: Vec<&BindingInfo> =
+                bindings.iter().filter(|b| b.action == Some(action)).collect();
+            let matches = query.is_empty()
+                || action.to_lowercase().contains(&query)
+                || action_bindings.iter().any(|b| {
+                    b.keystrokes.to_lowercase().contains(&query)
+                        || b.context.to_lowercase().contains(&query)
+                });
+            if !matches {
+                continue;
+            }
+
+            let recording = self.recording.as_ref().filter(|r| r.action == action);
+            let record_bindings: Vec<BindingInfo> =
+                action_bindings.iter().map(|b| (*b).clone()).collect();
+            let mut group = v_flex()
+                .id(("keymap-action", ix))
+                .border_b_1()
+                .border_color(cx.theme().border)
+                .child(
+                    h_flex()
+                        .gap_2()
+                        .child(div().flex_1().min_w(px(0.0)).child(self.render_binding_row(
+                            action,
+                            action_bindings.first().copied(),
+                            cx,
+                        )))
+                        .child(
+                            Button::new("keymap-record")
+                                .small()
+                                .label("Record")
+                                .disabled(recording.is_some())
+                                .on_click(cx.listener(move |this, _, window, cx| {
+                                    this.start_recording(action, &record_bindings, window, cx)
+                                })),
+                        ),
+                );
+            for binding in action_bindings.iter().skip(1) {
+                group = group.child(h_flex().gap_2().pr(px(72.0)).child(
+                    div().flex_1().min_w(px(0.0)).child(self.render_binding_row(
+                        action,
+                        Some(binding),
+                        cx,
+                    )),
+                ));
+            }
+            if let Some(recording) = recording {
+                group = group.child(
+                    div().pb(px(8.0)).child(self.render_recording(recording, &bindings, cx)),
+                );
+            }
+            rows.push(group.into_any_element());
+        }
+
+        v_flex()
             .size_full()
-            .flex()
-            .flex_col()
-            .items_center()
-            .justify_center()
+            .gap_3()
+            .p(px(24.0))
             .bg(cx.theme().background)
+            .track_focus(&self.focus_handle)
+            .on_key_down(cx.listener(Self::on_key_down))
             .child(
-                div()
-                    .text_2xl()
-                    .font_weight(gpui::FontWeight::BOLD)
-                    .text_color(cx.theme().primary)
-                    .child("⌨️ Keymap"),
+                v_flex()
+                    .gap_1()
+                    .child(div().text_xl().font_weight(gpui::FontWeight::SEMIBOLD).child("Keymap"))
+                    .children(path.map(|path| {
+                        div().text_xs().text_color(cx.theme().muted_foreground).child(path)
+                    })),
             )
+            .children(problems.into_iter().map(|problem| {
+                div().text_sm().text_color(cx.theme().danger).child(problem).into_any_element()
+            }))
+            .children(conflicts.into_iter().map(|conflict| {
+                div().text_sm().text_color(cx.theme().warning).child(conflict).into_any_element()
+            }))
+            .child(Input::new(&self.search))
             .child(
-                div()
-                    .mt(px(16.0))
-                    .text_base()
-                    .text_color(cx.theme().primary)
-                    .child("Keymap customization feature to be implemented"),
+                h_flex()
+                    .gap_3()
+                    .pr(px(72.0))
+                    .text_xs()
+                    .text_color(cx.theme().muted_foreground)
+                    .child(div().flex_1().child("Action"))
+                    .child(div().w(px(180.0)).child("Keystrokes"))
+                    .child(div().w(px(100.0)).child("Context"))
+                    .child(div().w(px(70.0)).child("Source")),
+            )
+            .child(
+                div().id("keymap-rows").flex_1().min_h(px(0.0)).overflow_y_scroll().children(rows),
             )
     }
 }
@@ -1,6 +1,6 @@
 use gpui::{
-    AnyElement, App, Axis, Context, Element, Global, IntoElement, ParentElement, Render,
-    SharedString, Styled, Window,
+    div, prelude::FluentBuilder as _, px, AnyElement, App, Axis, Context, Element, Global,
+    IntoElement, ParentElement, Render, SharedString, Styled, Window,
 };
 use gpui_component::{
     button::Button,
@@ -14,9 +14,16 @@ use gpui_component::{
     text::markdown,
     v_flex, ActiveTheme, Icon, IconName, Sizable, Size, Theme, ThemeMode,
 };
+use serde::{Deserialize, Serialize};
 
-struct AppSettings {
+use crate::{core::datetime::DateFormat, ui::settings_store::SettingsStore};
+
+/// The settings shown on the Settings page, saved in the `general` section of the settings file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct AppSettings {
     auto_switch_theme: bool,
+    date_format: DateFormat,
     cli_path: SharedString,
     font_family: SharedString,
     font_size: f64,
@@ -30,6 +37,7 @@ impl Default for AppSettings {
     fn default() -> Self {
         Self {
             auto_switch_theme: false,
+            date_format: DateFormat::default(),
             cli_path: "/usr/local/bin/bash".into(),
             font_family: "Arial".into(),
             font_size: 14.0,
@@ -51,6 +59,11 @@ impl AppSettings {
     pub fn global_mut(cx: &mut App) -> &mut AppSettings {
         cx.global_mut::<AppSettings>()
     }
+
+    /// Date format chosen in Settings, usable before the Settings page has ever been opened.
+    pub(crate) fn date_format(cx: &App) -> DateFormat {
+        cx.try_global::<AppSettings>().map(|s| s.date_format).unwrap_or_default()
+    }
 }
 
 struct OpenURLSettingField {
@@ -202,6 +215,25 @@ impl SettingsPage {
                         )
                         .description("Select the size for the setting group."),
                     ]),
+                    SettingGroup::new().title("Date & Time").items(vec![SettingItem::new(
+                        "Date Format",
+                        SettingField::dropdown(
+                            DateFormat::all()
+                                .into_iter()
+                                .map(|f| (f.as_str().into(), f.label().into()))
+                                .collect(),
+                            |cx: &App| SharedString::from(AppSettings::date_format(cx).as_str()),
+                            |val: SharedString, cx: &mut App| {
+                                AppSettings::global_mut(cx).date_format =
+                                    DateFormat::from_str(val.as_str());
+                                cx.refresh_windows();
+                            },
+                        )
+                        .default_value(default_settings.date_format.as_str().to_string()),
+                    )
+                    .description(
+                        "How dates are shown in the file list, grid, footer and preview.",
+                    )]),
                     SettingGroup::new()
                         .title("Font")
                         .item(
@@ -367,13 +399,51 @@ impl SettingsPage {
     }
 }
 
+impl SettingsPage {
+    /// Where the settings are saved, or why they can't be.
+    fn render_file_status(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let store = SettingsStore::global(cx);
+        let (path, error) = {
+            let store = store.read(cx);
+            (store.path().map(|p| p.display().to_string()), store.error().map(str::to_string))
+        };
+
+        h_flex().gap_3().px_4().py_2().border_b_1().border_color(cx.theme().border).text_xs().map(
+            |this| match error {
+                Some(error) => {
+                    this.bg(cx.theme().danger.opacity(0.1))
+                        .child(div().flex_1().text_color(cx.theme().danger).child(format!(
+                            "{error}. Changes are not saved until the file is fixed."
+                        )))
+                        .child(
+                            Button::new("settings-replace-file")
+                                .small()
+                                .label("Replace File")
+                                .on_click(move |_, _, cx| {
+                                    store.update(cx, |store, cx| store.replace_invalid_file(cx));
+                                }),
+                        )
+                },
+                None => this.text_color(cx.theme().muted_foreground).child(match path {
+                    Some(path) => format!("Saved in {path}"),
+                    None => "No config folder; settings are not saved".to_string(),
+                }),
+            },
+        )
+    }
+}
+
 impl Render for SettingsPage {
     fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         Self::ensure_settings_initialized(cx);
-        Settings::new("app-settings")
-            .with_size(self.size)
-            .with_group_variant(self.group_variant)
-            .pages(self.setting_pages(window, cx))
+        v_flex().size_full().child(self.render_file_status(cx)).child(
+            div().flex_1().min_h(px(0.0)).child(
+                Settings::new("app-settings")
+                    .with_size(self.size)
+                    .with_group_variant(self.group_variant)
+                    .pages(self.setting_pages(window, cx)),
+            ),
+        )
     }
 }
 
@@ -0,0 +1,39 @@
+use std::{
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use crate::core::errors::Result;
+
+/// Writes `bytes` to `path`, creating its folder if needed. The data goes to a temporary sibling
+/// first and is renamed over `path` once it is on disk, so readers and crashes only ever see the
+/// old file or the complete new one.
+pub fn atomic_write(path: &Path, bytes: impl AsRef<[u8]>) -> Result<()> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(dir)?;
+    }
+    let tmp = temp_sibling(path);
+    let result = (|| {
+        let mut file = fs::File::create(&tmp)?;
+        file.write_all(bytes.as_ref())?;
+        file.sync_all()?;
+        fs::rename(&tmp, path)?;
+        Ok(())
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp);
+    }
+    result
+}
+
+/// A free name next to `path` for writing its replacement, hidden on Unix: `.name.1.tmp`,
+/// `.name.2.tmp`, ... Being in the same folder keeps the final rename on one filesystem.
+pub fn temp_sibling(path: &Path) -> PathBuf {
+    let parent = path.parent().unwrap_or(Path::new(""));
+    let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    (1..)
+        .map(|n| parent.join(format!(".{name}.{n}.tmp")))
+        .find(|candidate| fs::symlink_metadata(candidate).is_err())
+        .expect("unbounded range always yields a free name")
+}
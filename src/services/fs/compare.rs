@@ -0,0 +1,413 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs::{self, File},
+    io::{self, Read},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+    time::UNIX_EPOCH,
+};
+
+use walkdir::WalkDir;
+
+use super::listing::FileEntryDto;
+use crate::{
+    core::errors::{Error, Result},
+    models::file_entry::FileKind,
+};
+
+/// Modification times closer than this count as equal: FAT and some network shares only keep
+/// even seconds, so a copy can be a second off its original.
+pub const MTIME_TOLERANCE_SECS: u64 = 2;
+
+/// How an entry on one side relates to the entry of the same name on the other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Difference {
+    LeftOnly,
+    RightOnly,
+    /// Both sides have it, but of a different kind, size or modification time.
+    Different,
+}
+
+/// Compares two folder listings by name, returning the names that are not the same on both
+/// sides. Files match when size and modification time agree; folders match by name alone.
+pub fn compare_listings(
+    left: &[FileEntryDto],
+    right: &[FileEntryDto],
+) -> HashMap<String, Difference> {
+    let right_by_name: HashMap<&str, &FileEntryDto> =
+        right.iter().map(|e| (e.name.as_str(), e)).collect();
+    let left_names: HashSet<&str> = left.iter().map(|e| e.name.as_str()).collect();
+    let mut differences = HashMap::new();
+
+    for entry in left {
+        match right_by_name.get(entry.name.as_str()) {
+            None => {
+                differences.insert(entry.name.clone(), Difference::LeftOnly);
+            },
+            Some(other) if !same_entry(entry, other) => {
+                differences.insert(entry.name.clone(), Difference::Different);
+            },
+            Some(_) => {},
+        }
+    }
+    for entry in right {
+        if !left_names.contains(entry.name.as_str()) {
+            differences.insert(entry.name.clone(), Difference::RightOnly);
+        }
+    }
+    differences
+}
+
+fn same_entry(a: &FileEntryDto, b: &FileEntryDto) -> bool {
+    if a.kind != b.kind {
+        return false;
+    }
+    if a.is_dir() {
+        return true;
+    }
+    a.size == b.size && a.modified.abs_diff(b.modified) < MTIME_TOLERANCE_SECS
+}
+
+/// How [`compare_trees`] decides whether two files are the same.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CompareMethod {
+    /// Same size and modification time. Fast, but misses edits that kept both.
+    #[default]
+    SizeAndTime,
+    /// Same size and the same bytes, read from both sides. Timestamps are ignored.
+    Contents,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryStatus {
+    LeftOnly,
+    RightOnly,
+    Identical,
+    /// Both sides have it, but not the same. A folder is different when anything inside it is.
+    Different,
+}
+
+/// One side of a compared entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EntrySide {
+    pub kind: FileKind,
+    pub size: u64,
+    /// Seconds since the Unix epoch.
+    pub modified: u64,
+}
+
+impl EntrySide {
+    pub fn is_dir(&self) -> bool {
+        self.kind == FileKind::Dir
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ComparedEntry {
+    /// Relative to both roots.
+    pub path: PathBuf,
+    pub status: EntryStatus,
+    pub left: Option<EntrySide>,
+    pub right: Option<EntrySide>,
+}
+
+/// The result of [`compare_trees`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TreeComparison {
+    pub left: PathBuf,
+    pub right: PathBuf,
+    pub method: CompareMethod,
+    /// Parents before their children. Below a folder that only one side has, nothing is listed:
+    /// the folder stands for its whole tree.
+    pub entries: Vec<ComparedEntry>,
+}
+
+impl TreeComparison {
+    pub fn count(&self, status: EntryStatus) -> usize {
+        self.entries.iter().filter(|e| e.status == status).count()
+    }
+
+    /// Whether nothing differs between the two trees.
+    pub fn is_identical(&self) -> bool {
+        self.entries.iter().all(|e| e.status == EntryStatus::Identical)
+    }
+}
+
+/// How often the walk checks for cancellation.
+const CANCEL_CHECK_INTERVAL: usize = 256;
+const CONTENT_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Walks the folders `left` and `right` and classifies every entry found in either. Symlinks are
+/// compared as links, not followed. Entries that can't be read are left out of the walk.
+///
+/// Returns [`Error::Cancelled`] as soon as `cancel` is set.
+pub fn compare_trees(
+    left: &Path,
+    right: &Path,
+    method: CompareMethod,
+    cancel: &AtomicBool,
+) -> Result<TreeComparison> {
+    let left_entries = scan(left, cancel)?;
+    let right_entries = scan(right, cancel)?;
+
+    let mut paths: Vec<&PathBuf> = left_entries.keys().chain(right_entries.keys()).collect();
+    paths.sort();
+    paths.dedup();
+
+    let mut entries: Vec<ComparedEntry> = Vec::new();
+    // Set while walking the tree of a folder that is not on both sides as a folder.
+    let mut one_sided: Option<&Path> = None;
+    for (ix, path) in paths.into_iter().enumerate() {
+        if ix % CANCEL_CHECK_INTERVAL == 0 && cancel.load(Ordering::Relaxed) {
+            return Err(Error::Cancelled);
+        }
+        // Children sort right after their parent, so one prefix at a time is enough.
+        if let Some(prefix) = one_sided {
+            if path.starts_with(prefix) {
+                continue;
+            }
+            one_sided = None;
+        }
+        let l = left_entries.get(path).copied();
+        let r = right_entries.get(path).copied();
+        let status = match (l, r) {
+            (Some(l), None) => {
+                if l.is_dir() {
+                    one_sided = Some(path.as_path());
+                }
+                EntryStatus::LeftOnly
+            },
+            (None, Some(r)) => {
+                if r.is_dir() {
+                    one_sided = Some(path.as_path());
+                }
+                EntryStatus::RightOnly
+            },
+            (Some(l), Some(r)) if l.is_dir() && r.is_dir() => EntryStatus::Identical,
+            (Some(l), Some(r)) if l.is_dir() || r.is_dir() => {
+                one_sided = Some(path.as_path());
+                EntryStatus::Different
+            },
+            (Some(l), Some(r)) => {
+                if same_file(&left.join(path), &right.join(path), l, r, method, cancel)? {
+                    EntryStatus::Identical
+                } else {
+                    EntryStatus::Different
+                }
+            },
+            (None, None) => unreachable!("every path comes from one of the sides"),
+        };
+        entries.push(ComparedEntry { path: path.clone(), status, left: l, right: r });
+    }
+
+    // A folder on both sides differs when anything below it does.
+    let index: HashMap<PathBuf, usize> =
+        entries.iter().enumerate().map(|(ix, e)| (e.path.clone(), ix)).collect();
+    for ix in 0..entries.len() {
+        if entries[ix].status == EntryStatus::Identical {
+            continue;
+        }
+        let parents: Vec<usize> = entries[ix]
+            .path
+            .ancestors()
+            .skip(1)
+            .filter_map(|parent| index.get(parent).copied())
+            .collect();
+        for parent in parents {
+            entries[parent].status = EntryStatus::Different;
+        }
+    }
+
+    Ok(TreeComparison { left: left.to_path_buf(), right: right.to_path_buf(), method, entries })
+}
+
+/// Every entry below `root`, keyed by its path relative to `root`.
+fn scan(root: &Path, cancel: &AtomicBool) -> Result<BTreeMap<PathBuf, EntrySide>> {
+    if !fs::metadata(root)?.is_dir() {
+        return Err(Error::Other(format!("{} is not a folder", root.display())));
+    }
+    let mut entries = BTreeMap::new();
+    for (ix, entry) in WalkDir::new(root).min_depth(1).follow_links(false).into_iter().enumerate() {
+        if ix % CANCEL_CHECK_INTERVAL == 0 && cancel.load(Ordering::Relaxed) {
+            return Err(Error::Cancelled);
+        }
+        let Ok(entry) = entry else {
+            continue;
+        };
+        let Ok(md) = entry.metadata() else {
+            continue;
+        };
+        let Ok(relative) = entry.path().strip_prefix(root) else {
+            continue;
+        };
+        let modified = md
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_secs());
+        let kind = FileKind::from_file_type(md.file_type());
+        let size = if kind == FileKind::Dir { 0 } else { md.len() };
+        entries.insert(relative.to_path_buf(), EntrySide { kind, size, modified });
+    }
+    Ok(entries)
+}
+
+fn same_file(
+    left_path: &Path,
+    right_path: &Path,
+    left: EntrySide,
+    right: EntrySide,
+    method: CompareMethod,
+    cancel: &AtomicBool,
+) -> Result<bool> {
+    if left.kind != right.kind || left.size != right.size {
+        return Ok(false);
+    }
+    if left.kind == FileKind::Symlink {
+        return Ok(fs::read_link(left_path).ok() == fs::read_link(right_path).ok());
+    }
+    match method {
+        CompareMethod::SizeAndTime => {
+            Ok(left.modified.abs_diff(right.modified) < MTIME_TOLERANCE_SECS)
+        },
+        CompareMethod::Contents if left.kind != FileKind::File => Ok(true),
+        CompareMethod::Contents => match same_contents(left_path, right_path, cancel) {
+            Ok(same) => Ok(same),
+            Err(Error::Cancelled) => Err(Error::Cancelled),
+            Err(err) => {
+                // A file that can't be read can't be shown to be the same.
+                tracing::debug!("Can't compare {}: {}", left_path.display(), err);
+                Ok(false)
+            },
+        },
+    }
+}
+
+fn same_contents(left: &Path, right: &Path, cancel: &AtomicBool) -> Result<bool> {
+    let (mut left, mut right) = (File::open(left)?, File::open(right)?);
+    let mut left_buf = vec![0; CONTENT_CHUNK_SIZE];
+    let mut right_buf = vec![0; CONTENT_CHUNK_SIZE];
+    loop {
+        if cancel.load(Ordering::Relaxed) {
+            return Err(Error::Cancelled);
+        }
+        let n = read_full(&mut left, &mut left_buf)?;
+        let m = read_full(&mut right, &mut right_buf)?;
+        if n != m || left_buf[..n] != right_buf[..m] {
+            return Ok(false);
+        }
+        if n == 0 {
+            return Ok(true);
+        }
+    }
+}
+
+/// Reads until `buf` is full or the file ends, so both sides are compared in step.
+fn read_full(file: &mut File, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match file.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {},
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(filled)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::core::testing::ScratchDir;
+
+    fn write(path: &Path, body: &str, modified: u64) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, body).unwrap();
+        let file = File::options().write(true).open(path).unwrap();
+        file.set_modified(UNIX_EPOCH + Duration::from_secs(modified)).unwrap();
+    }
+
+    /// Compares the `left` and `right` folders of `dir`, as `path status` lines in walk order.
+    fn compare(dir: &ScratchDir, method: CompareMethod) -> Vec<String> {
+        let (left, right) = (dir.join("left"), dir.join("right"));
+        fs::create_dir_all(&left).unwrap();
+        fs::create_dir_all(&right).unwrap();
+        let comparison = compare_trees(&left, &right, method, &AtomicBool::new(false)).unwrap();
+        comparison
+            .entries
+            .iter()
+            .map(|entry| {
+                let parts: Vec<_> =
+                    entry.path.components().map(|c| c.as_os_str().to_string_lossy()).collect();
+                format!("{} {:?}", parts.join("/"), entry.status)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn one_sided_folders_stand_for_their_tree() {
+        let dir = ScratchDir::new("compare-one-sided");
+        write(&dir.join("left/a/x.txt"), "x", 1000);
+        write(&dir.join("left/a/deeper/y.txt"), "y", 1000);
+        // Sorts after everything below `a/`, and is not below it.
+        write(&dir.join("left/a-b"), "same", 1000);
+        write(&dir.join("right/a-b"), "same", 1000);
+        write(&dir.join("right/r/z.txt"), "z", 1000);
+
+        assert_eq!(compare(&dir, CompareMethod::SizeAndTime), [
+            "a LeftOnly",
+            "a-b Identical",
+            "r RightOnly"
+        ]);
+    }
+
+    #[test]
+    fn a_folder_facing_a_file_is_different() {
+        let dir = ScratchDir::new("compare-kind");
+        write(&dir.join("left/kind"), "a file", 1000);
+        write(&dir.join("right/kind/inner.txt"), "a folder", 1000);
+
+        assert_eq!(compare(&dir, CompareMethod::SizeAndTime), ["kind Different"]);
+    }
+
+    #[test]
+    fn nested_differences_mark_every_parent() {
+        let dir = ScratchDir::new("compare-nested");
+        write(&dir.join("left/docs/same.txt"), "same", 1000);
+        write(&dir.join("right/docs/same.txt"), "same", 1000);
+        write(&dir.join("left/docs/sub/file.txt"), "left", 1000);
+        write(&dir.join("right/docs/sub/file.txt"), "right!", 1000);
+        write(&dir.join("left/other/x.txt"), "x", 1000);
+        write(&dir.join("right/other/x.txt"), "x", 1000);
+
+        assert_eq!(compare(&dir, CompareMethod::SizeAndTime), [
+            "docs Different",
+            "docs/same.txt Identical",
+            "docs/sub Different",
+            "docs/sub/file.txt Different",
+            "other Identical",
+            "other/x.txt Identical",
+        ]);
+    }
+
+    #[test]
+    fn contents_catch_edits_that_kept_size_and_time() {
+        let dir = ScratchDir::new("compare-contents");
+        write(&dir.join("left/edited.txt"), "abc", 1000);
+        write(&dir.join("right/edited.txt"), "abd", 1000);
+        write(&dir.join("left/touched.txt"), "same", 1000);
+        write(&dir.join("right/touched.txt"), "same", 5000);
+
+        assert_eq!(compare(&dir, CompareMethod::SizeAndTime), [
+            "edited.txt Identical",
+            "touched.txt Different"
+        ]);
+        assert_eq!(compare(&dir, CompareMethod::Contents), [
+            "edited.txt Different",
+            "touched.txt Identical"
+        ]);
+    }
+}
@@ -0,0 +1,256 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Mutex, OnceLock,
+    },
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+use walkdir::WalkDir;
+
+use crate::core::errors::{Error, Result};
+
+/// How often a running calculation reports progress.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SizeOptions {
+    /// Descend into mount points below the starting directory.
+    pub cross_filesystems: bool,
+}
+
+/// Totals for a directory tree. Also used for intermediate progress reports.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DirSize {
+    /// Apparent size of all regular files, counting hard-linked files once.
+    pub bytes: u64,
+    pub files: u64,
+    pub dirs: u64,
+    /// Entries that could not be read (permission denied, vanished while walking, ...).
+    pub errors: u64,
+}
+
+pub enum SizeEvent {
+    Progress { path: PathBuf, size: DirSize },
+    Done { path: PathBuf, result: Result<DirSize> },
+}
+
+/// A running batch of size calculations started by [`spawn_dir_sizes`]. Dropping it cancels the
+/// remaining work.
+pub struct SizeJob {
+    rx: mpsc::Receiver<SizeEvent>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl SizeJob {
+    /// Blocks until the next event. Returns `None` once every path has been reported.
+    pub fn recv(&self) -> Option<SizeEvent> {
+        self.rx.recv().ok()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Drop for SizeJob {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+/// Computes the recursive size of each path in turn on a worker thread.
+///
+/// Results already in the cache (same path, same directory mtime) are reported immediately without
+/// walking the tree again.
+pub fn spawn_dir_sizes(paths: Vec<PathBuf>, options: SizeOptions) -> SizeJob {
+    let (tx, rx) = mpsc::channel();
+    let cancelled = Arc::new(AtomicBool::new(false));
+
+    let worker_cancelled = cancelled.clone();
+    let spawned = thread::Builder::new().name("nohrs-dir-size".into()).spawn(move || {
+        for path in paths {
+            if worker_cancelled.load(Ordering::Relaxed) {
+                return;
+            }
+            let result = dir_size_cached(&path, options, &worker_cancelled, |size| {
+                let _ = tx.send(SizeEvent::Progress { path: path.clone(), size: *size });
+            });
+            if matches!(result, Err(Error::Cancelled)) {
+                return;
+            }
+            if tx.send(SizeEvent::Done { path, result }).is_err() {
+                return;
+            }
+        }
+    });
+    if let Err(err) = spawned {
+        tracing::error!("Failed to start folder size worker: {}", err);
+    }
+
+    SizeJob { rx, cancelled }
+}
+
+/// [`dir_size`] with a lookup in, and an update of, the process-wide cache.
+pub fn dir_size_cached(
+    path: &Path,
+    options: SizeOptions,
+    cancelled: &AtomicBool,
+    on_progress: impl FnMut(&DirSize),
+) -> Result<DirSize> {
+    let mtime = fs::metadata(path)?.modified().ok();
+    let key = (path.to_path_buf(), options.cross_filesystems);
+
+    if let Some(mtime) = mtime {
+        if let Some(hit) = cache().lock().ok().and_then(|c| c.get(&key).copied()) {
+            if hit.0 == mtime {
+                return Ok(hit.1);
+            }
+        }
+    }
+
+    let size = dir_size(path, options, cancelled, on_progress)?;
+    if let (Some(mtime), Ok(mut cache)) = (mtime, cache().lock()) {
+        cache.insert(key, (mtime, size));
+    }
+    Ok(size)
+}
+
+/// Forgets the cached sizes that a change to `paths` makes stale: those of every folder holding one
+/// of them, however far up, and of anything inside them. A folder's mtime only follows its direct
+/// children, so the cache can't tell by itself.
+pub fn invalidate_cached_sizes(paths: &[PathBuf]) {
+    let Ok(mut cache) = cache().lock() else {
+        return;
+    };
+    cache.retain(|(cached, _), _| {
+        !paths.iter().any(|path| path.starts_with(cached) || cached.starts_with(path))
+    });
+}
+
+/// Walks `path` and adds up the sizes of all regular files below it.
+///
+/// Symlinks are not followed. Returns [`Error::Cancelled`] as soon as `cancelled` is set.
+pub fn dir_size(
+    path: &Path,
+    options: SizeOptions,
+    cancelled: &AtomicBool,
+    mut on_progress: impl FnMut(&DirSize),
+) -> Result<DirSize> {
+    // Fail early (and with a proper error) if the root itself is unreadable.
+    fs::read_dir(path)?;
+
+    let mut total = DirSize::default();
+    let mut seen_links: HashSet<(u64, u64)> = HashSet::new();
+    let mut last_report = Instant::now();
+
+    let walker = WalkDir::new(path)
+        .min_depth(1)
+        .follow_links(false)
+        .same_file_system(!options.cross_filesystems);
+
+    for entry in walker {
+        if cancelled.load(Ordering::Relaxed) {
+            return Err(Error::Cancelled);
+        }
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(_) => {
+                total.errors += 1;
+                continue;
+            },
+        };
+
+        let file_type = entry.file_type();
+        if file_type.is_dir() {
+            total.dirs += 1;
+        } else if file_type.is_file() {
+            match entry.metadata() {
+                Ok(md) => {
+                    if first_link(&md, &mut seen_links) {
+                        total.bytes += md.len();
+                    }
+                    total.files += 1;
+                },
+                Err(_) => total.errors += 1,
+            }
+        }
+
+        if last_report.elapsed() >= PROGRESS_INTERVAL {
+            on_progress(&total);
+            last_report = Instant::now();
+        }
+    }
+
+    Ok(total)
+}
+
+/// Returns false for the second and later occurrences of a hard-linked file.
+#[cfg(unix)]
+fn first_link(md: &fs::Metadata, seen: &mut HashSet<(u64, u64)>) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    md.nlink() <= 1 || seen.insert((md.dev(), md.ino()))
+}
+
+#[cfg(not(unix))]
+fn first_link(_md: &fs::Metadata, _seen: &mut HashSet<(u64, u64)>) -> bool {
+    true
+}
+
+type SizeCache = HashMap<(PathBuf, bool), (SystemTime, DirSize)>;
+
+fn cache() -> &'static Mutex<SizeCache> {
+    static CACHE: OnceLock<Mutex<SizeCache>> = OnceLock::new();
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::testing::ScratchDir;
+
+    fn cached_size(path: &Path) -> u64 {
+        let cancelled = AtomicBool::new(false);
+        dir_size_cached(path, SizeOptions::default(), &cancelled, |_| {}).unwrap().bytes
+    }
+
+    fn is_cached(path: &Path) -> bool {
+        cache().lock().unwrap().contains_key(&(path.to_path_buf(), false))
+    }
+
+    #[test]
+    fn changes_deep_down_invalidate_every_ancestor() {
+        let root = ScratchDir::new("dir-size-ancestors");
+        let deep = root.join("a/b");
+        fs::create_dir_all(&deep).unwrap();
+        fs::write(deep.join("file"), b"1234").unwrap();
+        assert_eq!(cached_size(&root), 4);
+        assert_eq!(cached_size(&root.join("a")), 4);
+
+        // The top folders' mtimes don't change, so the cache alone would keep the old size.
+        fs::write(deep.join("file"), b"12345678").unwrap();
+        assert_eq!(cached_size(&root), 4);
+
+        invalidate_cached_sizes(&[deep.join("file")]);
+        assert!(!is_cached(&root) && !is_cached(&root.join("a")));
+        assert_eq!(cached_size(&root), 8);
+    }
+
+    #[test]
+    fn changed_folders_invalidate_what_is_inside() {
+        let root = ScratchDir::new("dir-size-inside");
+        let sub = root.join("sub");
+        fs::create_dir_all(sub.join("inner")).unwrap();
+        let sibling = ScratchDir::new("dir-size-inside-sibling");
+        cached_size(&sub.join("inner"));
+        cached_size(&sibling);
+
+        invalidate_cached_sizes(std::slice::from_ref(&sub));
+        assert!(!is_cached(&sub.join("inner")));
+        assert!(is_cached(&sibling));
+    }
+}
@@ -1,28 +1,218 @@
 use std::{
+    cmp::Ordering as CmpOrdering,
     ffi::OsStr,
     fs,
     path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc,
+    },
+    thread,
     time::UNIX_EPOCH,
 };
 
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use tokio::task;
 
-use crate::core::errors::Result;
+use super::metadata::{entry_metadata, is_hidden, EntryMetadata, OwnerCache};
+use crate::{
+    core::errors::{Error, Result},
+    models::file_entry::{FileKind, LinkTarget},
+};
+
+/// Number of entries sent per batch by [`list_dir_stream`] when the caller has no preference.
+pub const DEFAULT_STREAM_BATCH_SIZE: usize = 512;
 
 #[derive(Debug, Serialize, Clone)]
 pub struct FileEntryDto {
     pub name: String,
     pub path: String,
-    pub kind: String,
+    pub kind: FileKind,
     pub size: u64,
     pub modified: u64,
+    /// Set for symlinks only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub link: Option<LinkTarget>,
+    pub hidden: bool,
+    /// Permissions, ownership, timestamps and inode details; `None` unless the listing was
+    /// requested with `detailed` set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<EntryMetadata>,
+}
+
+impl FileEntryDto {
+    pub fn is_dir(&self) -> bool {
+        self.kind == FileKind::Dir
+    }
+
+    pub fn is_file(&self) -> bool {
+        self.kind == FileKind::File
+    }
+
+    /// Whether opening this entry should navigate into it: a directory, or a symlink that
+    /// resolves to one.
+    pub fn opens_as_dir(&self) -> bool {
+        self.is_dir() || self.link.as_ref().and_then(|l| l.kind) == Some(FileKind::Dir)
+    }
+
+    /// Whether the entry's content can be previewed as a file, following symlinks.
+    pub fn opens_as_file(&self) -> bool {
+        self.is_file() || self.link.as_ref().and_then(|l| l.kind) == Some(FileKind::File)
+    }
 }
 
 pub struct ListParams<'a> {
     pub path: &'a str,
     pub limit: usize,
     pub cursor: Option<&'a str>,
+    pub options: ListOptions,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortKey {
+    /// Case-insensitive name order.
+    #[default]
+    Name,
+    /// Like `Name`, but runs of digits compare by value ("file2" before "file10").
+    Natural,
+    Size,
+    Modified,
+    /// Extension order; folders first, then files without an extension last.
+    Type,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortDirection {
+    #[default]
+    Ascending,
+    Descending,
+}
+
+/// Sorting and filtering applied to a listing before it is paged.
+///
+/// The explorer uses the same [`ListOptions::compare`] and [`ListOptions::matches`] for the rows it
+/// renders, so pages from [`list_dir`] line up with what the user sees.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ListOptions {
+    pub sort: SortKey,
+    pub direction: SortDirection,
+    /// Keep folders grouped before everything else regardless of `direction`.
+    pub dirs_first: bool,
+    /// Include dot-files.
+    pub show_hidden: bool,
+    /// Case-insensitive name filter. Treated as a glob when it contains `*`, `?` or `[`, and as a
+    /// substring otherwise.
+    pub filter: Option<String>,
+    /// Fill [`FileEntryDto::metadata`] for every returned entry. Turn off to skip the owner and
+    /// group lookups when only names, sizes and dates are needed.
+    pub detailed: bool,
+}
+
+impl Default for ListOptions {
+    fn default() -> Self {
+        Self {
+            sort: SortKey::Name,
+            direction: SortDirection::Ascending,
+            dirs_first: true,
+            show_hidden: true,
+            filter: None,
+            detailed: true,
+        }
+    }
+}
+
+impl ListOptions {
+    /// Whether an entry with this name passes the hidden-file toggle and the name filter.
+    pub fn matches(&self, name: &str) -> bool {
+        if !self.show_hidden && name.starts_with('.') {
+            return false;
+        }
+        match self.filter.as_deref().filter(|f| !f.is_empty()) {
+            None => true,
+            Some(filter) if is_glob(filter) => glob_match(filter, name),
+            Some(filter) => name.to_lowercase().contains(&filter.to_lowercase()),
+        }
+    }
+
+    /// Like [`ListOptions::matches`], but also honours the platform hidden flag of a listed entry.
+    pub fn matches_entry(&self, entry: &FileEntryDto) -> bool {
+        (self.show_hidden || !entry.hidden) && self.matches(&entry.name)
+    }
+
+    /// Orders two entries according to these options.
+    pub fn compare(&self, a: &FileEntryDto, b: &FileEntryDto) -> CmpOrdering {
+        self.compare_fields(&SortFields::from(a), &SortFields::from(b))
+    }
+
+    /// Merges `batch` into `entries` in one linear pass. Both must already be in the order
+    /// [`ListOptions::compare`] gives; on ties the entries already there come first.
+    pub fn merge_sorted(&self, entries: &mut Vec<FileEntryDto>, batch: Vec<FileEntryDto>) {
+        if batch.is_empty() {
+            return;
+        }
+        // A folder that reads back in sort order only ever appends.
+        let appends = entries
+            .last()
+            .zip(batch.first())
+            .is_none_or(|(last, first)| self.compare(last, first) != CmpOrdering::Greater);
+        if appends {
+            entries.extend(batch);
+            return;
+        }
+
+        let old = std::mem::replace(entries, Vec::with_capacity(entries.len() + batch.len()));
+        let mut old = old.into_iter().peekable();
+        let mut batch = batch.into_iter().peekable();
+        while let (Some(a), Some(b)) = (old.peek(), batch.peek()) {
+            let next =
+                if self.compare(a, b) == CmpOrdering::Greater { batch.next() } else { old.next() };
+            entries.extend(next);
+        }
+        entries.extend(old);
+        entries.extend(batch);
+    }
+
+    fn compare_fields(&self, a: &SortFields<'_>, b: &SortFields<'_>) -> CmpOrdering {
+        if self.dirs_first && a.is_dir != b.is_dir {
+            return b.is_dir.cmp(&a.is_dir);
+        }
+
+        let order = match self.sort {
+            SortKey::Name => CmpOrdering::Equal,
+            SortKey::Natural => natural_cmp(a.name, b.name),
+            SortKey::Size => a.size.cmp(&b.size),
+            SortKey::Modified => a.modified.cmp(&b.modified),
+            SortKey::Type => type_rank(a).cmp(&type_rank(b)),
+        }
+        .then_with(|| caseless_cmp(a.name, b.name))
+        .then_with(|| a.name.cmp(b.name))
+        .then_with(|| a.ino.cmp(&b.ino));
+
+        match self.direction {
+            SortDirection::Ascending => order,
+            SortDirection::Descending => order.reverse(),
+        }
+    }
+
+    /// Size and modification time are only known after a `stat`, which paging can skip otherwise.
+    fn needs_metadata(&self) -> bool {
+        matches!(self.sort, SortKey::Size | SortKey::Modified)
+    }
+
+    /// Compact description embedded in cursors so a cursor is only honoured with the options it
+    /// was issued for.
+    fn signature(&self) -> String {
+        format!(
+            "{:?}/{:?}/{}/{}/{}",
+            self.sort,
+            self.direction,
+            self.dirs_first,
+            self.show_hidden,
+            self.filter.as_deref().unwrap_or_default()
+        )
+    }
 }
 
 pub struct ListResult {
@@ -30,77 +220,747 @@ pub struct ListResult {
     pub next_cursor: Option<String>,
 }
 
+pub struct StreamParams<'a> {
+    pub path: &'a str,
+    pub batch_size: usize,
+    /// Orders the entries within each batch and decides whether they carry detailed metadata. The
+    /// filter and hidden-file toggle are not applied: callers keep every entry so they can change
+    /// those without reading the folder again.
+    pub options: ListOptions,
+}
+
+/// Receiving end of a streaming directory listing.
+///
+/// Batches arrive while a worker thread reads the directory, each sorted on its own; merging them
+/// (see [`ListOptions::merge_sorted`]) gives the sorted listing. The worker stops as soon as the
+/// stream is dropped, so abandoning a listing of a huge folder does not keep reading it in the
+/// background.
+pub struct ListStream {
+    rx: mpsc::Receiver<Result<Vec<FileEntryDto>>>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl ListStream {
+    /// Blocks until the next batch is available. Returns `None` once the listing is complete.
+    pub fn recv(&self) -> Option<Result<Vec<FileEntryDto>>> {
+        self.rx.recv().ok()
+    }
+
+    /// Returns the next batch if one is ready, without blocking.
+    pub fn try_recv(&self) -> Option<Result<Vec<FileEntryDto>>> {
+        self.rx.try_recv().ok()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Drop for ListStream {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+impl Iterator for ListStream {
+    type Item = Result<Vec<FileEntryDto>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.recv()
+    }
+}
+
 pub async fn list_dir(params: ListParams<'_>) -> Result<ListResult> {
     // Use a blocking task for filesystem IO to avoid blocking async executors.
     let path = params.path.to_string();
     let limit = params.limit;
     let cursor = params.cursor.map(|s| s.to_string());
+    let options = params.options;
 
-    task::spawn_blocking(move || list_dir_impl(&path, limit, cursor.as_deref())).await.unwrap()
+    task::spawn_blocking(move || list_dir_impl(&path, limit, cursor.as_deref(), &options))
+        .await
+        .unwrap()
 }
 
 /// Synchronous variant for UI contexts where an async runtime is not available.
 pub fn list_dir_sync(params: ListParams<'_>) -> Result<ListResult> {
-    list_dir_impl(params.path, params.limit, params.cursor)
+    list_dir_impl(params.path, params.limit, params.cursor, &params.options)
+}
+
+/// Starts listing a directory on a worker thread and returns a stream of entry batches.
+///
+/// Unlike [`list_dir`], there is no item cap and the folder is not sorted as a whole up front: each
+/// batch is sorted and sent as soon as `batch_size` entries have been read, so callers can render
+/// the first entries of a directory with hundreds of thousands of children right away. An error
+/// reading the directory itself is delivered as the first (and only) item of the stream.
+pub fn list_dir_stream(params: StreamParams<'_>) -> ListStream {
+    let path = params.path.to_string();
+    let batch_size = params.batch_size.max(1);
+    let options = params.options;
+    let (tx, rx) = mpsc::channel();
+    let cancelled = Arc::new(AtomicBool::new(false));
+
+    let worker_cancelled = cancelled.clone();
+    let spawned = thread::Builder::new().name("nohrs-list-dir".into()).spawn(move || {
+        let send = |mut batch: Vec<FileEntryDto>| {
+            batch.sort_by(|a, b| options.compare(a, b));
+            tx.send(Ok(batch)).is_ok()
+        };
+        if let Err(err) =
+            stream_dir_impl(&path, batch_size, options.detailed, &worker_cancelled, send)
+        {
+            let _ = tx.send(Err(err));
+        }
+    });
+    if let Err(err) = spawned {
+        // The sender was moved into the closure that failed to spawn, so report through a fresh
+        // channel.
+        let (tx, rx) = mpsc::channel();
+        let _ = tx.send(Err(err.into()));
+        return ListStream { rx, cancelled };
+    }
+
+    ListStream { rx, cancelled }
+}
+
+/// Reads a single entry the same way a listing would. Returns `None` if `path` no longer exists,
+/// which is how watchers tell a removal from a modification.
+pub fn stat_entry(path: &Path, detailed: bool) -> Option<FileEntryDto> {
+    fs::symlink_metadata(path).ok()?;
+    let name = path.file_name().map(os_str_to_string)?;
+    let mut owners = OwnerCache::default();
+    Some(read_entry(name, path, detailed.then_some(&mut owners)))
+}
+
+fn stream_dir_impl(
+    path: &str,
+    batch_size: usize,
+    detailed: bool,
+    cancelled: &AtomicBool,
+    mut send: impl FnMut(Vec<FileEntryDto>) -> bool,
+) -> Result<()> {
+    let mut batch = Vec::with_capacity(batch_size);
+    let mut owners = OwnerCache::default();
+
+    for entry in fs::read_dir(Path::new(path))? {
+        if cancelled.load(Ordering::Relaxed) {
+            return Ok(());
+        }
+        // A single unreadable entry should not abort the whole listing.
+        let Ok(entry) = entry else {
+            continue;
+        };
+        let owners = detailed.then_some(&mut owners);
+        batch.push(read_entry(os_str_to_string(entry.file_name()), &entry.path(), owners));
+
+        if batch.len() >= batch_size {
+            let full = std::mem::replace(&mut batch, Vec::with_capacity(batch_size));
+            if !send(full) {
+                return Ok(());
+            }
+        }
+    }
+
+    if !batch.is_empty() && !cancelled.load(Ordering::Relaxed) {
+        send(batch);
+    }
+    Ok(())
 }
 
-fn list_dir_impl(path: &str, limit: usize, cursor: Option<&str>) -> Result<ListResult> {
+fn list_dir_impl(
+    path: &str,
+    limit: usize,
+    cursor: Option<&str>,
+    options: &ListOptions,
+) -> Result<ListResult> {
     let dir = Path::new(path);
-    let mut names: Vec<(String, PathBuf)> = Vec::new();
+    let dir_id = dir_identity(&fs::metadata(dir)?);
+
+    let after = cursor.map(Cursor::decode).transpose()?;
+    if let Some(after) = &after {
+        if after.dir != dir_id {
+            return Err(Error::StaleCursor(format!(
+                "{path} was replaced since the cursor was issued"
+            )));
+        }
+        if after.options != options.signature() {
+            return Err(Error::InvalidCursor(
+                "cursor was issued for different sort or filter options".into(),
+            ));
+        }
+    }
 
-    // Read directory entries: collect names and paths only (cheap), then sort by name for stable
-    // paging.
+    // Read directory entries: collect sort keys and paths only (cheap unless the sort needs
+    // metadata), filter and sort them, then page.
+    let mut names: Vec<(PageKey, PathBuf)> = Vec::new();
     for entry in fs::read_dir(dir)? {
         let entry = entry?;
-        let file_name = os_str_to_string(entry.file_name());
-        names.push((file_name, entry.path()));
+        let name = os_str_to_string(entry.file_name());
+        if !options.matches(&name) {
+            continue;
+        }
+        // As `matches_entry` does for streamed listings. Only Windows has a hidden attribute, and
+        // its directory entries carry their metadata, so nothing is stat'ed elsewhere.
+        if !options.show_hidden && cfg!(windows) && is_hidden(&name, entry.metadata().ok().as_ref())
+        {
+            continue;
+        }
+        let mut key = PageKey {
+            name,
+            is_dir: entry.file_type().map(|t| t.is_dir()).unwrap_or(false),
+            size: 0,
+            modified: 0,
+            ino: entry_ino(&entry),
+        };
+        if options.needs_metadata() {
+            let dto = read_entry(String::new(), &entry.path(), None);
+            key.size = dto.size;
+            key.modified = dto.modified;
+        }
+        names.push((key, entry.path()));
     }
-    names.sort_by_key(|a| a.0.to_lowercase());
+    names.sort_by(|a, b| options.compare_fields(&a.0.fields(), &b.0.fields()));
 
+    // Resume strictly after the last key the caller has seen. Entries added or removed in the
+    // meantime shift offsets but not keys, so nothing is skipped or repeated.
     let total = names.len();
-    let offset = cursor.and_then(|s| s.parse::<usize>().ok()).unwrap_or(0).min(total);
-
-    let end = (offset + limit).min(total);
-    let slice = &names[offset..end];
-
-    let mut entries = Vec::with_capacity(slice.len());
-    for (name, path) in slice.iter() {
-        let md = fs::symlink_metadata(path);
-        let (kind, size, modified) = match md {
-            Ok(md) => {
-                let modified = md
-                    .modified()
-                    .ok()
-                    .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
-                    .map(|d| d.as_secs())
-                    .unwrap_or(0);
-                if md.file_type().is_dir() {
-                    ("dir".to_string(), 0, modified)
-                } else if md.file_type().is_file() {
-                    ("file".to_string(), md.len(), modified)
-                } else if md.file_type().is_symlink() {
-                    ("symlink".to_string(), 0, modified)
-                } else {
-                    ("other".to_string(), 0, modified)
+    let start = match &after {
+        Some(after) => names.partition_point(|(key, _)| {
+            options.compare_fields(&key.fields(), &after.last.fields()) != CmpOrdering::Greater
+        }),
+        None => 0,
+    };
+    let end = (start + limit.max(1)).min(total);
+    let slice = &names[start..end];
+
+    let mut owners = OwnerCache::default();
+    let entries = slice
+        .iter()
+        .map(|(key, path)| {
+            read_entry(key.name.clone(), path, options.detailed.then_some(&mut owners))
+        })
+        .collect();
+
+    let next_cursor = if end < total {
+        Some(
+            Cursor { dir: dir_id, options: options.signature(), last: names[end - 1].0.clone() }
+                .encode(),
+        )
+    } else {
+        None
+    };
+
+    Ok(ListResult { entries, next_cursor })
+}
+
+/// Borrowed view of everything [`ListOptions::compare_fields`] looks at.
+struct SortFields<'a> {
+    name: &'a str,
+    is_dir: bool,
+    size: u64,
+    modified: u64,
+    ino: u64,
+}
+
+impl<'a> From<&'a FileEntryDto> for SortFields<'a> {
+    fn from(entry: &'a FileEntryDto) -> Self {
+        Self {
+            name: &entry.name,
+            is_dir: entry.is_dir(),
+            size: entry.size,
+            modified: entry.modified,
+            ino: 0,
+        }
+    }
+}
+
+/// Sort position of an entry, as recorded in a cursor.
+#[derive(Debug, Clone)]
+struct PageKey {
+    name: String,
+    is_dir: bool,
+    size: u64,
+    modified: u64,
+    ino: u64,
+}
+
+impl PageKey {
+    fn fields(&self) -> SortFields<'_> {
+        SortFields {
+            name: &self.name,
+            is_dir: self.is_dir,
+            size: self.size,
+            modified: self.modified,
+            ino: self.ino,
+        }
+    }
+}
+
+/// Decoded form of the opaque cursor returned in [`ListResult::next_cursor`].
+///
+/// The cursor records the directory and options it was issued for and the last key of the page,
+/// hex-encoded so callers treat it as an opaque token rather than an offset they can do
+/// arithmetic on.
+struct Cursor {
+    dir: (u64, u64),
+    options: String,
+    last: PageKey,
+}
+
+impl Cursor {
+    const VERSION: &'static str = "v2";
+
+    fn encode(&self) -> String {
+        let raw = format!(
+            "{}\0{}\0{}\0{}\0{}\0{}\0{}\0{}\0{}",
+            Self::VERSION,
+            self.dir.0,
+            self.dir.1,
+            self.options,
+            u8::from(self.last.is_dir),
+            self.last.size,
+            self.last.modified,
+            self.last.ino,
+            self.last.name
+        );
+        raw.bytes().map(|b| format!("{b:02x}")).collect()
+    }
+
+    fn decode(cursor: &str) -> Result<Self> {
+        let invalid = |reason: &str| Error::InvalidCursor(format!("{reason}: {cursor:?}"));
+
+        if !cursor.len().is_multiple_of(2) {
+            return Err(invalid("odd length"));
+        }
+        let bytes = (0..cursor.len())
+            .step_by(2)
+            .map(|i| cursor.get(i..i + 2).and_then(|h| u8::from_str_radix(h, 16).ok()))
+            .collect::<Option<Vec<u8>>>()
+            .ok_or_else(|| invalid("not hex"))?;
+        let raw = String::from_utf8(bytes).map_err(|_| invalid("not utf-8"))?;
+
+        let mut parts = raw.splitn(9, '\0');
+        let version = parts.next().unwrap_or_default();
+        if version != Self::VERSION {
+            return Err(invalid("unknown version"));
+        }
+        let dev = parts.next().and_then(|p| p.parse().ok()).ok_or_else(|| invalid("bad dev"))?;
+        let dir_ino =
+            parts.next().and_then(|p| p.parse().ok()).ok_or_else(|| invalid("bad inode"))?;
+        let options = parts.next().ok_or_else(|| invalid("missing options"))?.to_string();
+        let mut number = || -> Result<u64> {
+            parts.next().and_then(|p| p.parse().ok()).ok_or_else(|| invalid("missing field"))
+        };
+        let is_dir = number()? != 0;
+        let size = number()?;
+        let modified = number()?;
+        let ino = number()?;
+        let name = parts.next().filter(|n| !n.is_empty()).ok_or_else(|| invalid("missing name"))?;
+
+        Ok(Self {
+            dir: (dev, dir_ino),
+            options,
+            last: PageKey { name: name.to_string(), is_dir, size, modified, ino },
+        })
+    }
+}
+
+fn caseless_cmp(a: &str, b: &str) -> CmpOrdering {
+    a.chars().flat_map(char::to_lowercase).cmp(b.chars().flat_map(char::to_lowercase))
+}
+
+/// Case-insensitive comparison that orders embedded numbers by value.
+fn natural_cmp(a: &str, b: &str) -> CmpOrdering {
+    let mut a = a.chars().flat_map(char::to_lowercase).peekable();
+    let mut b = b.chars().flat_map(char::to_lowercase).peekable();
+
+    loop {
+        match (a.peek().copied(), b.peek().copied()) {
+            (None, None) => return CmpOrdering::Equal,
+            (None, Some(_)) => return CmpOrdering::Less,
+            (Some(_), None) => return CmpOrdering::Greater,
+            (Some(ca), Some(cb)) if ca.is_ascii_digit() && cb.is_ascii_digit() => {
+                let mut da = String::new();
+                while let Some(c) = a.next_if(char::is_ascii_digit) {
+                    da.push(c);
+                }
+                let mut db = String::new();
+                while let Some(c) = b.next_if(char::is_ascii_digit) {
+                    db.push(c);
+                }
+                let ta = da.trim_start_matches('0');
+                let tb = db.trim_start_matches('0');
+                let order = ta.len().cmp(&tb.len()).then_with(|| ta.cmp(tb));
+                if order != CmpOrdering::Equal {
+                    return order;
+                }
+            },
+            (Some(ca), Some(cb)) => {
+                if ca != cb {
+                    return ca.cmp(&cb);
                 }
+                a.next();
+                b.next();
             },
-            Err(_) => ("unknown".to_string(), 0, 0),
+        }
+    }
+}
+
+fn type_rank(fields: &SortFields<'_>) -> (u8, String) {
+    if fields.is_dir {
+        return (0, String::new());
+    }
+    match Path::new(fields.name).extension().and_then(|e| e.to_str()) {
+        Some(ext) => (1, ext.to_lowercase()),
+        None => (2, String::new()),
+    }
+}
+
+fn is_glob(pattern: &str) -> bool {
+    pattern.contains(['*', '?', '['])
+}
+
+/// Case-insensitive glob match supporting `*`, `?` and `[...]` classes (with `!` negation and
+/// ranges).
+fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().flat_map(char::to_lowercase).collect();
+    let name: Vec<char> = name.chars().flat_map(char::to_lowercase).collect();
+
+    let (mut p, mut n) = (0, 0);
+    // Position to resume from after the most recent `*`: (pattern index, name index).
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while n < name.len() {
+        let step = match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p + 1, n));
+                p += 1;
+                continue;
+            },
+            Some('?') => Some(p + 1),
+            Some('[') => match_class(&pattern, p, name[n]),
+            Some(&c) if c == name[n] => Some(p + 1),
+            _ => None,
         };
+        match (step, backtrack) {
+            (Some(next), _) => {
+                p = next;
+                n += 1;
+            },
+            (None, Some((bp, bn))) => {
+                p = bp;
+                n = bn + 1;
+                backtrack = Some((bp, bn + 1));
+            },
+            (None, None) => return false,
+        }
+    }
 
-        entries.push(FileEntryDto {
-            name: name.clone(),
-            path: path.to_string_lossy().to_string(),
-            kind,
-            size,
-            modified,
-        });
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Matches `c` against the class starting at `pattern[start] == '['`. Returns the index just past
+/// the class on success. An unterminated class matches a literal `[`.
+fn match_class(pattern: &[char], start: usize, c: char) -> Option<usize> {
+    let Some(close) = pattern[start + 1..].iter().skip(1).position(|&ch| ch == ']') else {
+        return (c == '[').then_some(start + 1);
+    };
+    let close = start + 2 + close;
+
+    let mut body = &pattern[start + 1..close];
+    let negated = matches!(body.first(), Some('!') | Some('^'));
+    if negated {
+        body = &body[1..];
     }
 
-    let next_cursor = if end < total { Some(end.to_string()) } else { None };
+    let mut found = false;
+    let mut i = 0;
+    while i < body.len() {
+        if i + 2 < body.len() && body[i + 1] == '-' {
+            found |= (body[i]..=body[i + 2]).contains(&c);
+            i += 3;
+        } else {
+            found |= body[i] == c;
+            i += 1;
+        }
+    }
 
-    Ok(ListResult { entries, next_cursor })
+    (found != negated).then_some(close + 1)
+}
+
+#[cfg(unix)]
+fn dir_identity(md: &fs::Metadata) -> (u64, u64) {
+    use std::os::unix::fs::MetadataExt;
+    (md.dev(), md.ino())
+}
+
+#[cfg(not(unix))]
+fn dir_identity(_md: &fs::Metadata) -> (u64, u64) {
+    (0, 0)
+}
+
+#[cfg(unix)]
+fn entry_ino(entry: &fs::DirEntry) -> u64 {
+    use std::os::unix::fs::DirEntryExt;
+    entry.ino()
+}
+
+#[cfg(not(unix))]
+fn entry_ino(_entry: &fs::DirEntry) -> u64 {
+    0
+}
+
+/// Stats a single entry. Detailed metadata is collected when `owners` is given.
+fn read_entry(name: String, path: &Path, owners: Option<&mut OwnerCache>) -> FileEntryDto {
+    let md = fs::symlink_metadata(path).ok();
+    let (kind, size, modified) = match &md {
+        Some(md) => {
+            let modified = md
+                .modified()
+                .ok()
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+            let kind = FileKind::from_file_type(md.file_type());
+            let size = if kind == FileKind::File { md.len() } else { 0 };
+            (kind, size, modified)
+        },
+        None => (FileKind::Unknown, 0, 0),
+    };
+    let link = (kind == FileKind::Symlink).then(|| read_link_target(path));
+    let hidden = is_hidden(&name, md.as_ref());
+    let metadata = md.as_ref().zip(owners).map(|(md, owners)| entry_metadata(md, owners));
+
+    FileEntryDto {
+        name,
+        path: path.to_string_lossy().to_string(),
+        kind,
+        size,
+        modified,
+        link,
+        hidden,
+        metadata,
+    }
+}
+
+fn read_link_target(path: &Path) -> LinkTarget {
+    let target = fs::read_link(path).map(|t| t.to_string_lossy().to_string()).unwrap_or_default();
+    // `metadata` follows the whole chain, so any dangling hop (or a loop) marks the link broken.
+    let kind = fs::metadata(path).ok().map(|md| FileKind::from_file_type(md.file_type()));
+    LinkTarget { path: target, kind }
 }
 
 fn os_str_to_string(s: impl AsRef<OsStr>) -> String {
     s.as_ref().to_string_lossy().into_owned()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::testing::ScratchDir;
+
+    fn entry(name: &str, kind: FileKind, size: u64) -> FileEntryDto {
+        FileEntryDto {
+            name: name.to_string(),
+            path: format!("/tmp/{name}"),
+            kind,
+            size,
+            modified: 0,
+            link: None,
+            hidden: false,
+            metadata: None,
+        }
+    }
+
+    fn names(entries: &[FileEntryDto]) -> Vec<&str> {
+        entries.iter().map(|e| e.name.as_str()).collect()
+    }
+
+    #[test]
+    fn merge_sorted_interleaves_batches() {
+        let options = ListOptions { sort: SortKey::Size, ..ListOptions::default() };
+        let mut entries = vec![
+            entry("src", FileKind::Dir, 0),
+            entry("a", FileKind::File, 1),
+            entry("c", FileKind::File, 3),
+        ];
+        let batch = vec![
+            entry("docs", FileKind::Dir, 0),
+            entry("b", FileKind::File, 2),
+            entry("d", FileKind::File, 4),
+        ];
+        options.merge_sorted(&mut entries, batch);
+        assert_eq!(names(&entries), ["docs", "src", "a", "b", "c", "d"]);
+
+        options.merge_sorted(&mut entries, vec![entry("e", FileKind::File, 5)]);
+        assert_eq!(names(&entries).last(), Some(&"e"));
+        options.merge_sorted(&mut entries, Vec::new());
+        assert_eq!(entries.len(), 7);
+
+        let descending = ListOptions { direction: SortDirection::Descending, ..options };
+        let mut entries = vec![entry("b", FileKind::File, 2)];
+        descending.merge_sorted(&mut entries, vec![entry("c", FileKind::File, 3)]);
+        assert_eq!(names(&entries), ["c", "b"]);
+    }
+
+    #[test]
+    fn glob_patterns() {
+        assert!(glob_match("", ""));
+        assert!(!glob_match("", "a"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match("**", "anything.txt"));
+        assert!(glob_match("a**b", "a/x/b"));
+        assert!(glob_match("*.RS", "main.rs"));
+        assert!(!glob_match("*.rs", "main.rsx"));
+        assert!(glob_match("?.txt", "a.txt"));
+        assert!(!glob_match("?.txt", ".txt"));
+        assert!(glob_match("file[0-9].log", "file7.log"));
+        assert!(!glob_match("file[!0-9].log", "file7.log"));
+        assert!(glob_match("file[^0-9].log", "filex.log"));
+        assert!(glob_match("[]]", "]"));
+        // An unterminated class is a literal bracket.
+        assert!(glob_match("[abc", "[abc"));
+        assert!(glob_match("*a*b*c*", "xxaxxbxxcxx"));
+        assert!(!glob_match("*a*b*c*", "xxcxxbxxaxx"));
+    }
+
+    #[test]
+    fn name_filter() {
+        let options = |filter: &str| ListOptions {
+            show_hidden: false,
+            filter: Some(filter.to_string()),
+            ..ListOptions::default()
+        };
+        assert!(options("").matches("notes.txt"));
+        assert!(!options("").matches(".hidden"));
+        assert!(options("NOTE").matches("notes.txt"));
+        assert!(options("*.txt").matches("notes.txt"));
+        assert!(!options("*.md").matches("notes.txt"));
+    }
+
+    #[test]
+    fn natural_order() {
+        assert_eq!(natural_cmp("file2", "file10"), CmpOrdering::Less);
+        assert_eq!(natural_cmp("File10", "file9"), CmpOrdering::Greater);
+        assert_eq!(natural_cmp("a", "a1"), CmpOrdering::Less);
+        assert_eq!(natural_cmp("", "0"), CmpOrdering::Less);
+        // Leading zeros do not change the value.
+        assert_eq!(natural_cmp("img007", "img7"), CmpOrdering::Equal);
+        assert_eq!(natural_cmp("img007", "img8"), CmpOrdering::Less);
+        assert_eq!(natural_cmp("v1.010", "v1.9"), CmpOrdering::Greater);
+        assert_eq!(natural_cmp("00", "0"), CmpOrdering::Equal);
+        assert_eq!(natural_cmp("18446744073709551616", "9"), CmpOrdering::Greater);
+
+        // The full comparison still tells equal values apart, so the order is total.
+        let options = ListOptions { sort: SortKey::Natural, ..ListOptions::default() };
+        let mut entries =
+            ["img10", "img007", "img7", "img1"].map(|name| entry(name, FileKind::File, 0)).to_vec();
+        entries.sort_by(|a, b| options.compare(a, b));
+        assert_eq!(names(&entries), ["img1", "img007", "img7", "img10"]);
+    }
+
+    #[test]
+    fn cursor_round_trip() {
+        let cursor = Cursor {
+            dir: (7, 42),
+            options: ListOptions::default().signature(),
+            last: PageKey {
+                name: "résumé 2.pdf".to_string(),
+                is_dir: true,
+                size: 1 << 40,
+                modified: 1_700_000_000,
+                ino: u64::MAX,
+            },
+        };
+        let encoded = cursor.encode();
+        assert!(encoded.bytes().all(|b| b.is_ascii_hexdigit()));
+
+        let decoded = Cursor::decode(&encoded).unwrap();
+        assert_eq!(decoded.dir, cursor.dir);
+        assert_eq!(decoded.options, cursor.options);
+        assert_eq!(decoded.last.name, cursor.last.name);
+        assert_eq!(
+            (decoded.last.is_dir, decoded.last.size, decoded.last.modified, decoded.last.ino),
+            (true, 1 << 40, 1_700_000_000, u64::MAX)
+        );
+    }
+
+    #[test]
+    fn malformed_cursors_are_rejected() {
+        let hex = |raw: &str| raw.bytes().map(|b| format!("{b:02x}")).collect::<String>();
+        let cases = [
+            String::new(),
+            "abc".to_string(),
+            "zz".to_string(),
+            hex("v1\x001\x002\x00opts\x000\x000\x000\x000\x00name"),
+            hex("v2\x00one\x002\x00opts\x000\x000\x000\x000\x00name"),
+            hex("v2\x001\x002\x00opts\x000\x000\x000\x000\x00"),
+            hex("v2\x001\x002\x00opts\x000\x000"),
+            "ff".repeat(4),
+        ];
+        for cursor in cases {
+            assert!(
+                matches!(Cursor::decode(&cursor), Err(Error::InvalidCursor(_))),
+                "accepted {cursor:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn cursors_only_resume_their_listing() {
+        let dir = ScratchDir::new("cursor");
+        for ix in 0..5 {
+            fs::write(dir.join(format!("f{ix}")), "").unwrap();
+        }
+        let path = dir.to_str().unwrap();
+        let list = |cursor: Option<&str>, options: ListOptions| {
+            list_dir_sync(ListParams { path, limit: 2, cursor, options })
+        };
+
+        let first = list(None, ListOptions::default()).unwrap();
+        let cursor = first.next_cursor.unwrap();
+        let second = list(Some(&cursor), ListOptions::default()).unwrap();
+        assert_eq!(names(&second.entries), ["f2", "f3"]);
+
+        let by_size = ListOptions { sort: SortKey::Size, ..ListOptions::default() };
+        assert!(matches!(list(Some(&cursor), by_size), Err(Error::InvalidCursor(_))));
+
+        #[cfg(unix)]
+        {
+            // Keep the old folder around so the new one cannot reuse its inode.
+            let old = dir.with_extension("old");
+            let _ = fs::remove_dir_all(&old);
+            fs::rename(&dir, &old).unwrap();
+            fs::create_dir_all(&dir).unwrap();
+            let stale = list(Some(&cursor), ListOptions::default());
+            assert!(matches!(stale, Err(Error::StaleCursor(_))));
+            fs::remove_dir_all(&old).unwrap();
+        }
+    }
+
+    #[test]
+    fn stream_batches_arrive_sorted() {
+        let dir = ScratchDir::new("listing-stream");
+        for ix in 0..20 {
+            fs::write(dir.join(format!("f{ix}")), "x".repeat(ix)).unwrap();
+        }
+        let options = ListOptions {
+            sort: SortKey::Size,
+            direction: SortDirection::Descending,
+            ..ListOptions::default()
+        };
+        let stream = list_dir_stream(StreamParams {
+            path: dir.to_str().unwrap(),
+            batch_size: 7,
+            options: options.clone(),
+        });
+        let mut entries = Vec::new();
+        for batch in stream {
+            let batch = batch.unwrap();
+            assert!(batch.is_sorted_by(|a, b| options.compare(a, b) != CmpOrdering::Greater));
+            options.merge_sorted(&mut entries, batch);
+        }
+        let sizes: Vec<u64> = entries.iter().map(|e| e.size).collect();
+        assert_eq!(sizes, (0..20).rev().collect::<Vec<u64>>());
+    }
+}
@@ -0,0 +1,272 @@
+use std::{
+    collections::HashMap,
+    fs::Metadata,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+
+/// A point in time with nanosecond precision, relative to the Unix epoch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub struct Timestamp {
+    pub secs: i64,
+    pub nanos: u32,
+}
+
+impl Timestamp {
+    pub fn from_system_time(time: SystemTime) -> Self {
+        match time.duration_since(UNIX_EPOCH) {
+            Ok(d) => Self { secs: d.as_secs() as i64, nanos: d.subsec_nanos() },
+            Err(err) => {
+                // Before the epoch: borrow a second so `nanos` stays positive.
+                let d = err.duration();
+                match d.subsec_nanos() {
+                    0 => Self { secs: -(d.as_secs() as i64), nanos: 0 },
+                    n => Self { secs: -(d.as_secs() as i64) - 1, nanos: 1_000_000_000 - n },
+                }
+            },
+        }
+    }
+}
+
+/// The expensive part of a listing entry: permission bits, ownership, all timestamps and inode
+/// information. Only filled in when a listing asks for detailed metadata.
+#[derive(Debug, Clone, Serialize)]
+pub struct EntryMetadata {
+    /// Raw `st_mode` bits (file type and permissions). On non-Unix platforms only the read-only
+    /// flag is reflected.
+    pub mode: u32,
+    /// `ls -l` style permission string, e.g. `rwxr-xr-x`.
+    pub permissions: String,
+    pub readonly: bool,
+    pub uid: Option<u32>,
+    pub gid: Option<u32>,
+    pub owner: Option<String>,
+    pub group: Option<String>,
+    pub created: Option<Timestamp>,
+    pub modified: Option<Timestamp>,
+    pub accessed: Option<Timestamp>,
+    /// Inode change time (`st_ctime`); not available on every platform.
+    pub changed: Option<Timestamp>,
+    pub nlink: Option<u64>,
+    pub ino: Option<u64>,
+    pub dev: Option<u64>,
+}
+
+/// Caches uid/gid to name lookups for the duration of one listing; a directory usually has very
+/// few distinct owners, while the lookups go through NSS and can be slow.
+#[derive(Default)]
+pub struct OwnerCache {
+    users: HashMap<u32, Option<String>>,
+    groups: HashMap<u32, Option<String>>,
+}
+
+impl OwnerCache {
+    pub fn user_name(&mut self, uid: u32) -> Option<String> {
+        self.users.entry(uid).or_insert_with(|| lookup_user(uid)).clone()
+    }
+
+    pub fn group_name(&mut self, gid: u32) -> Option<String> {
+        self.groups.entry(gid).or_insert_with(|| lookup_group(gid)).clone()
+    }
+}
+
+pub fn entry_metadata(md: &Metadata, owners: &mut OwnerCache) -> EntryMetadata {
+    let readonly = md.permissions().readonly();
+    let created = md.created().ok().map(Timestamp::from_system_time);
+    let modified = md.modified().ok().map(Timestamp::from_system_time);
+    let accessed = md.accessed().ok().map(Timestamp::from_system_time);
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+
+        let mode = md.mode();
+        EntryMetadata {
+            mode,
+            permissions: permission_string(mode),
+            readonly,
+            uid: Some(md.uid()),
+            gid: Some(md.gid()),
+            owner: owners.user_name(md.uid()),
+            group: owners.group_name(md.gid()),
+            created,
+            modified,
+            accessed,
+            changed: Some(Timestamp { secs: md.ctime(), nanos: md.ctime_nsec() as u32 }),
+            nlink: Some(md.nlink()),
+            ino: Some(md.ino()),
+            dev: Some(md.dev()),
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = owners;
+        let mode = if readonly { 0o444 } else { 0o644 };
+        EntryMetadata {
+            mode,
+            permissions: permission_string(mode),
+            readonly,
+            uid: None,
+            gid: None,
+            owner: None,
+            group: None,
+            created,
+            modified,
+            accessed,
+            changed: None,
+            nlink: None,
+            ino: None,
+            dev: None,
+        }
+    }
+}
+
+/// Whether the entry is hidden: a dot-file, or on Windows an entry with the hidden attribute.
+pub fn is_hidden(name: &str, md: Option<&Metadata>) -> bool {
+    if name.starts_with('.') {
+        return true;
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::MetadataExt;
+        const FILE_ATTRIBUTE_HIDDEN: u32 = 0x2;
+        if let Some(md) = md {
+            return md.file_attributes() & FILE_ATTRIBUTE_HIDDEN != 0;
+        }
+    }
+    #[cfg(not(windows))]
+    let _ = md;
+    false
+}
+
+/// Formats the permission bits of `mode` like `ls -l` does, including setuid/setgid/sticky.
+pub fn permission_string(mode: u32) -> String {
+    let bit = |mask: u32, c: char| if mode & mask != 0 { c } else { '-' };
+    let special = |exec: bool, special: bool, set: char| match (exec, special) {
+        (true, true) => set,
+        (false, true) => set.to_ascii_uppercase(),
+        (true, false) => 'x',
+        (false, false) => '-',
+    };
+
+    [
+        bit(0o400, 'r'),
+        bit(0o200, 'w'),
+        special(mode & 0o100 != 0, mode & 0o4000 != 0, 's'),
+        bit(0o040, 'r'),
+        bit(0o020, 'w'),
+        special(mode & 0o010 != 0, mode & 0o2000 != 0, 's'),
+        bit(0o004, 'r'),
+        bit(0o002, 'w'),
+        special(mode & 0o001 != 0, mode & 0o1000 != 0, 't'),
+    ]
+    .iter()
+    .collect()
+}
+
+#[cfg(unix)]
+fn lookup_user(uid: u32) -> Option<String> {
+    use std::ffi::CStr;
+
+    let mut buf = vec![0 as libc::c_char; 1024];
+    loop {
+        let mut pwd: libc::passwd = unsafe { std::mem::zeroed() };
+        let mut result: *mut libc::passwd = std::ptr::null_mut();
+        // SAFETY: all pointers refer to live, correctly sized buffers owned by this frame.
+        let rc =
+            unsafe { libc::getpwuid_r(uid, &mut pwd, buf.as_mut_ptr(), buf.len(), &mut result) };
+        if rc == libc::ERANGE && buf.len() < 1 << 20 {
+            buf.resize(buf.len() * 2, 0);
+            continue;
+        }
+        if rc != 0 || result.is_null() {
+            return None;
+        }
+        // SAFETY: on success `pw_name` points to a NUL-terminated string inside `buf`.
+        return Some(unsafe { CStr::from_ptr(pwd.pw_name) }.to_string_lossy().into_owned());
+    }
+}
+
+#[cfg(unix)]
+fn lookup_group(gid: u32) -> Option<String> {
+    use std::ffi::CStr;
+
+    let mut buf = vec![0 as libc::c_char; 1024];
+    loop {
+        let mut grp: libc::group = unsafe { std::mem::zeroed() };
+        let mut result: *mut libc::group = std::ptr::null_mut();
+        // SAFETY: all pointers refer to live, correctly sized buffers owned by this frame.
+        let rc =
+            unsafe { libc::getgrgid_r(gid, &mut grp, buf.as_mut_ptr(), buf.len(), &mut result) };
+        if rc == libc::ERANGE && buf.len() < 1 << 20 {
+            buf.resize(buf.len() * 2, 0);
+            continue;
+        }
+        if rc != 0 || result.is_null() {
+            return None;
+        }
+        // SAFETY: on success `gr_name` points to a NUL-terminated string inside `buf`.
+        return Some(unsafe { CStr::from_ptr(grp.gr_name) }.to_string_lossy().into_owned());
+    }
+}
+
+#[cfg(not(unix))]
+fn lookup_user(_uid: u32) -> Option<String> {
+    None
+}
+
+#[cfg(not(unix))]
+fn lookup_group(_gid: u32) -> Option<String> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::testing::ScratchDir;
+
+    #[test]
+    fn permission_string_shows_special_bits() {
+        assert_eq!(permission_string(0o644), "rw-r--r--");
+        assert_eq!(permission_string(0o755), "rwxr-xr-x");
+        assert_eq!(permission_string(0o4755), "rwsr-xr-x");
+        assert_eq!(permission_string(0o4644), "rwSr--r--");
+        assert_eq!(permission_string(0o2755), "rwxr-sr-x");
+        assert_eq!(permission_string(0o2745), "rwxr-Sr-x");
+        assert_eq!(permission_string(0o1777), "rwxrwxrwt");
+        assert_eq!(permission_string(0o1776), "rwxrwxrwT");
+        assert_eq!(permission_string(0o7000), "--S--S--T");
+        // File type bits are ignored.
+        assert_eq!(permission_string(0o100_600), "rw-------");
+    }
+
+    #[test]
+    fn dot_files_are_hidden() {
+        assert!(is_hidden(".git", None));
+        assert!(is_hidden(".", None));
+        assert!(!is_hidden("README.md", None));
+        assert!(!is_hidden("a.b", None));
+
+        let dir = ScratchDir::new("metadata-plain");
+        let path = dir.file("plain-name", b"");
+        assert!(!is_hidden("plain-name", fs_metadata(&path).as_ref()));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn hidden_attribute_is_hidden() {
+        let dir = ScratchDir::new("metadata-hidden");
+        let path = dir.file("plain-name", b"");
+        let status = std::process::Command::new("attrib").arg("+h").arg(&path).status().unwrap();
+        assert!(status.success());
+        assert!(is_hidden("plain-name", fs_metadata(&path).as_ref()));
+        // Deleting hidden files needs the attribute gone first.
+        let _ = std::process::Command::new("attrib").arg("-h").arg(&path).status();
+    }
+
+    fn fs_metadata(path: &std::path::Path) -> Option<Metadata> {
+        std::fs::metadata(path).ok()
+    }
+}
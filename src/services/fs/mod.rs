@@ -1 +1,8 @@
+pub mod atomic;
+pub mod compare;
+pub mod dir_size;
 pub mod listing;
+pub mod metadata;
+pub mod ops;
+pub mod trash;
+pub mod watcher;
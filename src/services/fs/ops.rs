@@ -0,0 +1,787 @@
+use std::{
+    fs,
+    io::{self, ErrorKind, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    core::errors::{Error, Result},
+    services::fs::atomic::temp_sibling,
+};
+
+/// What to do when the destination of a copy or move already exists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConflictPolicy {
+    /// Leave the existing entry alone and do not transfer the source.
+    Skip,
+    /// Replace the existing entry. Folders are merged, applying the policy to their children.
+    Overwrite,
+    /// Transfer under a free name such as `report (1).pdf`.
+    KeepBoth,
+    /// Consult the caller for every conflict.
+    #[default]
+    Ask,
+}
+
+/// A destination that already exists, as passed to the `ask` callback.
+#[derive(Debug, Clone)]
+pub struct Conflict {
+    pub source: PathBuf,
+    pub target: PathBuf,
+    pub source_is_dir: bool,
+    pub target_is_dir: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TransferOptions {
+    pub conflict: ConflictPolicy,
+    /// Copy permissions, timestamps and (where allowed) ownership along with the contents.
+    pub preserve_metadata: bool,
+}
+
+impl Default for TransferOptions {
+    fn default() -> Self {
+        Self { conflict: ConflictPolicy::Ask, preserve_metadata: true }
+    }
+}
+
+/// Files larger than this are copied in chunks, so progress is reported and pausing or cancelling
+/// takes effect in the middle of the file. Smaller ones go through `fs::copy`, which can use
+/// copy-on-write clones and in-kernel copies.
+const CHUNKED_COPY_THRESHOLD: u64 = 4 * 1024 * 1024;
+const COPY_CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Hooks for answering conflicts, following progress and stopping a running transfer. Every
+/// method has a no-op default; a plain closure works as a listener that only answers conflicts.
+pub trait TransferListener {
+    /// Decides a conflict when the policy is [`ConflictPolicy::Ask`]. Answering `Ask` skips.
+    fn on_conflict(&mut self, _conflict: &Conflict) -> ConflictPolicy {
+        ConflictPolicy::Skip
+    }
+
+    /// Another `bytes` of file contents have been written.
+    fn on_bytes(&mut self, _bytes: u64) {}
+
+    /// A file or link has been transferred completely.
+    fn on_entry_done(&mut self, _source: &Path) {}
+
+    /// `source` was moved to `target` by a rename, without its contents being copied.
+    fn on_renamed(&mut self, _source: &Path, _target: &Path) {}
+
+    /// Called before every entry and between chunks of large files. May block while the transfer
+    /// is paused; returning [`Error::Cancelled`] stops it.
+    fn checkpoint(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl<F: FnMut(&Conflict) -> ConflictPolicy> TransferListener for F {
+    fn on_conflict(&mut self, conflict: &Conflict) -> ConflictPolicy {
+        self(conflict)
+    }
+}
+
+/// An entry that could not be transferred.
+#[derive(Debug)]
+pub struct OpFailure {
+    pub source: PathBuf,
+    /// The folder `source` was being transferred into; retrying means transferring `source` into
+    /// it again.
+    pub dest_dir: PathBuf,
+    pub error: Error,
+}
+
+/// Outcome of a copy or move. Failures of individual entries do not stop the rest of the
+/// transfer, so callers should check `failed` rather than rely on an early error.
+#[derive(Debug, Default)]
+pub struct OpReport {
+    /// `(source, destination)` of every top-level entry that was transferred.
+    pub completed: Vec<(PathBuf, PathBuf)>,
+    /// Destinations in `completed` that already existed: folders merged into and entries replaced.
+    /// Removing them would not restore the previous state.
+    pub replaced: Vec<PathBuf>,
+    /// Entries left alone because of a conflict, at any depth.
+    pub skipped: Vec<PathBuf>,
+    pub failed: Vec<OpFailure>,
+    /// The listener stopped the transfer before every entry was handled.
+    pub cancelled: bool,
+}
+
+impl OpReport {
+    pub fn is_ok(&self) -> bool {
+        self.failed.is_empty() && !self.cancelled
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Copy,
+    Move,
+}
+
+/// Copies `sources` into `dest_dir`, recursing into folders.
+///
+/// Symlinks are copied as links, not followed. Copying an entry onto itself (pasting into the same
+/// folder) always keeps both, like a file manager's "duplicate".
+pub fn copy_entries(
+    sources: &[PathBuf],
+    dest_dir: &Path,
+    options: TransferOptions,
+    listener: &mut dyn TransferListener,
+) -> OpReport {
+    transfer(sources, dest_dir, options, listener, Mode::Copy)
+}
+
+/// Moves `sources` into `dest_dir`. Falls back to copy-then-delete when the destination is on a
+/// different filesystem; the source is only deleted if everything below it was copied.
+pub fn move_entries(
+    sources: &[PathBuf],
+    dest_dir: &Path,
+    options: TransferOptions,
+    listener: &mut dyn TransferListener,
+) -> OpReport {
+    transfer(sources, dest_dir, options, listener, Mode::Move)
+}
+
+/// The top-level conflicts a transfer of `sources` into `dest_dir` would run into, so a UI can ask
+/// once up front instead of per entry.
+pub fn find_conflicts(sources: &[PathBuf], dest_dir: &Path) -> Vec<Conflict> {
+    sources
+        .iter()
+        .filter_map(|source| {
+            let target = dest_dir.join(source.file_name()?);
+            let target_md = fs::symlink_metadata(&target).ok()?;
+            if same_entry(source, &target) {
+                return None;
+            }
+            let source_is_dir = fs::symlink_metadata(source).map(|md| md.is_dir()).ok()?;
+            Some(Conflict {
+                source: source.clone(),
+                target,
+                source_is_dir,
+                target_is_dir: target_md.is_dir(),
+            })
+        })
+        .collect()
+}
+
+/// Renames `path` within its folder and returns the new path.
+pub fn rename_entry(path: &Path, new_name: &str) -> Result<PathBuf> {
+    validate_name(new_name)?;
+    let parent = path.parent().ok_or_else(|| Error::InvalidName(path.display().to_string()))?;
+    let target = parent.join(new_name);
+    if target == path {
+        return Ok(target);
+    }
+    // A case-only rename on a case-insensitive filesystem "conflicts" with the entry itself.
+    if fs::symlink_metadata(&target).is_ok() && !same_entry(path, &target) {
+        return Err(Error::AlreadyExists(target.display().to_string()));
+    }
+    fs::rename(path, &target)?;
+    Ok(target)
+}
+
+pub fn create_dir(parent: &Path, name: &str) -> Result<PathBuf> {
+    validate_name(name)?;
+    let path = parent.join(name);
+    fs::create_dir(&path).map_err(|err| exists_error(err, &path))?;
+    Ok(path)
+}
+
+/// Creates an empty file; never truncates an existing one.
+pub fn create_file(parent: &Path, name: &str) -> Result<PathBuf> {
+    validate_name(name)?;
+    let path = parent.join(name);
+    fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&path)
+        .map_err(|err| exists_error(err, &path))?;
+    Ok(path)
+}
+
+/// Creates a symlink called `name` in `parent` pointing at `target`, which is stored as given
+/// (relative targets stay relative).
+pub fn create_symlink(parent: &Path, name: &str, target: &Path) -> Result<PathBuf> {
+    validate_name(name)?;
+    let path = parent.join(name);
+    let target_is_dir = fs::metadata(parent.join(target)).map(|md| md.is_dir()).unwrap_or(false);
+    symlink(target, &path, target_is_dir).map_err(|err| exists_error(err, &path))?;
+    Ok(path)
+}
+
+/// The first of `name`, `name (1)`, `name (2)`, ... that does not exist yet. The number goes
+/// before the extension of files (`notes (1).txt`) but after the whole name of folders.
+pub fn unique_name(path: &Path) -> PathBuf {
+    if fs::symlink_metadata(path).is_err() {
+        return path.to_path_buf();
+    }
+    let parent = path.parent().unwrap_or(Path::new(""));
+    let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    let is_dir = fs::symlink_metadata(path).map(|md| md.is_dir()).unwrap_or(false);
+    let (stem, ext) = match name.rfind('.') {
+        Some(dot) if dot > 0 && !is_dir => name.split_at(dot),
+        _ => (name.as_str(), ""),
+    };
+
+    (1..)
+        .map(|n| parent.join(format!("{stem} ({n}){ext}")))
+        .find(|candidate| fs::symlink_metadata(candidate).is_err())
+        .expect("unbounded range always yields a free name")
+}
+
+/// Rejects names that are empty, special (`.`/`..`) or would escape the folder.
+pub fn validate_name(name: &str) -> Result<()> {
+    let invalid = name.trim().is_empty()
+        || name == "."
+        || name == ".."
+        || name.contains('/')
+        || name.contains('\0')
+        || (cfg!(windows) && name.contains('\\'));
+    if invalid {
+        return Err(Error::InvalidName(name.to_string()));
+    }
+    Ok(())
+}
+
+struct Transfer<'a> {
+    options: TransferOptions,
+    listener: &'a mut dyn TransferListener,
+    report: OpReport,
+}
+
+fn transfer(
+    sources: &[PathBuf],
+    dest_dir: &Path,
+    options: TransferOptions,
+    listener: &mut dyn TransferListener,
+    mode: Mode,
+) -> OpReport {
+    let mut ctx = Transfer { options, listener, report: OpReport::default() };
+    for source in sources {
+        match transfer_one(source, dest_dir, mode, &mut ctx) {
+            Ok(()) => {},
+            Err(Error::Cancelled) => {
+                ctx.report.cancelled = true;
+                break;
+            },
+            Err(error) => ctx.fail(source, dest_dir, error),
+        }
+    }
+    ctx.report
+}
+
+fn transfer_one(source: &Path, dest_dir: &Path, mode: Mode, ctx: &mut Transfer<'_>) -> Result<()> {
+    ctx.listener.checkpoint()?;
+    let name =
+        source.file_name().ok_or_else(|| Error::InvalidName(source.display().to_string()))?;
+    let source_md = fs::symlink_metadata(source)?;
+    if source_md.is_dir() && is_within(dest_dir, source) {
+        return Err(Error::Other(format!(
+            "cannot {} {} into itself",
+            if mode == Mode::Copy { "copy" } else { "move" },
+            source.display()
+        )));
+    }
+
+    let target = dest_dir.join(name);
+    let existed = fs::symlink_metadata(&target).is_ok();
+    let placement = if same_entry(source, &target) {
+        match mode {
+            Mode::Copy => Some(Placement::At(unique_name(&target))),
+            Mode::Move => None,
+        }
+    } else {
+        ctx.resolve(source, &target)?
+    };
+    let Some(placement) = placement else {
+        ctx.report.skipped.push(source.to_path_buf());
+        return Ok(());
+    };
+
+    let target = ctx.place(source, placement, mode)?;
+    if existed && target == dest_dir.join(name) {
+        ctx.report.replaced.push(target.clone());
+    }
+    ctx.report.completed.push((source.to_path_buf(), target));
+    Ok(())
+}
+
+/// Copies `source` to exactly `target`, which must not exist yet. Used to replay a recorded copy.
+pub fn copy_to(source: &Path, target: &Path) -> Result<()> {
+    transfer_to(source, target, Mode::Copy)
+}
+
+/// Moves `source` to exactly `target`, which must not exist yet, copying across filesystems if
+/// necessary. Used to replay or reverse a recorded move.
+pub fn move_to(source: &Path, target: &Path) -> Result<()> {
+    transfer_to(source, target, Mode::Move)
+}
+
+fn transfer_to(source: &Path, target: &Path, mode: Mode) -> Result<()> {
+    if fs::symlink_metadata(target).is_ok() {
+        return Err(Error::AlreadyExists(target.display().to_string()));
+    }
+    let mut skip = |_: &Conflict| ConflictPolicy::Skip;
+    let mut ctx = Transfer {
+        options: TransferOptions::default(),
+        listener: &mut skip,
+        report: OpReport::default(),
+    };
+    match mode {
+        Mode::Copy => ctx.copy_item(source, target)?,
+        Mode::Move => ctx.move_item(source, target)?,
+    }
+    match ctx.report.failed.into_iter().next() {
+        Some(failure) => Err(failure.error),
+        None => Ok(()),
+    }
+}
+
+/// Where [`Transfer::resolve`] decided an entry goes.
+enum Placement {
+    /// A free path, or an existing folder to merge into.
+    At(PathBuf),
+    /// An existing entry to replace once the new one is complete.
+    Replace(PathBuf),
+}
+
+impl Transfer<'_> {
+    fn fail(&mut self, source: &Path, dest_dir: &Path, error: Error) {
+        self.report.failed.push(OpFailure {
+            source: source.to_path_buf(),
+            dest_dir: dest_dir.to_path_buf(),
+            error,
+        });
+    }
+
+    /// Decides where `source` should go when `target` may already exist. `None` means skip.
+    fn resolve(&mut self, source: &Path, target: &Path) -> Result<Option<Placement>> {
+        let Ok(target_md) = fs::symlink_metadata(target) else {
+            return Ok(Some(Placement::At(target.to_path_buf())));
+        };
+        let source_is_dir = fs::symlink_metadata(source)?.is_dir();
+
+        let mut policy = self.options.conflict;
+        if policy == ConflictPolicy::Ask {
+            policy = self.listener.on_conflict(&Conflict {
+                source: source.to_path_buf(),
+                target: target.to_path_buf(),
+                source_is_dir,
+                target_is_dir: target_md.is_dir(),
+            });
+        }
+
+        match policy {
+            ConflictPolicy::Skip | ConflictPolicy::Ask => Ok(None),
+            ConflictPolicy::KeepBoth => Ok(Some(Placement::At(unique_name(target)))),
+            ConflictPolicy::Overwrite => {
+                if contains_entry(target, source) {
+                    return Err(Error::Other(format!(
+                        "cannot replace {} with an entry inside it",
+                        target.display()
+                    )));
+                }
+                // Folder onto folder merges; anything else replaces the existing entry.
+                if source_is_dir && target_md.is_dir() {
+                    Ok(Some(Placement::At(target.to_path_buf())))
+                } else {
+                    Ok(Some(Placement::Replace(target.to_path_buf())))
+                }
+            },
+        }
+    }
+
+    /// Transfers `source` as [`Transfer::resolve`] decided and returns where it ended up.
+    fn place(&mut self, source: &Path, placement: Placement, mode: Mode) -> Result<PathBuf> {
+        match (placement, mode) {
+            (Placement::At(target), Mode::Copy) => self.copy_item(source, &target).map(|()| target),
+            (Placement::At(target), Mode::Move) => self.move_item(source, &target).map(|()| target),
+            (Placement::Replace(target), mode) => {
+                self.replace_item(source, &target, mode).map(|()| target)
+            },
+        }
+    }
+
+    /// Transfers `source` over the existing entry `target`. The new entry is assembled under a
+    /// temporary name next to `target` and only swapped in once it is complete, so a failure or
+    /// cancellation leaves the old entry as it was. A move across filesystems deletes the source
+    /// last, after the swap.
+    fn replace_item(&mut self, source: &Path, target: &Path, mode: Mode) -> Result<()> {
+        let tmp = temp_sibling(target);
+        let renamed = mode == Mode::Move
+            && match fs::rename(source, &tmp) {
+                Ok(()) => {
+                    self.listener.on_renamed(source, &tmp);
+                    true
+                },
+                Err(err) if err.kind() == ErrorKind::CrossesDevices => false,
+                Err(err) => return Err(err.into()),
+            };
+        if !renamed {
+            let (skipped, failed) = (self.report.skipped.len(), self.report.failed.len());
+            let result = self.copy_item(source, &tmp);
+            if result.is_err()
+                || self.report.skipped.len() != skipped
+                || self.report.failed.len() != failed
+            {
+                discard(&tmp);
+                result?;
+                return Err(Error::Other(format!(
+                    "kept {} because not everything could be transferred",
+                    target.display()
+                )));
+            }
+        }
+
+        if let Err(err) = swap_into_place(&tmp, target) {
+            if renamed {
+                let _ = fs::rename(&tmp, source);
+            } else {
+                discard(&tmp);
+            }
+            return Err(err.into());
+        }
+        if mode == Mode::Move && !renamed {
+            remove_path(source, &fs::symlink_metadata(source)?)?;
+        }
+        Ok(())
+    }
+
+    /// Resolves and transfers every child of `source` into the existing folder `target`. Failures
+    /// are recorded per child; only cancellation stops the loop.
+    fn merge_children(&mut self, source: &Path, target: &Path, mode: Mode) -> Result<()> {
+        for entry in fs::read_dir(source)? {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(err) => {
+                    let dest_dir = target.parent().unwrap_or(target);
+                    self.fail(source, dest_dir, err.into());
+                    continue;
+                },
+            };
+            let child = entry.path();
+            let result = self.listener.checkpoint().and_then(|()| {
+                match self.resolve(&child, &target.join(entry.file_name()))? {
+                    None => {
+                        self.report.skipped.push(child.clone());
+                        Ok(())
+                    },
+                    Some(placement) => self.place(&child, placement, mode).map(|_| ()),
+                }
+            });
+            match result {
+                Ok(()) => {},
+                Err(Error::Cancelled) => return Err(Error::Cancelled),
+                Err(error) => self.fail(&child, target, error),
+            }
+        }
+        Ok(())
+    }
+
+    fn copy_item(&mut self, source: &Path, target: &Path) -> Result<()> {
+        let md = fs::symlink_metadata(source)?;
+        let file_type = md.file_type();
+
+        if file_type.is_dir() {
+            match fs::create_dir(target) {
+                Ok(()) => {},
+                Err(err) if err.kind() == ErrorKind::AlreadyExists && target.is_dir() => {},
+                Err(err) => return Err(err.into()),
+            }
+            self.merge_children(source, target, Mode::Copy)?;
+            // Last, so neither writing the children nor a read-only mode gets in the way.
+            if self.options.preserve_metadata {
+                copy_metadata(&md, target);
+            }
+        } else if file_type.is_symlink() {
+            let link = fs::read_link(source)?;
+            let target_is_dir = fs::metadata(source).map(|md| md.is_dir()).unwrap_or(false);
+            symlink(&link, target, target_is_dir)?;
+            self.listener.on_entry_done(source);
+        } else if file_type.is_file() {
+            if md.len() > CHUNKED_COPY_THRESHOLD {
+                self.copy_file_chunked(source, target, &md)?;
+            } else {
+                // `fs::copy` already carries over the permission bits.
+                fs::copy(source, target)?;
+                self.listener.on_bytes(md.len());
+            }
+            if self.options.preserve_metadata {
+                copy_metadata(&md, target);
+            }
+            self.listener.on_entry_done(source);
+        } else {
+            return Err(Error::Other(format!("cannot copy special file {}", source.display())));
+        }
+        Ok(())
+    }
+
+    /// Copies a large file piece by piece. A partially written target is removed on failure or
+    /// cancellation.
+    fn copy_file_chunked(&mut self, source: &Path, target: &Path, md: &fs::Metadata) -> Result<()> {
+        let result = (|| {
+            let mut reader = fs::File::open(source)?;
+            let mut writer = fs::File::create(target)?;
+            let mut buf = vec![0; COPY_CHUNK_SIZE];
+            loop {
+                self.listener.checkpoint()?;
+                let n = reader.read(&mut buf)?;
+                if n == 0 {
+                    break;
+                }
+                writer.write_all(&buf[..n])?;
+                self.listener.on_bytes(n as u64);
+            }
+            fs::set_permissions(target, md.permissions())?;
+            Ok(())
+        })();
+        if result.is_err() {
+            let _ = fs::remove_file(target);
+        }
+        result
+    }
+
+    fn move_item(&mut self, source: &Path, target: &Path) -> Result<()> {
+        let source_md = fs::symlink_metadata(source)?;
+        if source_md.is_dir() && fs::symlink_metadata(target).is_ok_and(|md| md.is_dir()) {
+            self.merge_children(source, target, Mode::Move)?;
+            // Children that were skipped or failed are still in there; keep the folder then.
+            let _ = fs::remove_dir(source);
+            return Ok(());
+        }
+
+        match fs::rename(source, target) {
+            Ok(()) => {
+                self.listener.on_renamed(source, target);
+                Ok(())
+            },
+            Err(err) if err.kind() == ErrorKind::CrossesDevices => {
+                let (skipped, failed) = (self.report.skipped.len(), self.report.failed.len());
+                self.copy_item(source, target)?;
+                if self.report.skipped.len() == skipped && self.report.failed.len() == failed {
+                    remove_path(source, &source_md)?;
+                }
+                Ok(())
+            },
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+fn remove_path(path: &Path, md: &fs::Metadata) -> io::Result<()> {
+    if md.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
+
+/// Removes a partly built replacement.
+fn discard(path: &Path) {
+    if let Ok(md) = fs::symlink_metadata(path) {
+        let _ = remove_path(path, &md);
+    }
+}
+
+/// Puts `replacement` where the existing `target` is. A file or link is renamed straight over it;
+/// when either side is a folder the old entry is moved aside first, and back if the second rename
+/// fails.
+fn swap_into_place(replacement: &Path, target: &Path) -> io::Result<()> {
+    let target_md = fs::symlink_metadata(target)?;
+    if !target_md.is_dir() && !fs::symlink_metadata(replacement)?.is_dir() {
+        return fs::rename(replacement, target);
+    }
+    let old = temp_sibling(target);
+    fs::rename(target, &old)?;
+    if let Err(err) = fs::rename(replacement, target) {
+        let _ = fs::rename(&old, target);
+        return Err(err);
+    }
+    // The new entry is in place; a leftover of the old one is not worth failing the transfer for.
+    if let Err(err) = remove_path(&old, &target_md) {
+        tracing::warn!("Failed to remove replaced {}: {}", old.display(), err);
+    }
+    Ok(())
+}
+
+/// Best effort: ownership usually cannot be changed without privileges, and some filesystems
+/// reject timestamps, neither of which should fail the copy.
+fn copy_metadata(md: &fs::Metadata, target: &Path) {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let _ = std::os::unix::fs::lchown(target, Some(md.uid()), Some(md.gid()));
+    }
+    // After chown, which clears setuid/setgid bits.
+    let _ = fs::set_permissions(target, md.permissions());
+
+    let mut times = fs::FileTimes::new();
+    if let Ok(accessed) = md.accessed() {
+        times = times.set_accessed(accessed);
+    }
+    if let Ok(modified) = md.modified() {
+        times = times.set_modified(modified);
+    }
+    if let Ok(file) = fs::File::open(target) {
+        let _ = file.set_times(times);
+    }
+}
+
+/// Whether `path` is `dir` or lies somewhere below it.
+fn is_within(path: &Path, dir: &Path) -> bool {
+    match (fs::canonicalize(path), fs::canonicalize(dir)) {
+        (Ok(path), Ok(dir)) => path.starts_with(dir),
+        _ => false,
+    }
+}
+
+/// Whether replacing `target` would take `source` with it: `target` is `source` or a folder above
+/// it. Only the parents are resolved, since a link at `target` is replaced, not followed.
+fn contains_entry(target: &Path, source: &Path) -> bool {
+    let entry = |path: &Path| Some(fs::canonicalize(path.parent()?).ok()?.join(path.file_name()?));
+    match (entry(target), entry(source)) {
+        (Some(target), Some(source)) => source.starts_with(target),
+        _ => false,
+    }
+}
+
+/// Whether both paths name the same directory entry (not merely the same file behind a link).
+fn same_entry(a: &Path, b: &Path) -> bool {
+    let (Some(a_parent), Some(b_parent)) = (a.parent(), b.parent()) else {
+        return false;
+    };
+    if fs::symlink_metadata(b).is_err() {
+        return false;
+    }
+    let same_dir = match (fs::canonicalize(a_parent), fs::canonicalize(b_parent)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    };
+    same_dir && same_inode(a, b)
+}
+
+#[cfg(unix)]
+fn same_inode(a: &Path, b: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    match (fs::symlink_metadata(a), fs::symlink_metadata(b)) {
+        (Ok(a), Ok(b)) => (a.dev(), a.ino()) == (b.dev(), b.ino()),
+        _ => false,
+    }
+}
+
+#[cfg(not(unix))]
+fn same_inode(a: &Path, b: &Path) -> bool {
+    a.file_name().map(|n| n.to_ascii_lowercase()) == b.file_name().map(|n| n.to_ascii_lowercase())
+}
+
+#[cfg(unix)]
+fn symlink(target: &Path, link: &Path, _target_is_dir: bool) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(windows)]
+fn symlink(target: &Path, link: &Path, target_is_dir: bool) -> io::Result<()> {
+    if target_is_dir {
+        std::os::windows::fs::symlink_dir(target, link)
+    } else {
+        std::os::windows::fs::symlink_file(target, link)
+    }
+}
+
+fn exists_error(err: io::Error, path: &Path) -> Error {
+    if err.kind() == ErrorKind::AlreadyExists {
+        Error::AlreadyExists(path.display().to_string())
+    } else {
+        err.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::testing::ScratchDir;
+
+    fn overwrite() -> TransferOptions {
+        TransferOptions { conflict: ConflictPolicy::Overwrite, ..TransferOptions::default() }
+    }
+
+    fn no_temp_files(dir: &Path) -> bool {
+        fs::read_dir(dir)
+            .unwrap()
+            .all(|e| !e.unwrap().file_name().to_string_lossy().ends_with(".tmp"))
+    }
+
+    #[test]
+    fn overwrite_replaces_files_and_folders() {
+        let dir = ScratchDir::new("ops-replace");
+        let (src, dst) = (dir.join("src"), dir.join("dst"));
+        fs::create_dir_all(src.join("folder")).unwrap();
+        fs::create_dir_all(dst.join("file")).unwrap();
+        fs::write(src.join("file"), "new").unwrap();
+        fs::write(src.join("folder/inner"), "inner").unwrap();
+        fs::write(dst.join("folder"), "old").unwrap();
+        fs::write(dst.join("file/old"), "old").unwrap();
+
+        let sources = [src.join("file"), src.join("folder")];
+        let report = copy_entries(&sources, &dst, overwrite(), &mut |_: &Conflict| unreachable!());
+        assert!(report.is_ok(), "{report:?}");
+        assert_eq!(fs::read_to_string(dst.join("file")).unwrap(), "new");
+        assert_eq!(fs::read_to_string(dst.join("folder/inner")).unwrap(), "inner");
+        assert_eq!(report.replaced.len(), 2);
+        assert!(no_temp_files(&dst));
+
+        fs::write(src.join("file"), "moved").unwrap();
+        let report = move_entries(&[src.join("file")], &dst, overwrite(), &mut |_: &Conflict| {
+            ConflictPolicy::Skip
+        });
+        assert!(report.is_ok(), "{report:?}");
+        assert_eq!(fs::read_to_string(dst.join("file")).unwrap(), "moved");
+        assert!(!src.join("file").exists());
+    }
+
+    #[test]
+    fn overwrite_refuses_a_folder_containing_the_source() {
+        let dir = ScratchDir::new("ops-ancestor");
+        let outer = dir.join("x");
+        let source = outer.join("x");
+        fs::create_dir_all(&source).unwrap();
+        fs::write(source.join("keep"), "data").unwrap();
+
+        for mode in [Mode::Copy, Mode::Move] {
+            let report = transfer(
+                std::slice::from_ref(&source),
+                &dir,
+                overwrite(),
+                &mut |_: &Conflict| ConflictPolicy::Skip,
+                mode,
+            );
+            assert_eq!(report.failed.len(), 1);
+            assert_eq!(fs::read_to_string(source.join("keep")).unwrap(), "data");
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn failed_replacement_keeps_the_old_entry() {
+        let dir = ScratchDir::new("ops-failed");
+        let (src, dst) = (dir.join("src"), dir.join("dst"));
+        fs::create_dir_all(&src).unwrap();
+        fs::create_dir_all(&dst).unwrap();
+        fs::write(dst.join("file"), "old").unwrap();
+        // A FIFO cannot be copied, so the replacement never completes.
+        let fifo = src.join("file");
+        let c_path = std::ffi::CString::new(fifo.as_os_str().as_encoded_bytes()).unwrap();
+        // SAFETY: `c_path` is a valid NUL-terminated path.
+        assert_eq!(unsafe { libc::mkfifo(c_path.as_ptr(), 0o644) }, 0);
+
+        let report =
+            copy_entries(&[fifo], &dst, overwrite(), &mut |_: &Conflict| ConflictPolicy::Skip);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(fs::read_to_string(dst.join("file")).unwrap(), "old");
+        assert!(no_temp_files(&dst));
+    }
+}
@@ -0,0 +1,129 @@
+use std::{fs, path::PathBuf};
+
+use ::trash::TrashItem;
+
+use crate::{
+    core::errors::{Error, Result},
+    models::file_entry::FileKind,
+};
+
+/// An entry in the OS trash.
+#[derive(Debug, Clone)]
+pub struct TrashedItem {
+    pub name: String,
+    /// Where the entry lived before it was trashed, and where [`restore`] puts it back.
+    pub original_path: PathBuf,
+    /// Unix timestamp of the deletion.
+    pub deleted: i64,
+    /// `Dir` or `File`; other kinds are not distinguished by the trash.
+    pub kind: FileKind,
+    /// Size in bytes for files, number of direct children for folders.
+    pub size: u64,
+    // Not read where the trash cannot be browsed (macOS).
+    #[cfg_attr(target_os = "macos", allow(dead_code))]
+    item: TrashItem,
+}
+
+/// Moves `paths` to the OS trash. This is what "delete" means throughout the explorer.
+pub fn trash_entries(paths: &[PathBuf]) -> Result<()> {
+    ::trash::delete_all(paths).map_err(trash_error)
+}
+
+/// Deletes `paths` for good, bypassing the trash. Stops at the first failure.
+pub fn delete_permanently(paths: &[PathBuf]) -> Result<()> {
+    for path in paths {
+        let md = fs::symlink_metadata(path)?;
+        if md.is_dir() {
+            fs::remove_dir_all(path)?;
+        } else {
+            fs::remove_file(path)?;
+        }
+    }
+    Ok(())
+}
+
+// The trash crate can only list, restore and purge on Windows and freedesktop systems. On macOS
+// browsing the trash is left to Finder.
+#[cfg(any(
+    target_os = "windows",
+    all(unix, not(target_os = "macos"), not(target_os = "ios"), not(target_os = "android"))
+))]
+mod os {
+    use std::fs;
+
+    use ::trash::{os_limited, TrashItemSize};
+
+    use super::{trash_error, TrashedItem};
+    use crate::{core::errors::Result, models::file_entry::FileKind};
+
+    /// Lists the trash, most recently deleted first.
+    pub fn list_trash() -> Result<Vec<TrashedItem>> {
+        let mut items: Vec<TrashedItem> = os_limited::list()
+            .map_err(trash_error)?
+            .into_iter()
+            .map(|item| {
+                let (kind, size) = match os_limited::metadata(&item).map(|md| md.size) {
+                    Ok(TrashItemSize::Entries(n)) => (FileKind::Dir, n as u64),
+                    Ok(TrashItemSize::Bytes(n)) => (FileKind::File, n),
+                    Err(_) => (FileKind::Unknown, 0),
+                };
+                TrashedItem {
+                    name: item.name.to_string_lossy().into_owned(),
+                    original_path: item.original_path(),
+                    deleted: item.time_deleted,
+                    kind,
+                    size,
+                    item,
+                }
+            })
+            .collect();
+        items.sort_by(|a, b| b.deleted.cmp(&a.deleted).then_with(|| a.name.cmp(&b.name)));
+        Ok(items)
+    }
+
+    /// Puts `items` back where they were deleted from, recreating missing parent folders. Fails
+    /// with `Error::AlreadyExists` if something new already occupies an original location.
+    pub fn restore(items: &[TrashedItem]) -> Result<()> {
+        for item in items {
+            fs::create_dir_all(&item.item.original_parent)?;
+        }
+        os_limited::restore_all(items.iter().map(|i| i.item.clone())).map_err(trash_error)
+    }
+
+    /// Removes `items` from the trash for good.
+    pub fn purge(items: &[TrashedItem]) -> Result<()> {
+        os_limited::purge_all(items.iter().map(|i| i.item.clone())).map_err(trash_error)
+    }
+}
+
+#[cfg(not(any(
+    target_os = "windows",
+    all(unix, not(target_os = "macos"), not(target_os = "ios"), not(target_os = "android"))
+)))]
+mod os {
+    use super::TrashedItem;
+    use crate::core::errors::{Error, Result};
+
+    pub fn list_trash() -> Result<Vec<TrashedItem>> {
+        Err(Error::NotImplemented("listing the trash on this platform"))
+    }
+
+    pub fn restore(_items: &[TrashedItem]) -> Result<()> {
+        Err(Error::NotImplemented("restoring from the trash on this platform"))
+    }
+
+    pub fn purge(_items: &[TrashedItem]) -> Result<()> {
+        Err(Error::NotImplemented("purging the trash on this platform"))
+    }
+}
+
+pub use os::{list_trash, purge, restore};
+
+fn trash_error(err: ::trash::Error) -> Error {
+    match err {
+        ::trash::Error::RestoreCollision { path, .. } => {
+            Error::AlreadyExists(path.display().to_string())
+        },
+        err => Error::Other(format!("trash: {err}")),
+    }
+}
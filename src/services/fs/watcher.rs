@@ -0,0 +1,268 @@
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use notify::{
+    event::{Flag, ModifyKind},
+    Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher,
+};
+
+use crate::core::errors::{Error, Result};
+
+/// Quiet period after the last event before a batch is delivered.
+pub const DEFAULT_DEBOUNCE: Duration = Duration::from_millis(150);
+
+/// Upper bound on how long a continuous stream of events (a build writing thousands of files) can
+/// hold back a batch.
+const MAX_BATCH_LATENCY: Duration = Duration::from_secs(1);
+
+/// A coalesced set of changes to the watched directory.
+#[derive(Debug, Default)]
+pub struct WatchBatch {
+    /// Paths that were created, modified, removed or renamed (both the old and the new name). Each
+    /// path appears once; callers should re-stat it to find out its current state.
+    pub changed: Vec<PathBuf>,
+    /// The event queue overflowed or the directory itself changed; the listing should be reloaded
+    /// from scratch.
+    pub rescan: bool,
+}
+
+/// Watches a single directory (not recursively) and delivers debounced [`WatchBatch`]es. Watching
+/// stops when this is dropped.
+pub struct DirWatcher {
+    rx: mpsc::Receiver<WatchBatch>,
+    _watcher: RecommendedWatcher,
+}
+
+impl DirWatcher {
+    /// Blocks until the next batch. Returns `None` once the watcher has shut down.
+    pub fn recv(&self) -> Option<WatchBatch> {
+        self.rx.recv().ok()
+    }
+}
+
+/// Starts watching the direct children of `path`.
+///
+/// Raw events are collected until `debounce` passes without a new one (or at most one second) and
+/// then delivered as a single [`WatchBatch`], so saving a file or extracting an archive produces
+/// one update instead of hundreds. Changed paths start with `path` as given, even where the
+/// platform reports them with symlinks resolved (FSEvents under `/tmp` on macOS).
+pub fn watch_dir(path: &Path, debounce: Duration) -> Result<DirWatcher> {
+    let (raw_tx, raw_rx) = mpsc::channel::<notify::Result<Event>>();
+    let mut watcher = notify::recommended_watcher(move |event| {
+        let _ = raw_tx.send(event);
+    })
+    .map_err(watch_error)?;
+    watcher.watch(path, RecursiveMode::NonRecursive).map_err(watch_error)?;
+
+    let (tx, rx) = mpsc::channel();
+    let root = WatchRoot {
+        canonical: fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf()),
+        given: path.to_path_buf(),
+    };
+    thread::Builder::new()
+        .name("nohrs-watch".into())
+        .spawn(move || coalesce(&root, raw_rx, tx, debounce))?;
+
+    Ok(DirWatcher { rx, _watcher: watcher })
+}
+
+/// The watched folder as the caller named it, and with symlinks resolved.
+struct WatchRoot {
+    given: PathBuf,
+    canonical: PathBuf,
+}
+
+impl WatchRoot {
+    /// `path` under the caller's name for the folder.
+    fn to_given(&self, path: PathBuf) -> PathBuf {
+        match path.strip_prefix(&self.canonical) {
+            Ok(rest) if self.canonical != self.given => self.given.join(rest),
+            _ => path,
+        }
+    }
+}
+
+/// Groups raw notify events into batches until the watcher (and with it `raw_rx`'s sender) or the
+/// consumer goes away. What was collected when the watcher goes is still delivered.
+fn coalesce(
+    root: &WatchRoot,
+    raw_rx: mpsc::Receiver<notify::Result<Event>>,
+    tx: mpsc::Sender<WatchBatch>,
+    debounce: Duration,
+) {
+    while let Ok(first) = raw_rx.recv() {
+        let started = Instant::now();
+        let mut changed: Vec<PathBuf> = Vec::new();
+        let mut seen: HashSet<PathBuf> = HashSet::new();
+        let mut rescan = false;
+        let mut disconnected = false;
+
+        let mut next = Some(first);
+        while let Some(event) = next.take() {
+            match event {
+                Ok(event) => {
+                    if event.flag() == Some(Flag::Rescan) {
+                        rescan = true;
+                    }
+                    // The directory itself going away (or being renamed) invalidates the whole
+                    // listing; attribute changes on it do not matter.
+                    let replaces_root = matches!(
+                        event.kind,
+                        EventKind::Remove(_) | EventKind::Modify(ModifyKind::Name(_))
+                    );
+                    if !matches!(event.kind, EventKind::Access(_)) {
+                        for path in event.paths.into_iter().map(|path| root.to_given(path)) {
+                            if path == root.given {
+                                rescan |= replaces_root;
+                            } else if seen.insert(path.clone()) {
+                                changed.push(path);
+                            }
+                        }
+                    }
+                },
+                Err(err) => {
+                    tracing::warn!("Watch error for {}: {}", root.given.display(), err);
+                    rescan = true;
+                },
+            }
+
+            let remaining = MAX_BATCH_LATENCY.saturating_sub(started.elapsed());
+            if remaining.is_zero() {
+                break;
+            }
+            match raw_rx.recv_timeout(debounce.min(remaining)) {
+                Ok(event) => next = Some(event),
+                Err(mpsc::RecvTimeoutError::Timeout) => break,
+                Err(mpsc::RecvTimeoutError::Disconnected) => {
+                    disconnected = true;
+                    break;
+                },
+            }
+        }
+
+        let delivered =
+            (changed.is_empty() && !rescan) || tx.send(WatchBatch { changed, rescan }).is_ok();
+        if disconnected || !delivered {
+            return;
+        }
+    }
+}
+
+fn watch_error(err: notify::Error) -> Error {
+    Error::Other(format!("watch failed: {err}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use notify::event::{
+        AccessKind, CreateKind, DataChange, MetadataKind, ModifyKind, RemoveKind, RenameMode,
+    };
+
+    use super::*;
+
+    const DEBOUNCE: Duration = Duration::from_millis(20);
+
+    fn root(path: &str) -> WatchRoot {
+        WatchRoot { given: PathBuf::from(path), canonical: PathBuf::from(path) }
+    }
+
+    fn event(kind: EventKind, path: &str) -> notify::Result<Event> {
+        Ok(Event::new(kind).add_path(PathBuf::from(path)))
+    }
+
+    /// Runs `coalesce` over `events` and returns every batch it delivers.
+    fn run(root: WatchRoot, events: Vec<notify::Result<Event>>) -> Vec<WatchBatch> {
+        let (raw_tx, raw_rx) = mpsc::channel();
+        for event in events {
+            raw_tx.send(event).unwrap();
+        }
+        drop(raw_tx);
+        let (tx, rx) = mpsc::channel();
+        coalesce(&root, raw_rx, tx, DEBOUNCE);
+        rx.into_iter().collect()
+    }
+
+    #[test]
+    fn burst_is_one_deduplicated_batch_without_access_events() {
+        let batches = run(root("/w"), vec![
+            event(EventKind::Create(CreateKind::File), "/w/a"),
+            event(EventKind::Modify(ModifyKind::Data(DataChange::Content)), "/w/a"),
+            event(EventKind::Access(AccessKind::Read), "/w/b"),
+            event(EventKind::Remove(RemoveKind::File), "/w/c"),
+        ]);
+        assert_eq!(batches.len(), 1);
+        assert_eq!(batches[0].changed, [PathBuf::from("/w/a"), PathBuf::from("/w/c")]);
+        assert!(!batches[0].rescan);
+    }
+
+    #[test]
+    fn access_only_delivers_nothing() {
+        let batches = run(root("/w"), vec![event(EventKind::Access(AccessKind::Read), "/w/a")]);
+        assert!(batches.is_empty());
+    }
+
+    #[test]
+    fn quiet_period_ends_a_batch() {
+        let (raw_tx, raw_rx) = mpsc::channel();
+        let (tx, rx) = mpsc::channel();
+        let worker = thread::spawn(move || coalesce(&root("/w"), raw_rx, tx, DEBOUNCE));
+
+        raw_tx.send(event(EventKind::Create(CreateKind::File), "/w/a")).unwrap();
+        assert_eq!(rx.recv().unwrap().changed, [PathBuf::from("/w/a")]);
+        raw_tx.send(event(EventKind::Create(CreateKind::File), "/w/b")).unwrap();
+        assert_eq!(rx.recv().unwrap().changed, [PathBuf::from("/w/b")]);
+
+        drop(raw_tx);
+        worker.join().unwrap();
+        assert!(rx.recv().is_err());
+    }
+
+    #[test]
+    fn removing_or_renaming_the_root_asks_for_a_rescan() {
+        for kind in [
+            EventKind::Remove(RemoveKind::Folder),
+            EventKind::Modify(ModifyKind::Name(RenameMode::From)),
+        ] {
+            let batches = run(root("/w"), vec![event(kind, "/w")]);
+            assert_eq!(batches.len(), 1);
+            assert!(batches[0].rescan);
+            assert!(batches[0].changed.is_empty());
+        }
+        // Attribute changes on the folder itself don't matter.
+        let batches = run(root("/w"), vec![event(
+            EventKind::Modify(ModifyKind::Metadata(MetadataKind::Permissions)),
+            "/w",
+        )]);
+        assert!(batches.is_empty());
+    }
+
+    #[test]
+    fn errors_and_overflow_ask_for_a_rescan() {
+        let batches = run(root("/w"), vec![Err(notify::Error::generic("queue overflow"))]);
+        assert!(batches[0].rescan);
+
+        let overflow = Event::new(EventKind::Other).set_flag(Flag::Rescan);
+        let batches = run(root("/w"), vec![Ok(overflow)]);
+        assert!(batches[0].rescan);
+    }
+
+    #[test]
+    fn canonical_paths_are_reported_under_the_given_root() {
+        let root = WatchRoot {
+            given: PathBuf::from("/tmp/w"),
+            canonical: PathBuf::from("/private/tmp/w"),
+        };
+        let batches = run(root, vec![
+            event(EventKind::Create(CreateKind::File), "/private/tmp/w/a"),
+            event(EventKind::Remove(RemoveKind::Folder), "/private/tmp/w"),
+        ]);
+        assert_eq!(batches[0].changed, [PathBuf::from("/tmp/w/a")]);
+        assert!(batches[0].rescan);
+    }
+}
@@ -0,0 +1,758 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::{mpsc, Arc, Condvar, Mutex, MutexGuard},
+    thread,
+    time::{Duration, Instant},
+};
+
+use walkdir::WalkDir;
+
+use crate::{
+    core::errors::{Error, Result},
+    services::fs::{
+        ops::{copy_entries, move_entries, OpFailure, OpReport, TransferListener, TransferOptions},
+        trash::trash_entries,
+    },
+};
+
+/// How often a running job reports progress.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// How long the idle worker sleeps before checking whether the queue has been dropped.
+const IDLE_POLL: Duration = Duration::from_secs(1);
+
+pub type JobId = u64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobKind {
+    Copy,
+    Move,
+    /// Moves `sources` to the trash; `dest` is not used.
+    Trash,
+}
+
+impl JobKind {
+    /// Present participle for progress labels ("Copying 3 items").
+    pub fn verb(self) -> &'static str {
+        match self {
+            JobKind::Copy => "Copying",
+            JobKind::Move => "Moving",
+            JobKind::Trash => "Trashing",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobState {
+    Queued,
+    /// Counting the files and bytes to transfer.
+    Scanning,
+    Running,
+    Paused,
+    Completed,
+    /// Finished, but some entries could not be transferred; see [`JobSnapshot::errors`].
+    Failed,
+    Cancelled,
+}
+
+impl JobState {
+    pub fn is_finished(self) -> bool {
+        matches!(self, JobState::Completed | JobState::Failed | JobState::Cancelled)
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            JobState::Queued => "Queued",
+            JobState::Scanning => "Preparing",
+            JobState::Running => "Running",
+            JobState::Paused => "Paused",
+            JobState::Completed => "Done",
+            JobState::Failed => "Failed",
+            JobState::Cancelled => "Cancelled",
+        }
+    }
+}
+
+/// What a job does: copy or move `sources` into the folder `dest`, or move them to the trash.
+#[derive(Debug, Clone)]
+pub struct JobSpec {
+    pub kind: JobKind,
+    pub sources: Vec<PathBuf>,
+    pub dest: PathBuf,
+    /// Conflicts below the top level cannot be asked about from the worker; with
+    /// [`ConflictPolicy::Ask`](crate::services::fs::ops::ConflictPolicy::Ask) they are skipped.
+    pub options: TransferOptions,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct JobProgress {
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+    pub files_done: u64,
+    pub files_total: u64,
+    /// The entry being transferred right now.
+    pub current: Option<PathBuf>,
+    /// Bytes per second while the job was actually running (pauses excluded).
+    pub throughput: f64,
+}
+
+impl JobProgress {
+    /// Completed share between 0 and 1, by bytes or, for trees of empty files, by file count.
+    pub fn fraction(&self) -> f32 {
+        let (done, total) = if self.bytes_total > 0 {
+            (self.bytes_done, self.bytes_total)
+        } else {
+            (self.files_done, self.files_total)
+        };
+        if total == 0 {
+            return 0.0;
+        }
+        (done as f64 / total as f64).clamp(0.0, 1.0) as f32
+    }
+
+    /// Estimated time left at the current throughput, once there is one to go by.
+    pub fn eta(&self) -> Option<Duration> {
+        if self.throughput <= 0.0 {
+            return None;
+        }
+        let remaining = self.bytes_total.saturating_sub(self.bytes_done);
+        Some(Duration::from_secs_f64(remaining as f64 / self.throughput))
+    }
+}
+
+/// An entry that failed to transfer. Retrying transfers `source` into `dest_dir` again.
+#[derive(Debug, Clone)]
+pub struct JobError {
+    pub source: PathBuf,
+    pub dest_dir: PathBuf,
+    pub message: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct JobSnapshot {
+    pub id: JobId,
+    pub spec: JobSpec,
+    pub state: JobState,
+    pub progress: JobProgress,
+    /// Unresolved failures; retried or skipped ones are removed.
+    pub errors: Vec<JobError>,
+    /// `(source, destination)` of every top-level entry that was transferred.
+    pub completed: Vec<(PathBuf, PathBuf)>,
+    /// Destinations in `completed` that existed before (merged or replaced).
+    pub replaced: Vec<PathBuf>,
+}
+
+impl JobSnapshot {
+    /// "Copying report.pdf" or "Moving 3 items".
+    pub fn title(&self) -> String {
+        match self.spec.sources.as_slice() {
+            [source] => format!(
+                "{} {}",
+                self.spec.kind.verb(),
+                source.file_name().unwrap_or(source.as_os_str()).to_string_lossy()
+            ),
+            sources => format!("{} {} items", self.spec.kind.verb(), sources.len()),
+        }
+    }
+}
+
+pub enum JobEvent {
+    /// A job was added or its state, progress or errors changed.
+    Changed(JobSnapshot),
+    Removed(JobId),
+}
+
+struct Slot {
+    job: JobSnapshot,
+    /// Set while the worker has picked the job up.
+    started: bool,
+    pause: bool,
+    /// What a paused job goes back to when resumed: queued, or the phase it was paused in.
+    resume_state: JobState,
+    cancel: bool,
+}
+
+struct Inner {
+    slots: Vec<Slot>,
+    next_id: JobId,
+}
+
+struct Shared {
+    inner: Mutex<Inner>,
+    /// Signalled when a job is queued, paused, resumed or cancelled.
+    changed: Condvar,
+    events: mpsc::Sender<JobEvent>,
+}
+
+impl Shared {
+    fn lock(&self) -> MutexGuard<'_, Inner> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn emit(&self, job: &JobSnapshot) {
+        let _ = self.events.send(JobEvent::Changed(job.clone()));
+    }
+
+    /// Applies `f` to job `id` and reports the result. Does nothing if the job is gone.
+    fn update(&self, id: JobId, f: impl FnOnce(&mut Slot)) {
+        let mut inner = self.lock();
+        if let Some(slot) = inner.slots.iter_mut().find(|s| s.job.id == id) {
+            f(slot);
+            self.emit(&slot.job);
+        }
+        self.changed.notify_all();
+    }
+}
+
+/// A FIFO of copy and move jobs run one at a time on a worker thread.
+///
+/// Handles are cheap to clone. The worker stops once every handle has been dropped; a job that is
+/// running at that point is finished first.
+#[derive(Clone)]
+pub struct JobQueue {
+    shared: Arc<Shared>,
+}
+
+impl JobQueue {
+    /// Starts the worker. Every change to a job is reported on the returned receiver.
+    pub fn start() -> Result<(JobQueue, mpsc::Receiver<JobEvent>)> {
+        let (events, rx) = mpsc::channel();
+        let shared = Arc::new(Shared {
+            inner: Mutex::new(Inner { slots: Vec::new(), next_id: 1 }),
+            changed: Condvar::new(),
+            events,
+        });
+
+        let worker_shared = shared.clone();
+        thread::Builder::new().name("nohrs-jobs".into()).spawn(move || worker(worker_shared))?;
+        Ok((JobQueue { shared }, rx))
+    }
+
+    pub fn enqueue(&self, spec: JobSpec) -> JobId {
+        let mut inner = self.shared.lock();
+        let id = inner.next_id;
+        inner.next_id += 1;
+        let job = JobSnapshot {
+            id,
+            spec,
+            state: JobState::Queued,
+            progress: JobProgress::default(),
+            errors: Vec::new(),
+            completed: Vec::new(),
+            replaced: Vec::new(),
+        };
+        self.shared.emit(&job);
+        inner.slots.push(Slot {
+            job,
+            started: false,
+            pause: false,
+            resume_state: JobState::Queued,
+            cancel: false,
+        });
+        self.shared.changed.notify_all();
+        id
+    }
+
+    /// Holds a queued job back, or stops a running one at the next chunk boundary.
+    pub fn pause(&self, id: JobId) {
+        self.shared.update(id, |slot| {
+            if !slot.job.state.is_finished() && !slot.pause {
+                slot.pause = true;
+                slot.resume_state = slot.job.state;
+                slot.job.state = JobState::Paused;
+            }
+        });
+    }
+
+    pub fn resume(&self, id: JobId) {
+        self.shared.update(id, |slot| {
+            if slot.job.state == JobState::Paused {
+                slot.pause = false;
+                slot.job.state = slot.resume_state;
+            }
+        });
+    }
+
+    /// Cancels a job. Entries that were already transferred stay where they are; a partially
+    /// copied file is removed.
+    pub fn cancel(&self, id: JobId) {
+        self.shared.update(id, |slot| {
+            if slot.job.state.is_finished() {
+                return;
+            }
+            slot.cancel = true;
+            if !slot.started {
+                slot.job.state = JobState::Cancelled;
+            }
+        });
+    }
+
+    /// Queues a new job for the failed entry at `index` of job `id`, or for all of them if `index`
+    /// is `None`. Failures that went into the same folder are retried together.
+    pub fn retry(&self, id: JobId, index: Option<usize>) {
+        let mut retries: Vec<(PathBuf, Vec<PathBuf>)> = Vec::new();
+        let mut spec = None;
+        self.shared.update(id, |slot| {
+            for error in take_errors(slot, index) {
+                match retries.iter_mut().find(|(dest, _)| *dest == error.dest_dir) {
+                    Some((_, sources)) => sources.push(error.source),
+                    None => retries.push((error.dest_dir, vec![error.source])),
+                }
+            }
+            spec = Some(slot.job.spec.clone());
+        });
+
+        let Some(spec) = spec else {
+            return;
+        };
+        for (dest, sources) in retries {
+            self.enqueue(JobSpec { sources, dest, ..spec.clone() });
+        }
+    }
+
+    /// Gives up on the failed entry at `index` of job `id`, or on all of them if `index` is `None`.
+    pub fn skip(&self, id: JobId, index: Option<usize>) {
+        self.shared.update(id, |slot| {
+            take_errors(slot, index);
+        });
+    }
+
+    /// Forgets every finished job.
+    pub fn clear_finished(&self) {
+        let mut inner = self.shared.lock();
+        inner.slots.retain(|slot| {
+            let keep = !slot.job.state.is_finished();
+            if !keep {
+                let _ = self.shared.events.send(JobEvent::Removed(slot.job.id));
+            }
+            keep
+        });
+    }
+}
+
+/// Removes errors from a failed job; once none are left it counts as completed.
+fn take_errors(slot: &mut Slot, index: Option<usize>) -> Vec<JobError> {
+    let errors = match index {
+        Some(ix) if ix < slot.job.errors.len() => vec![slot.job.errors.remove(ix)],
+        Some(_) => Vec::new(),
+        None => std::mem::take(&mut slot.job.errors),
+    };
+    if slot.job.state == JobState::Failed && slot.job.errors.is_empty() {
+        slot.job.state = JobState::Completed;
+    }
+    errors
+}
+
+fn worker(shared: Arc<Shared>) {
+    loop {
+        let (id, spec) = {
+            let mut inner = shared.lock();
+            loop {
+                let next = inner
+                    .slots
+                    .iter_mut()
+                    .find(|s| s.job.state == JobState::Queued && !s.pause && !s.cancel);
+                if let Some(slot) = next {
+                    slot.started = true;
+                    slot.job.state = JobState::Scanning;
+                    shared.emit(&slot.job);
+                    break (slot.job.id, slot.job.spec.clone());
+                }
+                if Arc::strong_count(&shared) == 1 {
+                    return;
+                }
+                inner = shared
+                    .changed
+                    .wait_timeout(inner, IDLE_POLL)
+                    .unwrap_or_else(|e| e.into_inner())
+                    .0;
+            }
+        };
+        run_job(&shared, id, &spec);
+    }
+}
+
+fn run_job(shared: &Shared, id: JobId, spec: &JobSpec) {
+    let mut runner = Runner::new(shared, id);
+
+    let mut totals = HashMap::new();
+    if spec.kind == JobKind::Trash {
+        // One rename per entry, so progress goes by entries rather than bytes.
+        runner.progress.files_total = spec.sources.len() as u64;
+    } else {
+        for source in &spec.sources {
+            match tree_totals(source, || runner.checkpoint()) {
+                Ok(size) => {
+                    runner.progress.bytes_total += size.0;
+                    runner.progress.files_total += size.1;
+                    totals.insert(source.clone(), size);
+                },
+                // Reported per entry by the transfer itself.
+                Err(Error::Cancelled) => break,
+                Err(_) => {},
+            }
+        }
+    }
+    runner.totals = totals;
+    runner.set_state(JobState::Running);
+
+    let report = match spec.kind {
+        JobKind::Copy => copy_entries(&spec.sources, &spec.dest, spec.options, &mut runner),
+        JobKind::Move => move_entries(&spec.sources, &spec.dest, spec.options, &mut runner),
+        JobKind::Trash => trash_each(&spec.sources, &mut runner),
+    };
+
+    let throughput = runner.throughput();
+    let progress = runner.progress;
+    shared.update(id, |slot| {
+        slot.job.progress = JobProgress { current: None, throughput, ..progress };
+        slot.job.completed = report.completed;
+        slot.job.replaced = report.replaced;
+        slot.job.errors = report
+            .failed
+            .into_iter()
+            .map(|f| JobError {
+                source: f.source,
+                dest_dir: f.dest_dir,
+                message: f.error.to_string(),
+            })
+            .collect();
+        slot.job.state = if report.cancelled || slot.cancel {
+            JobState::Cancelled
+        } else if slot.job.errors.is_empty() {
+            JobState::Completed
+        } else {
+            JobState::Failed
+        };
+    });
+}
+
+/// Moves `sources` to the trash one at a time, so one that can't be trashed doesn't hold back the
+/// rest and the job can be paused or cancelled in between. Completed entries are reported as
+/// `(path, path)`, since where the trash keeps them is up to the platform.
+fn trash_each(sources: &[PathBuf], runner: &mut Runner<'_>) -> OpReport {
+    let mut report = OpReport::default();
+    for source in sources {
+        if runner.checkpoint().is_err() {
+            report.cancelled = true;
+            break;
+        }
+        match trash_entries(std::slice::from_ref(source)) {
+            Ok(()) => report.completed.push((source.clone(), source.clone())),
+            Err(error) => report.failed.push(OpFailure {
+                source: source.clone(),
+                dest_dir: source.parent().map(Path::to_path_buf).unwrap_or_default(),
+                error,
+            }),
+        }
+        runner.on_entry_done(source);
+    }
+    report
+}
+
+/// Bytes and number of files and links below `path`, for the progress total. `checkpoint` is
+/// consulted every few hundred entries so a huge tree can be cancelled while it is counted.
+fn tree_totals(path: &Path, mut checkpoint: impl FnMut() -> Result<()>) -> Result<(u64, u64)> {
+    let (mut bytes, mut files) = (0, 0);
+    for (ix, entry) in WalkDir::new(path).follow_links(false).into_iter().enumerate() {
+        if ix % 256 == 0 {
+            checkpoint()?;
+        }
+        let Ok(entry) = entry else {
+            continue;
+        };
+        if entry.file_type().is_dir() {
+            continue;
+        }
+        files += 1;
+        if entry.file_type().is_file() {
+            bytes += entry.metadata().map(|md| md.len()).unwrap_or(0);
+        }
+    }
+    Ok((bytes, files))
+}
+
+/// Follows a running job: counts progress, publishes it at most every [`PROGRESS_INTERVAL`] and
+/// blocks while the job is paused.
+struct Runner<'a> {
+    shared: &'a Shared,
+    id: JobId,
+    progress: JobProgress,
+    /// Scanned `(bytes, files)` per top-level source, credited at once when it is renamed.
+    totals: HashMap<PathBuf, (u64, u64)>,
+    last_report: Instant,
+    /// Time spent running (not paused), for the throughput.
+    active: Duration,
+    active_since: Instant,
+}
+
+impl<'a> Runner<'a> {
+    fn new(shared: &'a Shared, id: JobId) -> Self {
+        let now = Instant::now();
+        Runner {
+            shared,
+            id,
+            progress: JobProgress::default(),
+            totals: HashMap::new(),
+            last_report: now,
+            active: Duration::ZERO,
+            active_since: now,
+        }
+    }
+
+    fn throughput(&self) -> f64 {
+        let secs = (self.active + self.active_since.elapsed()).as_secs_f64();
+        if secs > 0.0 {
+            self.progress.bytes_done as f64 / secs
+        } else {
+            0.0
+        }
+    }
+
+    fn set_state(&mut self, state: JobState) {
+        let progress = self.progress.clone();
+        self.shared.update(self.id, |slot| {
+            slot.job.progress = progress;
+            if slot.pause {
+                slot.resume_state = state;
+            } else if !slot.cancel {
+                slot.job.state = state;
+            }
+        });
+        self.last_report = Instant::now();
+    }
+
+    fn report(&mut self, force: bool) {
+        if !force && self.last_report.elapsed() < PROGRESS_INTERVAL {
+            return;
+        }
+        self.progress.throughput = self.throughput();
+        let progress = self.progress.clone();
+        self.shared.update(self.id, |slot| slot.job.progress = progress);
+        self.last_report = Instant::now();
+    }
+}
+
+impl TransferListener for Runner<'_> {
+    fn on_bytes(&mut self, bytes: u64) {
+        self.progress.bytes_done += bytes;
+        self.report(false);
+    }
+
+    fn on_entry_done(&mut self, source: &Path) {
+        self.progress.files_done += 1;
+        self.progress.current = Some(source.to_path_buf());
+        self.report(false);
+    }
+
+    fn on_renamed(&mut self, source: &Path, target: &Path) {
+        // Nested entries of a merged folder were not counted separately; measure them where they
+        // ended up.
+        let (bytes, files) = match self.totals.get(source) {
+            Some(size) => *size,
+            None => tree_totals(target, || Ok(())).unwrap_or_default(),
+        };
+        self.progress.bytes_done += bytes;
+        self.progress.files_done += files;
+        self.progress.current = Some(source.to_path_buf());
+        self.report(false);
+    }
+
+    fn checkpoint(&mut self) -> Result<()> {
+        let mut inner = self.shared.lock();
+        let mut paused = false;
+        loop {
+            let Some(slot) = inner.slots.iter().find(|s| s.job.id == self.id) else {
+                return Err(Error::Cancelled);
+            };
+            if slot.cancel {
+                return Err(Error::Cancelled);
+            }
+            if !slot.pause {
+                break;
+            }
+            if !paused {
+                self.active += self.active_since.elapsed();
+                paused = true;
+            }
+            inner = self.shared.changed.wait(inner).unwrap_or_else(|e| e.into_inner());
+        }
+        if paused {
+            self.active_since = Instant::now();
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::testing::ScratchDir;
+
+    /// A queue without a worker, so jobs stay where the test puts them.
+    fn idle_queue() -> (JobQueue, mpsc::Receiver<JobEvent>) {
+        let (events, rx) = mpsc::channel();
+        let shared = Arc::new(Shared {
+            inner: Mutex::new(Inner { slots: Vec::new(), next_id: 1 }),
+            changed: Condvar::new(),
+            events,
+        });
+        (JobQueue { shared }, rx)
+    }
+
+    fn spec(kind: JobKind, sources: &[&str], dest: &str) -> JobSpec {
+        JobSpec {
+            kind,
+            sources: sources.iter().map(PathBuf::from).collect(),
+            dest: PathBuf::from(dest),
+            options: TransferOptions::default(),
+        }
+    }
+
+    fn state(queue: &JobQueue, id: JobId) -> JobState {
+        let inner = queue.shared.lock();
+        inner.slots.iter().find(|s| s.job.id == id).unwrap().job.state
+    }
+
+    /// Puts job `id` where the worker would have it after picking it up.
+    fn start(queue: &JobQueue, id: JobId) {
+        queue.shared.update(id, |slot| {
+            slot.started = true;
+            slot.job.state = JobState::Scanning;
+        });
+    }
+
+    #[test]
+    fn queued_jobs_pause_resume_and_cancel() {
+        let (queue, _events) = idle_queue();
+        let id = queue.enqueue(spec(JobKind::Copy, &["/a"], "/b"));
+        assert_eq!(state(&queue, id), JobState::Queued);
+
+        queue.pause(id);
+        assert_eq!(state(&queue, id), JobState::Paused);
+        queue.resume(id);
+        assert_eq!(state(&queue, id), JobState::Queued);
+
+        queue.cancel(id);
+        assert_eq!(state(&queue, id), JobState::Cancelled);
+        queue.pause(id);
+        queue.resume(id);
+        assert_eq!(state(&queue, id), JobState::Cancelled);
+
+        queue.clear_finished();
+        assert!(queue.shared.lock().slots.is_empty());
+    }
+
+    #[test]
+    fn resume_returns_to_the_phase_the_job_was_paused_in() {
+        let (queue, _events) = idle_queue();
+        let id = queue.enqueue(spec(JobKind::Copy, &["/a"], "/b"));
+        start(&queue, id);
+
+        queue.pause(id);
+        queue.pause(id);
+        queue.resume(id);
+        assert_eq!(state(&queue, id), JobState::Scanning);
+
+        // Scanning finishes while paused: the job stays paused and resumes running.
+        queue.pause(id);
+        Runner::new(&queue.shared, id).set_state(JobState::Running);
+        assert_eq!(state(&queue, id), JobState::Paused);
+        queue.resume(id);
+        assert_eq!(state(&queue, id), JobState::Running);
+    }
+
+    #[test]
+    fn cancelling_a_running_job_leaves_the_state_to_the_worker() {
+        let (queue, _events) = idle_queue();
+        let id = queue.enqueue(spec(JobKind::Copy, &["/a"], "/b"));
+        start(&queue, id);
+        queue.cancel(id);
+        assert_eq!(state(&queue, id), JobState::Scanning);
+
+        let mut runner = Runner::new(&queue.shared, id);
+        assert!(matches!(runner.checkpoint(), Err(Error::Cancelled)));
+        runner.set_state(JobState::Running);
+        assert_eq!(state(&queue, id), JobState::Scanning);
+    }
+
+    #[test]
+    fn retry_and_skip_resolve_failures() {
+        let (queue, _events) = idle_queue();
+        let id = queue.enqueue(spec(JobKind::Move, &["/src/a", "/src/b", "/src/c"], "/dest"));
+        queue.shared.update(id, |slot| {
+            slot.job.state = JobState::Failed;
+            slot.job.errors = [("/src/a", "/dest"), ("/src/b/x", "/dest/b"), ("/src/c", "/dest")]
+                .into_iter()
+                .map(|(source, dest_dir)| JobError {
+                    source: PathBuf::from(source),
+                    dest_dir: PathBuf::from(dest_dir),
+                    message: "denied".to_string(),
+                })
+                .collect();
+        });
+
+        queue.skip(id, Some(1));
+        assert_eq!(state(&queue, id), JobState::Failed);
+        queue.retry(id, None);
+        assert_eq!(state(&queue, id), JobState::Completed);
+
+        let inner = queue.shared.lock();
+        let retried: Vec<&JobSpec> = inner.slots.iter().skip(1).map(|s| &s.job.spec).collect();
+        assert_eq!(retried.len(), 1);
+        assert_eq!(retried[0].kind, JobKind::Move);
+        assert_eq!(retried[0].dest, PathBuf::from("/dest"));
+        assert_eq!(retried[0].sources, [PathBuf::from("/src/a"), PathBuf::from("/src/c")]);
+    }
+
+    #[test]
+    fn eta_goes_by_throughput() {
+        let progress = JobProgress {
+            bytes_done: 400,
+            bytes_total: 1000,
+            throughput: 100.0,
+            ..JobProgress::default()
+        };
+        assert_eq!(progress.eta(), Some(Duration::from_secs(6)));
+        assert!((progress.fraction() - 0.4).abs() < f32::EPSILON);
+        assert_eq!(JobProgress { throughput: 0.0, ..progress }.eta(), None);
+
+        let empty_files = JobProgress { files_done: 1, files_total: 4, ..JobProgress::default() };
+        assert!((empty_files.fraction() - 0.25).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn worker_copies_and_reports_completion() {
+        let dir = ScratchDir::new("jobs-copy");
+        let source = dir.file("a.txt", b"hello");
+        let dest = dir.join("dest");
+        std::fs::create_dir(&dest).unwrap();
+
+        let (queue, events) = JobQueue::start().unwrap();
+        let id = queue.enqueue(JobSpec {
+            kind: JobKind::Copy,
+            sources: vec![source.clone()],
+            dest: dest.clone(),
+            options: TransferOptions::default(),
+        });
+
+        let mut seen = Vec::new();
+        let finished = loop {
+            match events.recv_timeout(Duration::from_secs(10)).expect("job never finished") {
+                JobEvent::Changed(job) if job.id == id => {
+                    seen.push(job.state);
+                    if job.state.is_finished() {
+                        break job;
+                    }
+                },
+                _ => {},
+            }
+        };
+        assert_eq!(finished.state, JobState::Completed, "{:?}", finished.errors);
+        assert_eq!(seen[..3], [JobState::Queued, JobState::Scanning, JobState::Running]);
+        assert_eq!(finished.progress.bytes_done, 5);
+        assert_eq!(finished.completed, [(source, dest.join("a.txt"))]);
+        assert_eq!(std::fs::read_to_string(dest.join("a.txt")).unwrap(), "hello");
+    }
+}
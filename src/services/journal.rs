@@ -0,0 +1,545 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    core::errors::{Error, Result},
+    services::fs::{
+        atomic::atomic_write,
+        ops::{copy_to, create_dir, create_file, create_symlink, move_to},
+        trash::{list_trash, restore, trash_entries},
+    },
+};
+
+/// Entries beyond this are dropped from the start of the history.
+const MAX_ENTRIES: usize = 500;
+
+const JOURNAL_VERSION: u32 = 1;
+
+/// Something Nohrs created on behalf of the user.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Created {
+    Dir { path: PathBuf },
+    File { path: PathBuf },
+    Symlink { path: PathBuf, target: PathBuf },
+}
+
+impl Created {
+    pub fn path(&self) -> &Path {
+        match self {
+            Created::Dir { path } | Created::File { path } | Created::Symlink { path, .. } => path,
+        }
+    }
+}
+
+/// A reversible file operation, with the paths it ended up touching.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum Operation {
+    Rename {
+        from: PathBuf,
+        to: PathBuf,
+    },
+    /// `(source, destination)` pairs.
+    Move {
+        moves: Vec<(PathBuf, PathBuf)>,
+    },
+    /// `(source, copy)` pairs.
+    Copy {
+        copies: Vec<(PathBuf, PathBuf)>,
+    },
+    Create {
+        items: Vec<Created>,
+    },
+    /// Paths as they were before being moved to the trash.
+    Trash {
+        paths: Vec<PathBuf>,
+    },
+}
+
+impl Operation {
+    /// A one-line description for menus and the history list.
+    pub fn describe(&self) -> String {
+        match self {
+            Operation::Rename { from, to } => format!("Rename {} to {}", name(from), name(to)),
+            Operation::Move { moves } => {
+                format!("Move {}{}", items(moves.iter().map(|(s, _)| s)), into(&moves[..]))
+            },
+            Operation::Copy { copies } => {
+                format!("Copy {}{}", items(copies.iter().map(|(s, _)| s)), into(&copies[..]))
+            },
+            Operation::Create { items: created } => match created.as_slice() {
+                [Created::Dir { path }] => format!("New folder {}", name(path)),
+                [Created::File { path }] => format!("New file {}", name(path)),
+                [Created::Symlink { path, .. }] => format!("New link {}", name(path)),
+                _ => format!("Create {}", items(created.iter().map(Created::path))),
+            },
+            Operation::Trash { paths } => format!("Move {} to the trash", items(paths.iter())),
+        }
+    }
+}
+
+fn name(path: &Path) -> String {
+    path.file_name().unwrap_or(path.as_os_str()).to_string_lossy().into_owned()
+}
+
+fn items<P: AsRef<Path>>(mut paths: impl ExactSizeIterator<Item = P>) -> String {
+    let len = paths.len();
+    match (len, paths.next()) {
+        (1, Some(path)) => name(path.as_ref()),
+        _ => format!("{len} items"),
+    }
+}
+
+/// " to <folder>" when every destination is in the same folder.
+fn into(pairs: &[(PathBuf, PathBuf)]) -> String {
+    let mut parents = pairs.iter().filter_map(|(_, dest)| dest.parent());
+    match parents.next() {
+        Some(first) if parents.all(|p| p == first) => format!(" to {}", first.display()),
+        _ => String::new(),
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub id: u64,
+    /// Unix timestamp of when the operation was done.
+    pub time: i64,
+    pub op: Operation,
+    /// Reversed and waiting on the redo side.
+    #[serde(default)]
+    pub undone: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JournalFile {
+    version: u32,
+    entries: Vec<JournalEntry>,
+}
+
+/// The history of file operations done through Nohrs, oldest first.
+///
+/// Done entries form the undo stack and undone ones (always at the end) the redo stack; recording
+/// a new operation discards whatever could still be redone. The history is saved as
+/// human-readable JSON after every change.
+pub struct Journal {
+    path: Option<PathBuf>,
+    entries: Vec<JournalEntry>,
+}
+
+impl Journal {
+    /// Loads the journal saved at `path`. A missing file starts an empty history; an unreadable
+    /// one is set aside as `<name>.bak` so it is not overwritten.
+    pub fn open(path: PathBuf) -> Journal {
+        let entries = match fs::read_to_string(&path) {
+            Ok(json) => match serde_json::from_str::<JournalFile>(&json) {
+                Ok(file) if file.version <= JOURNAL_VERSION => file.entries,
+                Ok(file) => {
+                    tracing::warn!(
+                        "Ignoring journal version {} at {}",
+                        file.version,
+                        path.display()
+                    );
+                    Vec::new()
+                },
+                Err(err) => {
+                    tracing::warn!("Unreadable journal {}: {}", path.display(), err);
+                    let _ = fs::rename(&path, path.with_extension("json.bak"));
+                    Vec::new()
+                },
+            },
+            Err(_) => Vec::new(),
+        };
+        Journal { path: Some(path), entries }
+    }
+
+    /// A history that is not saved anywhere.
+    pub fn in_memory() -> Journal {
+        Journal { path: None, entries: Vec::new() }
+    }
+
+    /// Where the history is saved, for showing to the user.
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    pub fn entries(&self) -> &[JournalEntry] {
+        &self.entries
+    }
+
+    pub fn record(&mut self, op: Operation) {
+        self.entries.retain(|e| !e.undone);
+        let id = self.next_id();
+        self.entries.push(JournalEntry { id, time: now(), op, undone: false });
+        if self.entries.len() > MAX_ENTRIES {
+            self.entries.drain(..self.entries.len() - MAX_ENTRIES);
+        }
+        self.save();
+    }
+
+    /// The entry [`undo`] would reverse next.
+    pub fn next_undo(&self) -> Option<&JournalEntry> {
+        self.entries.iter().rev().find(|e| !e.undone)
+    }
+
+    /// The entry [`redo`] would repeat next.
+    pub fn next_redo(&self) -> Option<&JournalEntry> {
+        self.entries.iter().find(|e| e.undone)
+    }
+
+    /// Records the outcome of undoing or redoing entry `id`.
+    pub fn set_undone(&mut self, id: u64, undone: bool) {
+        if let Some(entry) = self.entries.iter_mut().find(|e| e.id == id) {
+            entry.undone = undone;
+            self.save();
+        }
+    }
+
+    /// Records that undoing (`undoing`) or redoing entry `id` went through for `finished` only.
+    /// `remaining` stays where the entry was, so trying again picks up the items that failed;
+    /// `finished` becomes an entry of its own on the other side, next to it.
+    pub fn set_partly_undone(
+        &mut self,
+        id: u64,
+        finished: Operation,
+        remaining: Operation,
+        undoing: bool,
+    ) {
+        let new_id = self.next_id();
+        let Some(ix) = self.entries.iter().position(|e| e.id == id) else {
+            return;
+        };
+        let entry = &mut self.entries[ix];
+        // Undone entries are all at the end, the one redone next first.
+        let split = if undoing {
+            entry.op = remaining;
+            JournalEntry { id: new_id, time: entry.time, op: finished, undone: true }
+        } else {
+            entry.op = finished;
+            entry.undone = false;
+            JournalEntry { id: new_id, time: entry.time, op: remaining, undone: true }
+        };
+        self.entries.insert(ix + 1, split);
+        self.save();
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.save();
+    }
+
+    /// Split entries are not in id order, so this goes by the highest id rather than the last.
+    fn next_id(&self) -> u64 {
+        self.entries.iter().map(|e| e.id).max().map_or(1, |id| id + 1)
+    }
+
+    fn save(&self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        let file = JournalFile { version: JOURNAL_VERSION, entries: self.entries.clone() };
+        let result = serde_json::to_string_pretty(&file)
+            .map_err(|err| Error::Other(err.to_string()))
+            .and_then(|json| atomic_write(path, json));
+        if let Err(err) = result {
+            tracing::warn!("Failed to save journal {}: {}", path.display(), err);
+        }
+    }
+}
+
+fn now() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs() as i64).unwrap_or(0)
+}
+
+/// What undoing or redoing an entry did. Items are handled one at a time, so some can fail while
+/// the others go through.
+#[derive(Debug, Default)]
+pub struct Outcome {
+    /// Paths that changed.
+    pub changed: Vec<PathBuf>,
+    /// When some items failed: the part of the operation that went through, and the rest.
+    pub partial: Option<(Operation, Operation)>,
+    /// The items that failed, by path, with why.
+    pub failures: Vec<(PathBuf, Error)>,
+}
+
+/// Reverses `entry`, each item after checking that what it produced is still in place and nothing
+/// new occupies the place it would restore. Items that fail are left as they are and listed in the
+/// outcome; if none went through, the first failure is the error.
+///
+/// Copies and trashed entries are never deleted for good: undoing a copy moves the copies to the
+/// trash, and undoing a trash restores from it.
+pub fn undo(entry: &JournalEntry) -> Result<Outcome> {
+    match &entry.op {
+        Operation::Rename { from, to } => {
+            expect_present(to)?;
+            expect_absent(from)?;
+            fs::rename(to, from)?;
+            Ok(Outcome { changed: vec![to.clone(), from.clone()], ..Outcome::default() })
+        },
+        Operation::Move { moves } => per_item(
+            moves,
+            |(_, dest)| dest,
+            |moves| Operation::Move { moves },
+            |(source, dest)| {
+                expect_present(dest)?;
+                expect_absent(source)?;
+                move_to(dest, source)?;
+                Ok(vec![dest.clone(), source.clone()])
+            },
+        ),
+        Operation::Copy { copies } => per_item(
+            copies,
+            |(_, copy)| copy,
+            |copies| Operation::Copy { copies },
+            |(_, copy)| {
+                expect_present(copy)?;
+                trash_entries(std::slice::from_ref(copy))?;
+                Ok(vec![copy.clone()])
+            },
+        ),
+        Operation::Create { items } => per_item(
+            items,
+            Created::path,
+            |items| Operation::Create { items },
+            |item| {
+                expect_untouched(item)?;
+                match item {
+                    Created::Dir { path } => fs::remove_dir(path)?,
+                    Created::File { path } | Created::Symlink { path, .. } => {
+                        fs::remove_file(path)?
+                    },
+                }
+                Ok(vec![item.path().to_path_buf()])
+            },
+        ),
+        Operation::Trash { paths } => {
+            let trashed = list_trash()?;
+            per_item(
+                paths,
+                PathBuf::as_path,
+                |paths| Operation::Trash { paths },
+                |path| {
+                    expect_absent(path)?;
+                    // The newest trashed item for the path that is not older than the entry; a
+                    // few seconds of slack cover the trash recording its own clock.
+                    let item = trashed
+                        .iter()
+                        .find(|item| item.original_path == *path && item.deleted >= entry.time - 5)
+                        .ok_or_else(|| {
+                            Error::Diverged(format!("{} is no longer in the trash", path.display()))
+                        })?;
+                    restore(std::slice::from_ref(item))?;
+                    Ok(vec![path.clone()])
+                },
+            )
+        },
+    }
+}
+
+/// Does `entry` again after it was undone, with the same checks and per-item outcome as [`undo`].
+pub fn redo(entry: &JournalEntry) -> Result<Outcome> {
+    match &entry.op {
+        Operation::Rename { from, to } => {
+            expect_present(from)?;
+            expect_absent(to)?;
+            fs::rename(from, to)?;
+            Ok(Outcome { changed: vec![from.clone(), to.clone()], ..Outcome::default() })
+        },
+        Operation::Move { moves } => per_item(
+            moves,
+            |(source, _)| source,
+            |moves| Operation::Move { moves },
+            |(source, dest)| {
+                expect_present(source)?;
+                expect_absent(dest)?;
+                move_to(source, dest)?;
+                Ok(vec![source.clone(), dest.clone()])
+            },
+        ),
+        Operation::Copy { copies } => per_item(
+            copies,
+            |(source, _)| source,
+            |copies| Operation::Copy { copies },
+            |(source, copy)| {
+                expect_present(source)?;
+                expect_absent(copy)?;
+                copy_to(source, copy)?;
+                Ok(vec![copy.clone()])
+            },
+        ),
+        Operation::Create { items } => per_item(
+            items,
+            Created::path,
+            |items| Operation::Create { items },
+            |item| {
+                let path = item.path();
+                expect_absent(path)?;
+                let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+                    return Err(Error::InvalidName(path.display().to_string()));
+                };
+                let name = name.to_string_lossy();
+                match item {
+                    Created::Dir { .. } => create_dir(parent, &name)?,
+                    Created::File { .. } => create_file(parent, &name)?,
+                    Created::Symlink { target, .. } => create_symlink(parent, &name, target)?,
+                };
+                Ok(vec![path.to_path_buf()])
+            },
+        ),
+        Operation::Trash { paths } => per_item(
+            paths,
+            PathBuf::as_path,
+            |paths| Operation::Trash { paths },
+            |path| {
+                expect_present(path)?;
+                trash_entries(std::slice::from_ref(path))?;
+                Ok(vec![path.clone()])
+            },
+        ),
+    }
+}
+
+/// Runs `action` on each item, carrying on past failures. `path` names an item in the outcome and
+/// `rebuild` makes an operation of the items that went through, and of those that did not.
+fn per_item<T: Clone>(
+    items: &[T],
+    path: impl Fn(&T) -> &Path,
+    rebuild: impl Fn(Vec<T>) -> Operation,
+    mut action: impl FnMut(&T) -> Result<Vec<PathBuf>>,
+) -> Result<Outcome> {
+    let mut outcome = Outcome::default();
+    let (mut finished, mut remaining) = (Vec::new(), Vec::new());
+    for item in items {
+        match action(item) {
+            Ok(changed) => {
+                outcome.changed.extend(changed);
+                finished.push(item.clone());
+            },
+            Err(err) => {
+                outcome.failures.push((path(item).to_path_buf(), err));
+                remaining.push(item.clone());
+            },
+        }
+    }
+    if !remaining.is_empty() {
+        if finished.is_empty() {
+            return Err(outcome.failures.swap_remove(0).1);
+        }
+        outcome.partial = Some((rebuild(finished), rebuild(remaining)));
+    }
+    Ok(outcome)
+}
+
+fn expect_present(path: &Path) -> Result<()> {
+    if fs::symlink_metadata(path).is_err() {
+        return Err(Error::Diverged(format!("{} no longer exists", path.display())));
+    }
+    Ok(())
+}
+
+fn expect_absent(path: &Path) -> Result<()> {
+    if fs::symlink_metadata(path).is_ok() {
+        return Err(Error::AlreadyExists(path.display().to_string()));
+    }
+    Ok(())
+}
+
+/// Created entries are only removed while they are still as created: an empty folder, an empty
+/// file, a link to the same target.
+fn expect_untouched(item: &Created) -> Result<()> {
+    let path = item.path();
+    expect_present(path)?;
+    let md = fs::symlink_metadata(path)?;
+    let untouched = match item {
+        Created::Dir { .. } => md.is_dir() && fs::read_dir(path)?.next().is_none(),
+        Created::File { .. } => md.is_file() && md.len() == 0,
+        Created::Symlink { target, .. } => fs::read_link(path).is_ok_and(|t| t == *target),
+    };
+    if !untouched {
+        return Err(Error::Diverged(format!("{} has been modified", path.display())));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::testing::ScratchDir;
+
+    fn moves(dir: &Path, names: &[&str]) -> Operation {
+        let moves =
+            names.iter().map(|name| (dir.join(name), dir.join("dest").join(name))).collect();
+        Operation::Move { moves }
+    }
+
+    #[test]
+    fn undo_carries_on_past_failed_items() {
+        let dir = ScratchDir::new("journal-partial");
+        fs::create_dir(dir.join("dest")).unwrap();
+        for name in ["a", "b", "c"] {
+            fs::write(dir.join("dest").join(name), name).unwrap();
+        }
+        // Something new took b's old place, so b can't go back.
+        fs::write(dir.join("b"), "new").unwrap();
+
+        let mut journal = Journal::in_memory();
+        journal.record(moves(&dir, &["a", "b", "c"]));
+        let entry = journal.next_undo().unwrap().clone();
+        let outcome = undo(&entry).unwrap();
+
+        assert!(dir.join("a").is_file() && dir.join("c").is_file());
+        assert_eq!(fs::read_to_string(dir.join("b")).unwrap(), "new");
+        assert_eq!(outcome.failures.len(), 1);
+        assert_eq!(outcome.failures[0].0, dir.join("dest/b"));
+        let (finished, remaining) = outcome.partial.unwrap();
+        assert_eq!(finished, moves(&dir, &["a", "c"]));
+        assert_eq!(remaining, moves(&dir, &["b"]));
+
+        // The failed item stays to be undone; what went through can be redone.
+        journal.set_partly_undone(entry.id, finished, remaining, true);
+        assert_eq!(journal.next_undo().unwrap().op, moves(&dir, &["b"]));
+        assert_eq!(journal.next_redo().unwrap().op, moves(&dir, &["a", "c"]));
+
+        fs::remove_file(dir.join("b")).unwrap();
+        let retry = journal.next_undo().unwrap().clone();
+        let outcome = undo(&retry).unwrap();
+        assert!(outcome.partial.is_none() && outcome.failures.is_empty());
+        assert_eq!(fs::read_to_string(dir.join("b")).unwrap(), "b");
+    }
+
+    #[test]
+    fn undo_fails_when_no_item_goes_through() {
+        let dir = ScratchDir::new("journal-none");
+        let entry =
+            JournalEntry { id: 1, time: now(), op: moves(&dir, &["a", "b"]), undone: false };
+        assert!(matches!(undo(&entry), Err(Error::Diverged(_))));
+    }
+
+    #[test]
+    fn partial_redo_keeps_the_rest_on_the_redo_stack() {
+        let dir = ScratchDir::new("journal-redo");
+        let mut journal = Journal::in_memory();
+        journal.record(moves(&dir, &["a", "b"]));
+        journal.record(moves(&dir, &["c"]));
+        journal.set_undone(2, true);
+        journal.set_undone(1, true);
+
+        journal.set_partly_undone(1, moves(&dir, &["a"]), moves(&dir, &["b"]), false);
+        let ids: Vec<_> = journal.entries().iter().map(|e| (e.id, e.undone)).collect();
+        assert_eq!(ids, [(1, false), (3, true), (2, true)]);
+        assert_eq!(journal.next_undo().unwrap().op, moves(&dir, &["a"]));
+        assert_eq!(journal.next_redo().unwrap().op, moves(&dir, &["b"]));
+
+        // The split entry has the highest id, which new entries don't reuse.
+        journal.set_undone(3, false);
+        journal.record(moves(&dir, &["d"]));
+        let ids: Vec<_> = journal.entries().iter().map(|e| e.id).collect();
+        assert_eq!(ids, [1, 3, 4]);
+    }
+}
@@ -1 +1,7 @@
 pub mod fs;
+pub mod jobs;
+pub mod journal;
+pub mod preview;
+pub mod session;
+pub mod settings;
+pub mod sync;
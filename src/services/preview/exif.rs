@@ -0,0 +1,485 @@
+use std::{
+    fs::File,
+    io::{BufReader, Read},
+    path::Path,
+};
+
+use crate::core::errors::Result;
+
+const TAG_MAKE: u16 = 0x010f;
+const TAG_MODEL: u16 = 0x0110;
+const TAG_ORIENTATION: u16 = 0x0112;
+const TAG_SOFTWARE: u16 = 0x0131;
+const TAG_DATE_TIME: u16 = 0x0132;
+const TAG_EXIF_IFD: u16 = 0x8769;
+const TAG_EXPOSURE_TIME: u16 = 0x829a;
+const TAG_F_NUMBER: u16 = 0x829d;
+const TAG_ISO: u16 = 0x8827;
+const TAG_DATE_TIME_ORIGINAL: u16 = 0x9003;
+const TAG_FOCAL_LENGTH: u16 = 0x920a;
+const TAG_LENS_MODEL: u16 = 0xa434;
+
+const TYPE_ASCII: u16 = 2;
+const TYPE_SHORT: u16 = 3;
+const TYPE_LONG: u16 = 4;
+const TYPE_RATIONAL: u16 = 5;
+const TYPE_SRATIONAL: u16 = 10;
+
+/// The few EXIF fields worth showing next to a photo. Everything else in the block is skipped.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ExifSummary {
+    pub make: Option<String>,
+    pub model: Option<String>,
+    pub lens: Option<String>,
+    pub software: Option<String>,
+    /// When the photo was taken, as `YYYY-MM-DD HH:MM:SS` in the camera's local time.
+    pub taken: Option<String>,
+    /// 1 is upright; 2 to 8 are the usual TIFF rotations and mirrorings.
+    pub orientation: Option<u16>,
+    /// Seconds, as the numerator and denominator the camera wrote.
+    pub exposure: Option<(u32, u32)>,
+    pub f_number: Option<f64>,
+    pub iso: Option<u32>,
+    /// Millimetres.
+    pub focal_length: Option<f64>,
+}
+
+impl ExifSummary {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    /// Make and model, without the make repeated when the model already starts with it.
+    pub fn camera(&self) -> Option<String> {
+        match (&self.make, &self.model) {
+            (Some(make), Some(model)) if model.to_lowercase().starts_with(&make.to_lowercase()) => {
+                Some(model.clone())
+            },
+            (Some(make), Some(model)) => Some(format!("{make} {model}")),
+            (make, model) => make.clone().or_else(|| model.clone()),
+        }
+    }
+
+    /// Label and value pairs for the preview, in a fixed order.
+    pub fn rows(&self) -> Vec<(&'static str, String)> {
+        let mut rows = Vec::new();
+        if let Some(camera) = self.camera() {
+            rows.push(("Camera", camera));
+        }
+        if let Some(lens) = &self.lens {
+            rows.push(("Lens", lens.clone()));
+        }
+        if let Some(taken) = &self.taken {
+            rows.push(("Taken", taken.clone()));
+        }
+        let exposure = [
+            self.exposure.and_then(|(num, den)| format_exposure(num, den)),
+            self.f_number.map(|f| format!("f/{}", trim_float(f))),
+            self.focal_length.map(|mm| format!("{} mm", trim_float(mm))),
+            self.iso.map(|iso| format!("ISO {iso}")),
+        ];
+        let exposure: Vec<String> = exposure.into_iter().flatten().collect();
+        if !exposure.is_empty() {
+            rows.push(("Exposure", exposure.join(" · ")));
+        }
+        if let Some(orientation) = self.orientation.and_then(orientation_label) {
+            rows.push(("Orientation", orientation.to_string()));
+        }
+        if let Some(software) = &self.software {
+            rows.push(("Software", software.clone()));
+        }
+        rows
+    }
+}
+
+/// Reads the EXIF block of a JPEG file. `Ok(None)` for files that are not JPEGs or carry no EXIF
+/// data; only the headers in front of the image data are read.
+pub fn read_exif(path: &Path) -> Result<Option<ExifSummary>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut marker = [0u8; 2];
+    reader.read_exact(&mut marker)?;
+    if marker != [0xff, 0xd8] {
+        return Ok(None);
+    }
+
+    loop {
+        if reader.read_exact(&mut marker).is_err() || marker[0] != 0xff {
+            return Ok(None);
+        }
+        // Start of scan, end of image: the headers are over.
+        if marker[1] == 0xda || marker[1] == 0xd9 {
+            return Ok(None);
+        }
+        // Standalone markers and fill bytes carry no length.
+        if (0xd0..=0xd7).contains(&marker[1]) || marker[1] == 0x01 || marker[1] == 0xff {
+            continue;
+        }
+        let mut len = [0u8; 2];
+        reader.read_exact(&mut len)?;
+        let len = (u16::from_be_bytes(len) as usize).saturating_sub(2);
+        let mut segment = vec![0u8; len];
+        reader.read_exact(&mut segment)?;
+        if marker[1] == 0xe1 && segment.starts_with(b"Exif\0\0") {
+            return Ok(parse_tiff(&segment[6..]).filter(|summary| !summary.is_empty()));
+        }
+    }
+}
+
+/// Parses a TIFF structure as found in an EXIF block. `None` if it is malformed.
+pub fn parse_tiff(data: &[u8]) -> Option<ExifSummary> {
+    let little = match data.get(..2)? {
+        b"II" => true,
+        b"MM" => false,
+        _ => return None,
+    };
+    let tiff = Tiff { data, little };
+    if tiff.u16_at(2)? != 42 {
+        return None;
+    }
+
+    let mut summary = ExifSummary::default();
+    let mut exif_ifd = None;
+    for entry in tiff.entries(tiff.u32_at(4)? as usize)? {
+        match entry.tag {
+            TAG_MAKE => summary.make = tiff.ascii(&entry),
+            TAG_MODEL => summary.model = tiff.ascii(&entry),
+            TAG_SOFTWARE => summary.software = tiff.ascii(&entry),
+            TAG_DATE_TIME => summary.taken = tiff.ascii(&entry).map(|date| format_date(&date)),
+            TAG_ORIENTATION => summary.orientation = tiff.integer(&entry).map(|o| o as u16),
+            TAG_EXIF_IFD => exif_ifd = tiff.integer(&entry),
+            _ => {},
+        }
+    }
+
+    // A missing or broken Exif sub-IFD still leaves the camera fields.
+    let exif_entries = exif_ifd.and_then(|offset| tiff.entries(offset as usize));
+    for entry in exif_entries.unwrap_or_default() {
+        match entry.tag {
+            TAG_EXPOSURE_TIME => summary.exposure = tiff.rational(&entry),
+            TAG_F_NUMBER => summary.f_number = tiff.rational(&entry).and_then(ratio),
+            TAG_FOCAL_LENGTH => summary.focal_length = tiff.rational(&entry).and_then(ratio),
+            TAG_ISO => summary.iso = tiff.integer(&entry),
+            TAG_LENS_MODEL => summary.lens = tiff.ascii(&entry),
+            TAG_DATE_TIME_ORIGINAL => {
+                summary.taken = tiff.ascii(&entry).map(|date| format_date(&date));
+            },
+            _ => {},
+        }
+    }
+    Some(summary)
+}
+
+struct Entry {
+    tag: u16,
+    kind: u16,
+    count: usize,
+    /// Where the value starts: inline in the entry when it fits in four bytes, else at the offset
+    /// the entry points to.
+    value: usize,
+}
+
+struct Tiff<'a> {
+    data: &'a [u8],
+    little: bool,
+}
+
+impl Tiff<'_> {
+    fn u16_at(&self, pos: usize) -> Option<u16> {
+        let bytes: [u8; 2] = self.data.get(pos..pos.checked_add(2)?)?.try_into().ok()?;
+        Some(if self.little { u16::from_le_bytes(bytes) } else { u16::from_be_bytes(bytes) })
+    }
+
+    fn u32_at(&self, pos: usize) -> Option<u32> {
+        let bytes: [u8; 4] = self.data.get(pos..pos.checked_add(4)?)?.try_into().ok()?;
+        Some(if self.little { u32::from_le_bytes(bytes) } else { u32::from_be_bytes(bytes) })
+    }
+
+    fn entries(&self, offset: usize) -> Option<Vec<Entry>> {
+        let count = self.u16_at(offset)? as usize;
+        let mut entries = Vec::with_capacity(count);
+        for ix in 0..count {
+            let pos = offset.checked_add(2 + ix * 12)?;
+            let kind = self.u16_at(pos + 2)?;
+            let count = self.u32_at(pos + 4)? as usize;
+            let unit = match kind {
+                TYPE_SHORT => 2,
+                TYPE_LONG => 4,
+                TYPE_RATIONAL | TYPE_SRATIONAL => 8,
+                _ => 1,
+            };
+            let value = if count.saturating_mul(unit) <= 4 {
+                pos + 8
+            } else {
+                self.u32_at(pos + 8)? as usize
+            };
+            entries.push(Entry { tag: self.u16_at(pos)?, kind, count, value });
+        }
+        Some(entries)
+    }
+
+    fn ascii(&self, entry: &Entry) -> Option<String> {
+        if entry.kind != TYPE_ASCII {
+            return None;
+        }
+        let bytes = self.data.get(entry.value..entry.value.checked_add(entry.count)?)?;
+        let text = String::from_utf8_lossy(bytes);
+        let text = text.trim_end_matches('\0').trim();
+        (!text.is_empty()).then(|| text.to_string())
+    }
+
+    fn integer(&self, entry: &Entry) -> Option<u32> {
+        match entry.kind {
+            TYPE_SHORT => self.u16_at(entry.value).map(u32::from),
+            TYPE_LONG => self.u32_at(entry.value),
+            _ => None,
+        }
+    }
+
+    fn rational(&self, entry: &Entry) -> Option<(u32, u32)> {
+        if !matches!(entry.kind, TYPE_RATIONAL | TYPE_SRATIONAL) {
+            return None;
+        }
+        Some((self.u32_at(entry.value)?, self.u32_at(entry.value.checked_add(4)?)?))
+    }
+}
+
+fn ratio((num, den): (u32, u32)) -> Option<f64> {
+    (den != 0).then(|| num as f64 / den as f64)
+}
+
+/// `2024:05:01 12:00:00` as `2024-05-01 12:00:00`.
+fn format_date(date: &str) -> String {
+    date.replacen(':', "-", 2)
+}
+
+/// Short exposures as a fraction (`1/250 s`), long ones in seconds (`2.5 s`).
+fn format_exposure(num: u32, den: u32) -> Option<String> {
+    let seconds = ratio((num, den))?;
+    if seconds <= 0.0 {
+        return None;
+    }
+    Some(if seconds < 1.0 {
+        format!("1/{} s", (1.0 / seconds).round())
+    } else {
+        format!("{} s", trim_float(seconds))
+    })
+}
+
+/// One decimal place, without a trailing `.0`.
+fn trim_float(value: f64) -> String {
+    let text = format!("{value:.1}");
+    text.strip_suffix(".0").map(str::to_string).unwrap_or(text)
+}
+
+fn orientation_label(orientation: u16) -> Option<&'static str> {
+    match orientation {
+        3 => Some("Rotated 180°"),
+        6 => Some("Rotated 90° clockwise"),
+        8 => Some("Rotated 90° counterclockwise"),
+        2 | 4 | 5 | 7 => Some("Mirrored"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Builds a TIFF block: IFD0 with the given entries right after the header, then `data`.
+    /// Entry values that point into `data` are given relative to its start; ones too large for
+    /// that are written as they are.
+    struct Builder {
+        little: bool,
+        ifd0: Vec<(u16, u16, u32, u32)>,
+        exif: Vec<(u16, u16, u32, u32)>,
+        data: Vec<u8>,
+    }
+
+    impl Builder {
+        fn new(little: bool) -> Self {
+            Self { little, ifd0: Vec::new(), exif: Vec::new(), data: Vec::new() }
+        }
+
+        fn u16(&self, value: u16) -> [u8; 2] {
+            if self.little {
+                value.to_le_bytes()
+            } else {
+                value.to_be_bytes()
+            }
+        }
+
+        fn u32(&self, value: u32) -> [u8; 4] {
+            if self.little {
+                value.to_le_bytes()
+            } else {
+                value.to_be_bytes()
+            }
+        }
+
+        /// Adds `bytes` to the data area and returns their offset within it.
+        fn data(&mut self, bytes: &[u8]) -> u32 {
+            self.data.extend_from_slice(bytes);
+            (self.data.len() - bytes.len()) as u32
+        }
+
+        fn ascii(&mut self, exif: bool, tag: u16, text: &str) {
+            let offset = self.data(format!("{text}\0").as_bytes());
+            let entry = (tag, TYPE_ASCII, text.len() as u32 + 1, offset);
+            if exif {
+                self.exif.push(entry)
+            } else {
+                self.ifd0.push(entry)
+            }
+        }
+
+        fn rational(&mut self, tag: u16, num: u32, den: u32) {
+            let bytes = [self.u32(num), self.u32(den)].concat();
+            let offset = self.data(&bytes);
+            self.exif.push((tag, TYPE_RATIONAL, 1, offset));
+        }
+
+        fn build(&self) -> Vec<u8> {
+            let ifd_len = |entries: usize| 2 + entries * 12 + 4;
+            let exif_at = 8 + ifd_len(self.ifd0.len() + 1);
+            let data_at = (exif_at + ifd_len(self.exif.len())) as u32;
+            let inline = |kind: u16, count: u32| {
+                let unit = match kind {
+                    TYPE_SHORT => 2,
+                    TYPE_LONG => 4,
+                    TYPE_RATIONAL | TYPE_SRATIONAL => 8,
+                    _ => 1,
+                };
+                count.saturating_mul(unit) <= 4
+            };
+
+            let mut out = if self.little { b"II".to_vec() } else { b"MM".to_vec() };
+            out.extend(self.u16(42));
+            out.extend(self.u32(8));
+            let exif_entry = (TAG_EXIF_IFD, TYPE_LONG, 1, exif_at as u32);
+            for (ix, entries) in [&self.ifd0, &self.exif].into_iter().enumerate() {
+                let extra = (ix == 0).then_some(exif_entry);
+                out.extend(self.u16((entries.len() + extra.iter().count()) as u16));
+                for &(tag, kind, count, value) in entries.iter().chain(&extra) {
+                    out.extend(self.u16(tag));
+                    out.extend(self.u16(kind));
+                    out.extend(self.u32(count));
+                    if kind == TYPE_SHORT && inline(kind, count) {
+                        out.extend(self.u16(value as u16));
+                        out.extend([0, 0]);
+                    } else if inline(kind, count) || tag == TAG_EXIF_IFD {
+                        out.extend(self.u32(value));
+                    } else {
+                        out.extend(self.u32(value.checked_add(data_at).unwrap_or(value)));
+                    }
+                }
+                out.extend(self.u32(0));
+            }
+            out.extend(&self.data);
+            out
+        }
+    }
+
+    fn camera(little: bool) -> Builder {
+        let mut tiff = Builder::new(little);
+        tiff.ascii(false, TAG_MAKE, "Canon");
+        tiff.ascii(false, TAG_MODEL, "Canon EOS R5");
+        tiff.ifd0.push((TAG_ORIENTATION, TYPE_SHORT, 1, 6));
+        tiff.rational(TAG_EXPOSURE_TIME, 1, 250);
+        tiff.rational(TAG_F_NUMBER, 28, 10);
+        tiff.exif.push((TAG_ISO, TYPE_SHORT, 1, 400));
+        tiff.ascii(true, TAG_DATE_TIME_ORIGINAL, "2024:05:01 12:34:56");
+        tiff
+    }
+
+    #[test]
+    fn parses_both_byte_orders() {
+        for little in [true, false] {
+            let summary = parse_tiff(&camera(little).build()).unwrap();
+            assert_eq!(summary.camera().as_deref(), Some("Canon EOS R5"));
+            assert_eq!(summary.orientation, Some(6));
+            assert_eq!(summary.iso, Some(400));
+            assert_eq!(summary.taken.as_deref(), Some("2024-05-01 12:34:56"));
+            assert_eq!(summary.rows(), [
+                ("Camera", "Canon EOS R5".to_string()),
+                ("Taken", "2024-05-01 12:34:56".to_string()),
+                ("Exposure", "1/250 s · f/2.8 · ISO 400".to_string()),
+                ("Orientation", "Rotated 90° clockwise".to_string()),
+            ]);
+        }
+    }
+
+    #[test]
+    fn rejects_malformed_headers() {
+        let valid = camera(true).build();
+        assert_eq!(parse_tiff(&[]), None);
+        assert_eq!(parse_tiff(b"II"), None);
+        assert_eq!(parse_tiff(b"XX*\0\x08\0\0\0"), None);
+        assert_eq!(parse_tiff(b"II\x2b\0\x08\0\0\0"), None);
+        // Truncated anywhere inside IFD0.
+        for len in 4..30 {
+            assert_eq!(parse_tiff(&valid[..len]), None, "{len} bytes");
+        }
+    }
+
+    #[test]
+    fn survives_bad_offsets() {
+        for ifd0 in [0x1000, u32::MAX, u32::MAX - 1] {
+            let mut tiff = camera(true).build();
+            tiff[4..8].copy_from_slice(&ifd0.to_le_bytes());
+            assert_eq!(parse_tiff(&tiff), None);
+        }
+
+        // Values pointing past the end are skipped, without losing the rest.
+        let mut builder = camera(true);
+        builder.ifd0.push((TAG_SOFTWARE, TYPE_ASCII, 64, u32::MAX - 8));
+        builder.exif.push((TAG_LENS_MODEL, TYPE_ASCII, u32::MAX, 0));
+        builder.exif.push((TAG_FOCAL_LENGTH, TYPE_RATIONAL, 1, 0x10_0000));
+        let summary = parse_tiff(&builder.build()).unwrap();
+        assert_eq!(summary.software, None);
+        assert_eq!(summary.lens, None);
+        assert_eq!(summary.focal_length, None);
+        assert_eq!(summary.iso, Some(400));
+
+        // A broken Exif sub-IFD keeps the camera fields.
+        let mut builder = camera(true);
+        builder.exif.clear();
+        let mut tiff = builder.build();
+        let exif_value = 8 + 2 + 3 * 12 + 8;
+        tiff[exif_value..exif_value + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        let summary = parse_tiff(&tiff).unwrap();
+        assert_eq!(summary.camera().as_deref(), Some("Canon EOS R5"));
+        assert_eq!(summary.exposure, None);
+    }
+
+    #[test]
+    fn ignores_unusable_values() {
+        let mut builder = Builder::new(true);
+        builder.rational(TAG_F_NUMBER, 28, 0);
+        builder.rational(TAG_EXPOSURE_TIME, 0, 1);
+        builder.ascii(true, TAG_LENS_MODEL, "      ");
+        // An ISO stored as text is not a number.
+        builder.ascii(true, TAG_ISO, "400");
+        let summary = parse_tiff(&builder.build()).unwrap();
+        assert_eq!(summary.f_number, None);
+        assert_eq!(summary.lens, None);
+        assert_eq!(summary.iso, None);
+        assert!(summary.rows().is_empty(), "{:?}", summary.rows());
+    }
+
+    #[test]
+    fn camera_name() {
+        let summary = |make: Option<&str>, model: Option<&str>| ExifSummary {
+            make: make.map(str::to_string),
+            model: model.map(str::to_string),
+            ..ExifSummary::default()
+        };
+        assert_eq!(summary(Some("NIKON"), Some("Nikon Z6")).camera().as_deref(), Some("Nikon Z6"));
+        assert_eq!(
+            summary(Some("Apple"), Some("iPhone 15")).camera().as_deref(),
+            Some("Apple iPhone 15")
+        );
+        assert_eq!(summary(None, Some("X100V")).camera().as_deref(), Some("X100V"));
+        assert_eq!(summary(None, None).camera(), None);
+        assert!(summary(None, None).is_empty());
+    }
+}
@@ -0,0 +1,80 @@
+use std::{
+    fmt::Write as _,
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+    path::Path,
+};
+
+use crate::core::errors::Result;
+
+pub const BYTES_PER_ROW: usize = 16;
+/// Bytes read and shown at a time; the preview pages through larger files.
+pub const PAGE_SIZE: u64 = 64 * 1024;
+
+/// One page of a file, as read by [`read_page`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HexPage {
+    pub index: u64,
+    pub offset: u64,
+    pub bytes: Vec<u8>,
+}
+
+impl HexPage {
+    pub fn row_count(&self) -> usize {
+        self.bytes.len().div_ceil(BYTES_PER_ROW)
+    }
+
+    /// Offset and bytes of a row of the page.
+    pub fn row(&self, ix: usize) -> (u64, &[u8]) {
+        let start = (ix * BYTES_PER_ROW).min(self.bytes.len());
+        let end = (start + BYTES_PER_ROW).min(self.bytes.len());
+        (self.offset + start as u64, &self.bytes[start..end])
+    }
+}
+
+/// Pages needed for a file of `len` bytes; an empty file still has one (empty) page.
+pub fn page_count(len: u64) -> u64 {
+    len.div_ceil(PAGE_SIZE).max(1)
+}
+
+/// Reads page `index` of a file, seeking to it without reading what comes before.
+pub fn read_page(path: &Path, index: u64) -> Result<HexPage> {
+    let offset = index.saturating_mul(PAGE_SIZE);
+    let mut file = File::open(path)?;
+    file.seek(SeekFrom::Start(offset))?;
+    let mut bytes = Vec::with_capacity(PAGE_SIZE as usize);
+    file.take(PAGE_SIZE).read_to_end(&mut bytes)?;
+    Ok(HexPage { index, offset, bytes })
+}
+
+/// The offset column: eight hex digits, more for offsets past 4 GiB.
+pub fn format_offset(offset: u64) -> String {
+    format!("{offset:08x}")
+}
+
+/// Bytes as two-digit hex, with a wider gap between the two halves of a row. Short (last) rows
+/// are padded so the ASCII column stays aligned.
+pub fn format_hex(bytes: &[u8]) -> String {
+    let mut text = String::with_capacity(BYTES_PER_ROW * 3 + 1);
+    for ix in 0..BYTES_PER_ROW {
+        if ix == BYTES_PER_ROW / 2 {
+            text.push(' ');
+        }
+        match bytes.get(ix) {
+            Some(byte) => {
+                let _ = write!(text, "{byte:02x} ");
+            },
+            None => text.push_str("   "),
+        }
+    }
+    text.pop();
+    text
+}
+
+/// Printable ASCII as itself, everything else as `.`.
+pub fn format_ascii(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|&byte| if byte.is_ascii_graphic() || byte == b' ' { byte as char } else { '.' })
+        .collect()
+}
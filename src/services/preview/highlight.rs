@@ -0,0 +1,230 @@
+use std::{ffi::OsStr, ops::Range, path::Path, sync::OnceLock};
+
+use syntect::parsing::{ParseState, Scope, ScopeStack, SyntaxReference, SyntaxSet};
+
+/// Longer lines are left plain: minified files can take seconds to parse per line.
+pub const MAX_HIGHLIGHT_LINE_LEN: usize = 4096;
+
+/// What a token is, independent of any color scheme, so the UI can color it from its theme.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenClass {
+    Comment,
+    String,
+    Number,
+    Constant,
+    Keyword,
+    Function,
+    Type,
+    Tag,
+    Attribute,
+}
+
+/// TextMate scope prefixes and the class they stand for, most specific first. `None` keeps a
+/// token plain even inside a broader scope that has a class.
+const SCOPE_CLASSES: &[(&str, Option<TokenClass>)] = &[
+    ("comment", Some(TokenClass::Comment)),
+    ("string", Some(TokenClass::String)),
+    ("constant.numeric", Some(TokenClass::Number)),
+    ("constant.character.escape", Some(TokenClass::String)),
+    ("constant", Some(TokenClass::Constant)),
+    ("entity.name.function", Some(TokenClass::Function)),
+    ("support.function", Some(TokenClass::Function)),
+    ("variable.function", Some(TokenClass::Function)),
+    ("entity.name.tag", Some(TokenClass::Tag)),
+    ("entity.other.attribute-name", Some(TokenClass::Attribute)),
+    ("entity.name.type", Some(TokenClass::Type)),
+    ("entity.name.class", Some(TokenClass::Type)),
+    ("entity.name.struct", Some(TokenClass::Type)),
+    ("entity.name.enum", Some(TokenClass::Type)),
+    ("support.type", Some(TokenClass::Type)),
+    ("support.class", Some(TokenClass::Type)),
+    ("keyword.operator", None),
+    ("keyword", Some(TokenClass::Keyword)),
+    ("storage", Some(TokenClass::Keyword)),
+    ("markup.heading", Some(TokenClass::Keyword)),
+    ("markup.raw", Some(TokenClass::String)),
+    ("markup.underline.link", Some(TokenClass::Function)),
+];
+
+/// The bundled syntax definitions, loaded on first use.
+pub fn syntax_set() -> &'static SyntaxSet {
+    static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAXES.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+fn scope_classes() -> &'static [(Scope, Option<TokenClass>)] {
+    static CLASSES: OnceLock<Vec<(Scope, Option<TokenClass>)>> = OnceLock::new();
+    CLASSES.get_or_init(|| {
+        SCOPE_CLASSES
+            .iter()
+            .filter_map(|(scope, class)| Some((Scope::new(scope).ok()?, *class)))
+            .collect()
+    })
+}
+
+/// The syntax of a file, found by its extension, its whole name (`Makefile`, `Dockerfile`) or
+/// else its first line (a shebang such as `#!/usr/bin/env python3`, or a modeline). `None` for
+/// plain text.
+pub fn detect_syntax(path: &Path, first_line: &str) -> Option<&'static SyntaxReference> {
+    let syntaxes = syntax_set();
+    let by_name = |name: Option<&OsStr>| {
+        let name = name?.to_str()?;
+        syntaxes
+            .find_syntax_by_extension(name)
+            .or_else(|| syntaxes.find_syntax_by_extension(&name.to_lowercase()))
+    };
+    by_name(path.extension())
+        .or_else(|| by_name(path.file_name()))
+        .or_else(|| syntaxes.find_syntax_by_first_line(first_line))
+        .filter(|syntax| syntax.name != "Plain Text")
+}
+
+/// Highlights a file line by line. Parsing carries state from one line to the next, so lines
+/// have to be fed in order, but they can be fed in batches as the preview needs them.
+pub struct LineHighlighter {
+    parse_state: ParseState,
+    scopes: ScopeStack,
+    /// The line being parsed, with the line break the syntax definitions expect.
+    buf: String,
+}
+
+impl LineHighlighter {
+    pub fn new(syntax: &SyntaxReference) -> Self {
+        Self { parse_state: ParseState::new(syntax), scopes: ScopeStack::new(), buf: String::new() }
+    }
+
+    /// The classified byte ranges of the next line, given without its line break. Plain text in
+    /// between is left out.
+    pub fn highlight_line(&mut self, line: &str) -> Vec<(Range<usize>, TokenClass)> {
+        let mut spans = Vec::new();
+        if line.len() > MAX_HIGHLIGHT_LINE_LEN {
+            return spans;
+        }
+        self.buf.clear();
+        self.buf.push_str(line);
+        self.buf.push('\n');
+        let ops = match self.parse_state.parse_line(&self.buf, syntax_set()) {
+            Ok(ops) => ops,
+            Err(err) => {
+                tracing::debug!("Highlighting stopped: {}", err);
+                return spans;
+            },
+        };
+
+        let mut start = 0;
+        let mut class = classify(&self.scopes);
+        for (pos, op) in ops {
+            let pos = pos.min(line.len());
+            if pos > start {
+                push_span(&mut spans, start..pos, class);
+                start = pos;
+            }
+            if self.scopes.apply(&op).is_err() {
+                self.scopes = ScopeStack::new();
+            }
+            class = classify(&self.scopes);
+        }
+        if start < line.len() {
+            push_span(&mut spans, start..line.len(), class);
+        }
+        spans
+    }
+}
+
+/// The class of the innermost scope that has one.
+fn classify(scopes: &ScopeStack) -> Option<TokenClass> {
+    let classes = scope_classes();
+    scopes.as_slice().iter().rev().find_map(|scope| {
+        classes.iter().find(|(prefix, _)| prefix.is_prefix_of(*scope)).map(|(_, class)| *class)
+    })?
+}
+
+/// Appends a span, merging it into the previous one when they touch and share a class.
+fn push_span(
+    spans: &mut Vec<(Range<usize>, TokenClass)>,
+    range: Range<usize>,
+    class: Option<TokenClass>,
+) {
+    let Some(class) = class else {
+        return;
+    };
+    match spans.last_mut() {
+        Some((last, last_class)) if *last_class == class && last.end == range.start => {
+            last.end = range.end;
+        },
+        _ => spans.push((range, class)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn syntax_name(path: &str, first_line: &str) -> Option<&'static str> {
+        detect_syntax(Path::new(path), first_line).map(|syntax| syntax.name.as_str())
+    }
+
+    fn class_of(scopes: &str) -> Option<TokenClass> {
+        classify(&scopes.parse::<ScopeStack>().unwrap())
+    }
+
+    #[test]
+    fn syntax_by_extension_name_or_first_line() {
+        assert_eq!(syntax_name("src/main.rs", ""), Some("Rust"));
+        assert_eq!(syntax_name("MAIN.RS", ""), Some("Rust"));
+        assert_eq!(syntax_name("Makefile", ""), Some("Makefile"));
+        // The extension wins over the first line.
+        assert_eq!(syntax_name("lib.rs", "#!/usr/bin/env python3"), Some("Rust"));
+        assert_eq!(syntax_name("bin/tool", "#!/usr/bin/env python3"), Some("Python"));
+        assert_eq!(syntax_name("bin/tool", "#!/bin/bash"), Some("Bourne Again Shell (bash)"));
+        assert_eq!(syntax_name("notes.txt", ""), None);
+        assert_eq!(syntax_name("README", "hello"), None);
+    }
+
+    #[test]
+    fn scopes_map_to_the_innermost_class() {
+        assert_eq!(class_of("source.rust comment.line.double-slash"), Some(TokenClass::Comment));
+        assert_eq!(class_of("source.rust constant.numeric.integer"), Some(TokenClass::Number));
+        assert_eq!(class_of("source.rust constant.language"), Some(TokenClass::Constant));
+        assert_eq!(class_of("source.rust storage.type"), Some(TokenClass::Keyword));
+        assert_eq!(class_of("source.rust entity.name.function"), Some(TokenClass::Function));
+        assert_eq!(class_of("text.html entity.name.tag"), Some(TokenClass::Tag));
+        assert_eq!(
+            class_of("string.quoted.double constant.character.escape"),
+            Some(TokenClass::String)
+        );
+        // Operators stay plain, even inside something that has a class.
+        assert_eq!(class_of("source.rust keyword.operator.arithmetic"), None);
+        assert_eq!(class_of("string.quoted keyword.operator"), None);
+        assert_eq!(class_of("source.rust meta.block"), None);
+    }
+
+    #[test]
+    fn highlights_a_line_by_class() {
+        let syntax = detect_syntax(Path::new("main.rs"), "").unwrap();
+        let mut highlighter = LineHighlighter::new(syntax);
+        let line = "fn main() { let n = 42; } // done";
+        let spans = highlighter.highlight_line(line);
+        let class_at = |text: &str| {
+            let start = line.find(text).unwrap();
+            spans.iter().find(|(range, _)| range.contains(&start)).map(|(_, class)| *class)
+        };
+        assert_eq!(class_at("fn"), Some(TokenClass::Keyword));
+        assert_eq!(class_at("main"), Some(TokenClass::Function));
+        assert_eq!(class_at("42"), Some(TokenClass::Number));
+        assert_eq!(class_at("// done"), Some(TokenClass::Comment));
+        assert_eq!(class_at("="), None);
+        assert!(spans.iter().all(|(range, _)| range.end <= line.len()));
+    }
+
+    #[test]
+    fn long_lines_stay_plain_without_losing_the_state() {
+        let syntax = detect_syntax(Path::new("main.rs"), "").unwrap();
+        let mut highlighter = LineHighlighter::new(syntax);
+        let long = format!("let s = \"{}\";", "x".repeat(MAX_HIGHLIGHT_LINE_LEN));
+        assert!(highlighter.highlight_line(&long).is_empty());
+        let at_cap = format!("// {}", "x".repeat(MAX_HIGHLIGHT_LINE_LEN - 3));
+        assert_eq!(highlighter.highlight_line(&at_cap), [(0..at_cap.len(), TokenClass::Comment)]);
+        assert_eq!(highlighter.highlight_line("fn"), [(0..2, TokenClass::Keyword)]);
+    }
+}
@@ -0,0 +1,57 @@
+use std::path::Path;
+
+use image::ImageFormat;
+
+use crate::{
+    core::errors::{Error, Result},
+    services::preview::exif::{read_exif, ExifSummary},
+};
+
+/// Formats the preview can show. Only PNG and JPEG are decoded here, for thumbnails and
+/// dimensions; the others are drawn by the UI as they are.
+const EXTENSIONS: [&str; 6] = ["png", "jpg", "jpeg", "gif", "webp", "bmp"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageInfo {
+    pub format: String,
+    /// `None` for formats that are shown but not decoded here.
+    pub dimensions: Option<(u32, u32)>,
+    pub exif: Option<ExifSummary>,
+}
+
+pub fn is_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+/// Whether thumbnails and dimensions can be decoded from this file.
+pub fn can_decode(path: &Path) -> bool {
+    matches!(ImageFormat::from_path(path), Ok(ImageFormat::Png | ImageFormat::Jpeg))
+}
+
+/// Format, dimensions and EXIF summary of an image, read from its headers without decoding the
+/// pixels.
+pub fn read_image_info(path: &Path) -> Result<ImageInfo> {
+    let format = path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| match ext.to_lowercase().as_str() {
+            "jpg" | "jpeg" => "JPEG".to_string(),
+            ext => ext.to_uppercase(),
+        })
+        .unwrap_or_else(|| "Image".to_string());
+    let dimensions = if can_decode(path) {
+        Some(image::image_dimensions(path).map_err(|err| Error::Other(err.to_string()))?)
+    } else {
+        None
+    };
+    let exif = match read_exif(path) {
+        Ok(exif) => exif,
+        Err(err) => {
+            tracing::debug!("No EXIF data from {}: {}", path.display(), err);
+            None
+        },
+    };
+    Ok(ImageInfo { format, dimensions, exif })
+}
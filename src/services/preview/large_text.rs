@@ -0,0 +1,677 @@
+use std::{
+    fs::{self, File},
+    io::{Read, Seek, SeekFrom},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use crate::core::errors::{Error, Result};
+
+/// How much of the file encoding detection looks at.
+const SAMPLE_LEN: usize = 64 * 1024;
+const BLOCK_LEN: usize = 64 * 1024;
+/// Jumping to a line counts line breaks from the top in blocks this large.
+const SCAN_BLOCK_LEN: usize = 1024 * 1024;
+/// A chunk stops after this many bytes even short of its line count, so a file without line
+/// breaks is still shown in bounded memory.
+pub const MAX_CHUNK_BYTES: u64 = 4 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+    Latin1,
+    Gbk,
+}
+
+impl Encoding {
+    pub fn label(self) -> &'static str {
+        match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Utf16Le => "UTF-16 LE",
+            Encoding::Utf16Be => "UTF-16 BE",
+            Encoding::Latin1 => "Latin-1",
+            Encoding::Gbk => "GBK",
+        }
+    }
+
+    pub fn is_utf16(self) -> bool {
+        matches!(self, Encoding::Utf16Le | Encoding::Utf16Be)
+    }
+
+    /// Bytes per code unit; offsets and line breaks are aligned to it.
+    fn unit(self) -> usize {
+        if self.is_utf16() {
+            2
+        } else {
+            1
+        }
+    }
+
+    /// Whether the code unit at `ix` is a line feed. GBK trail bytes start at 0x40, so a 0x0A byte
+    /// is always a line feed there too.
+    fn is_newline(self, bytes: &[u8], ix: usize) -> bool {
+        match self {
+            Encoding::Utf16Le => bytes.get(ix..ix + 2) == Some(&b"\n\0"[..]),
+            Encoding::Utf16Be => bytes.get(ix..ix + 2) == Some(&b"\0\n"[..]),
+            _ => bytes.get(ix) == Some(&b'\n'),
+        }
+    }
+
+    /// Decodes bytes, replacing anything malformed with U+FFFD.
+    pub fn decode(self, bytes: &[u8]) -> String {
+        match self {
+            Encoding::Utf8 => String::from_utf8_lossy(bytes).into_owned(),
+            Encoding::Utf16Le | Encoding::Utf16Be => {
+                let units = bytes.chunks_exact(2).map(|pair| {
+                    let pair = [pair[0], pair[1]];
+                    if self == Encoding::Utf16Le {
+                        u16::from_le_bytes(pair)
+                    } else {
+                        u16::from_be_bytes(pair)
+                    }
+                });
+                char::decode_utf16(units)
+                    .map(|c| c.unwrap_or(char::REPLACEMENT_CHARACTER))
+                    .collect()
+            },
+            Encoding::Latin1 => bytes.iter().map(|&byte| byte as char).collect(),
+            Encoding::Gbk => decode_gbk(bytes),
+        }
+    }
+}
+
+/// The encoding of a file from its leading bytes, and the length of its byte order mark.
+///
+/// A BOM settles it. Without one, text with NUL bytes in every other position is taken as
+/// UTF-16, then valid UTF-8 as UTF-8, then valid GBK double-byte sequences that don't read as
+/// accented Latin-1 as GBK; anything else decodes as Latin-1, which accepts every byte.
+pub fn detect_encoding(sample: &[u8]) -> (Encoding, u64) {
+    if sample.starts_with(b"\xef\xbb\xbf") {
+        return (Encoding::Utf8, 3);
+    }
+    if sample.starts_with(b"\xff\xfe") {
+        return (Encoding::Utf16Le, 2);
+    }
+    if sample.starts_with(b"\xfe\xff") {
+        return (Encoding::Utf16Be, 2);
+    }
+    if let Some(encoding) = guess_utf16(sample) {
+        return (encoding, 0);
+    }
+    if is_utf8(sample) {
+        return (Encoding::Utf8, 0);
+    }
+    if is_gbk(sample) {
+        return (Encoding::Gbk, 0);
+    }
+    (Encoding::Latin1, 0)
+}
+
+/// Mostly-ASCII UTF-16 has a NUL as the high byte of nearly every unit, and almost nowhere else.
+fn guess_utf16(sample: &[u8]) -> Option<Encoding> {
+    let units = sample.len() / 2;
+    if units < 2 {
+        return None;
+    }
+    let even = sample.iter().step_by(2).filter(|&&byte| byte == 0).count();
+    let odd = sample.iter().skip(1).step_by(2).filter(|&&byte| byte == 0).count();
+    let mostly = |zeros: usize| zeros * 10 >= units * 3;
+    let rarely = |zeros: usize| zeros * 20 < units;
+    if mostly(odd) && rarely(even) {
+        Some(Encoding::Utf16Le)
+    } else if mostly(even) && rarely(odd) {
+        Some(Encoding::Utf16Be)
+    } else {
+        None
+    }
+}
+
+/// Valid UTF-8, allowing a sequence cut off by the end of the sample.
+fn is_utf8(sample: &[u8]) -> bool {
+    match std::str::from_utf8(sample) {
+        Ok(_) => true,
+        Err(err) => err.error_len().is_none(),
+    }
+}
+
+/// Bytes above 0x7F all pair up as GBK lead and trail bytes, allowing a pair cut off by the end
+/// of the sample, and at least two of the pairs look like Chinese rather than Latin-1.
+///
+/// Accented Latin-1 letters pair up as GBK too: "ñ" followed by an ASCII letter, or "öß" in
+/// "Größe". Pairs with an ASCII trail byte and lone pairs of Latin-1 letters prove nothing; pairs
+/// holding a byte that is no Latin-1 letter, and runs of pairs, are what Chinese text is made of.
+fn is_gbk(sample: &[u8]) -> bool {
+    let mut evidence = 0;
+    // A lone pair of Latin-1 letters, counted once another pair follows it.
+    let mut unproven = false;
+    let mut ix = 0;
+    while ix < sample.len() {
+        match sample[ix] {
+            0x00..=0x7f => {
+                unproven = false;
+                ix += 1;
+            },
+            lead @ 0x81..=0xfe => match sample.get(ix + 1) {
+                Some(0x40..=0x7e) => {
+                    unproven = false;
+                    ix += 2;
+                },
+                Some(&trail @ 0x80..=0xfe) => {
+                    let starts_run = ix == 0 || sample[ix - 1] < 0x80;
+                    if starts_run && is_latin1_letter(lead) && is_latin1_letter(trail) {
+                        unproven = true;
+                    } else {
+                        evidence += 1 + usize::from(std::mem::take(&mut unproven));
+                    }
+                    ix += 2;
+                },
+                Some(_) => return false,
+                None => break,
+            },
+            _ => return false,
+        }
+    }
+    evidence >= 2
+}
+
+/// À to ÿ, less × and ÷.
+fn is_latin1_letter(byte: u8) -> bool {
+    byte >= 0xc0 && byte != 0xd7 && byte != 0xf7
+}
+
+/// GBK, with malformed sequences shown as U+FFFD.
+fn decode_gbk(bytes: &[u8]) -> String {
+    encoding_rs::GBK.decode_without_bom_handling(bytes).0.into_owned()
+}
+
+/// A run of consecutive lines of a file.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TextChunk {
+    /// Byte offset of the first line.
+    pub start: u64,
+    /// Byte offset just past the last complete line; reading on from here continues the file.
+    pub end: u64,
+    /// 1-based number of the first line, when known. It is only known when the chunk was read
+    /// from the top or found by counting lines.
+    pub first_line: Option<u64>,
+    pub lines: Vec<String>,
+    /// The last line has no line break yet: the end of a file still being written, or a line cut
+    /// off by [`MAX_CHUNK_BYTES`]. It is not included in `end`, so reading on returns it again,
+    /// completed.
+    pub partial: bool,
+    /// Byte offset just past the last byte read, the partial line included. The file has grown
+    /// only when it is longer than this.
+    pub read_end: u64,
+    /// Length of the file when the chunk was read.
+    pub file_len: u64,
+}
+
+/// What [`LargeText::follow`] found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FollowUpdate {
+    Unchanged,
+    /// Lines from the offset followed, the first of them the partial line seen before, if any.
+    Appended(TextChunk),
+    /// The file got shorter (rotated or rewritten); start over from its tail.
+    Truncated,
+}
+
+/// A text file too large, or in an encoding other than UTF-8, to load whole. Only the lines asked
+/// for are read, by seeking to them.
+#[derive(Debug, Clone)]
+pub struct LargeText {
+    path: PathBuf,
+    encoding: Encoding,
+    /// Length of the byte order mark; the text starts after it.
+    bom: u64,
+}
+
+impl LargeText {
+    /// Opens a file and detects its encoding from the first 64 KiB.
+    pub fn open(path: &Path) -> Result<Self> {
+        let mut sample = Vec::with_capacity(SAMPLE_LEN);
+        File::open(path)?.take(SAMPLE_LEN as u64).read_to_end(&mut sample)?;
+        let (encoding, bom) = detect_encoding(&sample);
+        Ok(Self { path: path.to_path_buf(), encoding, bom })
+    }
+
+    pub fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    pub fn has_bom(&self) -> bool {
+        self.bom > 0
+    }
+
+    /// Rounds an offset down to a code unit boundary after the BOM.
+    fn align(&self, offset: u64) -> u64 {
+        let offset = offset.max(self.bom);
+        offset - (offset - self.bom) % self.encoding.unit() as u64
+    }
+
+    /// The first `lines` lines.
+    pub fn head(&self, lines: usize) -> Result<TextChunk> {
+        let mut chunk = self.read_lines(self.bom, lines)?;
+        chunk.first_line = Some(1);
+        Ok(chunk)
+    }
+
+    /// The last `lines` lines, found by reading backwards from the end.
+    pub fn tail(&self, lines: usize) -> Result<TextChunk> {
+        let mut file = File::open(&self.path)?;
+        let end = self.align(file.metadata()?.len());
+        let unit = self.encoding.unit();
+        let mut buf = vec![0u8; BLOCK_LEN];
+        let mut pos = end;
+        let mut breaks = 0;
+        let mut earliest_break = None;
+        let mut start = None;
+
+        'scan: while pos > self.bom && end - pos < MAX_CHUNK_BYTES {
+            let block_start = self.align(pos.saturating_sub(BLOCK_LEN as u64));
+            let block = &mut buf[..(pos - block_start) as usize];
+            file.seek(SeekFrom::Start(block_start))?;
+            file.read_exact(block)?;
+            let mut ix = block.len();
+            while ix >= unit {
+                ix -= unit;
+                if !self.encoding.is_newline(block, ix) {
+                    continue;
+                }
+                let after = block_start + (ix + unit) as u64;
+                // The break ending the last line doesn't start another one.
+                if after == end {
+                    continue;
+                }
+                breaks += 1;
+                earliest_break = Some(after);
+                if breaks >= lines {
+                    start = Some(after);
+                    break 'scan;
+                }
+            }
+            pos = block_start;
+        }
+
+        let start = match start {
+            Some(start) => start,
+            None if pos <= self.bom => self.bom,
+            // Hit the size limit: begin at a line boundary if one was seen.
+            None => earliest_break.unwrap_or(pos),
+        };
+        let mut chunk = self.read_lines(start, usize::MAX)?;
+        if start == self.bom {
+            chunk.first_line = Some(1);
+        }
+        Ok(chunk)
+    }
+
+    /// `lines` lines from the one holding byte `offset`.
+    pub fn at_offset(&self, offset: u64, lines: usize) -> Result<TextChunk> {
+        let mut file = File::open(&self.path)?;
+        let offset = self.align(offset.min(file.metadata()?.len()));
+        // Back up to the start of the line, looking at most one block back.
+        let back = self.align(offset.saturating_sub(BLOCK_LEN as u64));
+        let mut buf = vec![0u8; (offset - back) as usize];
+        file.seek(SeekFrom::Start(back))?;
+        file.read_exact(&mut buf)?;
+
+        let unit = self.encoding.unit();
+        let mut start = if back == self.bom { self.bom } else { offset };
+        let mut ix = buf.len();
+        while ix >= unit {
+            ix -= unit;
+            if self.encoding.is_newline(&buf, ix) {
+                start = back + (ix + unit) as u64;
+                break;
+            }
+        }
+
+        let mut chunk = self.read_lines(start, lines)?;
+        if start == self.bom {
+            chunk.first_line = Some(1);
+        }
+        Ok(chunk)
+    }
+
+    /// `lines` lines from line number `line` (1-based), found by counting line breaks from the
+    /// top. Past the end of the file this is the tail. Returns [`Error::Cancelled`] as soon as
+    /// `cancelled` is set.
+    pub fn at_line(&self, line: u64, lines: usize, cancelled: &AtomicBool) -> Result<TextChunk> {
+        if line <= 1 {
+            return self.head(lines);
+        }
+        let mut file = File::open(&self.path)?;
+        file.seek(SeekFrom::Start(self.bom))?;
+        let unit = self.encoding.unit();
+        let mut buf = vec![0u8; SCAN_BLOCK_LEN];
+        let mut pos = self.bom;
+        let mut current = 1;
+        let mut line_start = self.bom;
+
+        loop {
+            if cancelled.load(Ordering::Relaxed) {
+                return Err(Error::Cancelled);
+            }
+            let read = file.read(&mut buf)?;
+            if read == 0 {
+                break;
+            }
+            // Leave an odd trailing byte of UTF-16 for the next read.
+            let usable = read - read % unit;
+            for ix in (0..usable).step_by(unit) {
+                if self.encoding.is_newline(&buf[..usable], ix) {
+                    current += 1;
+                    line_start = pos + (ix + unit) as u64;
+                    if current == line {
+                        let mut chunk = self.read_lines(line_start, lines)?;
+                        chunk.first_line = Some(line);
+                        return Ok(chunk);
+                    }
+                }
+            }
+            pos += usable as u64;
+            if usable < read {
+                file.seek(SeekFrom::Start(pos))?;
+            }
+        }
+
+        // Fewer lines than asked for: show the end, numbered from the count just made.
+        let total = if line_start >= pos { current - 1 } else { current };
+        let mut chunk = self.tail(lines)?;
+        chunk.first_line = Some((total + 1).saturating_sub(chunk.lines.len() as u64).max(1));
+        Ok(chunk)
+    }
+
+    /// What has been written since a chunk was read: lines from its `end` if the file grew past
+    /// its `read_end`. Comparing with what was read rather than the file's length then keeps a
+    /// burst larger than [`MAX_CHUNK_BYTES`] coming in over several calls.
+    pub fn follow(&self, from: u64, read_end: u64) -> Result<FollowUpdate> {
+        let len = fs::metadata(&self.path)?.len();
+        if len < read_end || len < from {
+            return Ok(FollowUpdate::Truncated);
+        }
+        if len == read_end {
+            return Ok(FollowUpdate::Unchanged);
+        }
+        Ok(FollowUpdate::Appended(self.read_lines(from, usize::MAX)?))
+    }
+
+    /// Reads forward from `start` until `max_lines` complete lines, the end of the file or
+    /// [`MAX_CHUNK_BYTES`].
+    fn read_lines(&self, start: u64, max_lines: usize) -> Result<TextChunk> {
+        let mut file = File::open(&self.path)?;
+        let file_len = file.metadata()?.len();
+        file.seek(SeekFrom::Start(start))?;
+        let mut reader = file.take(MAX_CHUNK_BYTES);
+        let unit = self.encoding.unit();
+        let mut bytes = Vec::new();
+        let mut buf = vec![0u8; BLOCK_LEN];
+        let mut breaks = 0;
+        let mut complete = 0;
+        let mut scanned = 0;
+
+        'read: while breaks < max_lines {
+            let read = reader.read(&mut buf)?;
+            if read == 0 {
+                break;
+            }
+            bytes.extend_from_slice(&buf[..read]);
+            while scanned + unit <= bytes.len() {
+                if self.encoding.is_newline(&bytes, scanned) {
+                    breaks += 1;
+                    complete = scanned + unit;
+                    if breaks >= max_lines {
+                        break 'read;
+                    }
+                }
+                scanned += unit;
+            }
+        }
+        if breaks >= max_lines {
+            bytes.truncate(complete);
+        }
+
+        let partial = bytes.len() > complete;
+        let read_end = start + bytes.len() as u64;
+        let text = self.encoding.decode(&bytes);
+        let mut lines: Vec<String> =
+            text.split('\n').map(|line| line.trim_end_matches('\r').to_string()).collect();
+        if !partial {
+            // The empty remainder after the last line break.
+            lines.pop();
+        }
+        Ok(TextChunk {
+            start,
+            end: start + complete as u64,
+            first_line: None,
+            lines,
+            partial,
+            read_end,
+            file_len,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write as _;
+
+    use super::*;
+    use crate::core::testing::ScratchDir;
+
+    fn numbered(count: usize) -> String {
+        (1..=count).map(|n| format!("line {n}\n")).collect()
+    }
+
+    fn utf16le(text: &str) -> Vec<u8> {
+        text.encode_utf16().flat_map(u16::to_le_bytes).collect()
+    }
+
+    fn append(path: &Path, bytes: &[u8]) {
+        fs::OpenOptions::new().append(true).open(path).unwrap().write_all(bytes).unwrap();
+    }
+
+    #[test]
+    fn byte_order_marks_settle_the_encoding() {
+        assert_eq!(detect_encoding(b"\xef\xbb\xbfabc"), (Encoding::Utf8, 3));
+        assert_eq!(detect_encoding(b"\xff\xfea\0"), (Encoding::Utf16Le, 2));
+        assert_eq!(detect_encoding(b"\xfe\xff\0a"), (Encoding::Utf16Be, 2));
+    }
+
+    #[test]
+    fn encodings_are_told_apart_without_a_bom() {
+        let le = utf16le("plain text");
+        let be: Vec<u8> = "plain text".encode_utf16().flat_map(u16::to_be_bytes).collect();
+        assert_eq!(detect_encoding(&le), (Encoding::Utf16Le, 0));
+        assert_eq!(detect_encoding(&be), (Encoding::Utf16Be, 0));
+        assert_eq!(detect_encoding("naïve café".as_bytes()), (Encoding::Utf8, 0));
+        // A sequence cut off by the end of the sample.
+        assert_eq!(detect_encoding(&"中文".as_bytes()[..5]), (Encoding::Utf8, 0));
+        // 你好世界 and 中文测试.
+        let gbk = b"\xc4\xe3\xba\xc3\xca\xc0\xbd\xe7, \xd6\xd0\xce\xc4\xb2\xe2\xca\xd4\n";
+        assert_eq!(detect_encoding(gbk), (Encoding::Gbk, 0));
+        // Two characters are enough when they run together.
+        assert_eq!(detect_encoding(b"\xd6\xd0\xce\xc4 text"), (Encoding::Gbk, 0));
+    }
+
+    #[test]
+    fn decodes_gbk() {
+        let gbk = b"\xc4\xe3\xba\xc3\xca\xc0\xbd\xe7, \xd6\xd0\xce\xc4\xb2\xe2\xca\xd4\n";
+        assert_eq!(Encoding::Gbk.decode(gbk), "你好世界, 中文测试\n");
+        // A lead byte cut off by the end of the input.
+        assert_eq!(Encoding::Gbk.decode(b"\xd6\xd0\xce"), "中\u{fffd}");
+
+        let dir = ScratchDir::new("text-gbk");
+        let path = dir.file("text.txt", gbk);
+        let file = LargeText::open(&path).unwrap();
+        assert_eq!(file.encoding(), Encoding::Gbk);
+        assert_eq!(file.head(1).unwrap().lines, ["你好世界, 中文测试"]);
+    }
+
+    #[test]
+    fn accented_latin1_is_not_gbk() {
+        for text in [
+            &b"Gr\xf6\xdfe"[..],
+            b"se\xf1or",
+            b"d\xe9j\xe0 vu",
+            b"Die Gr\xf6\xdfe der Stra\xdfe, se\xf1or, 25\xb0C",
+            b"bl\xe5b\xe6rsyltet\xf8j",
+        ] {
+            assert_eq!(detect_encoding(text), (Encoding::Latin1, 0), "{text:?}");
+        }
+        // A byte GBK never uses.
+        assert_eq!(detect_encoding(b"\xc4\xe3\xff"), (Encoding::Latin1, 0));
+    }
+
+    #[test]
+    fn tail_reads_back_from_the_end() {
+        let dir = ScratchDir::new("text-tail");
+        let path = dir.file("text.txt", numbered(10).as_bytes());
+        let file = LargeText::open(&path).unwrap();
+
+        let chunk = file.tail(3).unwrap();
+        assert_eq!(chunk.lines, ["line 8", "line 9", "line 10"]);
+        assert_eq!(chunk.first_line, None);
+        assert!(!chunk.partial);
+        assert_eq!(chunk.end, chunk.file_len);
+
+        let all = file.tail(50).unwrap();
+        assert_eq!(all.lines.len(), 10);
+        assert_eq!(all.first_line, Some(1));
+
+        append(&path, b"unfinished");
+        let chunk = file.tail(2).unwrap();
+        assert_eq!(chunk.lines, ["line 10", "unfinished"]);
+        assert!(chunk.partial);
+        assert_eq!(chunk.read_end, chunk.file_len);
+        assert!(chunk.end < chunk.read_end);
+    }
+
+    #[test]
+    fn tail_of_utf16_keeps_code_units_whole() {
+        let mut bytes = b"\xff\xfe".to_vec();
+        bytes.extend(utf16le("première\r\nzweite\r\n第三\r\n"));
+        let dir = ScratchDir::new("text-tail-utf16");
+        let path = dir.file("text.txt", &bytes);
+        let file = LargeText::open(&path).unwrap();
+        assert_eq!(file.encoding(), Encoding::Utf16Le);
+        assert!(file.has_bom());
+        assert_eq!(file.tail(2).unwrap().lines, ["zweite", "第三"]);
+        assert_eq!(file.head(1).unwrap().lines, ["première"]);
+    }
+
+    #[test]
+    fn at_line_counts_from_the_top() {
+        let dir = ScratchDir::new("text-at-line");
+        let path = dir.file("text.txt", numbered(10).as_bytes());
+        let file = LargeText::open(&path).unwrap();
+        let cancelled = AtomicBool::new(false);
+
+        let chunk = file.at_line(5, 2, &cancelled).unwrap();
+        assert_eq!(chunk.lines, ["line 5", "line 6"]);
+        assert_eq!(chunk.first_line, Some(5));
+        assert_eq!(chunk.start, numbered(4).len() as u64);
+
+        assert_eq!(file.at_line(0, 1, &cancelled).unwrap().first_line, Some(1));
+
+        // Past the end: the tail, numbered from the count.
+        let chunk = file.at_line(50, 3, &cancelled).unwrap();
+        assert_eq!(chunk.lines, ["line 8", "line 9", "line 10"]);
+        assert_eq!(chunk.first_line, Some(8));
+
+        cancelled.store(true, Ordering::Relaxed);
+        assert!(matches!(file.at_line(5, 2, &cancelled), Err(Error::Cancelled)));
+    }
+
+    #[test]
+    fn at_line_in_utf16() {
+        let mut bytes = b"\xff\xfe".to_vec();
+        bytes.extend(utf16le(&numbered(6)));
+        let dir = ScratchDir::new("text-at-line-utf16");
+        let path = dir.file("text.txt", &bytes);
+        let file = LargeText::open(&path).unwrap();
+        let chunk = file.at_line(4, 1, &AtomicBool::new(false)).unwrap();
+        assert_eq!(chunk.lines, ["line 4"]);
+        assert_eq!(chunk.start, 2 + 2 * numbered(3).len() as u64);
+    }
+
+    #[test]
+    fn read_lines_stops_at_the_line_count() {
+        let dir = ScratchDir::new("text-read-lines");
+        let path = dir.file("text.txt", b"one\r\ntwo\nthree");
+        let file = LargeText::open(&path).unwrap();
+
+        let chunk = file.read_lines(0, 1).unwrap();
+        assert_eq!(chunk.lines, ["one"]);
+        assert_eq!((chunk.end, chunk.read_end, chunk.partial), (5, 5, false));
+
+        let chunk = file.read_lines(5, usize::MAX).unwrap();
+        assert_eq!(chunk.lines, ["two", "three"]);
+        assert_eq!((chunk.end, chunk.read_end, chunk.partial), (9, 14, true));
+
+        let chunk = file.read_lines(14, usize::MAX).unwrap();
+        assert!(chunk.lines.is_empty());
+        assert_eq!((chunk.end, chunk.read_end), (14, 14));
+    }
+
+    #[test]
+    fn read_lines_is_bounded_without_line_breaks() {
+        let dir = ScratchDir::new("text-read-lines-long");
+        let path = dir.file("text.txt", &vec![b'x'; MAX_CHUNK_BYTES as usize + 10]);
+        let file = LargeText::open(&path).unwrap();
+        let chunk = file.read_lines(0, 10).unwrap();
+        assert_eq!(chunk.lines.len(), 1);
+        assert!(chunk.partial);
+        assert_eq!(chunk.end, 0);
+        assert_eq!(chunk.read_end, MAX_CHUNK_BYTES);
+    }
+
+    #[test]
+    fn follow_reports_growth_and_truncation() {
+        let dir = ScratchDir::new("text-follow");
+        let path = dir.file("text.txt", b"a\nb");
+        let file = LargeText::open(&path).unwrap();
+        let chunk = file.head(10).unwrap();
+        assert!(chunk.partial);
+        assert_eq!(file.follow(chunk.end, chunk.read_end).unwrap(), FollowUpdate::Unchanged);
+
+        append(&path, b"c\nd\n");
+        let FollowUpdate::Appended(chunk) = file.follow(chunk.end, chunk.read_end).unwrap() else {
+            panic!("expected new lines");
+        };
+        // The partial line comes again, completed.
+        assert_eq!(chunk.lines, ["bc", "d"]);
+        assert!(!chunk.partial);
+        assert_eq!(file.follow(chunk.end, chunk.read_end).unwrap(), FollowUpdate::Unchanged);
+
+        fs::write(&path, b"new\n").unwrap();
+        assert_eq!(file.follow(chunk.end, chunk.read_end).unwrap(), FollowUpdate::Truncated);
+    }
+
+    #[test]
+    fn follow_keeps_up_with_bursts_larger_than_a_chunk() {
+        let dir = ScratchDir::new("text-follow-burst");
+        let path = dir.file("text.txt", b"start\n");
+        let file = LargeText::open(&path).unwrap();
+        let mut chunk = file.head(10).unwrap();
+
+        let line = format!("{}\n", "y".repeat(1023));
+        let burst = line.repeat(MAX_CHUNK_BYTES as usize / line.len() * 2 + 1);
+        append(&path, burst.as_bytes());
+        let len = fs::metadata(&path).unwrap().len();
+
+        let mut lines = 0;
+        let mut reads = 0;
+        while let FollowUpdate::Appended(next) = file.follow(chunk.end, chunk.read_end).unwrap() {
+            lines += next.lines.len();
+            reads += 1;
+            chunk = next;
+        }
+        assert!(reads >= 3);
+        assert_eq!(lines, burst.lines().count());
+        assert_eq!(chunk.read_end, len);
+    }
+}
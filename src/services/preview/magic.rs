@@ -0,0 +1,188 @@
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::Path,
+};
+
+/// How much of a file [`read_header`] reads: enough for the tar signature at 257 and for the PE
+/// header a DOS stub usually points to.
+pub const HEADER_LEN: usize = 4096;
+
+/// Signatures at a fixed offset and the file type they identify, checked in order.
+const SIGNATURES: &[(usize, &[u8], &str)] = &[
+    (0, b"\x7fELF", "ELF executable"),
+    (0, b"\xfe\xed\xfa\xce", "Mach-O executable"),
+    (0, b"\xfe\xed\xfa\xcf", "Mach-O executable"),
+    (0, b"\xce\xfa\xed\xfe", "Mach-O executable"),
+    (0, b"\xcf\xfa\xed\xfe", "Mach-O executable"),
+    (0, b"\0asm", "WebAssembly module"),
+    (0, b"PK\x03\x04", "Zip archive"),
+    (0, b"PK\x05\x06", "Zip archive (empty)"),
+    (0, b"\x1f\x8b", "Gzip compressed data"),
+    (0, b"BZh", "Bzip2 compressed data"),
+    (0, b"\xfd7zXZ\0", "XZ compressed data"),
+    (0, b"\x28\xb5\x2f\xfd", "Zstandard compressed data"),
+    (0, b"\x04\x22\x4d\x18", "LZ4 compressed data"),
+    (0, b"7z\xbc\xaf\x27\x1c", "7-Zip archive"),
+    (0, b"Rar!\x1a\x07", "RAR archive"),
+    (257, b"ustar", "Tar archive"),
+    (0, b"!<arch>\ndebian", "Debian package"),
+    (0, b"!<arch>\n", "ar archive"),
+    (0, b"\xed\xab\xee\xdb", "RPM package"),
+    (0, b"SQLite format 3\0", "SQLite database"),
+    (0, b"%PDF-", "PDF document"),
+    (0, b"\x89PNG\r\n\x1a\n", "PNG image"),
+    (0, b"\xff\xd8\xff", "JPEG image"),
+    (0, b"GIF87a", "GIF image"),
+    (0, b"GIF89a", "GIF image"),
+    (0, b"BM", "BMP image"),
+    (0, b"\0\0\x01\0", "ICO icon"),
+    (0, b"II*\0", "TIFF image"),
+    (0, b"MM\0*", "TIFF image"),
+    (0, b"8BPS", "Photoshop document"),
+    (0, b"ID3", "MP3 audio"),
+    (0, b"fLaC", "FLAC audio"),
+    (0, b"OggS", "Ogg media"),
+    (0, b"\x1a\x45\xdf\xa3", "Matroska/WebM video"),
+    (0, b"OTTO", "OpenType font"),
+    (0, b"\0\x01\0\0", "TrueType font"),
+    (0, b"wOFF", "WOFF font"),
+    (0, b"wOF2", "WOFF2 font"),
+    (0, b"\xd0\xcf\x11\xe0\xa1\xb1\x1a\xe1", "Microsoft Office document (legacy)"),
+];
+
+/// The type of a file according to its leading bytes, or `None` if nothing matches.
+pub fn detect_file_type(header: &[u8]) -> Option<&'static str> {
+    if header.starts_with(b"MZ") {
+        return Some(if is_pe(header) { "Windows executable (PE)" } else { "DOS executable" });
+    }
+    if header.starts_with(b"\xca\xfe\xba\xbe") {
+        // Shared by universal Mach-O binaries and Java classes: the former count their
+        // architectures here, the latter store a class file version of 45 or more.
+        let word = u32::from_be_bytes(header.get(4..8)?.try_into().ok()?);
+        return Some(if word < 45 { "Mach-O universal binary" } else { "Java class file" });
+    }
+    if header.starts_with(b"RIFF") {
+        return match header.get(8..12)? {
+            b"WEBP" => Some("WebP image"),
+            b"WAVE" => Some("WAV audio"),
+            b"AVI " => Some("AVI video"),
+            _ => Some("RIFF container"),
+        };
+    }
+    if header.get(4..8) == Some(&b"ftyp"[..]) {
+        return match header.get(8..12)? {
+            b"heic" | b"heix" | b"mif1" => Some("HEIF image"),
+            b"qt  " => Some("QuickTime video"),
+            _ => Some("MP4 media"),
+        };
+    }
+    SIGNATURES
+        .iter()
+        .find(|(offset, magic, _)| header.get(*offset..offset + magic.len()) == Some(*magic))
+        .map(|(_, _, name)| *name)
+}
+
+/// Whether a DOS stub points to a PE header.
+fn is_pe(header: &[u8]) -> bool {
+    let Some(bytes) = header.get(0x3c..0x40) else {
+        return false;
+    };
+    let pe_offset = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as usize;
+    header.get(pe_offset..pe_offset + 4) == Some(b"PE\0\0")
+}
+
+/// The first [`HEADER_LEN`] bytes of a file, or all of it if it is shorter.
+pub fn read_header(path: &Path) -> io::Result<Vec<u8>> {
+    let mut header = Vec::with_capacity(HEADER_LEN);
+    File::open(path)?.take(HEADER_LEN as u64).read_to_end(&mut header)?;
+    Ok(header)
+}
+
+/// Whether leading bytes look like binary data rather than text. Short signatures such as `BM`
+/// can start a text file too, so this goes by NUL bytes, which text files practically never
+/// contain.
+pub fn looks_binary(header: &[u8]) -> bool {
+    header.contains(&0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::testing::ScratchDir;
+
+    #[test]
+    fn known_signatures() {
+        let cases: [(&[u8], &str); 8] = [
+            (b"\x7fELF\x02\x01\x01", "ELF executable"),
+            (b"%PDF-1.7\n", "PDF document"),
+            (b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR", "PNG image"),
+            (b"!<arch>\ndebian-binary", "Debian package"),
+            (b"!<arch>\nfoo.o/", "ar archive"),
+            (b"RIFF\0\0\0\0WEBPVP8 ", "WebP image"),
+            (b"\0\0\0\x18ftypheic", "HEIF image"),
+            (b"\0\0\0\x18ftypisom", "MP4 media"),
+        ];
+        for (header, expected) in cases {
+            assert_eq!(detect_file_type(header), Some(expected), "{header:?}");
+        }
+
+        let mut tar = vec![0; 512];
+        tar[257..262].copy_from_slice(b"ustar");
+        assert_eq!(detect_file_type(&tar), Some("Tar archive"));
+    }
+
+    #[test]
+    fn shared_prefixes() {
+        let mut exe = vec![0; 0x90];
+        exe[..2].copy_from_slice(b"MZ");
+        assert_eq!(detect_file_type(&exe), Some("DOS executable"));
+        exe[0x3c] = 0x80;
+        exe[0x80..0x84].copy_from_slice(b"PE\0\0");
+        assert_eq!(detect_file_type(&exe), Some("Windows executable (PE)"));
+        // A PE offset past the header is a plain DOS stub.
+        exe[0x3c..0x40].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(detect_file_type(&exe), Some("DOS executable"));
+
+        assert_eq!(
+            detect_file_type(b"\xca\xfe\xba\xbe\0\0\0\x02"),
+            Some("Mach-O universal binary")
+        );
+        assert_eq!(detect_file_type(b"\xca\xfe\xba\xbe\0\0\0\x34"), Some("Java class file"));
+        assert_eq!(detect_file_type(b"RIFF\0\0\0\0XXXX"), Some("RIFF container"));
+    }
+
+    #[test]
+    fn truncated_magic_bytes() {
+        assert_eq!(detect_file_type(b""), None);
+        assert_eq!(detect_file_type(b"\x7fEL"), None);
+        assert_eq!(detect_file_type(b"PK\x03"), None);
+        assert_eq!(detect_file_type(b"\x89PNG\r\n"), None);
+        assert_eq!(detect_file_type(b"SQLite format 3"), None);
+        assert_eq!(detect_file_type(b"\xca\xfe\xba\xbe\0\0"), None);
+        assert_eq!(detect_file_type(b"RIFF\0\0\0\0WE"), None);
+        assert_eq!(detect_file_type(b"\0\0\0\x18ftyp"), None);
+        assert_eq!(detect_file_type(&[0; 260]), None);
+        assert_eq!(detect_file_type(b"MZ"), Some("DOS executable"));
+    }
+
+    #[test]
+    fn text_is_not_binary() {
+        assert!(!looks_binary(b"BM is also how this sentence starts"));
+        assert!(!looks_binary("héllo wörld\n".as_bytes()));
+        assert!(looks_binary(b"\x7fELF\x02\x01\x01\0"));
+        assert!(!looks_binary(b""));
+    }
+
+    #[test]
+    fn header_of_short_and_long_files() {
+        let dir = ScratchDir::new("magic");
+        let short = dir.join("short");
+        std::fs::write(&short, b"%PDF").unwrap();
+        assert_eq!(read_header(&short).unwrap(), b"%PDF");
+        let long = dir.join("long");
+        std::fs::write(&long, vec![7; HEADER_LEN * 3]).unwrap();
+        assert_eq!(read_header(&long).unwrap().len(), HEADER_LEN);
+        assert!(read_header(&dir.join("missing")).is_err());
+    }
+}
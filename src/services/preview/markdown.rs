@@ -0,0 +1,505 @@
+use std::{
+    ops::Range,
+    path::{Component, Path, PathBuf},
+};
+
+use comrak::{
+    nodes::{AstNode, ListType, NodeValue, TableAlignment},
+    parse_document, Arena, Options,
+};
+
+use super::highlight::{syntax_set, LineHighlighter, TokenClass};
+
+/// A parsed Markdown document, reduced to what the preview draws.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Block {
+    Heading {
+        level: u8,
+        text: RichText,
+        /// The fragment that links to it, as GitHub derives it (`#getting-started`).
+        anchor: String,
+    },
+    Paragraph(RichText),
+    /// Images are taken out of their paragraph and shown on their own.
+    Image {
+        source: ImageSource,
+        alt: String,
+    },
+    Code {
+        language: Option<String>,
+        lines: Vec<String>,
+        /// Per line; empty until [`highlight_code_blocks`] has been over the document.
+        highlights: Vec<Vec<(Range<usize>, TokenClass)>>,
+    },
+    Quote(Vec<Block>),
+    List {
+        start: Option<usize>,
+        items: Vec<ListItem>,
+    },
+    Table {
+        alignments: Vec<Alignment>,
+        header: Vec<RichText>,
+        rows: Vec<Vec<RichText>>,
+    },
+    Rule,
+    /// Raw HTML, shown as source.
+    Html(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListItem {
+    /// `Some` for task list items, with whether the task is done.
+    pub checked: Option<bool>,
+    pub blocks: Vec<Block>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Alignment {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImageSource {
+    /// A relative or absolute path, resolved against the document's folder.
+    Local(PathBuf),
+    Remote(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Link {
+    /// A web or mail link, for the browser. Links with other schemes are shown as plain text:
+    /// handing them to the system could start any registered URL handler.
+    External(String),
+    /// A file or folder, resolved against the document's folder.
+    Local(PathBuf),
+    /// A heading of the same document.
+    Anchor(String),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SpanStyle {
+    pub strong: bool,
+    pub emphasis: bool,
+    pub strikethrough: bool,
+    pub code: bool,
+    pub link: bool,
+}
+
+/// Text with its styling as consecutive, non-overlapping byte ranges, and the ranges that link
+/// somewhere.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RichText {
+    pub text: String,
+    pub spans: Vec<(Range<usize>, SpanStyle)>,
+    pub links: Vec<(Range<usize>, Link)>,
+}
+
+impl RichText {
+    fn push(&mut self, text: &str, style: SpanStyle) {
+        if text.is_empty() {
+            return;
+        }
+        let start = self.text.len();
+        self.text.push_str(text);
+        let range = start..self.text.len();
+        match self.spans.last_mut() {
+            Some((last, last_style)) if *last_style == style && last.end == start => {
+                last.end = range.end;
+            },
+            _ => self.spans.push((range, style)),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.trim().is_empty()
+    }
+}
+
+/// Parses `markdown` with the GitHub extensions (tables, task lists, strikethrough, autolinks),
+/// resolving relative links and images against `base_dir`.
+pub fn parse(markdown: &str, base_dir: &Path) -> Vec<Block> {
+    let arena = Arena::new();
+    let mut options = Options::default();
+    options.extension.table = true;
+    options.extension.tasklist = true;
+    options.extension.strikethrough = true;
+    options.extension.autolink = true;
+    options.extension.front_matter_delimiter = Some("---".to_string());
+    let root = parse_document(&arena, markdown, &options);
+    let converter = Converter { base_dir };
+    converter.blocks(root)
+}
+
+struct Converter<'p> {
+    base_dir: &'p Path,
+}
+
+impl Converter<'_> {
+    fn blocks<'a>(&self, parent: &'a AstNode<'a>) -> Vec<Block> {
+        let mut blocks = Vec::new();
+        for node in parent.children() {
+            self.block(node, &mut blocks);
+        }
+        blocks
+    }
+
+    fn block<'a>(&self, node: &'a AstNode<'a>, out: &mut Vec<Block>) {
+        let value = node.data.borrow().value.clone();
+        match value {
+            NodeValue::Heading(heading) => {
+                let text = self.rich_text(node);
+                let anchor = anchor_for(&text.text);
+                out.push(Block::Heading { level: heading.level, text, anchor });
+            },
+            NodeValue::Paragraph => self.paragraph(node, out),
+            NodeValue::CodeBlock(code) => {
+                let language = code
+                    .info
+                    .split_whitespace()
+                    .next()
+                    .filter(|l| !l.is_empty())
+                    .map(str::to_string);
+                let lines: Vec<String> =
+                    code.literal.trim_end_matches('\n').lines().map(str::to_string).collect();
+                out.push(Block::Code { language, lines, highlights: Vec::new() });
+            },
+            NodeValue::HtmlBlock(html) => {
+                out.push(Block::Html(html.literal.trim_end().to_string()));
+            },
+            NodeValue::BlockQuote => out.push(Block::Quote(self.blocks(node))),
+            NodeValue::List(list) => {
+                let start = (list.list_type == ListType::Ordered).then_some(list.start);
+                let items = node
+                    .children()
+                    .map(|item| {
+                        let checked = match item.data.borrow().value {
+                            NodeValue::TaskItem(symbol) => Some(symbol.is_some()),
+                            _ => None,
+                        };
+                        ListItem { checked, blocks: self.blocks(item) }
+                    })
+                    .collect();
+                out.push(Block::List { start, items });
+            },
+            NodeValue::Table(table) => {
+                let alignments = table
+                    .alignments
+                    .iter()
+                    .map(|alignment| match alignment {
+                        TableAlignment::Center => Alignment::Center,
+                        TableAlignment::Right => Alignment::Right,
+                        _ => Alignment::Left,
+                    })
+                    .collect();
+                let mut rows: Vec<Vec<RichText>> = node
+                    .children()
+                    .map(|row| row.children().map(|cell| self.rich_text(cell)).collect())
+                    .collect();
+                let header = if rows.is_empty() { Vec::new() } else { rows.remove(0) };
+                out.push(Block::Table { alignments, header, rows });
+            },
+            NodeValue::ThematicBreak => out.push(Block::Rule),
+            NodeValue::FrontMatter(_) => {},
+            // Anything else that holds blocks (footnote definitions, description lists) shows
+            // its content.
+            _ => {
+                for child in node.children() {
+                    self.block(child, out);
+                }
+            },
+        }
+    }
+
+    /// A paragraph, split around its images.
+    fn paragraph<'a>(&self, node: &'a AstNode<'a>, out: &mut Vec<Block>) {
+        let mut text = RichText::default();
+        self.inlines(node, SpanStyle::default(), &mut text, true, out);
+        if !text.is_empty() {
+            out.push(Block::Paragraph(text));
+        }
+    }
+
+    /// The text of a heading or table cell; images there show their description.
+    fn rich_text<'a>(&self, node: &'a AstNode<'a>) -> RichText {
+        let mut text = RichText::default();
+        self.inlines(node, SpanStyle::default(), &mut text, false, &mut Vec::new());
+        text
+    }
+
+    /// Appends the inline content of `node` to `text`. With `split_images`, an image ends the text
+    /// so far as a paragraph in `out` and follows it as a block of its own.
+    fn inlines<'a>(
+        &self,
+        node: &'a AstNode<'a>,
+        style: SpanStyle,
+        text: &mut RichText,
+        split_images: bool,
+        out: &mut Vec<Block>,
+    ) {
+        for child in node.children() {
+            let value = child.data.borrow().value.clone();
+            match value {
+                NodeValue::Text(literal) => text.push(&literal, style),
+                NodeValue::Code(code) => {
+                    text.push(&code.literal, SpanStyle { code: true, ..style })
+                },
+                NodeValue::SoftBreak => text.push(" ", style),
+                NodeValue::LineBreak => text.push("\n", style),
+                NodeValue::HtmlInline(html) => text.push(&html, SpanStyle { code: true, ..style }),
+                NodeValue::Emph => self.inlines(
+                    child,
+                    SpanStyle { emphasis: true, ..style },
+                    text,
+                    split_images,
+                    out,
+                ),
+                NodeValue::Strong => self.inlines(
+                    child,
+                    SpanStyle { strong: true, ..style },
+                    text,
+                    split_images,
+                    out,
+                ),
+                NodeValue::Strikethrough => self.inlines(
+                    child,
+                    SpanStyle { strikethrough: true, ..style },
+                    text,
+                    split_images,
+                    out,
+                ),
+                NodeValue::Link(link) => {
+                    let target = self.link(&link.url);
+                    let start = text.text.len();
+                    let link_style = SpanStyle { link: target.is_some(), ..style };
+                    self.inlines(child, link_style, text, split_images, out);
+                    let end = text.text.len();
+                    if let Some(target) = target.filter(|_| end > start) {
+                        text.links.push((start..end, target));
+                    }
+                },
+                NodeValue::Image(link) => {
+                    let alt = plain_text(child);
+                    if !split_images {
+                        text.push(&alt, style);
+                        continue;
+                    }
+                    let finished = std::mem::take(text);
+                    if !finished.is_empty() {
+                        out.push(Block::Paragraph(finished));
+                    }
+                    out.push(Block::Image { source: self.image(&link.url), alt });
+                },
+                _ => self.inlines(child, style, text, split_images, out),
+            }
+        }
+    }
+
+    /// Where a link goes; `None` for a scheme that isn't opened from here.
+    fn link(&self, url: &str) -> Option<Link> {
+        if let Some(anchor) = url.strip_prefix('#') {
+            return Some(Link::Anchor(anchor.to_string()));
+        }
+        if has_scheme(url) {
+            return opens_in_browser(url).then(|| Link::External(url.to_string()));
+        }
+        let path = url.split(['#', '?']).next().unwrap_or(url);
+        Some(Link::Local(self.resolve(path)))
+    }
+
+    fn image(&self, url: &str) -> ImageSource {
+        if has_scheme(url) {
+            ImageSource::Remote(url.to_string())
+        } else {
+            ImageSource::Local(self.resolve(url))
+        }
+    }
+
+    /// `path` as a file path, relative to the document's folder unless it is absolute.
+    fn resolve(&self, path: &str) -> PathBuf {
+        let path = PathBuf::from(percent_decode(path));
+        normalize(&self.base_dir.join(path))
+    }
+}
+
+/// Whether `url` starts with a scheme such as `https:` or `mailto:`. A Windows drive letter
+/// (`C:`) is too short to count.
+fn has_scheme(url: &str) -> bool {
+    match url.split_once(':') {
+        Some((scheme, _)) => {
+            scheme.len() > 1
+                && scheme.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+                && scheme.chars().all(|c| c.is_ascii_alphanumeric() || "+-.".contains(c))
+        },
+        None => false,
+    }
+}
+
+/// Whether `url` is a web or mail link, the only kinds passed on to the system.
+fn opens_in_browser(url: &str) -> bool {
+    url.split_once(':').is_some_and(|(scheme, _)| {
+        ["http", "https", "mailto"].iter().any(|allowed| scheme.eq_ignore_ascii_case(allowed))
+    })
+}
+
+/// Decodes `%20`-style escapes; malformed ones are kept as they are.
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        // Checked by hand: `from_str_radix` would also take a sign, as in `%+1`.
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .filter(|hex| hex.iter().all(u8::is_ascii_hexdigit))
+            .and_then(|hex| std::str::from_utf8(hex).ok());
+        match (bytes[i], hex.and_then(|hex| u8::from_str_radix(hex, 16).ok())) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            },
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            },
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Removes `.` and `..` components without touching the file system.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {},
+            Component::ParentDir => {
+                normalized.pop();
+            },
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// The fragment GitHub gives a heading: lowercase, punctuation dropped, spaces as dashes.
+pub fn anchor_for(heading: &str) -> String {
+    heading
+        .trim()
+        .to_lowercase()
+        .chars()
+        .filter_map(|c| match c {
+            ' ' => Some('-'),
+            c if c.is_alphanumeric() || c == '-' || c == '_' => Some(c),
+            _ => None,
+        })
+        .collect()
+}
+
+fn plain_text<'a>(node: &'a AstNode<'a>) -> String {
+    let mut text = String::new();
+    for descendant in node.descendants() {
+        match &descendant.data.borrow().value {
+            NodeValue::Text(literal) => text.push_str(literal),
+            NodeValue::Code(code) => text.push_str(&code.literal),
+            _ => {},
+        }
+    }
+    text
+}
+
+/// Highlights the fenced code blocks of a parsed document, including those in quotes and lists.
+/// Large documents can hold a lot of code, so this is left out of [`parse`] to run after the
+/// document is shown.
+pub fn highlight_code_blocks(blocks: &mut [Block]) {
+    for block in blocks {
+        match block {
+            Block::Code { language, lines, highlights } => {
+                *highlights = highlight_code(language.as_deref(), lines);
+            },
+            Block::Quote(blocks) => highlight_code_blocks(blocks),
+            Block::List { items, .. } => {
+                for item in items {
+                    highlight_code_blocks(&mut item.blocks);
+                }
+            },
+            _ => {},
+        }
+    }
+}
+
+fn highlight_code(
+    language: Option<&str>,
+    lines: &[String],
+) -> Vec<Vec<(Range<usize>, TokenClass)>> {
+    let Some(syntax) = language.and_then(|language| syntax_set().find_syntax_by_token(language))
+    else {
+        return Vec::new();
+    };
+    let mut highlighter = LineHighlighter::new(syntax);
+    lines.iter().map(|line| highlighter.highlight_line(line)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn drive_letters_are_not_schemes() {
+        assert!(has_scheme("https://example.com"));
+        assert!(has_scheme("mailto:someone@example.com"));
+        assert!(has_scheme("git+ssh://host/repo"));
+        assert!(!has_scheme("C:\\docs\\a.md"));
+        assert!(!has_scheme("C:/docs/a.md"));
+        assert!(!has_scheme("docs/a.md"));
+        assert!(!has_scheme("1http://example.com"));
+    }
+
+    #[test]
+    fn only_web_and_mail_links_open_externally() {
+        let converter = Converter { base_dir: Path::new("/docs") };
+        let external = |url: &str| Some(Link::External(url.to_string()));
+        assert_eq!(converter.link("HTTPS://example.com"), external("HTTPS://example.com"));
+        assert_eq!(converter.link("mailto:a@example.com"), external("mailto:a@example.com"));
+        assert_eq!(converter.link("file:///etc/passwd"), None);
+        assert_eq!(converter.link("smb://host/share"), None);
+        assert_eq!(converter.link("#usage"), Some(Link::Anchor("usage".to_string())));
+    }
+
+    #[test]
+    fn relative_links_resolve_against_the_document() {
+        let converter = Converter { base_dir: Path::new("/docs/guide") };
+        let local = |path: &str| Some(Link::Local(PathBuf::from(path)));
+        assert_eq!(converter.link("../a%20b.md#x"), local("/docs/a b.md"));
+        assert_eq!(converter.link("./img/x.png?raw=1"), local("/docs/guide/img/x.png"));
+        assert_eq!(converter.link("/etc/hosts"), local("/etc/hosts"));
+    }
+
+    #[test]
+    fn parent_components_stop_at_the_root() {
+        assert_eq!(normalize(Path::new("/a/../../../b/./c")), PathBuf::from("/b/c"));
+        let converter = Converter { base_dir: Path::new("/docs") };
+        assert_eq!(converter.link("../../../x.md"), Some(Link::Local(PathBuf::from("/x.md"))));
+    }
+
+    #[test]
+    fn percent_decode_keeps_malformed_escapes() {
+        assert_eq!(percent_decode("a%20b"), "a b");
+        assert_eq!(percent_decode("%E4%B8%AD%e6%96%87"), "中文");
+        assert_eq!(percent_decode("%+1"), "%+1");
+        assert_eq!(percent_decode("%-1x"), "%-1x");
+        assert_eq!(percent_decode("%zz"), "%zz");
+        assert_eq!(percent_decode("100%"), "100%");
+        assert_eq!(percent_decode("%4"), "%4");
+    }
+
+    #[test]
+    fn anchors_follow_github() {
+        assert_eq!(anchor_for("Getting Started"), "getting-started");
+        assert_eq!(anchor_for("  API v2.0 (beta)! "), "api-v20-beta");
+        assert_eq!(anchor_for("snake_case & co"), "snake_case--co");
+        assert_eq!(anchor_for("中文 标题"), "中文-标题");
+    }
+}
@@ -0,0 +1,8 @@
+pub mod exif;
+pub mod hex;
+pub mod highlight;
+pub mod image_info;
+pub mod large_text;
+pub mod magic;
+pub mod markdown;
+pub mod thumbnails;
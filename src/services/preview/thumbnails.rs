@@ -0,0 +1,269 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc, Arc, Once,
+    },
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use image::ImageFormat;
+
+use crate::{
+    core::{
+        errors::{Error, Result},
+        paths::cache_dir,
+    },
+    services::preview::image_info::can_decode,
+};
+
+/// Longest side of a cached thumbnail, in pixels; enough for the grid on high-DPI screens.
+pub const THUMBNAIL_SIZE: u32 = 256;
+/// Larger images are not decoded for a thumbnail.
+const MAX_SOURCE_BYTES: u64 = 64 * 1024 * 1024;
+/// The thumbnail cache is trimmed back to this once per run, oldest thumbnails first.
+const MAX_CACHE_BYTES: u64 = 256 * 1024 * 1024;
+/// Partial files older than this were left by a crash rather than a worker still writing them.
+const STALE_PART_AGE: Duration = Duration::from_secs(60 * 60);
+
+/// The folder thumbnails are cached in. `None` if there is no cache folder on this system.
+pub fn thumbnail_dir() -> Option<PathBuf> {
+    Some(cache_dir()?.join("thumbnails"))
+}
+
+/// An image to make a thumbnail of. Size and mtime are part of the cache key, so a changed file
+/// gets a new thumbnail and the stale one is never looked up again.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ThumbnailRequest {
+    pub path: PathBuf,
+    pub size: u64,
+    /// Seconds since the Unix epoch, as in the listing.
+    pub modified: u64,
+}
+
+impl ThumbnailRequest {
+    /// Where the thumbnail for this version of the file is cached. `None` if there is no cache
+    /// folder on this system.
+    pub fn cache_path(&self) -> Option<PathBuf> {
+        Some(thumbnail_dir()?.join(format!("{}.png", self.cache_key())))
+    }
+
+    /// A stable hash of path, mtime and size. FNV-1a rather than `DefaultHasher`, whose output
+    /// may change between Rust releases and would orphan the cache.
+    pub fn cache_key(&self) -> String {
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        let bytes = self.path.as_os_str().as_encoded_bytes().iter();
+        for byte in bytes.chain(&self.modified.to_le_bytes()).chain(&self.size.to_le_bytes()) {
+            hash ^= u64::from(*byte);
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+        format!("{hash:016x}")
+    }
+}
+
+pub struct ThumbnailEvent {
+    pub path: PathBuf,
+    /// The cached thumbnail.
+    pub result: Result<PathBuf>,
+}
+
+/// A running batch of thumbnails started by [`spawn_thumbnails`]. Dropping it cancels the
+/// remaining work.
+pub struct ThumbnailJob {
+    rx: mpsc::Receiver<ThumbnailEvent>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl ThumbnailJob {
+    /// Blocks until the next thumbnail. Returns `None` once every request has been reported.
+    pub fn recv(&self) -> Option<ThumbnailEvent> {
+        self.rx.recv().ok()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Drop for ThumbnailJob {
+    fn drop(&mut self) {
+        self.cancel();
+    }
+}
+
+/// Makes thumbnails for the decodable images among `requests` on a worker thread.
+///
+/// Thumbnails already in the cache are reported first, without decoding anything, so a folder
+/// seen before fills in at once; the rest follow as they are made.
+pub fn spawn_thumbnails(requests: Vec<ThumbnailRequest>) -> ThumbnailJob {
+    let (tx, rx) = mpsc::channel();
+    let cancelled = Arc::new(AtomicBool::new(false));
+
+    let worker_cancelled = cancelled.clone();
+    let spawned = thread::Builder::new().name("nohrs-thumbnails".into()).spawn(move || {
+        // Before anything from the cache is handed out, so no thumbnail on screen is pruned.
+        static PRUNE: Once = Once::new();
+        PRUNE.call_once(|| {
+            if let Some(dir) = thumbnail_dir() {
+                match prune_cache(&dir, MAX_CACHE_BYTES) {
+                    Ok(0) => {},
+                    Ok(removed) => tracing::debug!("Pruned {} cached thumbnails", removed),
+                    Err(err) => tracing::debug!("Failed to prune {}: {}", dir.display(), err),
+                }
+            }
+        });
+
+        let mut missing = Vec::new();
+        for request in requests {
+            if !can_decode(&request.path) || request.size > MAX_SOURCE_BYTES {
+                continue;
+            }
+            let Some(dest) = request.cache_path() else {
+                return;
+            };
+            if dest.is_file() {
+                if tx.send(ThumbnailEvent { path: request.path, result: Ok(dest) }).is_err() {
+                    return;
+                }
+            } else {
+                missing.push((request, dest));
+            }
+        }
+
+        for (request, dest) in missing {
+            if worker_cancelled.load(Ordering::Relaxed) {
+                return;
+            }
+            let result = make_thumbnail(&request.path, &dest).map(|()| dest);
+            if tx.send(ThumbnailEvent { path: request.path, result }).is_err() {
+                return;
+            }
+        }
+    });
+    if let Err(err) = spawned {
+        tracing::error!("Failed to start thumbnail worker: {}", err);
+    }
+
+    ThumbnailJob { rx, cancelled }
+}
+
+/// Decodes `source`, scales it to fit [`THUMBNAIL_SIZE`] and writes it to `dest` as PNG. The file
+/// is written under a temporary name and renamed into place, so a crash never leaves a truncated
+/// thumbnail behind that later lookups would trust.
+pub fn make_thumbnail(source: &Path, dest: &Path) -> Result<()> {
+    let image = image::open(source).map_err(|err| Error::Other(err.to_string()))?;
+    let thumbnail = image.thumbnail(THUMBNAIL_SIZE, THUMBNAIL_SIZE);
+    if let Some(dir) = dest.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let partial = dest.with_extension("png.part");
+    thumbnail
+        .save_with_format(&partial, ImageFormat::Png)
+        .map_err(|err| Error::Other(err.to_string()))?;
+    fs::rename(&partial, dest)?;
+    Ok(())
+}
+
+/// Deletes the oldest thumbnails in `dir` until the rest fit in `max_bytes`, along with partial
+/// files a crash left behind. Age is the file's mtime, i.e. when the thumbnail was made. Returns
+/// how many files were deleted; a missing folder has nothing to prune.
+pub fn prune_cache(dir: &Path, max_bytes: u64) -> Result<usize> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(err) => return Err(err.into()),
+    };
+
+    let now = SystemTime::now();
+    let mut removed = 0;
+    let mut total = 0;
+    let mut thumbnails = Vec::new();
+    for entry in entries.flatten() {
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if !metadata.is_file() {
+            continue;
+        }
+        let path = entry.path();
+        let modified = metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+        if path.extension().is_some_and(|ext| ext == "part") {
+            let stale = now.duration_since(modified).is_ok_and(|age| age > STALE_PART_AGE);
+            if stale && fs::remove_file(&path).is_ok() {
+                removed += 1;
+            }
+            continue;
+        }
+        total += metadata.len();
+        thumbnails.push((modified, metadata.len(), path));
+    }
+
+    thumbnails.sort();
+    for (_, len, path) in thumbnails {
+        if total <= max_bytes {
+            break;
+        }
+        if fs::remove_file(&path).is_ok() {
+            total -= len;
+            removed += 1;
+        }
+    }
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::testing::ScratchDir;
+
+    fn write_aged(path: &Path, len: usize, age_secs: u64) {
+        fs::write(path, vec![0u8; len]).unwrap();
+        let file = fs::File::options().write(true).open(path).unwrap();
+        file.set_modified(SystemTime::now() - Duration::from_secs(age_secs)).unwrap();
+    }
+
+    #[test]
+    fn prune_removes_oldest_thumbnails_over_the_cap() {
+        let dir = ScratchDir::new("thumbnails-cap");
+        write_aged(&dir.join("old.png"), 100, 300);
+        write_aged(&dir.join("mid.png"), 100, 200);
+        write_aged(&dir.join("new.png"), 100, 100);
+
+        assert_eq!(prune_cache(&dir, 250).unwrap(), 1);
+        assert!(!dir.join("old.png").exists());
+        assert!(dir.join("mid.png").exists());
+        assert!(dir.join("new.png").exists());
+
+        assert_eq!(prune_cache(&dir, 250).unwrap(), 0);
+    }
+
+    #[test]
+    fn prune_removes_only_stale_partial_files() {
+        let dir = ScratchDir::new("thumbnails-part");
+        write_aged(&dir.join("crashed.png.part"), 10, 2 * 60 * 60);
+        write_aged(&dir.join("writing.png.part"), 10, 0);
+
+        assert_eq!(prune_cache(&dir, u64::MAX).unwrap(), 1);
+        assert!(!dir.join("crashed.png.part").exists());
+        assert!(dir.join("writing.png.part").exists());
+    }
+
+    #[test]
+    fn prune_of_a_missing_folder_is_a_no_op() {
+        let dir = ScratchDir::new("thumbnails-missing");
+        assert_eq!(prune_cache(&dir.join("thumbnails"), 0).unwrap(), 0);
+    }
+
+    #[test]
+    fn cache_key_tracks_size_and_mtime() {
+        let request = ThumbnailRequest { path: PathBuf::from("/a/b.png"), size: 10, modified: 5 };
+        assert_eq!(request.cache_key(), request.clone().cache_key());
+        assert_ne!(
+            request.cache_key(),
+            ThumbnailRequest { size: 11, ..request.clone() }.cache_key()
+        );
+        assert_ne!(request.cache_key(), ThumbnailRequest { modified: 6, ..request }.cache_key());
+    }
+}
@@ -0,0 +1,288 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::{
+    core::errors::{Error, Result},
+    services::{
+        fs::listing::{SortDirection, SortKey},
+        settings,
+    },
+};
+
+/// Layout version of the session file this build reads and writes.
+pub const SESSION_VERSION: u64 = 2;
+
+type Migration = fn(Map<String, Value>) -> Map<String, Value>;
+
+/// `MIGRATIONS[n]` upgrades a version `n + 1` file to version `n + 2`; there was no version 0.
+const MIGRATIONS: [Migration; SESSION_VERSION as usize - 1] = [v1_to_v2];
+
+/// What the open windows showed, so the next launch can pick up where this one left off.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    pub version: u64,
+    pub windows: Vec<WindowSession>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowSession {
+    pub geometry: Option<WindowGeometry>,
+    /// One per explorer tab, in tab order.
+    pub tabs: Vec<ExplorerSession>,
+    pub active_tab: usize,
+    /// The second explorer pane, when the window is split.
+    pub split: Option<SplitSession>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SplitSession {
+    pub orientation: SplitOrientation,
+    pub tabs: Vec<ExplorerSession>,
+    pub active_tab: usize,
+    /// Whether the second pane, rather than the first, had the focus.
+    pub second_active: bool,
+    pub compare: bool,
+}
+
+/// How the two panes of a split window are arranged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SplitOrientation {
+    /// Side by side.
+    #[default]
+    Horizontal,
+    /// One above the other.
+    Vertical,
+}
+
+/// Window position and size in logical pixels. For a maximized or full-screen window these are
+/// the bounds it returns to.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct WindowGeometry {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    #[serde(default)]
+    pub state: WindowState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WindowState {
+    #[default]
+    Windowed,
+    Maximized,
+    Fullscreen,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ViewMode {
+    #[default]
+    List,
+    Grid,
+}
+
+/// Listing column widths in logical pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ColumnWidths {
+    pub name: f32,
+    pub kind: f32,
+    pub size: f32,
+    pub modified: f32,
+}
+
+impl Default for ColumnWidths {
+    fn default() -> Self {
+        Self { name: 400.0, kind: 120.0, size: 120.0, modified: 180.0 }
+    }
+}
+
+/// The state of one explorer: where it is, how it got there and how it is laid out.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExplorerSession {
+    pub cwd: Option<String>,
+    pub history: Vec<String>,
+    pub history_index: usize,
+    pub view_mode: ViewMode,
+    pub sort_key: SortKey,
+    pub sort_direction: SortDirection,
+    pub columns: ColumnWidths,
+    pub sidebar_width: f32,
+    pub preview_width: f32,
+}
+
+impl Default for ExplorerSession {
+    fn default() -> Self {
+        Self {
+            cwd: None,
+            history: Vec::new(),
+            history_index: 0,
+            view_mode: ViewMode::default(),
+            sort_key: SortKey::default(),
+            sort_direction: SortDirection::default(),
+            columns: ColumnWidths::default(),
+            sidebar_width: 180.0,
+            preview_width: 240.0,
+        }
+    }
+}
+
+impl ExplorerSession {
+    /// The folder to open: the saved one, or its nearest ancestor that still exists.
+    pub fn existing_cwd(&self) -> Option<PathBuf> {
+        let cwd = PathBuf::from(self.cwd.as_ref()?);
+        cwd.ancestors().find(|dir| dir.is_dir()).map(Path::to_path_buf)
+    }
+}
+
+/// Reads the session file at `path`. A missing or empty file is an empty session.
+pub fn load(path: &Path) -> Result<Session> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Session::default()),
+        Err(err) => return Err(err.into()),
+    };
+    if text.trim().is_empty() {
+        return Ok(Session::default());
+    }
+    let parse_error = |err: String| Error::Parse(format!("{}: {}", path.display(), err));
+    let Value::Object(mut map) =
+        serde_json::from_str(&text).map_err(|err| parse_error(err.to_string()))?
+    else {
+        return Err(parse_error("expected a JSON object".to_string()));
+    };
+    let version = map.get("version").and_then(Value::as_u64).unwrap_or(1).max(1);
+    if version > SESSION_VERSION {
+        return Err(parse_error(format!(
+            "session version {version} is from a newer version of the app"
+        )));
+    }
+    for migration in &MIGRATIONS[version as usize - 1..] {
+        map = migration(map);
+    }
+    map.insert("version".to_string(), SESSION_VERSION.into());
+    serde_json::from_value(Value::Object(map)).map_err(|err| parse_error(err.to_string()))
+}
+
+/// Writes `session` to `path`, replacing the old file only once the new one is complete.
+pub fn save(path: &Path, session: &Session) -> Result<()> {
+    let json = serde_json::to_string_pretty(session)
+        .map_err(|err| Error::Other(format!("can't serialize the session: {err}")))?;
+    settings::save(path, &json)
+}
+
+/// Version 1 had a single explorer per window, before there were tabs.
+fn v1_to_v2(mut map: Map<String, Value>) -> Map<String, Value> {
+    if let Some(Value::Array(windows)) = map.get_mut("windows") {
+        for window in windows {
+            if let Value::Object(window) = window {
+                if let Some(explorer) = window.remove("explorer") {
+                    window.insert("tabs".to_string(), Value::Array(vec![explorer]));
+                }
+            }
+        }
+    }
+    map
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::testing::ScratchDir;
+
+    #[test]
+    fn missing_and_empty_files_are_empty_sessions() {
+        let dir = ScratchDir::new("session-empty");
+        let path = dir.join("session.json");
+        assert_eq!(load(&path).unwrap(), Session::default());
+        fs::write(&path, "\n").unwrap();
+        assert_eq!(load(&path).unwrap(), Session::default());
+    }
+
+    #[test]
+    fn round_trip() {
+        let dir = ScratchDir::new("session-round-trip");
+        let path = dir.join("session.json");
+        let tab = ExplorerSession {
+            cwd: Some("/tmp".to_string()),
+            view_mode: ViewMode::Grid,
+            sort_key: SortKey::Natural,
+            sort_direction: SortDirection::Descending,
+            ..ExplorerSession::default()
+        };
+        let session = Session {
+            version: SESSION_VERSION,
+            windows: vec![WindowSession {
+                tabs: vec![tab.clone(), ExplorerSession::default()],
+                active_tab: 1,
+                split: Some(SplitSession {
+                    orientation: SplitOrientation::Vertical,
+                    tabs: vec![tab],
+                    second_active: true,
+                    ..SplitSession::default()
+                }),
+                ..WindowSession::default()
+            }],
+        };
+        save(&path, &session).unwrap();
+        assert_eq!(load(&path).unwrap(), session);
+    }
+
+    #[test]
+    fn version_1_single_explorer_becomes_a_tab() {
+        let dir = ScratchDir::new("session-v1");
+        let path = dir.join("session.json");
+        let v1 = r#"{"version": 1, "windows": [{"explorer": {"sort_key": "size"}}, {}]}"#;
+        fs::write(&path, v1).unwrap();
+        let session = load(&path).unwrap();
+        assert_eq!(session.version, SESSION_VERSION);
+        assert_eq!(session.windows[0].tabs.len(), 1);
+        assert_eq!(session.windows[0].tabs[0].sort_key, SortKey::Size);
+        assert_eq!(session.windows[0].tabs[0].sidebar_width, 180.0);
+        assert!(session.windows[1].tabs.is_empty());
+
+        // Files from before the version field, or with a bogus 0, are version 1 as well.
+        fs::write(&path, r#"{"windows": [{"explorer": {}}]}"#).unwrap();
+        assert_eq!(load(&path).unwrap().windows[0].tabs.len(), 1);
+        fs::write(&path, r#"{"version": 0, "windows": [{"explorer": {}}]}"#).unwrap();
+        assert_eq!(load(&path).unwrap().windows[0].tabs.len(), 1);
+    }
+
+    #[test]
+    fn unreadable_sessions_are_refused() {
+        let dir = ScratchDir::new("session-refused");
+        let path = dir.join("session.json");
+        for text in [
+            r#"{"version": 3}"#,
+            "[]",
+            "{ broken",
+            r#"{"windows": [{"tabs": [{"view_mode": "cards"}]}]}"#,
+        ] {
+            fs::write(&path, text).unwrap();
+            assert!(matches!(load(&path), Err(Error::Parse(_))), "{text}");
+        }
+    }
+
+    #[test]
+    fn missing_folders_fall_back_to_an_ancestor() {
+        let dir = ScratchDir::new("session-cwd");
+        let tab = ExplorerSession {
+            cwd: Some(dir.join("gone/deeper").to_string_lossy().into_owned()),
+            ..ExplorerSession::default()
+        };
+        assert_eq!(tab.existing_cwd().as_deref(), Some(&*dir));
+        assert_eq!(ExplorerSession::default().existing_cwd(), None);
+    }
+}
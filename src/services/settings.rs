@@ -0,0 +1,152 @@
+use std::{fs, io, path::Path};
+
+use serde_json::{Map, Value};
+
+use crate::{
+    core::errors::{Error, Result},
+    services::fs::atomic::atomic_write,
+};
+
+/// Layout version of the settings file this build reads and writes.
+pub const SETTINGS_VERSION: u64 = 1;
+
+type Migration = fn(Map<String, Value>) -> Map<String, Value>;
+
+/// One step per layout change: `MIGRATIONS[n]` upgrades a version `n` file to version `n + 1`.
+const MIGRATIONS: [Migration; SETTINGS_VERSION as usize] = [v0_to_v1];
+
+/// Reads the settings file at `path` and upgrades it to [`SETTINGS_VERSION`]. Returns `Ok(None)`
+/// when there is no file (or it is empty), so defaults apply.
+pub fn load(path: &Path) -> Result<Option<Value>> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err.into()),
+    };
+    if text.trim().is_empty() {
+        return Ok(None);
+    }
+    let value: Value = serde_json::from_str(&text)
+        .map_err(|err| Error::Parse(format!("{}: {}", path.display(), err)))?;
+    migrate(value).map(Some).map_err(|err| match err {
+        Error::Parse(message) => Error::Parse(format!("{}: {}", path.display(), message)),
+        err => err,
+    })
+}
+
+/// Upgrades a settings document of any earlier version. A missing `version` means version 0.
+/// Documents from a newer build are refused rather than guessed at.
+pub fn migrate(value: Value) -> Result<Value> {
+    let Value::Object(mut map) = value else {
+        return Err(Error::Parse("expected a JSON object".to_string()));
+    };
+    let version = match map.get("version") {
+        None => 0,
+        Some(version) => version
+            .as_u64()
+            .ok_or_else(|| Error::Parse("\"version\" must be a whole number".to_string()))?,
+    };
+    if version > SETTINGS_VERSION {
+        return Err(Error::Parse(format!(
+            "settings version {version} is from a newer version of the app (this one reads up to \
+             {SETTINGS_VERSION})"
+        )));
+    }
+
+    for migration in &MIGRATIONS[version as usize..] {
+        map = migration(map);
+    }
+    map.insert("version".to_string(), SETTINGS_VERSION.into());
+    Ok(Value::Object(map))
+}
+
+/// Writes `json` to `path`, replacing the old file only once the new one is complete.
+pub fn save(path: &Path, json: &str) -> Result<()> {
+    atomic_write(path, json)
+}
+
+/// Version 0 is the old theme state file, which kept `theme` and `scrollbar_show` at the top
+/// level. Version 1 groups them under `appearance`, next to the `general` settings.
+fn v0_to_v1(mut map: Map<String, Value>) -> Map<String, Value> {
+    let mut appearance = match map.remove("appearance") {
+        Some(Value::Object(appearance)) => appearance,
+        _ => Map::new(),
+    };
+    for key in ["theme", "scrollbar_show"] {
+        if let Some(value) = map.remove(key) {
+            appearance.entry(key).or_insert(value);
+        }
+    }
+    map.insert("appearance".to_string(), Value::Object(appearance));
+    map
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::core::testing::ScratchDir;
+
+    #[test]
+    fn version_0_moves_appearance_keys() {
+        let migrated = migrate(json!({"theme": "Dark", "scrollbar_show": "always", "x": 1}));
+        assert_eq!(
+            migrated.unwrap(),
+            json!({
+                "version": 1,
+                "x": 1,
+                "appearance": {"theme": "Dark", "scrollbar_show": "always"},
+            })
+        );
+
+        // A nested value written by a newer build wins over the stale top-level one.
+        let mixed = json!({"version": 0, "theme": "Old", "appearance": {"theme": "New"}});
+        assert_eq!(migrate(mixed).unwrap()["appearance"], json!({"theme": "New"}));
+
+        let broken = json!({"theme": "Dark", "appearance": "not an object"});
+        assert_eq!(migrate(broken).unwrap()["appearance"], json!({"theme": "Dark"}));
+    }
+
+    #[test]
+    fn current_version_is_left_alone() {
+        let current = json!({"version": 1, "appearance": {"theme": "X"}, "general": {"a": 1}});
+        assert_eq!(migrate(current.clone()).unwrap(), current);
+    }
+
+    #[test]
+    fn unreadable_versions_are_refused() {
+        for value in [
+            json!({"version": SETTINGS_VERSION + 1}),
+            json!({"version": "1"}),
+            json!({"version": -1}),
+            json!({"version": 1.5}),
+            json!([{"version": 1}]),
+            json!(null),
+        ] {
+            assert!(matches!(migrate(value.clone()), Err(Error::Parse(_))), "{value}");
+        }
+    }
+
+    #[test]
+    fn load_and_save() {
+        let dir = ScratchDir::new("settings");
+        let path = dir.join("nested/settings.json");
+        assert_eq!(load(&path).unwrap(), None);
+
+        save(&path, "  \n").unwrap();
+        assert_eq!(load(&path).unwrap(), None);
+
+        save(&path, r#"{"theme": "A"}"#).unwrap();
+        assert_eq!(load(&path).unwrap().unwrap()["appearance"]["theme"], "A");
+
+        fs::write(&path, "{ broken").unwrap();
+        let Err(Error::Parse(message)) = load(&path) else {
+            panic!("broken JSON was accepted");
+        };
+        assert!(message.starts_with(&path.display().to_string()), "{message}");
+
+        fs::write(&path, r#"{"version": 99}"#).unwrap();
+        assert!(matches!(load(&path), Err(Error::Parse(_))));
+    }
+}
@@ -0,0 +1,295 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use crate::services::{
+    fs::{
+        compare::{ComparedEntry, EntryStatus, TreeComparison, MTIME_TOLERANCE_SECS},
+        ops::{ConflictPolicy, TransferOptions},
+    },
+    jobs::{JobKind, JobSpec},
+};
+
+/// How a synchronization settles the differences found by a comparison.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SyncMode {
+    /// Make the right side a copy of the left: copy what is new or different there, and trash
+    /// what only the right side has.
+    #[default]
+    Mirror,
+    /// Copy to the right what is new on the left or newer there. Nothing is deleted.
+    Update,
+    /// Copy what only one side has to the other, and the newer version of what differs.
+    TwoWay,
+}
+
+impl SyncMode {
+    pub const ALL: [SyncMode; 3] = [SyncMode::Mirror, SyncMode::Update, SyncMode::TwoWay];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SyncMode::Mirror => "Mirror",
+            SyncMode::Update => "Update",
+            SyncMode::TwoWay => "Two-Way",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SyncAction {
+    CopyToRight,
+    CopyToLeft,
+    TrashRight,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncStep {
+    /// Relative to both roots.
+    pub path: PathBuf,
+    pub action: SyncAction,
+}
+
+/// What a synchronization will do, for previewing before [`SyncPlan::jobs`] runs it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncPlan {
+    pub left: PathBuf,
+    pub right: PathBuf,
+    pub mode: SyncMode,
+    pub steps: Vec<SyncStep>,
+    /// Entries that differ with the same modification time on both sides, so neither is known
+    /// to be newer. They are left alone.
+    pub conflicts: Vec<PathBuf>,
+}
+
+/// Plans how `mode` brings the two compared trees in line.
+pub fn plan(comparison: &TreeComparison, mode: SyncMode) -> SyncPlan {
+    let mut steps = Vec::new();
+    let mut conflicts = Vec::new();
+    for entry in &comparison.entries {
+        match entry_action(entry, mode) {
+            Ok(Some(action)) => steps.push(SyncStep { path: entry.path.clone(), action }),
+            Ok(None) => {},
+            Err(()) => conflicts.push(entry.path.clone()),
+        }
+    }
+    SyncPlan {
+        left: comparison.left.clone(),
+        right: comparison.right.clone(),
+        mode,
+        steps,
+        conflicts,
+    }
+}
+
+/// The step for one entry, `None` when it needs none and `Err` for a conflict.
+fn entry_action(entry: &ComparedEntry, mode: SyncMode) -> Result<Option<SyncAction>, ()> {
+    Ok(match entry.status {
+        EntryStatus::Identical => None,
+        EntryStatus::LeftOnly => Some(SyncAction::CopyToRight),
+        EntryStatus::RightOnly => match mode {
+            SyncMode::Mirror => Some(SyncAction::TrashRight),
+            SyncMode::Update => None,
+            SyncMode::TwoWay => Some(SyncAction::CopyToLeft),
+        },
+        EntryStatus::Different => {
+            let (Some(left), Some(right)) = (entry.left, entry.right) else {
+                return Ok(None);
+            };
+            // Folders on both sides are settled by the steps for what is inside them.
+            if left.is_dir() && right.is_dir() {
+                return Ok(None);
+            }
+            if mode == SyncMode::Mirror {
+                return Ok(Some(SyncAction::CopyToRight));
+            }
+            if left.modified.abs_diff(right.modified) < MTIME_TOLERANCE_SECS {
+                return Err(());
+            }
+            match (left.modified > right.modified, mode) {
+                (true, _) => Some(SyncAction::CopyToRight),
+                (false, SyncMode::TwoWay) => Some(SyncAction::CopyToLeft),
+                (false, _) => None,
+            }
+        },
+    })
+}
+
+impl SyncPlan {
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    pub fn count(&self, action: SyncAction) -> usize {
+        self.steps.iter().filter(|s| s.action == action).count()
+    }
+
+    /// The plan as transfer jobs: one copy per destination folder, replacing what is there, and
+    /// the trashing last. Replacements are assembled next to the entry they replace and only
+    /// swapped in once complete, so a failed or cancelled sync never loses the old version.
+    pub fn jobs(&self) -> Vec<JobSpec> {
+        let options =
+            TransferOptions { conflict: ConflictPolicy::Overwrite, ..TransferOptions::default() };
+        let mut jobs: Vec<JobSpec> = Vec::new();
+        let mut copy_jobs: HashMap<(SyncAction, PathBuf), usize> = HashMap::new();
+        let mut trashed = Vec::new();
+        for step in &self.steps {
+            let (from, to) = match step.action {
+                SyncAction::CopyToRight => (&self.left, &self.right),
+                SyncAction::CopyToLeft => (&self.right, &self.left),
+                SyncAction::TrashRight => {
+                    trashed.push(self.right.join(&step.path));
+                    continue;
+                },
+            };
+            let dest = match step.path.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => to.join(parent),
+                _ => to.clone(),
+            };
+            let ix = *copy_jobs.entry((step.action, dest.clone())).or_insert_with(|| {
+                jobs.push(JobSpec { kind: JobKind::Copy, sources: Vec::new(), dest, options });
+                jobs.len() - 1
+            });
+            jobs[ix].sources.push(from.join(&step.path));
+        }
+        if !trashed.is_empty() {
+            jobs.push(JobSpec {
+                kind: JobKind::Trash,
+                sources: trashed,
+                dest: self.right.clone(),
+                options,
+            });
+        }
+        jobs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        path::Path,
+        sync::atomic::AtomicBool,
+        time::{Duration, UNIX_EPOCH},
+    };
+
+    use super::*;
+    use crate::{
+        core::testing::ScratchDir,
+        services::fs::{
+            compare::{compare_trees, CompareMethod},
+            ops::copy_entries,
+        },
+    };
+
+    fn write(path: &Path, body: &str, modified: u64) {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, body).unwrap();
+        let file = fs::File::options().write(true).open(path).unwrap();
+        file.set_modified(UNIX_EPOCH + Duration::from_secs(modified)).unwrap();
+    }
+
+    /// A left and right tree with one entry for every case a plan distinguishes, and the folder
+    /// holding them, which is removed when it is dropped.
+    fn compared(name: &str) -> (ScratchDir, TreeComparison) {
+        let dir = ScratchDir::new(&format!("sync-{name}"));
+        let (left, right) = (dir.join("left"), dir.join("right"));
+        write(&left.join("same.txt"), "same", 1000);
+        write(&right.join("same.txt"), "same", 1000);
+        write(&left.join("docs/new.txt"), "new", 1000);
+        write(&left.join("docs/newer.txt"), "left", 5000);
+        write(&right.join("docs/newer.txt"), "right!", 1000);
+        write(&left.join("older.txt"), "left", 1000);
+        write(&right.join("older.txt"), "right!", 5000);
+        write(&left.join("tie.txt"), "left", 3000);
+        write(&right.join("tie.txt"), "right!", 3000);
+        write(&left.join("kind"), "a file", 1000);
+        write(&right.join("kind/inner.txt"), "a folder", 1000);
+        write(&right.join("extra/deep.txt"), "extra", 1000);
+        write(&right.join("extra.txt"), "extra", 1000);
+        let comparison =
+            compare_trees(&left, &right, CompareMethod::SizeAndTime, &AtomicBool::new(false));
+        (dir, comparison.unwrap())
+    }
+
+    fn steps(plan: &SyncPlan, action: SyncAction) -> Vec<&str> {
+        let mut paths: Vec<&str> = plan
+            .steps
+            .iter()
+            .filter(|step| step.action == action)
+            .map(|step| step.path.to_str().unwrap())
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    #[test]
+    fn mirror_copies_every_difference_and_trashes_extras() {
+        let (_dir, comparison) = compared("mirror");
+        let plan = plan(&comparison, SyncMode::Mirror);
+        assert_eq!(steps(&plan, SyncAction::CopyToRight), [
+            "docs/new.txt",
+            "docs/newer.txt",
+            "kind",
+            "older.txt",
+            "tie.txt"
+        ]);
+        assert_eq!(steps(&plan, SyncAction::TrashRight), ["extra", "extra.txt"]);
+        assert!(steps(&plan, SyncAction::CopyToLeft).is_empty());
+        assert!(plan.conflicts.is_empty());
+
+        let jobs = plan.jobs();
+        assert_eq!(jobs.len(), 3);
+        assert_eq!(jobs[0].dest, plan.right.join("docs"));
+        assert_eq!(jobs[1].dest, plan.right);
+        assert_eq!(jobs[2].kind, JobKind::Trash);
+        assert_eq!(jobs[2].sources, [plan.right.join("extra"), plan.right.join("extra.txt")]);
+        assert!(jobs.iter().all(|job| job.options.conflict == ConflictPolicy::Overwrite));
+    }
+
+    #[test]
+    fn update_copies_newer_entries_and_deletes_nothing() {
+        let (_dir, comparison) = compared("update");
+        let plan = plan(&comparison, SyncMode::Update);
+        assert_eq!(steps(&plan, SyncAction::CopyToRight), ["docs/new.txt", "docs/newer.txt"]);
+        assert!(steps(&plan, SyncAction::TrashRight).is_empty());
+        assert!(plan.jobs().iter().all(|job| job.kind == JobKind::Copy));
+        assert_eq!(plan.conflicts, [PathBuf::from("tie.txt")]);
+    }
+
+    #[test]
+    fn two_way_copies_the_newer_side() {
+        let (_dir, comparison) = compared("two-way");
+        let plan = plan(&comparison, SyncMode::TwoWay);
+        assert_eq!(steps(&plan, SyncAction::CopyToLeft), [
+            "extra",
+            "extra.txt",
+            "kind",
+            "older.txt"
+        ]);
+        assert!(steps(&plan, SyncAction::TrashRight).is_empty());
+    }
+
+    #[test]
+    fn running_mirror_copies_replaces_the_right_side() {
+        let (_dir, comparison) = compared("run");
+        let plan = plan(&comparison, SyncMode::Mirror);
+        for job in plan.jobs().iter().filter(|job| job.kind == JobKind::Copy) {
+            let mut never = |_: &_| unreachable!("overwrite never asks");
+            let report = copy_entries(&job.sources, &job.dest, job.options, &mut never);
+            assert!(report.is_ok(), "{report:?}");
+        }
+        let read = |path: &str| fs::read_to_string(plan.right.join(path)).unwrap();
+        assert_eq!(read("docs/newer.txt"), "left");
+        assert_eq!(read("older.txt"), "left");
+        assert_eq!(read("kind"), "a file");
+
+        let rerun = compare_trees(
+            &plan.left,
+            &plan.right,
+            CompareMethod::SizeAndTime,
+            &AtomicBool::new(false),
+        )
+        .unwrap();
+        let remaining = super::plan(&rerun, SyncMode::Mirror);
+        assert_eq!(steps(&remaining, SyncAction::TrashRight), ["extra", "extra.txt"]);
+        assert!(steps(&remaining, SyncAction::CopyToRight).is_empty());
+    }
+}
@@ -1,23 +1,40 @@
 use gpui::{div, prelude::*, px, AnyElement, App, Context, Entity, IntoElement, Render, Window};
-use gpui_component::{input::InputState, orange_200, ActiveTheme, Icon};
+use gpui_component::{orange_200, ActiveTheme, Icon};
 use tracing::info;
 
 use crate::{
     pages::{
-        explorer::ExplorerPage, extensions::ExtensionsPage, git::GitPage,
-        icon_themes::IconThemesPage, keymap::KeymapPage, s3::S3Page, search::SearchPage,
-        settings::SettingsPage, themes::ThemesPage, PageKind,
+        extensions::ExtensionsPage,
+        git::GitPage,
+        icon_themes::IconThemesPage,
+        keymap::KeymapPage,
+        s3::S3Page,
+        search::SearchPage,
+        settings::{AppSettings, SettingsPage},
+        themes::ThemesPage,
+        PageKind,
     },
-    ui::components::layout::{
-        footer::{footer, FooterProps},
-        unified_toolbar::{AccountMenuAction, AccountMenuCommand},
+    services::session::{WindowGeometry, WindowSession},
+    ui::{
+        components::{
+            file_list::{format_date, human_bytes},
+            layout::{
+                footer::{footer, FooterProps},
+                unified_toolbar::{AccountMenuAction, AccountMenuCommand},
+            },
+            split_view::SplitView,
+            transfers::Transfers,
+        },
+        keymap::Keymap,
+        session_store::SessionStore,
+        settings_store::SettingsStore,
     },
 };
 
 pub struct NohrsApp {
     current_page: PageKind,
     // Page entities
-    explorer: Entity<ExplorerPage>,
+    explorer: Entity<SplitView>,
     search: Entity<SearchPage>,
     git: Entity<GitPage>,
     s3: Entity<S3Page>,
@@ -26,22 +43,48 @@ pub struct NohrsApp {
     keymap: Entity<KeymapPage>,
     themes: Entity<ThemesPage>,
     icon_themes: Entity<IconThemesPage>,
+    geometry: Option<WindowGeometry>,
 }
 impl NohrsApp {
-    pub fn new(window: &mut Window, cx: &mut Context<Self>) -> Self {
-        let search_input = cx.new(|cx| InputState::new(window, cx));
-
+    pub fn new(
+        restored: Option<WindowSession>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
         // Create page instances
-        let explorer = cx.new(|cx| ExplorerPage::new(search_input.clone(), cx.focus_handle()));
+        let explorer = cx.new(|cx| SplitView::new(restored, window, cx));
         let search = cx.new(|_cx| SearchPage::new());
         let git = cx.new(|_cx| GitPage::new());
         let s3 = cx.new(|_cx| S3Page::new());
         let extensions = cx.new(|_cx| ExtensionsPage::new());
         let settings = cx.new(|_cx| SettingsPage::new());
-        let keymap = cx.new(|_cx| KeymapPage::new());
+        let keymap = cx.new(|cx| KeymapPage::new(window, cx));
         let themes = cx.new(|_cx| ThemesPage::new());
         let icon_themes = cx.new(|_cx| IconThemesPage::new());
 
+        // Keep the footer's transfer progress current.
+        cx.observe(&Transfers::global(cx), |_, _, cx| cx.notify()).detach();
+
+        // Keep the session file current with the folder, layout and window bounds.
+        let this = cx.weak_entity();
+        SessionStore::global(cx).update(cx, |store, _| store.register(this));
+        cx.observe(&explorer, |_, _, cx| Self::schedule_session_save(cx)).detach();
+        cx.observe_window_bounds(window, |this, window, cx| {
+            this.geometry = Some(window.window_bounds().into());
+            Self::schedule_session_save(cx);
+        })
+        .detach();
+        cx.on_release(|_, cx| {
+            SessionStore::global(cx).update(cx, |store, cx| store.schedule_save(cx));
+        })
+        .detach();
+
+        // Config files are loaded before there is a window to report problems in.
+        window.defer(cx, |_, cx| {
+            SettingsStore::global(cx).update(cx, |store, cx| store.report_error(cx));
+            Keymap::global(cx).update(cx, |keymap, cx| keymap.report_problems(cx));
+        });
+
         Self {
             current_page: PageKind::Explorer,
             explorer,
@@ -53,15 +96,35 @@ impl NohrsApp {
             keymap,
             themes,
             icon_themes,
+            geometry: Some(window.window_bounds().into()),
         }
     }
 
     pub fn view(window: &mut Window, cx: &mut App) -> Entity<Self> {
-        cx.new(|cx| Self::new(window, cx))
+        cx.new(|cx| Self::new(None, window, cx))
+    }
+
+    /// A window showing what `session` saved.
+    pub fn restore(session: WindowSession, window: &mut Window, cx: &mut App) -> Entity<Self> {
+        cx.new(|cx| Self::new(Some(session), window, cx))
+    }
+
+    /// The state of this window to save in the session file.
+    pub fn session(&self, cx: &App) -> WindowSession {
+        let (tabs, active_tab, split) = self.explorer.read(cx).session(cx);
+        WindowSession { geometry: self.geometry, tabs, active_tab, split }
+    }
+
+    fn schedule_session_save(cx: &mut Context<Self>) {
+        SessionStore::global(cx).update(cx, |store, cx| store.schedule_save(cx));
     }
 
     pub fn set_page(&mut self, page: PageKind, cx: &mut Context<Self>) {
         if self.current_page != page {
+            if self.current_page == PageKind::KeyMap {
+                // Leaving mid-recording must not leave the key bindings suspended.
+                self.keymap.update(cx, |keymap, cx| keymap.stop_recording(cx));
+            }
             self.current_page = page;
             cx.notify();
         }
@@ -157,12 +220,24 @@ impl NohrsApp {
 impl Render for NohrsApp {
     fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
         let mut footer_props = FooterProps::default();
+        footer_props.transfers = Transfers::global(cx).read(cx).summary();
 
         // Update footer props based on current page
         if self.current_page == PageKind::Explorer {
-            let explorer = self.explorer.read(cx);
+            let explorer = self.explorer.read(cx).active_explorer(cx);
+            let explorer = explorer.read(cx);
             footer_props.selected_count = explorer.selected_count();
+            if footer_props.selected_count > 0 {
+                footer_props.selected_size = Some(human_bytes(explorer.selected_size()));
+            }
+            // A single date only means something for a single entry.
+            if footer_props.selected_count == 1 {
+                footer_props.selected_modified = explorer
+                    .selected_entry()
+                    .map(|entry| format_date(entry.modified, AppSettings::date_format(cx)));
+            }
             footer_props.total_count = explorer.total_count();
+            footer_props.total_size = human_bytes(explorer.total_size());
             footer_props.current_path = explorer.current_path().to_string();
         }
 
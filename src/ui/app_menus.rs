@@ -2,9 +2,19 @@ use gpui::{App, Entity, Menu, MenuItem, SharedString};
 use gpui_component::{menu::AppMenuBar, ActiveTheme as _, Theme, ThemeMode, ThemeRegistry};
 
 use super::{
+    components::{
+        history::{RedoOperation, ShowHistory, UndoOperation},
+        pane::{CloseTab, NewTab, NextTab, PreviousTab, ReopenClosedTab},
+        split_view::{
+            CompareFolders, CopyToOtherPane, FocusOtherPane, MoveToOtherPane, ToggleCompare,
+            ToggleSplit, ToggleSplitOrientation,
+        },
+        transfers::ShowTransfers,
+    },
     themes::{SwitchTheme, SwitchThemeMode},
     About, Open, Quit, SelectLocale, ToggleSearch,
 };
+use crate::pages::explorer::{GoToParent, OpenEntry};
 
 pub fn init(title: impl Into<SharedString>, cx: &mut App) -> Entity<AppMenuBar> {
     let app_menu_bar = AppMenuBar::new(cx);
@@ -63,6 +73,9 @@ fn update_app_menu(title: impl Into<SharedString>, app_menu_bar: Entity<AppMenuB
             items: vec![
                 MenuItem::action("Undo", gpui_component::input::Undo),
                 MenuItem::action("Redo", gpui_component::input::Redo),
+                MenuItem::action("Undo File Operation", UndoOperation),
+                MenuItem::action("Redo File Operation", RedoOperation),
+                MenuItem::action("History…", ShowHistory),
                 MenuItem::separator(),
                 MenuItem::action("Cut", gpui_component::input::Cut),
                 MenuItem::action("Copy", gpui_component::input::Copy),
@@ -80,9 +93,33 @@ fn update_app_menu(title: impl Into<SharedString>, app_menu_bar: Entity<AppMenuB
                 MenuItem::action("Select All", gpui_component::input::SelectAll),
             ],
         },
+        Menu {
+            name: "Go".into(),
+            items: vec![
+                MenuItem::action("Open", OpenEntry),
+                MenuItem::action("Enclosing Folder", GoToParent),
+            ],
+        },
         Menu {
             name: "Window".into(),
-            items: vec![MenuItem::action("Toggle Search", ToggleSearch)],
+            items: vec![
+                MenuItem::action("New Tab", NewTab),
+                MenuItem::action("Close Tab", CloseTab),
+                MenuItem::action("Reopen Closed Tab", ReopenClosedTab),
+                MenuItem::action("Next Tab", NextTab),
+                MenuItem::action("Previous Tab", PreviousTab),
+                MenuItem::separator(),
+                MenuItem::action("Split View", ToggleSplit),
+                MenuItem::action("Toggle Split Orientation", ToggleSplitOrientation),
+                MenuItem::action("Switch Pane", FocusOtherPane),
+                MenuItem::action("Compare Panes", ToggleCompare),
+                MenuItem::action("Compare Folders…", CompareFolders),
+                MenuItem::action("Copy to Other Pane", CopyToOtherPane),
+                MenuItem::action("Move to Other Pane", MoveToOtherPane),
+                MenuItem::separator(),
+                MenuItem::action("Toggle Search", ToggleSearch),
+                MenuItem::action("Transfers", ShowTransfers),
+            ],
         },
         Menu { name: "Help".into(), items: vec![MenuItem::action("Open Website", Open)] },
     ]);
@@ -0,0 +1,40 @@
+use std::ops::ControlFlow;
+
+use gpui::{AsyncApp, Context, WeakEntity};
+
+/// Hands what a worker reports to a view, item by item, until the worker is done.
+///
+/// Workers report over `std::sync::mpsc` channels, whose `recv` blocks, so each wait runs on the
+/// background executor and the source travels there and back with it. Each item is passed to
+/// `on_item` on the main thread, which can break off early. Returns `true` when the source ran
+/// dry, `false` when the view was released or `on_item` broke off.
+pub async fn forward_channel<V, S, T>(
+    mut source: S,
+    recv: fn(&S) -> Option<T>,
+    this: &WeakEntity<V>,
+    cx: &mut AsyncApp,
+    mut on_item: impl FnMut(&mut V, T, &mut Context<V>) -> ControlFlow<()>,
+) -> bool
+where
+    V: 'static,
+    S: Send + 'static,
+    T: Send + 'static,
+{
+    loop {
+        let (returned, item) = cx
+            .background_executor()
+            .spawn(async move {
+                let item = recv(&source);
+                (source, item)
+            })
+            .await;
+        source = returned;
+        let Some(item) = item else {
+            return true;
+        };
+        match this.update(cx, |this, cx| on_item(this, item, cx)) {
+            Ok(ControlFlow::Continue(())) => {},
+            Ok(ControlFlow::Break(())) | Err(_) => return false,
+        }
+    }
+}
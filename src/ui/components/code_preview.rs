@@ -0,0 +1,186 @@
+use std::{ops::Range, path::Path, rc::Rc, sync::Arc};
+
+use gpui::{
+    div, prelude::*, px, size, App, Context, FontStyle, HighlightStyle, Hsla, IntoElement, Pixels,
+    Render, SharedString, Size, StyledText, Task, Window,
+};
+use gpui_component::{h_flex, v_virtual_list, ActiveTheme, VirtualListScrollHandle};
+use syntect::parsing::SyntaxReference;
+
+use crate::services::preview::highlight::{detect_syntax, LineHighlighter, TokenClass};
+
+const LINE_HEIGHT: f32 = 20.0;
+/// Approximate advance of one monospace character, for the horizontal extent of the list.
+const CHAR_WIDTH: f32 = 7.8;
+/// Lines highlighted per background batch.
+const HIGHLIGHT_BATCH: usize = 500;
+/// How far past the last visible line highlighting runs ahead, so scrolling finds it done.
+const HIGHLIGHT_LOOKAHEAD: usize = 200;
+const TAB: &str = "    ";
+
+/// A text file split into lines and matched to a syntax, ready for [`CodePreview::new`]. Splitting
+/// a large file takes a while, so this is made on a background thread.
+pub struct CodeText {
+    lines: Vec<SharedString>,
+    syntax: Option<&'static SyntaxReference>,
+    longest: usize,
+}
+
+impl CodeText {
+    pub fn new(path: &Path, text: &str) -> Self {
+        let syntax = detect_syntax(path, text.lines().next().unwrap_or(""));
+        let lines: Vec<SharedString> = text
+            .lines()
+            .map(|line| SharedString::from(line.trim_end_matches('\r').replace('\t', TAB)))
+            .collect();
+        let longest = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0);
+        Self { lines, syntax, longest }
+    }
+}
+
+/// A text file with line numbers, highlighted in the background as far as it has been scrolled.
+pub struct CodePreview {
+    lines: Arc<Vec<SharedString>>,
+    language: Option<SharedString>,
+    /// Per highlighted line, from the top.
+    highlights: Vec<Vec<(Range<usize>, TokenClass)>>,
+    /// `None` while a batch is running, and for plain text.
+    highlighter: Option<LineHighlighter>,
+    /// Highlighting continues until it reaches this line.
+    wanted: usize,
+    line_sizes: Rc<Vec<Size<Pixels>>>,
+    scroll_handle: VirtualListScrollHandle,
+    _highlight_task: Task<()>,
+}
+
+impl CodePreview {
+    pub fn new(text: CodeText, cx: &mut Context<Self>) -> Self {
+        let CodeText { lines, syntax, longest } = text;
+        let width = px(gutter_width(lines.len()) + longest as f32 * CHAR_WIDTH);
+        let line_sizes = Rc::new(vec![size(width, px(LINE_HEIGHT)); lines.len()]);
+
+        let mut preview = Self {
+            lines: Arc::new(lines),
+            language: syntax.map(|syntax| SharedString::from(syntax.name.clone())),
+            highlights: Vec::new(),
+            highlighter: syntax.map(LineHighlighter::new),
+            wanted: HIGHLIGHT_LOOKAHEAD,
+            line_sizes,
+            scroll_handle: VirtualListScrollHandle::new(),
+            _highlight_task: Task::ready(()),
+        };
+        preview.highlight_more(cx);
+        preview
+    }
+
+    /// The detected language, `None` for plain text.
+    pub fn language(&self) -> Option<&str> {
+        self.language.as_deref()
+    }
+
+    pub fn line_count(&self) -> usize {
+        self.lines.len()
+    }
+
+    /// Highlights the next batch in the background, unless one is running or enough is done.
+    fn highlight_more(&mut self, cx: &mut Context<Self>) {
+        let start = self.highlights.len();
+        if start >= self.wanted.min(self.lines.len()) {
+            return;
+        }
+        let Some(mut highlighter) = self.highlighter.take() else {
+            return;
+        };
+        let end = (start + HIGHLIGHT_BATCH).min(self.lines.len());
+        let lines = self.lines.clone();
+        self._highlight_task = cx.spawn(async move |this, cx| {
+            let (highlighter, spans) = cx
+                .background_executor()
+                .spawn(async move {
+                    let spans: Vec<_> = lines[start..end]
+                        .iter()
+                        .map(|line| highlighter.highlight_line(line))
+                        .collect();
+                    (highlighter, spans)
+                })
+                .await;
+            let _ = this.update(cx, |this, cx| {
+                this.highlights.extend(spans);
+                this.highlighter = Some(highlighter);
+                cx.notify();
+                this.highlight_more(cx);
+            });
+        });
+    }
+
+    fn render_line(&self, ix: usize, gutter: f32, cx: &App) -> impl IntoElement {
+        let text = self.lines[ix].clone();
+        let text = match self.highlights.get(ix).filter(|spans| !spans.is_empty()) {
+            Some(spans) => StyledText::new(text).with_highlights(
+                spans.iter().map(|(range, class)| (range.clone(), token_style(*class, cx))),
+            ),
+            None => StyledText::new(text),
+        };
+
+        h_flex()
+            .h(px(LINE_HEIGHT))
+            .child(
+                div()
+                    .w(px(gutter))
+                    .flex_shrink_0()
+                    .pr_3()
+                    .text_right()
+                    .text_color(cx.theme().muted_foreground)
+                    .child((ix + 1).to_string()),
+            )
+            .child(div().whitespace_nowrap().text_color(cx.theme().foreground).child(text))
+    }
+}
+
+/// Room for the widest line number.
+fn gutter_width(line_count: usize) -> f32 {
+    let digits = line_count.max(1).ilog10() as f32 + 1.0;
+    digits * CHAR_WIDTH + 16.0
+}
+
+pub(crate) fn token_style(class: TokenClass, cx: &App) -> HighlightStyle {
+    let theme = cx.theme();
+    let color: Hsla = match class {
+        TokenClass::Comment => theme.muted_foreground,
+        TokenClass::String => theme.green,
+        TokenClass::Number | TokenClass::Constant => theme.yellow,
+        TokenClass::Keyword => theme.magenta,
+        TokenClass::Function => theme.blue,
+        TokenClass::Type => theme.cyan,
+        TokenClass::Tag => theme.red,
+        TokenClass::Attribute => theme.yellow,
+    };
+    HighlightStyle {
+        color: Some(color),
+        font_style: (class == TokenClass::Comment).then_some(FontStyle::Italic),
+        ..Default::default()
+    }
+}
+
+impl Render for CodePreview {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let gutter = gutter_width(self.lines.len());
+
+        div().size_full().font_family("monospace").text_xs().child(
+            v_virtual_list(
+                cx.entity().clone(),
+                "code-preview",
+                self.line_sizes.clone(),
+                move |view, visible_range, _window, cx| {
+                    let wanted = visible_range.end + HIGHLIGHT_LOOKAHEAD;
+                    if wanted > view.wanted {
+                        view.wanted = wanted;
+                        view.highlight_more(cx);
+                    }
+                    visible_range.map(|ix| view.render_line(ix, gutter, cx)).collect()
+                },
+            )
+            .track_scroll(&self.scroll_handle),
+        )
+    }
+}
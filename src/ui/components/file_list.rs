@@ -4,19 +4,32 @@ use gpui_component::{
     Icon, IconName, IndexPath,
 };
 
-use crate::services::fs::listing::FileEntryDto;
+use crate::{
+    core::datetime::{format_unix, DateFormat},
+    models::{file_entry::FileKind, selection::Selection},
+    pages::settings::AppSettings,
+    services::fs::{listing::FileEntryDto, metadata::Timestamp},
+};
 
 #[derive(Default)]
 pub struct FileListDelegate {
     pub items: Vec<FileEntryDto>,
+    /// The keyboard cursor of the list.
     pub selected: Option<IndexPath>,
+    /// The entries shown as selected, which may be more than the cursor.
+    pub selection: Selection,
     // Callback hooks
     pub on_confirm: Option<Box<dyn Fn(&FileEntryDto) + 'static>>,
 }
 
 impl FileListDelegate {
     pub fn new() -> Self {
-        Self { items: Vec::new(), selected: None, on_confirm: None }
+        Self {
+            items: Vec::new(),
+            selected: None,
+            selection: Selection::default(),
+            on_confirm: None,
+        }
     }
 
     pub fn set_items(&mut self, items: Vec<FileEntryDto>) {
@@ -40,21 +53,20 @@ impl ListDelegate for FileListDelegate {
         &mut self,
         ix: IndexPath,
         _window: &mut Window,
-        _cx: &mut gpui::Context<ListState<Self>>,
+        cx: &mut gpui::Context<ListState<Self>>,
     ) -> Option<Self::Item> {
         let item = self.items.get(ix.row)?;
+        let date_format = AppSettings::date_format(cx);
 
         // Icon based on file type
-        let icon_name = match item.kind.as_str() {
-            "dir" => IconName::Folder,
-            _ => IconName::File,
-        };
+        let icon_name = kind_icon(item);
 
         // Alternate row background for zebra striping
 
-        let file_type = get_file_type(&item.name, &item.kind);
+        let file_type = get_file_type(&item.name, item.kind);
 
         let mut row = ListItem::new(ix)
+            .selected(self.selection.contains(&item.path))
             .py(px(6.0)) // Reduced from 12.0 for compact rows
             .px(px(24.0))
             .child(
@@ -100,11 +112,7 @@ impl ListDelegate for FileListDelegate {
                             .text_sm()
                             .text_ellipsis()
                             .whitespace_nowrap()
-                            .child(match item.kind.as_str() {
-                                "file" => human_bytes(item.size),
-                                "dir" => "-".to_string(),
-                                other => other.to_string(),
-                            }),
+                            .child(size_label(item)),
                     )
                     .child(
                         // Modified column - compact
@@ -115,7 +123,7 @@ impl ListDelegate for FileListDelegate {
                             .overflow_hidden()
                             .text_ellipsis()
                             .whitespace_nowrap()
-                            .child(format_date(&item.modified)),
+                            .child(format_date(item.modified, date_format)),
                     )
                     .child(
                         // Actions column - compact
@@ -187,36 +195,56 @@ pub fn human_bytes(size: u64) -> String {
     }
 }
 
-pub fn format_date(timestamp: &u64) -> String {
-    // Convert unix timestamp to readable date
-    use std::time::{Duration, UNIX_EPOCH};
-
-    let d = UNIX_EPOCH + Duration::from_secs(*timestamp);
-    if let Ok(datetime) = d.duration_since(UNIX_EPOCH) {
-        let secs = datetime.as_secs();
-        let days = secs / 86400;
-        let years_since_epoch = days / 365;
-        let year = 1970 + years_since_epoch;
-        let remaining_days = days % 365;
-        let month = (remaining_days / 30) + 1;
-        let day = (remaining_days % 30) + 1;
-        format!("{:04}/{:02}/{:02}", year, month, day)
-    } else {
-        "-".to_string()
+pub fn format_date(timestamp: u64, format: DateFormat) -> String {
+    format_unix(timestamp as i64, 0, format)
+}
+
+/// Like [`format_date`], keeping the full nanosecond precision for the ISO-8601 format.
+pub fn format_timestamp(ts: &Timestamp, format: DateFormat) -> String {
+    match format {
+        DateFormat::Iso8601 => {
+            let base = format_unix(ts.secs, 0, format);
+            // Insert the fraction before the UTC offset: 2024-03-05T14:03:07.123456789+01:00
+            match base.len().checked_sub(6) {
+                Some(split) if base != "-" => {
+                    format!("{}.{:09}{}", &base[..split], ts.nanos, &base[split..])
+                },
+                _ => base,
+            }
+        },
+        _ => format_unix(ts.secs, ts.nanos, format),
     }
 }
 
-pub fn get_file_type(name: &str, kind: &str) -> String {
+pub fn get_file_type(name: &str, kind: FileKind) -> String {
     match kind {
-        "dir" => "Folder".to_string(),
-        "file" => {
+        FileKind::File => {
             if let Some(ext) = std::path::Path::new(name).extension().and_then(|e| e.to_str()) {
                 ext.to_uppercase()
             } else {
                 "File".to_string()
             }
         },
-        "symlink" => "Link".to_string(),
-        other => other.to_string(),
+        other => other.label().to_string(),
+    }
+}
+
+/// Text for the Size column.
+pub fn size_label(item: &FileEntryDto) -> String {
+    match item.kind {
+        FileKind::File => human_bytes(item.size),
+        FileKind::Dir => "-".to_string(),
+        FileKind::Symlink if item.link.as_ref().is_some_and(|l| l.is_broken()) => {
+            "Broken link".to_string()
+        },
+        other => other.label().to_string(),
+    }
+}
+
+pub fn kind_icon(item: &FileEntryDto) -> IconName {
+    if item.opens_as_dir() {
+        IconName::Folder
+    } else {
+        IconName::File
     }
 }
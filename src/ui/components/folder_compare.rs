@@ -0,0 +1,407 @@
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use gpui::{
+    div, prelude::*, px, size, App, Context, EventEmitter, Hsla, IntoElement, Pixels, Render, Size,
+    Task, Window,
+};
+use gpui_component::{
+    button::{Button, ButtonVariants as _},
+    h_flex,
+    notification::Notification,
+    v_flex, v_virtual_list, ActiveTheme, Disableable as _, Selectable as _, Sizable as _,
+    VirtualListScrollHandle, WindowExt as _,
+};
+
+use crate::{
+    core::errors::Error,
+    pages::settings::AppSettings,
+    services::{
+        fs::compare::{compare_trees, CompareMethod, EntrySide, EntryStatus, TreeComparison},
+        sync::{self, SyncAction, SyncMode, SyncPlan},
+    },
+    ui::components::{
+        file_list::{format_date, human_bytes},
+        transfers::Transfers,
+    },
+};
+
+const ROW_HEIGHT: f32 = 28.0;
+/// Wide enough for every column; the sheet clips what does not fit.
+const ROW_WIDTH: f32 = 720.0;
+
+pub enum FolderCompareEvent {
+    /// Show the folder holding this entry, relative to both roots, on each side.
+    Reveal(PathBuf),
+}
+
+enum CompareState {
+    Running,
+    Done(TreeComparison),
+    Failed(String),
+}
+
+/// Compares two folder trees and synchronizes them through the transfer queue, after showing
+/// what the synchronization is going to do.
+pub struct FolderCompare {
+    left: PathBuf,
+    right: PathBuf,
+    method: CompareMethod,
+    mode: SyncMode,
+    show_identical: bool,
+    state: CompareState,
+    plan: Option<SyncPlan>,
+    /// The planned step of each entry, by its relative path.
+    actions: HashMap<PathBuf, SyncAction>,
+    /// Indices into the comparison entries of the rows shown.
+    rows: Vec<usize>,
+    row_sizes: Rc<Vec<Size<Pixels>>>,
+    scroll_handle: VirtualListScrollHandle,
+    cancel: Arc<AtomicBool>,
+    _compare_task: Task<()>,
+}
+
+impl EventEmitter<FolderCompareEvent> for FolderCompare {}
+
+impl FolderCompare {
+    pub fn new(left: PathBuf, right: PathBuf, cx: &mut Context<Self>) -> Self {
+        let mut view = Self {
+            left,
+            right,
+            method: CompareMethod::default(),
+            mode: SyncMode::default(),
+            show_identical: false,
+            state: CompareState::Running,
+            plan: None,
+            actions: HashMap::new(),
+            rows: Vec::new(),
+            row_sizes: Rc::new(Vec::new()),
+            scroll_handle: VirtualListScrollHandle::new(),
+            cancel: Arc::new(AtomicBool::new(false)),
+            _compare_task: Task::ready(()),
+        };
+        view.rescan(cx);
+        view
+    }
+
+    /// Compares the trees again in the background, stopping a comparison still running.
+    fn rescan(&mut self, cx: &mut Context<Self>) {
+        self.cancel.store(true, Ordering::Relaxed);
+        let cancel = Arc::new(AtomicBool::new(false));
+        self.cancel = cancel.clone();
+        self.state = CompareState::Running;
+        self.refresh(cx);
+
+        let (left, right, method) = (self.left.clone(), self.right.clone(), self.method);
+        self._compare_task = cx.spawn(async move |this, cx| {
+            let result = cx
+                .background_executor()
+                .spawn(async move { compare_trees(&left, &right, method, &cancel) })
+                .await;
+            let _ = this.update(cx, |this, cx| {
+                this.state = match result {
+                    Ok(comparison) => CompareState::Done(comparison),
+                    Err(Error::Cancelled) => return,
+                    Err(err) => CompareState::Failed(err.to_string()),
+                };
+                this.refresh(cx);
+            });
+        });
+    }
+
+    /// Rebuilds the plan and the rows after the comparison, the mode or the filter changed.
+    fn refresh(&mut self, cx: &mut Context<Self>) {
+        let comparison = match &self.state {
+            CompareState::Done(comparison) => Some(comparison),
+            _ => None,
+        };
+        self.plan = comparison.map(|comparison| sync::plan(comparison, self.mode));
+        self.actions = self
+            .plan
+            .iter()
+            .flat_map(|plan| &plan.steps)
+            .map(|step| (step.path.clone(), step.action))
+            .collect();
+        self.rows = comparison
+            .map(|comparison| {
+                comparison
+                    .entries
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, e)| self.show_identical || e.status != EntryStatus::Identical)
+                    .map(|(ix, _)| ix)
+                    .collect()
+            })
+            .unwrap_or_default();
+        self.row_sizes = Rc::new(vec![size(px(ROW_WIDTH), px(ROW_HEIGHT)); self.rows.len()]);
+        cx.notify();
+    }
+
+    fn set_method(&mut self, method: CompareMethod, cx: &mut Context<Self>) {
+        if self.method != method {
+            self.method = method;
+            self.rescan(cx);
+        }
+    }
+
+    fn set_mode(&mut self, mode: SyncMode, cx: &mut Context<Self>) {
+        if self.mode != mode {
+            self.mode = mode;
+            self.refresh(cx);
+        }
+    }
+
+    fn synchronize(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        let Some(plan) = self.plan.as_ref().filter(|plan| !plan.is_empty()) else {
+            return;
+        };
+        let transfers = Transfers::global(cx);
+        for spec in plan.jobs() {
+            transfers.read(cx).enqueue(spec);
+        }
+        let message = format!("{} started for {} items", plan.mode.label(), plan.steps.len());
+        window.push_notification(Notification::info(message), cx);
+        window.close_sheet(cx);
+    }
+
+    fn render_side(&self, side: Option<EntrySide>, cx: &App) -> impl IntoElement {
+        let text = side.map(|side| {
+            let date = format_date(side.modified, AppSettings::date_format(cx));
+            if side.is_dir() {
+                date
+            } else {
+                format!("{}  {}", human_bytes(side.size), date)
+            }
+        });
+        div()
+            .w(px(200.0))
+            .flex_shrink_0()
+            .text_xs()
+            .text_color(cx.theme().muted_foreground)
+            .overflow_hidden()
+            .whitespace_nowrap()
+            .children(text)
+    }
+
+    fn render_row(&self, row: usize, cx: &mut Context<Self>) -> Option<impl IntoElement> {
+        let CompareState::Done(comparison) = &self.state else {
+            return None;
+        };
+        let entry = comparison.entries.get(*self.rows.get(row)?)?;
+        let (status, color) = status_label(entry.status, cx);
+        let action = match self.actions.get(&entry.path) {
+            Some(SyncAction::CopyToRight) => "→",
+            Some(SyncAction::CopyToLeft) => "←",
+            Some(SyncAction::TrashRight) => "✕",
+            None => "",
+        };
+        let is_dir = entry.left.or(entry.right).is_some_and(|side| side.is_dir());
+        let name = if is_dir {
+            format!("{}/", entry.path.display())
+        } else {
+            entry.path.display().to_string()
+        };
+        let path = entry.path.clone();
+
+        Some(
+            h_flex()
+                .id(row)
+                .w_full()
+                .h(px(ROW_HEIGHT))
+                .gap_2()
+                .px_2()
+                .border_b_1()
+                .border_color(cx.theme().border)
+                .hover(|style| style.bg(cx.theme().list_hover))
+                .on_click(cx.listener(move |_, _, _, cx| {
+                    cx.emit(FolderCompareEvent::Reveal(path.clone()));
+                }))
+                .child(div().w(px(72.0)).flex_shrink_0().text_xs().text_color(color).child(status))
+                .child(
+                    div()
+                        .flex_1()
+                        .min_w(px(0.0))
+                        .text_sm()
+                        .overflow_hidden()
+                        .text_ellipsis()
+                        .whitespace_nowrap()
+                        .child(name),
+                )
+                .child(self.render_side(entry.left, cx))
+                .child(div().w(px(16.0)).flex_shrink_0().text_sm().child(action))
+                .child(self.render_side(entry.right, cx)),
+        )
+    }
+
+    fn render_summary(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let text = match &self.state {
+            CompareState::Running => "Comparing…".to_string(),
+            CompareState::Failed(err) => format!("Comparison failed: {err}"),
+            CompareState::Done(comparison) if comparison.is_identical() => {
+                "The folders are identical".to_string()
+            },
+            CompareState::Done(comparison) => format!(
+                "{} only left · {} only right · {} different · {} identical",
+                comparison.count(EntryStatus::LeftOnly),
+                comparison.count(EntryStatus::RightOnly),
+                comparison.count(EntryStatus::Different),
+                comparison.count(EntryStatus::Identical),
+            ),
+        };
+        let color = match self.state {
+            CompareState::Failed(_) => cx.theme().danger,
+            _ => cx.theme().muted_foreground,
+        };
+        div().text_xs().text_color(color).child(text)
+    }
+
+    fn render_plan(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let text = match &self.plan {
+            None => String::new(),
+            Some(plan) if plan.is_empty() && plan.conflicts.is_empty() => {
+                "Nothing to synchronize".to_string()
+            },
+            Some(plan) => {
+                let mut parts = Vec::new();
+                for (action, label) in [
+                    (SyncAction::CopyToRight, "to copy right"),
+                    (SyncAction::CopyToLeft, "to copy left"),
+                    (SyncAction::TrashRight, "to trash on the right"),
+                ] {
+                    let count = plan.count(action);
+                    if count > 0 {
+                        parts.push(format!("{count} {label}"));
+                    }
+                }
+                if !plan.conflicts.is_empty() {
+                    parts.push(format!("{} skipped, changed on both sides", plan.conflicts.len()));
+                }
+                parts.join(" · ")
+            },
+        };
+        let can_run = self.plan.as_ref().is_some_and(|plan| !plan.is_empty());
+
+        h_flex()
+            .gap_2()
+            .children(SyncMode::ALL.map(|mode| {
+                Button::new(mode.label())
+                    .small()
+                    .label(mode.label())
+                    .selected(self.mode == mode)
+                    .on_click(cx.listener(move |this, _, _, cx| this.set_mode(mode, cx)))
+            }))
+            .child(
+                div()
+                    .flex_1()
+                    .min_w(px(0.0))
+                    .text_xs()
+                    .text_color(cx.theme().muted_foreground)
+                    .overflow_hidden()
+                    .text_ellipsis()
+                    .whitespace_nowrap()
+                    .child(text),
+            )
+            .child(
+                Button::new("compare-sync")
+                    .small()
+                    .primary()
+                    .label("Synchronize")
+                    .disabled(!can_run)
+                    .on_click(cx.listener(|this, _, window, cx| this.synchronize(window, cx))),
+            )
+    }
+}
+
+fn status_label(status: EntryStatus, cx: &App) -> (&'static str, Hsla) {
+    match status {
+        EntryStatus::LeftOnly => ("Only left", cx.theme().success),
+        EntryStatus::RightOnly => ("Only right", cx.theme().success),
+        EntryStatus::Different => ("Different", cx.theme().warning),
+        EntryStatus::Identical => ("Identical", cx.theme().muted_foreground),
+    }
+}
+
+impl Drop for FolderCompare {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Render for FolderCompare {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let muted = cx.theme().muted_foreground;
+        let running = matches!(self.state, CompareState::Running);
+        let method_button = |id: &'static str, label: &'static str, method: CompareMethod| {
+            Button::new(id)
+                .small()
+                .label(label)
+                .selected(self.method == method)
+                .on_click(cx.listener(move |this, _, _, cx| this.set_method(method, cx)))
+        };
+
+        v_flex()
+            .size_full()
+            .gap_2()
+            .child(
+                v_flex()
+                    .text_xs()
+                    .text_color(muted)
+                    .child(format!("Left: {}", self.left.display()))
+                    .child(format!("Right: {}", self.right.display())),
+            )
+            .child(
+                h_flex()
+                    .gap_2()
+                    .child(method_button(
+                        "compare-size-time",
+                        "Size and Date",
+                        CompareMethod::SizeAndTime,
+                    ))
+                    .child(method_button("compare-contents", "Contents", CompareMethod::Contents))
+                    .child(
+                        Button::new("compare-identical")
+                            .small()
+                            .label("Show Identical")
+                            .selected(self.show_identical)
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                this.show_identical = !this.show_identical;
+                                this.refresh(cx);
+                            })),
+                    )
+                    .child(div().flex_1())
+                    .child(
+                        Button::new("compare-rescan")
+                            .small()
+                            .label("Compare Again")
+                            .disabled(running)
+                            .on_click(cx.listener(|this, _, _, cx| this.rescan(cx))),
+                    ),
+            )
+            .child(self.render_summary(cx))
+            .child(self.render_plan(cx))
+            .child(
+                div().flex_1().min_h(px(0.0)).overflow_hidden().child(
+                    v_virtual_list(
+                        cx.entity().clone(),
+                        "compare-entries",
+                        self.row_sizes.clone(),
+                        |view, visible_range, _window, cx| {
+                            visible_range
+                                .filter_map(|row| {
+                                    view.render_row(row, cx).map(IntoElement::into_any_element)
+                                })
+                                .collect()
+                        },
+                    )
+                    .track_scroll(&self.scroll_handle),
+                ),
+            )
+    }
+}
@@ -0,0 +1,215 @@
+use std::{
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use gpui::{
+    div, prelude::*, px, size, App, Context, IntoElement, Pixels, Render, ScrollStrategy, Size,
+    Task, Window,
+};
+use gpui_component::{
+    button::{Button, ButtonVariants as _},
+    h_flex, v_flex, v_virtual_list, ActiveTheme, Disableable as _, Sizable as _,
+    VirtualListScrollHandle,
+};
+
+use crate::{
+    services::preview::{
+        hex::{
+            format_ascii, format_hex, format_offset, page_count, read_page, HexPage, BYTES_PER_ROW,
+            PAGE_SIZE,
+        },
+        magic::{detect_file_type, read_header},
+    },
+    ui::components::file_list::human_bytes,
+};
+
+const ROW_HEIGHT: f32 = 20.0;
+/// Approximate advance of one monospace character.
+const CHAR_WIDTH: f32 = 7.8;
+
+/// A hex dump of a binary file, one page at a time, with the file type its magic bytes show.
+pub struct HexPreview {
+    path: PathBuf,
+    len: u64,
+    /// `None` until the header has been read, and for unknown types.
+    file_type: Option<&'static str>,
+    page: Option<HexPage>,
+    /// The page being read, if any.
+    loading: Option<u64>,
+    error: Option<String>,
+    row_sizes: Rc<Vec<Size<Pixels>>>,
+    scroll_handle: VirtualListScrollHandle,
+    _header_task: Task<()>,
+    _read_task: Task<()>,
+}
+
+impl HexPreview {
+    pub fn new(path: &Path, len: u64, cx: &mut Context<Self>) -> Self {
+        let source = path.to_path_buf();
+        let header_task = cx.spawn(async move |this, cx| {
+            let header = cx.background_executor().spawn(async move { read_header(&source) }).await;
+            let _ = this.update(cx, |this, cx| {
+                if let Ok(header) = header {
+                    this.file_type = detect_file_type(&header);
+                }
+                cx.notify();
+            });
+        });
+        let mut preview = Self {
+            path: path.to_path_buf(),
+            len,
+            file_type: None,
+            page: None,
+            loading: None,
+            error: None,
+            row_sizes: Rc::new(Vec::new()),
+            scroll_handle: VirtualListScrollHandle::new(),
+            _header_task: header_task,
+            _read_task: Task::ready(()),
+        };
+        preview.load_page(0, cx);
+        preview
+    }
+
+    /// The file type, or a generic description when the magic bytes are not known.
+    pub fn file_type(&self) -> &'static str {
+        self.file_type.unwrap_or("Binary data")
+    }
+
+    fn page_index(&self) -> u64 {
+        self.loading.or(self.page.as_ref().map(|page| page.index)).unwrap_or(0)
+    }
+
+    fn load_page(&mut self, index: u64, cx: &mut Context<Self>) {
+        let index = index.min(page_count(self.len) - 1);
+        self.loading = Some(index);
+        let path = self.path.clone();
+        self._read_task = cx.spawn(async move |this, cx| {
+            let result =
+                cx.background_executor().spawn(async move { read_page(&path, index) }).await;
+            let _ = this.update(cx, |this, cx| {
+                this.loading = None;
+                match result {
+                    Ok(page) => {
+                        let width = px(row_width());
+                        this.row_sizes =
+                            Rc::new(vec![size(width, px(ROW_HEIGHT)); page.row_count()]);
+                        this.page = Some(page);
+                        this.error = None;
+                        this.scroll_handle.scroll_to_item(0, ScrollStrategy::Top);
+                    },
+                    Err(err) => this.error = Some(err.to_string()),
+                }
+                cx.notify();
+            });
+        });
+        cx.notify();
+    }
+
+    fn render_row(&self, ix: usize, cx: &App) -> impl IntoElement {
+        let Some(page) = &self.page else {
+            return h_flex();
+        };
+        let (offset, bytes) = page.row(ix);
+        h_flex()
+            .h(px(ROW_HEIGHT))
+            .gap_4()
+            .whitespace_nowrap()
+            .child(div().text_color(cx.theme().muted_foreground).child(format_offset(offset)))
+            .child(div().text_color(cx.theme().foreground).child(format_hex(bytes)))
+            .child(div().text_color(cx.theme().muted_foreground).child(format_ascii(bytes)))
+    }
+
+    fn render_pager(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        let pages = page_count(self.len);
+        let index = self.page_index();
+        let start = index * PAGE_SIZE;
+        let end = (start + PAGE_SIZE).min(self.len);
+        let range = format!(
+            "{}–{} of {} · page {} of {}",
+            format_offset(start),
+            format_offset(end.saturating_sub(1)),
+            human_bytes(self.len),
+            index + 1,
+            pages
+        );
+
+        h_flex()
+            .gap_1()
+            .text_xs()
+            .child(
+                Button::new("hex-first")
+                    .small()
+                    .ghost()
+                    .label("First")
+                    .disabled(index == 0)
+                    .on_click(cx.listener(|this, _, _, cx| this.load_page(0, cx))),
+            )
+            .child(
+                Button::new("hex-previous")
+                    .small()
+                    .ghost()
+                    .label("Previous")
+                    .disabled(index == 0)
+                    .on_click(cx.listener(move |this, _, _, cx| {
+                        this.load_page(index.saturating_sub(1), cx)
+                    })),
+            )
+            .child(
+                Button::new("hex-next")
+                    .small()
+                    .ghost()
+                    .label("Next")
+                    .disabled(index + 1 >= pages)
+                    .on_click(cx.listener(move |this, _, _, cx| this.load_page(index + 1, cx))),
+            )
+            .child(
+                Button::new("hex-last")
+                    .small()
+                    .ghost()
+                    .label("Last")
+                    .disabled(index + 1 >= pages)
+                    .on_click(cx.listener(move |this, _, _, cx| this.load_page(pages - 1, cx))),
+            )
+            .child(div().flex_1().text_right().text_color(cx.theme().muted_foreground).child(range))
+    }
+}
+
+/// Offset, hex and ASCII columns with the gaps between them.
+fn row_width() -> f32 {
+    let chars = 8 + BYTES_PER_ROW * 3 + BYTES_PER_ROW;
+    chars as f32 * CHAR_WIDTH + 2.0 * 16.0
+}
+
+impl Render for HexPreview {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let body = if let Some(error) = &self.error {
+            div()
+                .text_xs()
+                .text_color(cx.theme().red)
+                .child(format!("Can't read file: {error}"))
+                .into_any_element()
+        } else {
+            div()
+                .flex_1()
+                .min_h(px(0.0))
+                .font_family("monospace")
+                .text_xs()
+                .child(
+                    v_virtual_list(
+                        cx.entity().clone(),
+                        "hex-preview",
+                        self.row_sizes.clone(),
+                        |view, visible_range, _window, cx| {
+                            visible_range.map(|ix| view.render_row(ix, cx)).collect()
+                        },
+                    )
+                    .track_scroll(&self.scroll_handle),
+                )
+                .into_any_element()
+        };
+
+        v_flex().size_full().gap_2().child(self.render_pager(cx)).child(body)
+    }
+}
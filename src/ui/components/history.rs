@@ -0,0 +1,300 @@
+use std::path::PathBuf;
+
+use gpui::{
+    actions, div, prelude::*, px, App, AppContext, Context, Entity, Global, IntoElement, Render,
+    Window,
+};
+use gpui_component::{
+    button::{Button, ButtonVariants as _},
+    h_flex,
+    notification::Notification,
+    v_flex, ActiveTheme, Disableable as _, Sizable as _, WindowExt as _,
+};
+
+use crate::{
+    core::{errors::Error, paths::data_dir},
+    pages::settings::AppSettings,
+    services::journal::{self, Journal, JournalEntry, Operation, Outcome},
+    ui::components::file_list::format_date,
+};
+
+actions!(history, [UndoOperation, RedoOperation, ShowHistory]);
+
+struct GlobalHistory(Entity<History>);
+
+impl Global for GlobalHistory {}
+
+/// Loads the operation journal and handles [`UndoOperation`], [`RedoOperation`] and
+/// [`ShowHistory`] in the active window.
+pub fn init(cx: &mut App) {
+    let history = cx.new(|_| History::new());
+    cx.set_global(GlobalHistory(history));
+
+    cx.on_action(|_: &UndoOperation, cx: &mut App| {
+        with_active_window(cx, |history, window, cx| history.undo(window, cx));
+    });
+    cx.on_action(|_: &RedoOperation, cx: &mut App| {
+        with_active_window(cx, |history, window, cx| history.redo(window, cx));
+    });
+    cx.on_action(|_: &ShowHistory, cx: &mut App| {
+        let history = History::global(cx);
+        with_active_window(cx, move |_, window, cx| {
+            window.open_sheet(cx, move |sheet, _window, _cx| {
+                sheet.title("History").child(history.clone())
+            });
+        });
+    });
+}
+
+fn with_active_window(
+    cx: &mut App,
+    f: impl FnOnce(&mut History, &mut Window, &mut Context<History>) + 'static,
+) {
+    let history = History::global(cx);
+    if let Some(window) = cx.active_window() {
+        cx.defer(move |cx| {
+            let _ = window.update(cx, |_, window, cx| {
+                history.update(cx, |history, cx| f(history, window, cx));
+            });
+        });
+    }
+}
+
+/// The undo/redo history of file operations and the panel that lists it.
+pub struct History {
+    journal: Journal,
+    /// An undo or redo is running; further requests are ignored until it finishes.
+    busy: bool,
+}
+
+impl History {
+    fn new() -> Self {
+        let journal = match data_dir() {
+            Some(dir) => Journal::open(dir.join("journal.json")),
+            None => {
+                tracing::warn!("No data directory; the operation history will not be saved");
+                Journal::in_memory()
+            },
+        };
+        Self { journal, busy: false }
+    }
+
+    pub fn global(cx: &App) -> Entity<History> {
+        cx.global::<GlobalHistory>().0.clone()
+    }
+
+    /// Adds a completed operation to the history.
+    pub fn record(&mut self, op: Operation, cx: &mut Context<Self>) {
+        self.journal.record(op);
+        cx.notify();
+    }
+
+    pub fn undo(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if let Some(entry) = self.journal.next_undo().cloned() {
+            self.run(entry, true, window, cx);
+        }
+    }
+
+    pub fn redo(&mut self, window: &mut Window, cx: &mut Context<Self>) {
+        if let Some(entry) = self.journal.next_redo().cloned() {
+            self.run(entry, false, window, cx);
+        }
+    }
+
+    /// Reverses (`undoing`) or repeats `entry` in the background. The explorer's watcher picks up
+    /// the changes.
+    fn run(
+        &mut self,
+        entry: JournalEntry,
+        undoing: bool,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self.busy {
+            return;
+        }
+        self.busy = true;
+        cx.notify();
+
+        let work = cx.background_executor().spawn({
+            let entry = entry.clone();
+            async move {
+                if undoing {
+                    journal::undo(&entry)
+                } else {
+                    journal::redo(&entry)
+                }
+            }
+        });
+
+        cx.spawn_in(window, async move |this, cx| {
+            let result = work.await;
+            let _ = this.update_in(cx, |this, window, cx| {
+                this.busy = false;
+                match result {
+                    Ok(Outcome { partial: None, .. }) => this.journal.set_undone(entry.id, undoing),
+                    Ok(Outcome { partial: Some((finished, remaining)), failures, .. }) => {
+                        let message = format!(
+                            "{} \"{}\", but not {}",
+                            if undoing { "Undid" } else { "Redid" },
+                            finished.describe(),
+                            describe_failures(&failures)
+                        );
+                        this.journal.set_partly_undone(entry.id, finished, remaining, undoing);
+                        window.push_notification(Notification::error(message), cx);
+                    },
+                    Err(err) => {
+                        let message = format!(
+                            "Can't {} \"{}\": {}",
+                            if undoing { "undo" } else { "redo" },
+                            entry.op.describe(),
+                            err
+                        );
+                        window.push_notification(Notification::error(message), cx);
+                    },
+                }
+                cx.notify();
+            });
+        })
+        .detach();
+    }
+
+    fn render_entry(&self, entry: &JournalEntry, cx: &mut Context<Self>) -> impl IntoElement {
+        let muted = cx.theme().muted_foreground;
+
+        h_flex()
+            .gap_3()
+            .py(px(8.0))
+            .border_b_1()
+            .border_color(cx.theme().border)
+            .child(
+                div()
+                    .flex_1()
+                    .min_w(px(0.0))
+                    .text_sm()
+                    .overflow_hidden()
+                    .text_ellipsis()
+                    .whitespace_nowrap()
+                    .when(entry.undone, |this| this.text_color(muted).line_through())
+                    .child(entry.op.describe()),
+            )
+            .when(entry.undone, |this| {
+                this.child(div().text_xs().text_color(muted).child("Undone"))
+            })
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(muted)
+                    .child(format_date(entry.time.max(0) as u64, AppSettings::date_format(cx))),
+            )
+    }
+}
+
+impl Render for History {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let undo_label = self
+            .journal
+            .next_undo()
+            .map_or("Undo".to_string(), |e| format!("Undo {}", e.op.describe()));
+        let redo_label = self
+            .journal
+            .next_redo()
+            .map_or("Redo".to_string(), |e| format!("Redo {}", e.op.describe()));
+        let (can_undo, can_redo) =
+            (self.journal.next_undo().is_some(), self.journal.next_redo().is_some());
+        let busy = self.busy;
+
+        let entries: Vec<_> = self
+            .journal
+            .entries()
+            .iter()
+            .rev()
+            .map(|entry| self.render_entry(entry, cx).into_any_element())
+            .collect();
+
+        v_flex()
+            .size_full()
+            .gap_2()
+            .child(
+                v_flex()
+                    .gap_2()
+                    .child(
+                        Button::new("history-undo")
+                            .small()
+                            .label(undo_label)
+                            .disabled(busy || !can_undo)
+                            .on_click(cx.listener(|this, _, window, cx| this.undo(window, cx))),
+                    )
+                    .child(
+                        Button::new("history-redo")
+                            .small()
+                            .label(redo_label)
+                            .disabled(busy || !can_redo)
+                            .on_click(cx.listener(|this, _, window, cx| this.redo(window, cx))),
+                    ),
+            )
+            .child(
+                h_flex()
+                    .gap_2()
+                    .child(
+                        div()
+                            .flex_1()
+                            .min_w(px(0.0))
+                            .text_xs()
+                            .text_color(cx.theme().muted_foreground)
+                            .overflow_hidden()
+                            .text_ellipsis()
+                            .whitespace_nowrap()
+                            .children(
+                                self.journal
+                                    .path()
+                                    .map(|path| format!("Saved in {}", path.display())),
+                            ),
+                    )
+                    .child(
+                        Button::new("history-clear")
+                            .small()
+                            .danger()
+                            .label("Clear")
+                            .disabled(busy || entries.is_empty())
+                            .on_click(cx.listener(|this, _, _, cx| {
+                                this.journal.clear();
+                                cx.notify();
+                            })),
+                    ),
+            )
+            .when(entries.is_empty(), |this| {
+                this.child(
+                    div()
+                        .py(px(24.0))
+                        .text_sm()
+                        .text_color(cx.theme().muted_foreground)
+                        .child("No file operations yet"),
+                )
+            })
+            .child(
+                div()
+                    .id("history-entries")
+                    .flex_1()
+                    .min_h(px(0.0))
+                    .overflow_y_scroll()
+                    .children(entries),
+            )
+    }
+}
+
+/// The items a partial undo or redo left alone: one with the reason, several by name.
+fn describe_failures(failures: &[(PathBuf, Error)]) -> String {
+    let name = |path: &PathBuf| path.file_name().unwrap_or(path.as_os_str()).to_string_lossy();
+    match failures {
+        [(path, err)] => format!("{}: {}", name(path), err),
+        _ => {
+            let names: Vec<_> = failures.iter().take(3).map(|(path, _)| name(path)).collect();
+            let more = match failures.len().saturating_sub(names.len()) {
+                0 => String::new(),
+                more => format!(" and {more} more"),
+            };
+            format!("{} items ({}{})", failures.len(), names.join(", "), more)
+        },
+    }
+}
@@ -0,0 +1,94 @@
+use std::path::{Path, PathBuf};
+
+use gpui::{
+    div, img, prelude::*, px, Context, IntoElement, ObjectFit, Render, StyledImage, Task, Window,
+};
+use gpui_component::{h_flex, v_flex, ActiveTheme};
+
+use crate::services::preview::image_info::{read_image_info, ImageInfo};
+
+/// An image scaled to fit the panel, with its format, dimensions and EXIF summary below it.
+pub struct ImagePreview {
+    path: PathBuf,
+    /// `None` until the headers have been read.
+    info: Option<ImageInfo>,
+    error: Option<String>,
+    _info_task: Task<()>,
+}
+
+impl ImagePreview {
+    pub fn new(path: &Path, cx: &mut Context<Self>) -> Self {
+        let source = path.to_path_buf();
+        let info_task = cx.spawn(async move |this, cx| {
+            let result =
+                cx.background_executor().spawn(async move { read_image_info(&source) }).await;
+            let _ = this.update(cx, |this, cx| {
+                match result {
+                    Ok(info) => this.info = Some(info),
+                    Err(err) => this.error = Some(err.to_string()),
+                }
+                cx.notify();
+            });
+        });
+        Self { path: path.to_path_buf(), info: None, error: None, _info_task: info_task }
+    }
+
+    /// `PNG · 1920 × 1080`, once known.
+    pub fn summary(&self) -> Option<String> {
+        let info = self.info.as_ref()?;
+        Some(match info.dimensions {
+            Some((width, height)) => format!("{} · {width} × {height}", info.format),
+            None => info.format.clone(),
+        })
+    }
+
+    fn rows(&self) -> Vec<(&'static str, String)> {
+        let Some(info) = &self.info else {
+            return Vec::new();
+        };
+        let mut rows = Vec::new();
+        if let Some((width, height)) = info.dimensions {
+            rows.push(("Dimensions", format!("{width} × {height} px")));
+            if width > 0 && height > 0 {
+                rows.push((
+                    "Megapixels",
+                    format!("{:.1}", (u64::from(width) * u64::from(height)) as f64 / 1e6),
+                ));
+            }
+        }
+        if let Some(exif) = &info.exif {
+            rows.extend(exif.rows());
+        }
+        rows
+    }
+}
+
+impl Render for ImagePreview {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let theme = cx.theme();
+        v_flex()
+            .id("image-preview")
+            .size_full()
+            .gap_3()
+            .overflow_y_scroll()
+            .child(
+                div()
+                    .w_full()
+                    .h(px(320.0))
+                    .flex_shrink_0()
+                    .rounded(theme.radius)
+                    .bg(theme.secondary)
+                    .child(img(self.path.clone()).size_full().object_fit(ObjectFit::Contain)),
+            )
+            .children(self.error.clone().map(|error| {
+                div().text_xs().text_color(theme.red).child(format!("Can't read image: {error}"))
+            }))
+            .child(v_flex().gap_1().children(self.rows().into_iter().map(|(label, value)| {
+                h_flex()
+                    .gap_2()
+                    .text_xs()
+                    .child(div().w(px(80.0)).flex_shrink_0().text_color(theme.muted).child(label))
+                    .child(div().text_color(theme.foreground).child(value))
+            })))
+    }
+}
@@ -1,24 +1,36 @@
 use gpui::{div, prelude::*, px, Context, IntoElement};
 use gpui_component::{ActiveTheme, Icon, IconName};
+
+use crate::ui::components::transfers::{progress_bar, ShowTransfers, TransferSummary};
+
 #[derive(Clone)]
 pub struct FooterProps {
     pub selected_count: usize,
+    /// Combined size of the selection, already formatted for display.
+    pub selected_size: Option<String>,
+    /// Modification date of the selected entry, already formatted for display.
+    pub selected_modified: Option<String>,
     pub total_count: usize,
     pub total_size: String,
     pub current_path: String,
     pub git_branch: Option<String>,
     pub storage_status: Option<String>,
+    /// Running or failed background copies and moves.
+    pub transfers: Option<TransferSummary>,
 }
 
 impl Default for FooterProps {
     fn default() -> Self {
         Self {
             selected_count: 0,
+            selected_size: None,
+            selected_modified: None,
             total_count: 0,
             total_size: String::from("0 B"),
             current_path: String::from("/"),
             git_branch: None,
             storage_status: None,
+            transfers: None,
         }
     }
 }
@@ -55,7 +67,19 @@ pub fn footer<V: gpui::Render>(props: FooterProps, cx: &mut Context<V>) -> impl
                     this.child(footer_button(
                         ("footer-selected", 1_usize),
                         IconName::File,
-                        &format!("{} selected", props.selected_count),
+                        &match &props.selected_size {
+                            Some(size) => format!("{} selected ({size})", props.selected_count),
+                            None => format!("{} selected", props.selected_count),
+                        },
+                        cx,
+                    ))
+                })
+                // Modification date of the selection
+                .when_some(props.selected_modified.clone(), |this, modified| {
+                    this.child(footer_button(
+                        ("footer-modified", 6_usize),
+                        IconName::Calendar,
+                        &modified,
                         cx,
                     ))
                 })
@@ -80,6 +104,10 @@ pub fn footer<V: gpui::Render>(props: FooterProps, cx: &mut Context<V>) -> impl
                 .flex()
                 .items_center()
                 .gap_2()
+                // Transfer progress; opens the transfer panel
+                .when_some(props.transfers, |this, transfers| {
+                    this.child(transfer_indicator(transfers, cx))
+                })
                 // Storage status (S3 connection, etc)
                 .when_some(props.storage_status, |this, status| {
                     this.child(footer_button(
@@ -124,6 +152,30 @@ fn footer_button<V: gpui::Render>(
         })
 }
 
+fn transfer_indicator<V: gpui::Render>(
+    transfers: TransferSummary,
+    cx: &mut Context<V>,
+) -> impl IntoElement {
+    let color = if transfers.failed { cx.theme().danger } else { cx.theme().foreground };
+
+    div()
+        .id(("footer-transfers", 7_usize))
+        .h(px(24.0))
+        .px(px(8.0))
+        .flex()
+        .items_center()
+        .gap_1()
+        .rounded(px(4.0))
+        .cursor_pointer()
+        .hover(|style| style.bg(cx.theme().secondary_hover))
+        .on_click(|_, window, cx| window.dispatch_action(Box::new(ShowTransfers), cx))
+        .child(Icon::new(IconName::HardDrive).size_3().text_color(color))
+        .child(div().text_xs().text_color(color).child(transfers.label))
+        .when_some(transfers.fraction, |this, fraction| {
+            this.child(div().w(px(64.0)).child(progress_bar(fraction, cx)))
+        })
+}
+
 fn truncate_path(path: &str, max_len: usize) -> String {
     if path.len() <= max_len {
         return path.to_string();
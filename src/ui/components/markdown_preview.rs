@@ -0,0 +1,331 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+    rc::Rc,
+};
+
+use gpui::{
+    div, img, prelude::*, px, Action, AnyElement, App, Context, Div, Entity, FontStyle, FontWeight,
+    HighlightStyle, InteractiveText, IntoElement, Render, ScrollHandle, SharedString,
+    StrikethroughStyle, StyledText, Task, UnderlineStyle, Window,
+};
+use gpui_component::{
+    button::{Button, ButtonVariants as _},
+    h_flex, v_flex, ActiveTheme, Sizable as _,
+};
+
+use crate::{
+    services::preview::markdown::{self, Alignment, Block, ImageSource, Link, RichText, SpanStyle},
+    ui::components::code_preview::{token_style, CodePreview, CodeText},
+};
+
+/// Asks the explorer to go to a file or folder a Markdown link points to.
+#[derive(Action, Clone, PartialEq)]
+#[action(namespace = markdown_preview, no_json)]
+pub struct OpenLinkedPath(pub PathBuf);
+
+const EXTENSIONS: [&str; 5] = ["md", "markdown", "mdown", "mkd", "mkdn"];
+
+/// A Markdown file parsed into blocks, and split into lines for its source view, ready for
+/// [`MarkdownPreview::new`]. Made on a background thread like [`CodeText`].
+pub struct MarkdownText {
+    blocks: Vec<Block>,
+    source: CodeText,
+}
+
+impl MarkdownText {
+    pub fn new(path: &Path, text: &str) -> Self {
+        let base_dir = path.parent().unwrap_or(Path::new(""));
+        Self { blocks: markdown::parse(text, base_dir), source: CodeText::new(path, text) }
+    }
+}
+
+/// A Markdown file, rendered, with a toggle back to its highlighted source.
+pub struct MarkdownPreview {
+    blocks: Rc<Vec<Block>>,
+    source: Entity<CodePreview>,
+    show_source: bool,
+    scroll_handle: ScrollHandle,
+    /// Remote images the user chose to load. Others stay placeholders, so selecting a file never
+    /// makes requests (tracking pixels included) on its own.
+    loaded_images: HashSet<String>,
+    _highlight_task: Task<()>,
+}
+
+impl MarkdownPreview {
+    pub fn new(text: MarkdownText, cx: &mut Context<Self>) -> Self {
+        let MarkdownText { blocks, source } = text;
+        // The document shows at once; its code blocks take their colors when highlighted.
+        let mut highlighted = blocks.clone();
+        let highlight_task = cx.spawn(async move |this, cx| {
+            let blocks = cx
+                .background_executor()
+                .spawn(async move {
+                    markdown::highlight_code_blocks(&mut highlighted);
+                    highlighted
+                })
+                .await;
+            let _ = this.update(cx, |this, cx| {
+                this.blocks = Rc::new(blocks);
+                cx.notify();
+            });
+        });
+        Self {
+            blocks: Rc::new(blocks),
+            source: cx.new(|cx| CodePreview::new(source, cx)),
+            show_source: false,
+            scroll_handle: ScrollHandle::new(),
+            loaded_images: HashSet::new(),
+            _highlight_task: highlight_task,
+        }
+    }
+
+    pub fn is_markdown(path: &Path) -> bool {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+    }
+
+    fn toggle_source(&mut self, cx: &mut Context<Self>) {
+        self.show_source = !self.show_source;
+        cx.notify();
+    }
+
+    fn load_image(&mut self, url: String, cx: &mut Context<Self>) {
+        self.loaded_images.insert(url);
+        cx.notify();
+    }
+
+    fn scroll_to_anchor(&mut self, anchor: &str, cx: &mut Context<Self>) {
+        let anchor = anchor.to_lowercase();
+        let target = self.blocks.iter().position(|block| match block {
+            Block::Heading { anchor: heading, .. } => *heading == anchor,
+            _ => false,
+        });
+        if let Some(ix) = target {
+            self.show_source = false;
+            self.scroll_handle.scroll_to_item(ix);
+            cx.notify();
+        }
+    }
+
+    fn render_block(&self, block: &Block, ids: &mut usize, cx: &Context<Self>) -> AnyElement {
+        let theme = cx.theme();
+        match block {
+            Block::Heading { level, text, .. } => {
+                let heading =
+                    div().font_weight(FontWeight::BOLD).child(self.render_text(text, ids, cx));
+                match level {
+                    1 => heading.text_2xl().pb_1().border_b_1().border_color(theme.border),
+                    2 => heading.text_xl().pb_1().border_b_1().border_color(theme.border),
+                    3 => heading.text_lg(),
+                    _ => heading.text_base(),
+                }
+                .into_any_element()
+            },
+            Block::Paragraph(text) => {
+                div().text_sm().child(self.render_text(text, ids, cx)).into_any_element()
+            },
+            Block::Image { source, alt } => match source {
+                ImageSource::Local(path) if path.is_file() => {
+                    img(path.clone()).max_w_full().into_any_element()
+                },
+                ImageSource::Remote(url) if self.loaded_images.contains(url) => {
+                    img(SharedString::from(url.clone())).max_w_full().into_any_element()
+                },
+                ImageSource::Remote(url) => {
+                    *ids += 1;
+                    let url = url.clone();
+                    v_flex()
+                        .items_start()
+                        .gap_1()
+                        .text_xs()
+                        .text_color(theme.muted_foreground)
+                        .child(format!("[{alt}] {url}"))
+                        .child(
+                            Button::new(("markdown-load-image", *ids))
+                                .small()
+                                .ghost()
+                                .label("Load Image")
+                                .on_click(cx.listener(move |this, _, _, cx| {
+                                    this.load_image(url.clone(), cx)
+                                })),
+                        )
+                        .into_any_element()
+                },
+                ImageSource::Local(_) => div()
+                    .text_xs()
+                    .text_color(theme.muted_foreground)
+                    .child(format!("[{alt}]"))
+                    .into_any_element(),
+            },
+            Block::Code { lines, highlights, .. } => v_flex()
+                .p_2()
+                .rounded(theme.radius)
+                .bg(theme.secondary)
+                .font_family("monospace")
+                .text_xs()
+                .overflow_hidden()
+                .children(lines.iter().enumerate().map(|(ix, line)| {
+                    let spans = highlights.get(ix).cloned().unwrap_or_default();
+                    div().whitespace_nowrap().child(StyledText::new(line.clone()).with_highlights(
+                        spans.into_iter().map(|(range, class)| (range, token_style(class, cx))),
+                    ))
+                }))
+                .into_any_element(),
+            Block::Quote(blocks) => v_flex()
+                .gap_2()
+                .pl_3()
+                .border_l_4()
+                .border_color(theme.border)
+                .text_color(theme.muted_foreground)
+                .children(blocks.iter().map(|block| self.render_block(block, ids, cx)))
+                .into_any_element(),
+            Block::List { start, items } => v_flex()
+                .gap_1()
+                .children(items.iter().enumerate().map(|(ix, item)| {
+                    let marker = match (item.checked, start) {
+                        (Some(true), _) => "☑".to_string(),
+                        (Some(false), _) => "☐".to_string(),
+                        (None, Some(start)) => format!("{}.", start + ix),
+                        (None, None) => "•".to_string(),
+                    };
+                    h_flex()
+                        .items_start()
+                        .gap_2()
+                        .child(div().text_sm().text_color(theme.muted_foreground).child(marker))
+                        .child(v_flex().flex_1().min_w(px(0.0)).gap_1().children(
+                            item.blocks.iter().map(|block| self.render_block(block, ids, cx)),
+                        ))
+                }))
+                .into_any_element(),
+            Block::Table { alignments, header, rows } => v_flex()
+                .text_sm()
+                .border_1()
+                .border_color(theme.border)
+                .child(
+                    self.render_row(header, alignments, ids, cx)
+                        .font_weight(FontWeight::SEMIBOLD)
+                        .bg(theme.secondary),
+                )
+                .children(rows.iter().map(|row| self.render_row(row, alignments, ids, cx)))
+                .into_any_element(),
+            Block::Rule => div().h(px(1.0)).bg(theme.border).into_any_element(),
+            Block::Html(html) => div()
+                .font_family("monospace")
+                .text_xs()
+                .text_color(theme.muted_foreground)
+                .child(html.clone())
+                .into_any_element(),
+        }
+    }
+
+    fn render_row(
+        &self,
+        cells: &[RichText],
+        alignments: &[Alignment],
+        ids: &mut usize,
+        cx: &Context<Self>,
+    ) -> Div {
+        h_flex().border_b_1().border_color(cx.theme().border).children(
+            cells.iter().enumerate().map(|(ix, cell)| {
+                let cell_div = div()
+                    .flex_1()
+                    .min_w(px(0.0))
+                    .px_2()
+                    .py_1()
+                    .border_r_1()
+                    .border_color(cx.theme().border)
+                    .child(self.render_text(cell, ids, cx));
+                match alignments.get(ix).copied().unwrap_or_default() {
+                    Alignment::Left => cell_div,
+                    Alignment::Center => cell_div.text_center(),
+                    Alignment::Right => cell_div.text_right(),
+                }
+            }),
+        )
+    }
+
+    /// Styled text whose links can be clicked: web links open in the browser, anchors scroll to
+    /// their heading and local paths go to the explorer.
+    fn render_text(&self, text: &RichText, ids: &mut usize, cx: &Context<Self>) -> AnyElement {
+        let highlights: Vec<_> = text
+            .spans
+            .iter()
+            .filter(|(_, style)| *style != SpanStyle::default())
+            .map(|(range, style)| (range.clone(), span_style(*style, cx)))
+            .collect();
+        let styled = StyledText::new(text.text.clone()).with_highlights(highlights);
+        if text.links.is_empty() {
+            return styled.into_any_element();
+        }
+
+        *ids += 1;
+        let ranges = text.links.iter().map(|(range, _)| range.clone()).collect();
+        let links: Vec<Link> = text.links.iter().map(|(_, link)| link.clone()).collect();
+        let view = cx.entity().downgrade();
+        InteractiveText::new(("markdown-text", *ids), styled)
+            .on_click(ranges, move |ix, window, cx| match links.get(ix) {
+                Some(Link::External(url)) => cx.open_url(url),
+                Some(Link::Local(path)) => {
+                    window.dispatch_action(Box::new(OpenLinkedPath(path.clone())), cx)
+                },
+                Some(Link::Anchor(anchor)) => {
+                    let _ = view.update(cx, |this, cx| this.scroll_to_anchor(anchor, cx));
+                },
+                None => {},
+            })
+            .into_any_element()
+    }
+}
+
+fn span_style(style: SpanStyle, cx: &App) -> HighlightStyle {
+    let theme = cx.theme();
+    HighlightStyle {
+        color: style.link.then_some(theme.link),
+        background_color: style.code.then_some(theme.secondary),
+        font_weight: style.strong.then_some(FontWeight::BOLD),
+        font_style: style.emphasis.then_some(FontStyle::Italic),
+        strikethrough: style
+            .strikethrough
+            .then(|| StrikethroughStyle { thickness: px(1.0), ..Default::default() }),
+        underline: style.link.then(|| UnderlineStyle { thickness: px(1.0), ..Default::default() }),
+        ..Default::default()
+    }
+}
+
+impl Render for MarkdownPreview {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let label = if self.show_source { "Show Rendered" } else { "Show Source" };
+        let content = if self.show_source {
+            div().flex_1().min_h(px(0.0)).child(self.source.clone()).into_any_element()
+        } else {
+            let blocks = self.blocks.clone();
+            let mut ids = 0;
+            v_flex()
+                .id("markdown-preview")
+                .flex_1()
+                .min_h(px(0.0))
+                .gap_3()
+                .overflow_y_scroll()
+                .track_scroll(&self.scroll_handle)
+                .text_color(cx.theme().foreground)
+                .children(blocks.iter().map(|block| self.render_block(block, &mut ids, cx)))
+                .into_any_element()
+        };
+
+        v_flex()
+            .size_full()
+            .gap_2()
+            .child(
+                h_flex().justify_end().child(
+                    Button::new("markdown-toggle-source")
+                        .small()
+                        .ghost()
+                        .label(label)
+                        .on_click(cx.listener(|this, _, _, cx| this.toggle_source(cx))),
+                ),
+            )
+            .child(content)
+    }
+}
@@ -1,4 +1,13 @@
 // Shared UI components
+pub mod code_preview;
 pub mod file_list;
+pub mod folder_compare;
+pub mod hex_preview;
+pub mod history;
+pub mod image_preview;
 pub mod layout;
+pub mod markdown_preview;
 pub mod pane;
+pub mod split_view;
+pub mod text_preview;
+pub mod transfers;
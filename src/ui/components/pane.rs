@@ -1,32 +1,387 @@
-use gpui::{div, prelude::*, px, App, IntoElement};
-use gpui_component::ActiveTheme;
-/// Non-functional tab bar (placeholder)
-pub fn tab_bar(cx: &mut App) -> impl IntoElement {
-    div()
-        .flex()
-        .gap_2()
-        .p_2()
-        .border_1()
-        .border_color(cx.theme().border)
-        .bg(cx.theme().background)
-        .text_color(cx.theme().primary)
-        .child(div().px_2().py_1().border_1().border_color(cx.theme().border).child("Tab 1"))
-        .child(div().px_2().py_1().border_1().border_color(cx.theme().border).child("Tab 2"))
-}
-
-/// Split container with a vertical resize bar (non-functional placeholder)
-pub fn split_container<L: IntoElement, R: IntoElement>(
-    left: L,
-    right: R,
-    cx: &mut App,
-) -> impl IntoElement {
-    div()
-        .flex()
-        .gap_1()
-        .child(div().border_1().border_color(cx.theme().border).child(left))
-        .child(
-            // Resize bar placeholder
-            div().w(px(4.0)).bg(cx.theme().background),
-        )
-        .child(div().border_1().border_color(cx.theme().border).child(right))
+use std::path::Path;
+
+use gpui::{
+    actions, div, prelude::*, px, App, AppContext, Axis, Context, ElementId, Entity, EventEmitter,
+    FocusHandle, Focusable, IntoElement, KeyBinding, Render, SharedString, Subscription, Window,
+};
+use gpui_component::{
+    h_flex,
+    input::InputState,
+    resizable::{h_resizable, resizable_panel, v_resizable, ResizablePanelGroup, ResizableState},
+    ActiveTheme, Icon, IconName,
+};
+
+use crate::{
+    pages::explorer::{ExplorerEvent, ExplorerPage},
+    services::session::ExplorerSession,
+};
+
+actions!(pane, [NewTab, CloseTab, ReopenClosedTab, NextTab, PreviousTab]);
+
+const CONTEXT: &str = "Pane";
+/// Closed tabs beyond this many are forgotten.
+const MAX_CLOSED_TABS: usize = 20;
+
+pub fn init(cx: &mut App) {
+    cx.bind_keys([
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-t", NewTab, Some(CONTEXT)),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("ctrl-t", NewTab, Some(CONTEXT)),
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-w", CloseTab, Some(CONTEXT)),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("ctrl-w", CloseTab, Some(CONTEXT)),
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-shift-t", ReopenClosedTab, Some(CONTEXT)),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("ctrl-shift-t", ReopenClosedTab, Some(CONTEXT)),
+        KeyBinding::new("ctrl-tab", NextTab, Some(CONTEXT)),
+        KeyBinding::new("ctrl-shift-tab", PreviousTab, Some(CONTEXT)),
+    ]);
+}
+
+struct Tab {
+    explorer: Entity<ExplorerPage>,
+    _subscriptions: [Subscription; 2],
+}
+
+/// A tab being dragged to a new place in the tab bar.
+#[derive(Clone)]
+struct DraggedTab {
+    ix: usize,
+    title: SharedString,
+}
+
+impl Render for DraggedTab {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        div()
+            .px_3()
+            .py_1()
+            .rounded(px(6.0))
+            .border_1()
+            .border_color(cx.theme().border)
+            .bg(cx.theme().background)
+            .text_sm()
+            .text_color(cx.theme().primary)
+            .shadow_md()
+            .child(self.title.clone())
+    }
+}
+
+pub enum PaneEvent {
+    /// The user scrolled the active tab's listing.
+    Scrolled,
+}
+
+/// Explorer tabs, each with its own folder, history, selection and sorting, and the bar to
+/// switch, reorder and close them.
+pub struct Pane {
+    tabs: Vec<Tab>,
+    active: usize,
+    /// Most recently closed last.
+    closed: Vec<ExplorerSession>,
+}
+
+impl Pane {
+    /// A pane with the `restored` tabs, or a single tab in the working folder.
+    pub fn new(
+        restored: Vec<ExplorerSession>,
+        active: usize,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let mut pane = Self { tabs: Vec::new(), active: 0, closed: Vec::new() };
+        if restored.is_empty() {
+            pane.insert_tab(0, None, window, cx);
+        }
+        for (ix, session) in restored.into_iter().enumerate() {
+            pane.insert_tab(ix, Some(session), window, cx);
+        }
+        pane.active = active.min(pane.tabs.len() - 1);
+        pane
+    }
+
+    pub fn active_explorer(&self) -> &Entity<ExplorerPage> {
+        &self.tabs[self.active].explorer
+    }
+
+    /// The open tabs for the session file, and which one is active.
+    pub fn session(&self, cx: &App) -> (Vec<ExplorerSession>, usize) {
+        (self.tabs.iter().map(|tab| tab.explorer.read(cx).session(cx)).collect(), self.active)
+    }
+
+    fn insert_tab(
+        &mut self,
+        ix: usize,
+        session: Option<ExplorerSession>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let search_input = cx.new(|cx| InputState::new(window, cx));
+        let explorer = cx.new(|cx| ExplorerPage::new(search_input, session, cx));
+        let subscriptions = [
+            cx.observe(&explorer, |_, _, cx| cx.notify()),
+            cx.subscribe_in(
+                &explorer,
+                window,
+                |this, explorer, event: &ExplorerEvent, window, cx| match event {
+                    ExplorerEvent::OpenInNewTab(path) => {
+                        // In the background, like a link opened in a new browser tab.
+                        let ix = this.tab_index(explorer).map_or(this.tabs.len(), |ix| ix + 1);
+                        this.open_tab(ix, path, false, window, cx);
+                    },
+                    ExplorerEvent::Scrolled => {
+                        if this.active_explorer() == explorer {
+                            cx.emit(PaneEvent::Scrolled);
+                        }
+                    },
+                },
+            ),
+        ];
+        self.tabs.insert(ix, Tab { explorer, _subscriptions: subscriptions });
+        if ix <= self.active && self.tabs.len() > 1 {
+            self.active += 1;
+        }
+    }
+
+    fn tab_index(&self, explorer: &Entity<ExplorerPage>) -> Option<usize> {
+        self.tabs.iter().position(|tab| &tab.explorer == explorer)
+    }
+
+    /// Opens `path` in a new tab at `ix`, laid out like the active tab.
+    pub fn open_tab(
+        &mut self,
+        ix: usize,
+        path: &str,
+        activate: bool,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let session = ExplorerSession {
+            cwd: Some(path.to_string()),
+            history: Vec::new(),
+            history_index: 0,
+            ..self.active_explorer().read(cx).session(cx)
+        };
+        let ix = ix.min(self.tabs.len());
+        self.insert_tab(ix, Some(session), window, cx);
+        if activate {
+            self.activate(ix, window, cx);
+        }
+        cx.notify();
+    }
+
+    pub fn activate(&mut self, ix: usize, window: &mut Window, cx: &mut Context<Self>) {
+        if ix >= self.tabs.len() {
+            return;
+        }
+        self.active = ix;
+        self.focus_handle(cx).focus(window, cx);
+        cx.notify();
+    }
+
+    /// Closes the tab at `ix`, keeping it for [`ReopenClosedTab`]. The last tab stays open.
+    pub fn close_tab(&mut self, ix: usize, window: &mut Window, cx: &mut Context<Self>) {
+        if self.tabs.len() < 2 || ix >= self.tabs.len() {
+            return;
+        }
+        let tab = self.tabs.remove(ix);
+        self.closed.push(tab.explorer.read(cx).session(cx));
+        if self.closed.len() > MAX_CLOSED_TABS {
+            self.closed.remove(0);
+        }
+        let active = if ix < self.active || self.active == self.tabs.len() {
+            self.active - 1
+        } else {
+            self.active
+        };
+        self.activate(active, window, cx);
+    }
+
+    fn move_tab(&mut self, from: usize, to: usize, cx: &mut Context<Self>) {
+        if from == to || from >= self.tabs.len() || to >= self.tabs.len() {
+            return;
+        }
+        let active = self.tabs[self.active].explorer.clone();
+        let tab = self.tabs.remove(from);
+        self.tabs.insert(to, tab);
+        self.active = self.tab_index(&active).unwrap_or(0);
+        cx.notify();
+    }
+
+    fn on_new_tab(&mut self, _: &NewTab, window: &mut Window, cx: &mut Context<Self>) {
+        let path = self.active_explorer().read(cx).current_path().to_string();
+        self.open_tab(self.active + 1, &path, true, window, cx);
+    }
+
+    fn on_close_tab(&mut self, _: &CloseTab, window: &mut Window, cx: &mut Context<Self>) {
+        self.close_tab(self.active, window, cx);
+    }
+
+    fn on_reopen_closed_tab(
+        &mut self,
+        _: &ReopenClosedTab,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(session) = self.closed.pop() else {
+            return;
+        };
+        let ix = self.active + 1;
+        self.insert_tab(ix, Some(session), window, cx);
+        self.activate(ix, window, cx);
+    }
+
+    fn on_next_tab(&mut self, _: &NextTab, window: &mut Window, cx: &mut Context<Self>) {
+        self.activate((self.active + 1) % self.tabs.len(), window, cx);
+    }
+
+    fn on_previous_tab(&mut self, _: &PreviousTab, window: &mut Window, cx: &mut Context<Self>) {
+        self.activate((self.active + self.tabs.len() - 1) % self.tabs.len(), window, cx);
+    }
+
+    fn render_tab(&self, ix: usize, cx: &mut Context<Self>) -> impl IntoElement {
+        let path = self.tabs[ix].explorer.read(cx).current_path().to_string();
+        let title: SharedString = Path::new(&path)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_else(|| path.clone())
+            .into();
+        let active = ix == self.active;
+        let closable = self.tabs.len() > 1;
+        let dragged = DraggedTab { ix, title: title.clone() };
+
+        h_flex()
+            .id(("explorer-tab", ix))
+            .flex_shrink_0()
+            .max_w(px(200.0))
+            .h_full()
+            .gap_1()
+            .pl_3()
+            .pr_1()
+            .border_r_1()
+            .border_color(cx.theme().border)
+            .text_sm()
+            .cursor_pointer()
+            .when(active, |this| this.bg(cx.theme().background).text_color(cx.theme().primary))
+            .when(!active, |this| {
+                this.bg(cx.theme().muted)
+                    .text_color(cx.theme().muted_foreground)
+                    .hover(|style| style.bg(cx.theme().accordion_hover))
+            })
+            .on_click(cx.listener(move |this, event: &gpui::ClickEvent, window, cx| {
+                if let gpui::ClickEvent::Mouse(mouse) = event {
+                    if mouse.up.button != gpui::MouseButton::Left {
+                        return;
+                    }
+                }
+                this.activate(ix, window, cx);
+            }))
+            .on_mouse_down(
+                gpui::MouseButton::Middle,
+                cx.listener(move |this, _, window, cx| this.close_tab(ix, window, cx)),
+            )
+            .on_drag(dragged, |dragged, _, _, cx| cx.new(|_| dragged.clone()))
+            .drag_over::<DraggedTab>(|style, _, _, cx| style.bg(cx.theme().accent.opacity(0.3)))
+            .on_drop(cx.listener(move |this, dragged: &DraggedTab, _, cx| {
+                this.move_tab(dragged.ix, ix, cx);
+            }))
+            .child(
+                div()
+                    .flex_1()
+                    .min_w(px(0.0))
+                    .overflow_hidden()
+                    .text_ellipsis()
+                    .whitespace_nowrap()
+                    .child(title),
+            )
+            .child(
+                div()
+                    .id(("close-explorer-tab", ix))
+                    .p(px(2.0))
+                    .rounded(px(4.0))
+                    .when(!closable, |this| this.invisible())
+                    .hover(|style| style.bg(cx.theme().accordion_hover))
+                    .on_click(cx.listener(move |this, _, window, cx| {
+                        cx.stop_propagation();
+                        this.close_tab(ix, window, cx);
+                    }))
+                    .child(Icon::new(IconName::Close).size_3()),
+            )
+    }
+
+    fn render_tab_bar(&self, cx: &mut Context<Self>) -> impl IntoElement {
+        h_flex()
+            .id("explorer-tab-bar")
+            .flex_shrink_0()
+            .h(px(32.0))
+            .bg(cx.theme().muted)
+            .border_b_1()
+            .border_color(cx.theme().border)
+            .overflow_x_scroll()
+            .children((0..self.tabs.len()).map(|ix| self.render_tab(ix, cx)))
+            .child(
+                div()
+                    .id("new-explorer-tab")
+                    .flex_shrink_0()
+                    .px_2()
+                    .h_full()
+                    .flex()
+                    .items_center()
+                    .cursor_pointer()
+                    .text_color(cx.theme().muted_foreground)
+                    .hover(|style| style.text_color(cx.theme().primary))
+                    .on_click(cx.listener(|this, _, window, cx| {
+                        this.on_new_tab(&NewTab, window, cx);
+                    }))
+                    .child(Icon::new(IconName::Plus).size_4()),
+            )
+    }
+}
+
+impl Render for Pane {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        div()
+            .key_context(CONTEXT)
+            .size_full()
+            .flex()
+            .flex_col()
+            .min_h(px(0.0))
+            .on_action(cx.listener(Self::on_new_tab))
+            .on_action(cx.listener(Self::on_close_tab))
+            .on_action(cx.listener(Self::on_reopen_closed_tab))
+            .on_action(cx.listener(Self::on_next_tab))
+            .on_action(cx.listener(Self::on_previous_tab))
+            .child(self.render_tab_bar(cx))
+            .child(div().flex_1().min_h(px(0.0)).child(self.active_explorer().clone()))
+    }
+}
+
+impl EventEmitter<PaneEvent> for Pane {}
+
+impl Focusable for Pane {
+    /// The active tab's, so focusing the pane gives the keyboard to it.
+    fn focus_handle(&self, cx: &App) -> FocusHandle {
+        self.active_explorer().focus_handle(cx)
+    }
+}
+
+/// Two panes side by side on the horizontal axis, or stacked on the vertical one, with a divider
+/// that can be dragged to share the space differently.
+pub fn split_container(
+    id: impl Into<ElementId>,
+    axis: Axis,
+    state: &Entity<ResizableState>,
+    first: impl IntoElement,
+    second: impl IntoElement,
+) -> ResizablePanelGroup {
+    let group = match axis {
+        Axis::Horizontal => h_resizable(id),
+        Axis::Vertical => v_resizable(id),
+    };
+    group
+        .with_state(state)
+        .child(resizable_panel().child(first))
+        .child(resizable_panel().child(second))
 }
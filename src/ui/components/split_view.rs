@@ -0,0 +1,397 @@
+use std::{collections::HashMap, path::PathBuf};
+
+use gpui::{
+    actions, div, prelude::*, px, App, AppContext, Axis, Context, Entity, Focusable, IntoElement,
+    KeyBinding, Render, Subscription, Window,
+};
+use gpui_component::{
+    notification::Notification, resizable::ResizableState, ActiveTheme, WindowExt as _,
+};
+
+use crate::{
+    pages::explorer::ExplorerPage,
+    services::{
+        fs::compare::{compare_listings, Difference},
+        jobs::JobKind,
+        session::{ExplorerSession, SplitOrientation, SplitSession, WindowSession},
+    },
+    ui::components::{
+        folder_compare::{FolderCompare, FolderCompareEvent},
+        pane::{split_container, Pane, PaneEvent},
+    },
+};
+
+actions!(split_view, [
+    ToggleSplit,
+    ToggleSplitOrientation,
+    FocusOtherPane,
+    CopyToOtherPane,
+    MoveToOtherPane,
+    ToggleCompare,
+    CompareFolders
+]);
+
+const CONTEXT: &str = "SplitView";
+/// Key context of an explorer, inside a pane.
+const LISTING_CONTEXT: &str = "Explorer";
+
+pub fn init(cx: &mut App) {
+    cx.bind_keys([
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-\\", ToggleSplit, Some(CONTEXT)),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("ctrl-\\", ToggleSplit, Some(CONTEXT)),
+        #[cfg(target_os = "macos")]
+        KeyBinding::new("cmd-alt-\\", ToggleSplitOrientation, Some(CONTEXT)),
+        #[cfg(not(target_os = "macos"))]
+        KeyBinding::new("ctrl-alt-\\", ToggleSplitOrientation, Some(CONTEXT)),
+        // Only from a listing, so Tab still moves focus everywhere else.
+        KeyBinding::new("tab", FocusOtherPane, Some(LISTING_CONTEXT)),
+        KeyBinding::new("f5", CopyToOtherPane, Some(CONTEXT)),
+        KeyBinding::new("f6", MoveToOtherPane, Some(CONTEXT)),
+    ]);
+}
+
+struct PaneSlot {
+    pane: Entity<Pane>,
+    _subscriptions: [Subscription; 2],
+}
+
+/// The explorer area of a window: one pane, or two next to each other that can copy and move
+/// between them and be compared.
+pub struct SplitView {
+    /// One pane, or two when split.
+    panes: Vec<PaneSlot>,
+    active: usize,
+    orientation: SplitOrientation,
+    /// Highlights the differences between the two panes and scrolls them together.
+    compare: bool,
+    /// The explorers currently carrying compare marks.
+    compared: Vec<Entity<ExplorerPage>>,
+    /// The listing generations of `compared` when the marks were made; the panes notify far more
+    /// often than their listings change.
+    compared_generations: Vec<u64>,
+    split_state: Entity<ResizableState>,
+    /// Reveals the entries picked in the open folder comparison.
+    folder_compare_subscription: Option<Subscription>,
+}
+
+impl SplitView {
+    pub fn new(
+        restored: Option<WindowSession>,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) -> Self {
+        let restored = restored.unwrap_or_default();
+        let mut view = Self {
+            panes: Vec::new(),
+            active: 0,
+            orientation: SplitOrientation::default(),
+            compare: false,
+            compared: Vec::new(),
+            compared_generations: Vec::new(),
+            split_state: cx.new(|_| ResizableState::default()),
+            folder_compare_subscription: None,
+        };
+        view.add_pane(restored.tabs, restored.active_tab, window, cx);
+        if let Some(split) = restored.split {
+            view.orientation = split.orientation;
+            view.compare = split.compare;
+            view.add_pane(split.tabs, split.active_tab, window, cx);
+            view.active = usize::from(split.second_active);
+        }
+        view
+    }
+
+    fn add_pane(
+        &mut self,
+        tabs: Vec<ExplorerSession>,
+        active_tab: usize,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let pane = cx.new(|cx| Pane::new(tabs, active_tab, window, cx));
+        let observe = cx.observe(&pane, |this, _, cx| {
+            this.update_compare(cx);
+            cx.notify();
+        });
+        let subscribe = cx.subscribe_in(
+            &pane,
+            window,
+            |this, pane, event: &PaneEvent, window, cx| match event {
+                PaneEvent::Scrolled => {
+                    if this.compare {
+                        let pane = pane.clone();
+                        // The listing applies the scroll after the event, so copy it a frame later.
+                        cx.on_next_frame(window, move |this, _, cx| this.sync_scroll(&pane, cx));
+                    }
+                },
+            },
+        );
+        self.panes.push(PaneSlot { pane, _subscriptions: [observe, subscribe] });
+    }
+
+    pub fn active_explorer(&self, cx: &App) -> Entity<ExplorerPage> {
+        self.panes[self.active].pane.read(cx).active_explorer().clone()
+    }
+
+    /// The tabs of both panes, for the session file.
+    pub fn session(&self, cx: &App) -> (Vec<ExplorerSession>, usize, Option<SplitSession>) {
+        let (tabs, active_tab) = self.panes[0].pane.read(cx).session(cx);
+        let split = self.panes.get(1).map(|slot| {
+            let (tabs, active_tab) = slot.pane.read(cx).session(cx);
+            SplitSession {
+                orientation: self.orientation,
+                tabs,
+                active_tab,
+                second_active: self.active == 1,
+                compare: self.compare,
+            }
+        });
+        (tabs, active_tab, split)
+    }
+
+    fn other_pane(&self) -> Option<&Entity<Pane>> {
+        if self.panes.len() == 2 {
+            Some(&self.panes[1 - self.active].pane)
+        } else {
+            None
+        }
+    }
+
+    fn set_active(&mut self, ix: usize, cx: &mut Context<Self>) {
+        if self.active != ix && ix < self.panes.len() {
+            self.active = ix;
+            cx.notify();
+        }
+    }
+
+    fn on_toggle_split(&mut self, _: &ToggleSplit, window: &mut Window, cx: &mut Context<Self>) {
+        if self.panes.len() == 2 {
+            // Closing the split keeps the pane being worked in.
+            self.panes.remove(1 - self.active);
+            self.active = 0;
+            self.compare = false;
+            self.update_compare(cx);
+        } else {
+            // The new pane starts where the current one is.
+            let tab = self.active_explorer(cx).read(cx).session(cx);
+            self.add_pane(vec![tab], 0, window, cx);
+            self.active = 1;
+            self.split_state = cx.new(|_| ResizableState::default());
+        }
+        self.panes[self.active].pane.focus_handle(cx).focus(window, cx);
+        cx.notify();
+    }
+
+    fn on_toggle_orientation(
+        &mut self,
+        _: &ToggleSplitOrientation,
+        _: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.orientation = match self.orientation {
+            SplitOrientation::Horizontal => SplitOrientation::Vertical,
+            SplitOrientation::Vertical => SplitOrientation::Horizontal,
+        };
+        // Sizes along one axis mean nothing along the other.
+        self.split_state = cx.new(|_| ResizableState::default());
+        cx.notify();
+    }
+
+    fn on_focus_other_pane(
+        &mut self,
+        _: &FocusOtherPane,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        // Tab keeps moving focus between the explorer's inputs.
+        if self.panes.len() < 2 || window.has_focused_input(cx) {
+            cx.propagate();
+            return;
+        }
+        self.active = 1 - self.active;
+        self.panes[self.active].pane.focus_handle(cx).focus(window, cx);
+        cx.notify();
+    }
+
+    fn on_copy_to_other_pane(
+        &mut self,
+        _: &CopyToOtherPane,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.transfer_to_other_pane(JobKind::Copy, window, cx);
+    }
+
+    fn on_move_to_other_pane(
+        &mut self,
+        _: &MoveToOtherPane,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        self.transfer_to_other_pane(JobKind::Move, window, cx);
+    }
+
+    fn transfer_to_other_pane(
+        &mut self,
+        kind: JobKind,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(other) = self.other_pane() else {
+            return;
+        };
+        let dest = PathBuf::from(other.read(cx).active_explorer().read(cx).current_path());
+        self.active_explorer(cx).update(cx, |explorer, cx| {
+            explorer.transfer_selection(dest, kind, window, cx);
+        });
+    }
+
+    fn on_toggle_compare(&mut self, _: &ToggleCompare, _: &mut Window, cx: &mut Context<Self>) {
+        self.compare = !self.compare && self.panes.len() == 2;
+        self.update_compare(cx);
+        cx.notify();
+    }
+
+    /// Marks what differs between the two active tabs, and clears the marks of tabs that are no
+    /// longer compared. Nothing is compared again until a listing changes.
+    fn update_compare(&mut self, cx: &mut Context<Self>) {
+        let compared: Vec<Entity<ExplorerPage>> = if self.compare && self.panes.len() == 2 {
+            self.panes.iter().map(|slot| slot.pane.read(cx).active_explorer().clone()).collect()
+        } else {
+            Vec::new()
+        };
+        let generations: Vec<u64> =
+            compared.iter().map(|explorer| explorer.read(cx).listing_generation()).collect();
+        if compared == self.compared && generations == self.compared_generations {
+            return;
+        }
+        for stale in self.compared.iter().filter(|e| !compared.contains(e)) {
+            stale.update(cx, |explorer, cx| explorer.set_compare_marks(HashMap::new(), cx));
+        }
+        if let [left, right] = compared.as_slice() {
+            let differences = compare_listings(left.read(cx).entries(), right.read(cx).entries());
+            let (mut left_marks, mut right_marks) = (HashMap::new(), HashMap::new());
+            for (name, difference) in differences {
+                match difference {
+                    Difference::LeftOnly => {
+                        left_marks.insert(name, difference);
+                    },
+                    Difference::RightOnly => {
+                        right_marks.insert(name, difference);
+                    },
+                    Difference::Different => {
+                        left_marks.insert(name.clone(), difference);
+                        right_marks.insert(name, difference);
+                    },
+                }
+            }
+            left.update(cx, |explorer, cx| explorer.set_compare_marks(left_marks, cx));
+            right.update(cx, |explorer, cx| explorer.set_compare_marks(right_marks, cx));
+        }
+        self.compared = compared;
+        self.compared_generations = generations;
+    }
+
+    /// Compares the folder trees of the two panes in a sheet, from which they can be synchronized.
+    fn on_compare_folders(
+        &mut self,
+        _: &CompareFolders,
+        window: &mut Window,
+        cx: &mut Context<Self>,
+    ) {
+        if self.panes.len() < 2 {
+            let message = "Split the view to compare the folders of two panes";
+            window.push_notification(Notification::info(message), cx);
+            return;
+        }
+        let explorers: Vec<Entity<ExplorerPage>> =
+            self.panes.iter().map(|slot| slot.pane.read(cx).active_explorer().clone()).collect();
+        let [left, right] = [&explorers[0], &explorers[1]]
+            .map(|explorer| PathBuf::from(explorer.read(cx).current_path()));
+        let view = cx.new(|cx| FolderCompare::new(left.clone(), right.clone(), cx));
+        self.folder_compare_subscription = Some(cx.subscribe_in(
+            &view,
+            window,
+            move |_, _, event: &FolderCompareEvent, window, cx| match event {
+                FolderCompareEvent::Reveal(path) => {
+                    let parent = path.parent().filter(|parent| !parent.as_os_str().is_empty());
+                    for (explorer, root) in explorers.iter().zip([&left, &right]) {
+                        let folder =
+                            parent.map_or_else(|| root.clone(), |parent| root.join(parent));
+                        let folder = folder.to_string_lossy().to_string();
+                        explorer
+                            .update(cx, |explorer, cx| explorer.open_folder(folder, window, cx));
+                    }
+                },
+            },
+        ));
+        window.open_sheet(cx, move |sheet, _window, _cx| {
+            sheet.title("Compare Folders").size(px(760.0)).child(view.clone())
+        });
+    }
+
+    /// Scrolls the other pane to where `source` is.
+    fn sync_scroll(&mut self, source: &Entity<Pane>, cx: &mut Context<Self>) {
+        let Some(target) = self.panes.iter().map(|slot| &slot.pane).find(|pane| *pane != source)
+        else {
+            return;
+        };
+        let offset = source.read(cx).active_explorer().read(cx).scroll_offset();
+        let explorer = target.read(cx).active_explorer().clone();
+        if explorer.read(cx).scroll_offset() != offset {
+            explorer.update(cx, |explorer, cx| explorer.set_scroll_offset(offset, cx));
+        }
+    }
+
+    fn render_pane(&self, ix: usize, cx: &mut Context<Self>) -> impl IntoElement {
+        let active = ix == self.active;
+        let indicator = if active { cx.theme().accent } else { cx.theme().border };
+        div()
+            .size_full()
+            .flex()
+            .flex_col()
+            .min_h(px(0.0))
+            .border_t_2()
+            .border_color(indicator)
+            .capture_any_mouse_down(cx.listener(move |this, _, _, cx| this.set_active(ix, cx)))
+            .child(self.panes[ix].pane.clone())
+    }
+}
+
+impl Render for SplitView {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let content = if self.panes.len() == 2 {
+            let axis = match self.orientation {
+                SplitOrientation::Horizontal => Axis::Horizontal,
+                SplitOrientation::Vertical => Axis::Vertical,
+            };
+            split_container(
+                "explorer-split",
+                axis,
+                &self.split_state,
+                self.render_pane(0, cx),
+                self.render_pane(1, cx),
+            )
+            .into_any_element()
+        } else {
+            self.panes[0].pane.clone().into_any_element()
+        };
+
+        div()
+            .key_context(CONTEXT)
+            .size_full()
+            .flex()
+            .flex_col()
+            .min_h(px(0.0))
+            .on_action(cx.listener(Self::on_toggle_split))
+            .on_action(cx.listener(Self::on_toggle_orientation))
+            .on_action(cx.listener(Self::on_focus_other_pane))
+            .on_action(cx.listener(Self::on_copy_to_other_pane))
+            .on_action(cx.listener(Self::on_move_to_other_pane))
+            .on_action(cx.listener(Self::on_toggle_compare))
+            .on_action(cx.listener(Self::on_compare_folders))
+            .child(content)
+    }
+}
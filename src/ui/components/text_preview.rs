@@ -0,0 +1,428 @@
+use std::{
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use gpui::{
+    div, prelude::*, px, size, App, Context, Entity, IntoElement, Pixels, Render, ScrollStrategy,
+    SharedString, Size, Task, Window,
+};
+use gpui_component::{
+    button::{Button, ButtonVariants as _},
+    h_flex,
+    input::{Input, InputEvent, InputState},
+    v_flex, v_virtual_list, ActiveTheme, Disableable as _, Selectable as _, Sizable as _,
+    VirtualListScrollHandle,
+};
+
+use crate::{
+    core::errors::{Error, Result},
+    services::preview::{
+        hex::format_offset,
+        large_text::{FollowUpdate, LargeText, TextChunk},
+    },
+    ui::components::file_list::human_bytes,
+};
+
+/// Lines shown by Start, End, Next and a jump.
+const PAGE_LINES: usize = 2000;
+/// While following, older lines beyond this many are dropped from the top.
+const MAX_FOLLOW_LINES: usize = 20_000;
+/// How often a followed file is checked for new lines.
+const FOLLOW_INTERVAL: Duration = Duration::from_millis(500);
+const LINE_HEIGHT: f32 = 20.0;
+/// Approximate advance of one monospace character, for the horizontal extent of the list.
+const CHAR_WIDTH: f32 = 7.8;
+const GUTTER_WIDTH: f32 = 72.0;
+const TAB: &str = "    ";
+
+/// A text file read a page of lines at a time: large logs, and files in UTF-16, Latin-1 or GBK.
+/// It opens at the top, can jump to the end, a line or a byte offset, and can follow a growing
+/// file like `tail -f`.
+pub struct LargeTextPreview {
+    file: Option<Arc<LargeText>>,
+    lines: Vec<SharedString>,
+    first_line: Option<u64>,
+    start: u64,
+    end: u64,
+    partial: bool,
+    /// Where the last read stopped; following reads on once the file is longer.
+    read_end: u64,
+    file_len: u64,
+    longest: usize,
+    loading: bool,
+    following: bool,
+    error: Option<String>,
+    /// Created on first render, which has the window it needs.
+    jump_input: Option<Entity<InputState>>,
+    /// Set to stop a line count still running for a jump that has been superseded.
+    cancel: Arc<AtomicBool>,
+    line_sizes: Rc<Vec<Size<Pixels>>>,
+    scroll_handle: VirtualListScrollHandle,
+    _load_task: Task<()>,
+    _follow_task: Option<Task<()>>,
+}
+
+impl LargeTextPreview {
+    pub fn new(path: &Path, cx: &mut Context<Self>) -> Self {
+        let source: PathBuf = path.to_path_buf();
+        let open_task = cx.spawn(async move |this, cx| {
+            let result = cx
+                .background_executor()
+                .spawn(async move {
+                    let file = LargeText::open(&source)?;
+                    let chunk = file.head(PAGE_LINES)?;
+                    Ok::<_, Error>((file, chunk))
+                })
+                .await;
+            let _ = this.update(cx, |this, cx| {
+                match result {
+                    Ok((file, chunk)) => {
+                        this.file = Some(Arc::new(file));
+                        this.show(chunk, false);
+                    },
+                    Err(err) => this.error = Some(err.to_string()),
+                }
+                this.loading = false;
+                cx.notify();
+            });
+        });
+
+        Self {
+            file: None,
+            lines: Vec::new(),
+            first_line: None,
+            start: 0,
+            end: 0,
+            partial: false,
+            read_end: 0,
+            file_len: 0,
+            longest: 0,
+            loading: true,
+            following: false,
+            error: None,
+            jump_input: None,
+            cancel: Arc::new(AtomicBool::new(false)),
+            line_sizes: Rc::new(Vec::new()),
+            scroll_handle: VirtualListScrollHandle::new(),
+            _load_task: open_task,
+            _follow_task: None,
+        }
+    }
+
+    /// Encoding and size, once the file has been opened: `UTF-16 LE with BOM · 3.4 GB`.
+    pub fn summary(&self) -> Option<String> {
+        let file = self.file.as_ref()?;
+        let bom = if file.has_bom() { " with BOM" } else { "" };
+        Some(format!("{}{} · {}", file.encoding().label(), bom, human_bytes(self.file_len)))
+    }
+
+    /// Reads a chunk in the background and shows it, replacing what is shown.
+    fn load(
+        &mut self,
+        read: impl FnOnce(&LargeText, &AtomicBool) -> Result<TextChunk> + Send + 'static,
+        scroll_to_end: bool,
+        cx: &mut Context<Self>,
+    ) {
+        let Some(file) = self.file.clone() else {
+            return;
+        };
+        self.cancel.store(true, Ordering::Relaxed);
+        let cancel = Arc::new(AtomicBool::new(false));
+        self.cancel = cancel.clone();
+        self.loading = true;
+        self.error = None;
+
+        self._load_task = cx.spawn(async move |this, cx| {
+            let result = cx.background_executor().spawn(async move { read(&file, &cancel) }).await;
+            let _ = this.update(cx, |this, cx| {
+                match result {
+                    Ok(chunk) => this.show(chunk, scroll_to_end),
+                    Err(Error::Cancelled) => return,
+                    Err(err) => this.error = Some(err.to_string()),
+                }
+                this.loading = false;
+                cx.notify();
+            });
+        });
+        cx.notify();
+    }
+
+    fn show(&mut self, chunk: TextChunk, scroll_to_end: bool) {
+        self.lines.clear();
+        self.longest = 0;
+        self.first_line = chunk.first_line;
+        self.start = chunk.start;
+        self.partial = false;
+        self.append(chunk);
+        let last = if scroll_to_end { self.lines.len().saturating_sub(1) } else { 0 };
+        self.scroll_handle.scroll_to_item(last, ScrollStrategy::Top);
+    }
+
+    /// Adds the lines of a chunk that continues the one shown, replacing the unfinished last line
+    /// it starts with.
+    fn append(&mut self, chunk: TextChunk) {
+        if self.partial {
+            self.lines.pop();
+        }
+        for line in chunk.lines {
+            let line = line.replace('\t', TAB);
+            self.longest = self.longest.max(line.chars().count());
+            self.lines.push(line.into());
+        }
+        if self.lines.len() > MAX_FOLLOW_LINES {
+            let dropped = self.lines.len() - MAX_FOLLOW_LINES;
+            self.lines.drain(..dropped);
+            self.first_line = self.first_line.map(|line| line + dropped as u64);
+        }
+        self.end = chunk.end;
+        self.partial = chunk.partial;
+        self.read_end = chunk.read_end;
+        self.file_len = chunk.file_len;
+        let width = px(GUTTER_WIDTH + self.longest as f32 * CHAR_WIDTH);
+        self.line_sizes = Rc::new(vec![size(width, px(LINE_HEIGHT)); self.lines.len()]);
+    }
+
+    fn show_start(&mut self, cx: &mut Context<Self>) {
+        self.stop_following();
+        self.load(|file, _| file.head(PAGE_LINES), false, cx);
+    }
+
+    fn show_end(&mut self, cx: &mut Context<Self>) {
+        self.load(|file, _| file.tail(PAGE_LINES), true, cx);
+    }
+
+    /// The page after the one shown, numbered on from it when its numbers are known.
+    fn show_next(&mut self, cx: &mut Context<Self>) {
+        self.stop_following();
+        let end = self.end;
+        // An unfinished last line is where the next page starts.
+        let shown = self.lines.len() - usize::from(self.partial);
+        let next_line = self.first_line.map(|line| line + shown as u64);
+        self.load(
+            move |file, _| {
+                let mut chunk = file.at_offset(end, PAGE_LINES)?;
+                chunk.first_line = chunk.first_line.or(next_line);
+                Ok(chunk)
+            },
+            false,
+            cx,
+        );
+    }
+
+    /// Jumps to what the input holds: a byte offset written as `0x…`, else a line number.
+    fn jump(&mut self, input: &Entity<InputState>, cx: &mut Context<Self>) {
+        let text = input.read(cx).text().to_string();
+        let text = text.trim();
+        self.stop_following();
+        if let Some(hex) = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+            match u64::from_str_radix(hex, 16) {
+                Ok(offset) => {
+                    self.load(move |file, _| file.at_offset(offset, PAGE_LINES), false, cx)
+                },
+                Err(_) => self.error = Some(format!("{text} is not a hex offset")),
+            }
+        } else {
+            match text.replace(',', "").parse::<u64>() {
+                Ok(line) => {
+                    self.load(move |file, cancel| file.at_line(line, PAGE_LINES, cancel), false, cx)
+                },
+                Err(_) => self.error = Some("Enter a line number or a 0x offset".to_string()),
+            }
+        }
+        cx.notify();
+    }
+
+    fn toggle_follow(&mut self, cx: &mut Context<Self>) {
+        if self.following {
+            self.stop_following();
+            cx.notify();
+            return;
+        }
+        self.following = true;
+        self.show_end(cx);
+        self._follow_task = Some(cx.spawn(async move |this, cx| loop {
+            cx.background_executor().timer(FOLLOW_INTERVAL).await;
+            let Ok(state) = this.update(cx, |this, _| {
+                if !this.following {
+                    return None;
+                }
+                // Wait for a jump to the end to land before reading on from it.
+                let file = this.file.clone().filter(|_| !this.loading);
+                Some(file.map(|file| (file, this.end, this.read_end)))
+            }) else {
+                return;
+            };
+            let Some(state) = state else {
+                return;
+            };
+            let Some((file, end, read_end)) = state else {
+                continue;
+            };
+            let update =
+                cx.background_executor().spawn(async move { file.follow(end, read_end) }).await;
+            let alive = this.update(cx, |this, cx| this.apply_follow(update, cx)).is_ok();
+            if !alive {
+                return;
+            }
+        }));
+    }
+
+    fn stop_following(&mut self) {
+        self.following = false;
+        self._follow_task = None;
+    }
+
+    fn apply_follow(&mut self, update: Result<FollowUpdate>, cx: &mut Context<Self>) {
+        if !self.following {
+            return;
+        }
+        match update {
+            Ok(FollowUpdate::Unchanged) => {},
+            Ok(FollowUpdate::Truncated) => self.show_end(cx),
+            Ok(FollowUpdate::Appended(chunk)) => {
+                self.append(chunk);
+                let last = self.lines.len().saturating_sub(1);
+                self.scroll_handle.scroll_to_item(last, ScrollStrategy::Top);
+                cx.notify();
+            },
+            Err(err) => {
+                // The follow loop ends on its own when it sees this.
+                self.error = Some(err.to_string());
+                self.following = false;
+                cx.notify();
+            },
+        }
+    }
+
+    /// Where the shown lines are in the file: their numbers, or their byte offset when the
+    /// numbers are unknown (after jumping to the end or to an offset).
+    fn position(&self) -> String {
+        if self.lines.is_empty() {
+            return "Empty".to_string();
+        }
+        if self.following {
+            return format!("Following · last {} lines", self.lines.len());
+        }
+        match self.first_line {
+            Some(first) => {
+                format!("Lines {}–{}", first, first + self.lines.len() as u64 - 1)
+            },
+            None => format!("{} lines from {}", self.lines.len(), format_offset(self.start)),
+        }
+    }
+
+    fn render_line(&self, ix: usize, cx: &App) -> impl IntoElement {
+        let number = self.first_line.map(|first| (first + ix as u64).to_string());
+        h_flex()
+            .h(px(LINE_HEIGHT))
+            .child(
+                div()
+                    .w(px(GUTTER_WIDTH))
+                    .flex_shrink_0()
+                    .pr_3()
+                    .text_right()
+                    .text_color(cx.theme().muted_foreground)
+                    .children(number),
+            )
+            .child(
+                div()
+                    .whitespace_nowrap()
+                    .text_color(cx.theme().foreground)
+                    .child(self.lines[ix].clone()),
+            )
+    }
+
+    fn render_toolbar(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let input = self
+            .jump_input
+            .get_or_insert_with(|| {
+                let input =
+                    cx.new(|cx| InputState::new(window, cx).placeholder("Line, or 0x offset"));
+                cx.subscribe(&input, |this, input, event: &InputEvent, cx| {
+                    if let InputEvent::PressEnter { .. } = event {
+                        this.jump(&input, cx);
+                    }
+                })
+                .detach();
+                input
+            })
+            .clone();
+        let opened = self.file.is_some();
+
+        h_flex()
+            .gap_1()
+            .child(
+                Button::new("text-start")
+                    .small()
+                    .ghost()
+                    .label("Start")
+                    .disabled(!opened)
+                    .on_click(cx.listener(|this, _, _, cx| this.show_start(cx))),
+            )
+            .child(
+                Button::new("text-next")
+                    .small()
+                    .ghost()
+                    .label("Next")
+                    .disabled(!opened || self.end >= self.file_len)
+                    .on_click(cx.listener(|this, _, _, cx| this.show_next(cx))),
+            )
+            .child(Button::new("text-end").small().ghost().label("End").disabled(!opened).on_click(
+                cx.listener(|this, _, _, cx| {
+                    this.stop_following();
+                    this.show_end(cx);
+                }),
+            ))
+            .child(
+                Button::new("text-follow")
+                    .small()
+                    .ghost()
+                    .label("Follow")
+                    .selected(self.following)
+                    .disabled(!opened)
+                    .on_click(cx.listener(|this, _, _, cx| this.toggle_follow(cx))),
+            )
+            .child(div().w(px(140.0)).child(Input::new(&input).small()))
+            .child(
+                div()
+                    .flex_1()
+                    .text_xs()
+                    .text_right()
+                    .text_color(cx.theme().muted_foreground)
+                    .child(if self.loading { "Loading…".to_string() } else { self.position() }),
+            )
+    }
+}
+
+impl Render for LargeTextPreview {
+    fn render(&mut self, window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        v_flex()
+            .size_full()
+            .gap_2()
+            .child(self.render_toolbar(window, cx))
+            .children(
+                self.error
+                    .clone()
+                    .map(|error| div().text_xs().text_color(cx.theme().red).child(error)),
+            )
+            .child(
+                div().flex_1().min_h(px(0.0)).font_family("monospace").text_xs().child(
+                    v_virtual_list(
+                        cx.entity().clone(),
+                        "large-text-preview",
+                        self.line_sizes.clone(),
+                        |view, visible_range, _window, cx| {
+                            visible_range.map(|ix| view.render_line(ix, cx)).collect()
+                        },
+                    )
+                    .track_scroll(&self.scroll_handle),
+                ),
+            )
+    }
+}
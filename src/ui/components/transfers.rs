@@ -0,0 +1,383 @@
+use std::{ops::ControlFlow, sync::mpsc, time::Duration};
+
+use gpui::{
+    actions, div, prelude::*, px, relative, App, AppContext, Context, Entity, Global, IntoElement,
+    Render, Task, Window,
+};
+use gpui_component::{
+    button::{Button, ButtonVariants as _},
+    h_flex, v_flex, ActiveTheme, Disableable as _, Sizable as _, WindowExt as _,
+};
+
+use crate::{
+    services::{
+        jobs::{JobEvent, JobId, JobKind, JobQueue, JobSnapshot, JobSpec, JobState},
+        journal::Operation,
+    },
+    ui::{
+        channel::forward_channel,
+        components::{file_list::human_bytes, history::History},
+    },
+};
+
+actions!(transfers, [ShowTransfers]);
+
+struct GlobalTransfers(Entity<Transfers>);
+
+impl Global for GlobalTransfers {}
+
+/// Starts the transfer queue and makes [`ShowTransfers`] open the transfer panel in the active
+/// window.
+pub fn init(cx: &mut App) {
+    let transfers = cx.new(Transfers::new);
+    cx.set_global(GlobalTransfers(transfers));
+
+    cx.on_action(|_: &ShowTransfers, cx: &mut App| {
+        let transfers = Transfers::global(cx);
+        if let Some(window) = cx.active_window() {
+            cx.defer(move |cx| {
+                let _ = window.update(cx, |_, window, cx| {
+                    window.open_sheet(cx, move |sheet, _window, _cx| {
+                        sheet.title("Transfers").child(transfers.clone())
+                    });
+                });
+            });
+        }
+    });
+}
+
+/// The next event and whatever else piled up behind it, so a burst is applied in one update.
+fn take_events(rx: &mpsc::Receiver<JobEvent>) -> Option<Vec<JobEvent>> {
+    let first = rx.recv().ok()?;
+    Some(std::iter::once(first).chain(rx.try_iter()).collect())
+}
+
+/// What the footer shows while transfers are running or need attention.
+#[derive(Clone)]
+pub struct TransferSummary {
+    pub label: String,
+    /// Combined progress of the unfinished jobs; `None` when only failures are left.
+    pub fraction: Option<f32>,
+    pub failed: bool,
+}
+
+/// Background copies and moves of the whole app, and the panel that shows them.
+pub struct Transfers {
+    queue: JobQueue,
+    /// Jobs in the order they were queued, finished ones included until cleared.
+    jobs: Vec<JobSnapshot>,
+    _events_task: Task<()>,
+}
+
+impl Transfers {
+    fn new(cx: &mut Context<Self>) -> Self {
+        let (queue, rx) = JobQueue::start().expect("failed to start the transfer worker");
+
+        let events_task = cx.spawn(async move |this, cx| {
+            forward_channel(rx, take_events, &this, cx, |this, events, cx| {
+                this.apply_events(events, cx);
+                ControlFlow::Continue(())
+            })
+            .await;
+        });
+
+        Self { queue, jobs: Vec::new(), _events_task: events_task }
+    }
+
+    pub fn global(cx: &App) -> Entity<Transfers> {
+        cx.global::<GlobalTransfers>().0.clone()
+    }
+
+    /// Queues a copy or move; it runs after the jobs queued before it.
+    pub fn enqueue(&self, spec: JobSpec) -> JobId {
+        self.queue.enqueue(spec)
+    }
+
+    fn apply_events(&mut self, events: Vec<JobEvent>, cx: &mut Context<Self>) {
+        for event in events {
+            match event {
+                JobEvent::Changed(job) => {
+                    let existing = self.jobs.iter_mut().find(|j| j.id == job.id);
+                    let was_finished = existing.as_ref().is_some_and(|j| j.state.is_finished());
+                    if job.state.is_finished() && !was_finished {
+                        record_job(&job, cx);
+                    }
+                    match existing {
+                        Some(existing) => *existing = job,
+                        None => self.jobs.push(job),
+                    }
+                },
+                JobEvent::Removed(id) => self.jobs.retain(|j| j.id != id),
+            }
+        }
+        cx.notify();
+    }
+
+    pub fn summary(&self) -> Option<TransferSummary> {
+        let active: Vec<&JobSnapshot> =
+            self.jobs.iter().filter(|j| !j.state.is_finished()).collect();
+        let failed = self.jobs.iter().filter(|j| j.state == JobState::Failed).count();
+
+        if active.is_empty() {
+            return (failed > 0).then(|| TransferSummary {
+                label: match failed {
+                    1 => "1 transfer failed".to_string(),
+                    n => format!("{n} transfers failed"),
+                },
+                fraction: None,
+                failed: true,
+            });
+        }
+
+        let (done, total) = active.iter().fold((0, 0), |(done, total), job| {
+            (done + job.progress.bytes_done, total + job.progress.bytes_total)
+        });
+        let fraction = match active.as_slice() {
+            [job] => job.progress.fraction(),
+            _ if total > 0 => (done as f64 / total as f64).min(1.0) as f32,
+            _ => 0.0,
+        };
+        let mut label = match active.as_slice() {
+            [job] => job.title(),
+            jobs => format!("{} transfers", jobs.len()),
+        };
+        label.push_str(&format!(" {:.0}%", fraction * 100.0));
+        if let [job] = active.as_slice() {
+            if let Some(eta) = job.progress.eta().filter(|_| job.state == JobState::Running) {
+                label.push_str(&format!(" · {} left", format_duration(eta)));
+            }
+        }
+
+        Some(TransferSummary { label, fraction: Some(fraction), failed: failed > 0 })
+    }
+
+    fn render_job(&self, job: &JobSnapshot, cx: &mut Context<Self>) -> impl IntoElement {
+        let id = job.id;
+        let progress = &job.progress;
+        let finished = job.state.is_finished();
+
+        let mut stats = vec![if progress.bytes_total > 0 {
+            format!("{} of {}", human_bytes(progress.bytes_done), human_bytes(progress.bytes_total))
+        } else {
+            human_bytes(progress.bytes_done)
+        }];
+        if progress.files_total > 0 {
+            stats.push(format!("{} of {} files", progress.files_done, progress.files_total));
+        }
+        if progress.throughput > 0.0 {
+            stats.push(format!("{}/s", human_bytes(progress.throughput as u64)));
+        }
+        if job.state == JobState::Running {
+            if let Some(eta) = progress.eta() {
+                stats.push(format!("{} left", format_duration(eta)));
+            }
+        }
+
+        let controls = h_flex()
+            .gap_2()
+            .when(!finished, |this| {
+                let paused = job.state == JobState::Paused;
+                this.child(
+                    Button::new("transfer-pause")
+                        .small()
+                        .label(if paused { "Resume" } else { "Pause" })
+                        .on_click(cx.listener(move |this, _, _, _| {
+                            if paused {
+                                this.queue.resume(id);
+                            } else {
+                                this.queue.pause(id);
+                            }
+                        })),
+                )
+                .child(
+                    Button::new("transfer-cancel")
+                        .small()
+                        .danger()
+                        .label("Cancel")
+                        .on_click(cx.listener(move |this, _, _, _| this.queue.cancel(id))),
+                )
+            })
+            .when(job.errors.len() > 1, |this| {
+                this.child(
+                    Button::new("transfer-retry-all")
+                        .small()
+                        .label("Retry All")
+                        .on_click(cx.listener(move |this, _, _, _| this.queue.retry(id, None))),
+                )
+                .child(
+                    Button::new("transfer-skip-all")
+                        .small()
+                        .label("Skip All")
+                        .on_click(cx.listener(move |this, _, _, _| this.queue.skip(id, None))),
+                )
+            });
+
+        let errors: Vec<_> =
+            job.errors
+                .iter()
+                .enumerate()
+                .map(|(ix, error)| {
+                    h_flex()
+                        .id(("transfer-error", ix))
+                        .gap_2()
+                        .child(
+                            v_flex()
+                                .flex_1()
+                                .min_w(px(0.0))
+                                .child(
+                                    div()
+                                        .text_xs()
+                                        .overflow_hidden()
+                                        .text_ellipsis()
+                                        .whitespace_nowrap()
+                                        .child(error.source.to_string_lossy().to_string()),
+                                )
+                                .child(
+                                    div()
+                                        .text_xs()
+                                        .text_color(cx.theme().danger)
+                                        .child(error.message.clone()),
+                                ),
+                        )
+                        .child(Button::new("transfer-retry").small().label("Retry").on_click(
+                            cx.listener(move |this, _, _, _| this.queue.retry(id, Some(ix))),
+                        ))
+                        .child(Button::new("transfer-skip").small().label("Skip").on_click(
+                            cx.listener(move |this, _, _, _| this.queue.skip(id, Some(ix))),
+                        ))
+                })
+                .collect();
+
+        // Element ids below are scoped to the job.
+        v_flex()
+            .id(("transfer-job", id as usize))
+            .gap_2()
+            .py(px(12.0))
+            .border_b_1()
+            .border_color(cx.theme().border)
+            .child(
+                h_flex()
+                    .gap_2()
+                    .child(
+                        div()
+                            .flex_1()
+                            .text_sm()
+                            .font_weight(gpui::FontWeight::SEMIBOLD)
+                            .overflow_hidden()
+                            .text_ellipsis()
+                            .whitespace_nowrap()
+                            .child(job.title()),
+                    )
+                    .child(
+                        div()
+                            .text_xs()
+                            .text_color(cx.theme().muted_foreground)
+                            .child(job.state.label()),
+                    ),
+            )
+            .child(
+                div()
+                    .text_xs()
+                    .text_color(cx.theme().muted_foreground)
+                    .overflow_hidden()
+                    .text_ellipsis()
+                    .whitespace_nowrap()
+                    .child(match job.spec.kind {
+                        JobKind::Trash => "to the trash".to_string(),
+                        _ => format!("to {}", job.spec.dest.display()),
+                    }),
+            )
+            .child(progress_bar(progress.fraction(), cx))
+            .child(div().text_xs().text_color(cx.theme().muted_foreground).child(stats.join(" · ")))
+            .when_some(progress.current.clone().filter(|_| !finished), |this, current| {
+                this.child(
+                    div()
+                        .text_xs()
+                        .text_color(cx.theme().muted_foreground)
+                        .overflow_hidden()
+                        .text_ellipsis()
+                        .whitespace_nowrap()
+                        .child(current.to_string_lossy().to_string()),
+                )
+            })
+            .child(controls)
+            .children(errors)
+    }
+}
+
+impl Render for Transfers {
+    fn render(&mut self, _window: &mut Window, cx: &mut Context<Self>) -> impl IntoElement {
+        let has_finished = self.jobs.iter().any(|j| j.state.is_finished());
+
+        v_flex()
+            .size_full()
+            .gap_2()
+            .child(
+                h_flex().justify_end().child(
+                    Button::new("transfers-clear")
+                        .small()
+                        .label("Clear Finished")
+                        .disabled(!has_finished)
+                        .on_click(cx.listener(|this, _, _, _| this.queue.clear_finished())),
+                ),
+            )
+            .when(self.jobs.is_empty(), |this| {
+                this.child(
+                    div()
+                        .py(px(24.0))
+                        .text_sm()
+                        .text_color(cx.theme().muted_foreground)
+                        .child("No transfers"),
+                )
+            })
+            .child(
+                div().id("transfers-list").flex_1().min_h(px(0.0)).overflow_y_scroll().children(
+                    // Newest first.
+                    self.jobs
+                        .iter()
+                        .rev()
+                        .map(|job| self.render_job(job, cx).into_any_element())
+                        .collect::<Vec<_>>(),
+                ),
+            )
+    }
+}
+
+/// Adds what a finished job transferred to the undo history. Entries that merged into or replaced
+/// an existing one are left out, since reversing them would touch what was there before.
+fn record_job(job: &JobSnapshot, cx: &mut App) {
+    let pairs: Vec<_> =
+        job.completed.iter().filter(|(_, dest)| !job.replaced.contains(dest)).cloned().collect();
+    if pairs.is_empty() {
+        return;
+    }
+    let op = match job.spec.kind {
+        JobKind::Copy => Operation::Copy { copies: pairs },
+        JobKind::Move => Operation::Move { moves: pairs },
+        JobKind::Trash => {
+            Operation::Trash { paths: pairs.into_iter().map(|(path, _)| path).collect() }
+        },
+    };
+    History::global(cx).update(cx, |history, cx| history.record(op, cx));
+}
+
+/// A thin horizontal bar filled to `fraction`.
+pub fn progress_bar<V: Render>(fraction: f32, cx: &mut Context<V>) -> impl IntoElement {
+    div().w_full().h(px(4.0)).rounded(px(2.0)).bg(cx.theme().muted).child(
+        div()
+            .h_full()
+            .w(relative(fraction.clamp(0.0, 1.0)))
+            .rounded(px(2.0))
+            .bg(cx.theme().primary),
+    )
+}
+
+/// "45s", "3m 12s", "1h 5m".
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    match secs {
+        0..60 => format!("{secs}s"),
+        60..3600 => format!("{}m {}s", secs / 60, secs % 60),
+        _ => format!("{}h {}m", secs / 3600, secs % 3600 / 60),
+    }
+}
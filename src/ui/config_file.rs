@@ -0,0 +1,42 @@
+use std::{fs, ops::ControlFlow, path::PathBuf};
+
+use gpui::{Context, Task};
+
+use crate::{
+    services::fs::watcher::{watch_dir, DirWatcher, DEFAULT_DEBOUNCE},
+    ui::channel::forward_channel,
+};
+
+/// Calls `on_change` whenever the file at `path` is created, written or removed, for as long as
+/// the returned task is kept.
+///
+/// The parent folder is watched (and created if missing) rather than the file itself, since many
+/// editors save by replacing the file and the file may not exist yet.
+pub fn watch_config_file<T: 'static>(
+    path: PathBuf,
+    cx: &mut Context<T>,
+    on_change: impl Fn(&mut T, &mut Context<T>) + 'static,
+) -> Option<Task<()>> {
+    let dir = path.parent()?.to_path_buf();
+    if let Err(err) = fs::create_dir_all(&dir) {
+        tracing::warn!("Not watching {}: {}", path.display(), err);
+        return None;
+    }
+    let watcher = match watch_dir(&dir, DEFAULT_DEBOUNCE) {
+        Ok(watcher) => watcher,
+        Err(err) => {
+            tracing::warn!("Not watching {}: {}", path.display(), err);
+            return None;
+        },
+    };
+
+    Some(cx.spawn(async move |this, cx| {
+        forward_channel(watcher, DirWatcher::recv, &this, cx, |this, batch, cx| {
+            if batch.rescan || batch.changed.contains(&path) {
+                on_change(this, cx);
+            }
+            ControlFlow::Continue(())
+        })
+        .await;
+    }))
+}
@@ -0,0 +1,503 @@
+use std::{collections::HashMap, fmt, fs, io, path::PathBuf, rc::Rc};
+
+use anyhow::{anyhow, bail, Context as _, Result};
+use gpui::{
+    is_no_action, Action, App, AppContext, Context, Entity, Global, KeyBinding,
+    KeyBindingContextPredicate, NoAction, SharedString, Task,
+};
+use gpui_component::{notification::Notification, WindowExt as _};
+use serde::{
+    de::{MapAccess, Visitor},
+    ser::SerializeMap,
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use serde_json::Value;
+
+use crate::{core::paths::config_dir, ui::config_file::watch_config_file};
+
+const KEYMAP_FILE: &str = "keymap.json";
+
+/// Contexts a binding can be scoped to: the name used in the keymap file and the key context the
+/// UI sets for it. Bindings without a context apply everywhere.
+pub const CONTEXTS: [(&str, Option<&str>); 6] = [
+    ("global", None),
+    ("split", Some("SplitView")),
+    ("pane", Some("Pane")),
+    ("explorer", Some("Explorer")),
+    ("preview", Some("Preview")),
+    ("search", Some("Search")),
+];
+
+/// One block of the keymap file:
+///
+/// ```json
+/// [
+///   { "context": "explorer", "bindings": { "ctrl-l": "explorer::GoToParent", "backspace": null } }
+/// ]
+/// ```
+///
+/// A binding maps keystrokes to an action name, to `[name, arguments]` for actions that take
+/// arguments, or to `null` to remove a default binding.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct KeymapSection {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    context: Option<String>,
+    /// In file order, duplicates included, so a key bound twice shows up as a conflict rather than
+    /// the earlier entry vanishing while the file is parsed.
+    #[serde(default, with = "ordered_bindings")]
+    bindings: Vec<(String, Value)>,
+}
+
+/// (De)serializes a JSON object as its entries in order, where a map would keep only the last
+/// value of a repeated key.
+mod ordered_bindings {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        bindings: &[(String, Value)],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(bindings.len()))?;
+        for (keystrokes, action) in bindings {
+            map.serialize_entry(keystrokes, action)?;
+        }
+        map.end()
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<(String, Value)>, D::Error> {
+        struct EntriesVisitor;
+
+        impl<'de> Visitor<'de> for EntriesVisitor {
+            type Value = Vec<(String, Value)>;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an object of keystrokes to actions")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+                let mut entries = Vec::with_capacity(map.size_hint().unwrap_or(0));
+                while let Some(entry) = map.next_entry()? {
+                    entries.push(entry);
+                }
+                Ok(entries)
+            }
+        }
+
+        deserializer.deserialize_map(EntriesVisitor)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum BindingSource {
+    Default,
+    User,
+}
+
+/// A binding as the Keymap page lists it.
+#[derive(Clone)]
+pub struct BindingInfo {
+    pub keystrokes: SharedString,
+    /// The keymap file name of the context, or the raw key context for component bindings.
+    pub context: SharedString,
+    /// `None` for a user entry that removes a default binding.
+    pub action: Option<&'static str>,
+    pub source: BindingSource,
+    /// A default replaced or removed by a user binding for the same keystrokes and context.
+    pub overridden: bool,
+}
+
+/// User bindings that will not do what the keymap file suggests.
+pub struct KeymapConflict {
+    pub keystrokes: SharedString,
+    pub context: SharedString,
+    pub message: String,
+}
+
+struct GlobalKeymap(Entity<Keymap>);
+
+impl Global for GlobalKeymap {}
+
+/// Takes the bindings registered so far as the defaults and layers the user's keymap file on top,
+/// reloading it whenever it changes. Call after everything else has bound its keys.
+pub fn init(cx: &mut App) {
+    let defaults: Vec<KeyBinding> = cx.key_bindings().borrow().bindings().cloned().collect();
+    let keymap = cx.new(|cx| Keymap::new(defaults, cx));
+    cx.set_global(GlobalKeymap(keymap.clone()));
+    keymap.update(cx, |keymap, cx| {
+        keymap.reload(cx);
+        for problem in &keymap.problems {
+            tracing::warn!("Keymap: {problem}");
+        }
+    });
+}
+
+/// The default and user key bindings of the app.
+pub struct Keymap {
+    path: Option<PathBuf>,
+    defaults: Vec<KeyBinding>,
+    user: Vec<KeyBinding>,
+    /// Entries of the keymap file that could not be loaded.
+    problems: Vec<String>,
+    conflicts: Vec<KeymapConflict>,
+    /// A chord is being recorded; all bindings are suspended so every key reaches the recorder.
+    recording: bool,
+    _watch_task: Option<Task<()>>,
+}
+
+impl Keymap {
+    fn new(defaults: Vec<KeyBinding>, cx: &mut Context<Self>) -> Self {
+        let path = config_dir().map(|dir| dir.join(KEYMAP_FILE));
+        let watch_task = path.clone().and_then(|path| {
+            watch_config_file(path, cx, |this: &mut Self, cx| {
+                this.reload(cx);
+                this.report_problems(cx);
+            })
+        });
+        Self {
+            path,
+            defaults,
+            user: Vec::new(),
+            problems: Vec::new(),
+            conflicts: Vec::new(),
+            recording: false,
+            _watch_task: watch_task,
+        }
+    }
+
+    pub fn global(cx: &App) -> Entity<Keymap> {
+        cx.global::<GlobalKeymap>().0.clone()
+    }
+
+    pub fn path(&self) -> Option<&PathBuf> {
+        self.path.as_ref()
+    }
+
+    pub fn problems(&self) -> &[String] {
+        &self.problems
+    }
+
+    pub fn conflicts(&self) -> &[KeymapConflict] {
+        &self.conflicts
+    }
+
+    /// Re-reads the keymap file and rebinds everything.
+    pub fn reload(&mut self, cx: &mut Context<Self>) {
+        let (user, problems) = match &self.path {
+            Some(path) => load_user_bindings(path, cx),
+            None => (Vec::new(), Vec::new()),
+        };
+        self.user = user;
+        self.problems = problems;
+        self.conflicts = find_conflicts(&self.defaults, &self.user);
+        if !self.recording {
+            self.apply(cx);
+        }
+        cx.notify();
+    }
+
+    fn apply(&self, cx: &mut App) {
+        cx.clear_key_bindings();
+        // Later bindings take precedence, so user bindings replace defaults for the same keys.
+        cx.bind_keys(self.defaults.iter().cloned());
+        cx.bind_keys(self.user.iter().cloned());
+    }
+
+    /// Shows the keymap file's problems, if any, in the active window.
+    pub fn report_problems(&self, cx: &mut App) {
+        if self.problems.is_empty() {
+            return;
+        }
+        let message = match self.problems.as_slice() {
+            [problem] => format!("Keymap: {problem}"),
+            problems => format!("The keymap has {} problems; see the Keymap page", problems.len()),
+        };
+        if let Some(window) = cx.active_window() {
+            cx.defer(move |cx| {
+                let _ = window.update(cx, |_, window, cx| {
+                    window.push_notification(Notification::error(message), cx);
+                });
+            });
+        }
+    }
+
+    /// Suspends all bindings while a chord is recorded, and restores them afterwards.
+    pub fn set_recording(&mut self, recording: bool, cx: &mut Context<Self>) {
+        if self.recording == recording {
+            return;
+        }
+        self.recording = recording;
+        if recording {
+            cx.clear_key_bindings();
+        } else {
+            self.apply(cx);
+        }
+    }
+
+    /// Every binding, defaults first, for display.
+    pub fn bindings(&self) -> Vec<BindingInfo> {
+        let user: Vec<BindingInfo> =
+            self.user.iter().map(|b| binding_info(b, BindingSource::User)).collect();
+        let mut bindings: Vec<BindingInfo> = self
+            .defaults
+            .iter()
+            .map(|b| {
+                let mut info = binding_info(b, BindingSource::Default);
+                info.overridden = user
+                    .iter()
+                    .any(|u| u.keystrokes == info.keystrokes && u.context == info.context);
+                info
+            })
+            .collect();
+        bindings.extend(user);
+        bindings
+    }
+
+    /// Adds a binding to the keymap file, replacing one for the same keystrokes and context, and
+    /// reloads.
+    pub fn add_binding(
+        &mut self,
+        keystrokes: &str,
+        context: &str,
+        action: &str,
+        cx: &mut Context<Self>,
+    ) -> Result<()> {
+        let path =
+            self.path.clone().ok_or_else(|| anyhow!("there is no config folder to save it in"))?;
+        let mut sections: Vec<KeymapSection> = match fs::read_to_string(&path) {
+            Ok(text) if text.trim().is_empty() => Vec::new(),
+            Ok(text) => serde_json::from_str(&text)
+                .with_context(|| format!("{} has errors; fix them first", path.display()))?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(err.into()),
+        };
+
+        let target = resolve_context(Some(context)).map_err(|err| anyhow!(err))?;
+        let action = Value::String(action.to_string());
+        match sections.iter_mut().find(|s| resolve_context(s.context.as_deref()) == Ok(target)) {
+            Some(section) => {
+                section.bindings.retain(|(existing, _)| existing != keystrokes);
+                section.bindings.push((keystrokes.to_string(), action));
+            },
+            None => sections.push(KeymapSection {
+                context: target.is_some().then(|| context.to_string()),
+                bindings: vec![(keystrokes.to_string(), action)],
+            }),
+        }
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&path, serde_json::to_string_pretty(&sections)?)?;
+        self.reload(cx);
+        Ok(())
+    }
+}
+
+/// Reads the keymap file. A missing file means no user bindings; every entry that can't be used
+/// is reported and skipped, so one typo doesn't throw away the rest of the file.
+fn load_user_bindings(path: &PathBuf, cx: &App) -> (Vec<KeyBinding>, Vec<String>) {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return (Vec::new(), Vec::new()),
+        Err(err) => return (Vec::new(), vec![format!("can't read {}: {}", path.display(), err)]),
+    };
+    if text.trim().is_empty() {
+        return (Vec::new(), Vec::new());
+    }
+    let sections: Vec<KeymapSection> = match serde_json::from_str(&text) {
+        Ok(sections) => sections,
+        Err(err) => return (Vec::new(), vec![format!("{}: {}", path.display(), err)]),
+    };
+
+    let mut bindings = Vec::new();
+    let mut problems = Vec::new();
+    for section in sections {
+        let context = match resolve_context(section.context.as_deref()) {
+            Ok(context) => context,
+            Err(err) => {
+                problems.push(err);
+                continue;
+            },
+        };
+        for (keystrokes, action) in section.bindings {
+            match build_binding(&keystrokes, &action, context, cx) {
+                Ok(binding) => bindings.push(binding),
+                Err(err) => problems.push(format!("\"{keystrokes}\": {err}")),
+            }
+        }
+    }
+    (bindings, problems)
+}
+
+/// Maps a context name from the keymap file to the key context the UI uses.
+fn resolve_context(name: Option<&str>) -> Result<Option<&'static str>, String> {
+    let name = name.unwrap_or("global");
+    CONTEXTS
+        .iter()
+        .find(|(file_name, _)| file_name.eq_ignore_ascii_case(name))
+        .map(|(_, context)| *context)
+        .ok_or_else(|| {
+            let known: Vec<&str> = CONTEXTS.iter().map(|(name, _)| *name).collect();
+            format!("unknown context \"{name}\" (expected one of {})", known.join(", "))
+        })
+}
+
+fn build_binding(
+    keystrokes: &str,
+    action: &Value,
+    context: Option<&str>,
+    cx: &App,
+) -> Result<KeyBinding> {
+    let action: Box<dyn Action> = match action {
+        Value::Null => NoAction.boxed_clone(),
+        Value::String(name) => cx.build_action(name, None)?,
+        Value::Array(items) => match items.as_slice() {
+            [Value::String(name), arguments] => cx.build_action(name, Some(arguments.clone()))?,
+            _ => bail!("expected [\"action\", arguments]"),
+        },
+        _ => bail!("expected an action name, [\"action\", arguments] or null"),
+    };
+    let predicate = context.map(KeyBindingContextPredicate::parse).transpose()?.map(Rc::new);
+    let binding = KeyBinding::load(
+        keystrokes,
+        action,
+        predicate,
+        false,
+        None,
+        cx.keyboard_mapper().as_ref(),
+    )?;
+    Ok(binding)
+}
+
+fn binding_info(binding: &KeyBinding, source: BindingSource) -> BindingInfo {
+    let action = binding.action();
+    BindingInfo {
+        keystrokes: keystrokes_text(binding).into(),
+        context: context_text(binding).into(),
+        action: (!is_no_action(action)).then(|| action.name()),
+        source,
+        overridden: false,
+    }
+}
+
+fn keystrokes_text(binding: &KeyBinding) -> String {
+    binding.keystrokes().iter().map(|k| k.to_string()).collect::<Vec<_>>().join(" ")
+}
+
+/// The keymap file name of a binding's context, falling back to the predicate as written.
+fn context_text(binding: &KeyBinding) -> String {
+    let predicate = binding.predicate().map(|p| p.to_string());
+    CONTEXTS
+        .iter()
+        .find(|(_, context)| context.map(str::to_string) == predicate)
+        .map_or_else(|| predicate.unwrap_or_default(), |(name, _)| name.to_string())
+}
+
+/// Finds user bindings that won't take effect as written: the same keystrokes bound more than once
+/// in one context, within a section or across sections (the later entry wins), and global
+/// bindings that a default of a more specific context takes precedence over.
+fn find_conflicts(defaults: &[KeyBinding], user: &[KeyBinding]) -> Vec<KeymapConflict> {
+    let mut conflicts = Vec::new();
+
+    let mut seen: HashMap<(String, String), &'static str> = HashMap::new();
+    for binding in user {
+        let key = (keystrokes_text(binding), context_text(binding));
+        let name = binding.action().name();
+        if let Some(previous) = seen.insert(key.clone(), name) {
+            let message = if previous == name {
+                format!("bound to {name} more than once")
+            } else {
+                format!("bound to both {previous} and {name}; {name} wins")
+            };
+            conflicts.push(KeymapConflict {
+                keystrokes: key.0.into(),
+                context: key.1.into(),
+                message,
+            });
+        }
+    }
+
+    for binding in user.iter().filter(|b| b.predicate().is_none()) {
+        let keystrokes = keystrokes_text(binding);
+        let shadowing = defaults.iter().filter(|d| {
+            d.predicate().is_some()
+                && !is_no_action(d.action())
+                && keystrokes_text(d) == keystrokes
+                && d.action().name() != binding.action().name()
+        });
+        for default in shadowing {
+            // A user binding for that context replaces the default, so it doesn't shadow anything.
+            let replaced = user.iter().any(|u| {
+                keystrokes_text(u) == keystrokes && context_text(u) == context_text(default)
+            });
+            if !replaced {
+                conflicts.push(KeymapConflict {
+                    keystrokes: keystrokes.clone().into(),
+                    context: "global".into(),
+                    message: format!(
+                        "runs {} instead in {}",
+                        default.action().name(),
+                        context_text(default)
+                    ),
+                });
+            }
+        }
+    }
+
+    conflicts
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn repeated_keys_are_kept_in_file_order() {
+        let text = r#"[{
+            "context": "explorer",
+            "bindings": { "ctrl-l": "explorer::First", "backspace": null, "ctrl-l": "explorer::Second" }
+        }]"#;
+        let sections: Vec<KeymapSection> = serde_json::from_str(text).unwrap();
+        assert_eq!(sections[0].bindings, [
+            ("ctrl-l".to_string(), json!("explorer::First")),
+            ("backspace".to_string(), Value::Null),
+            ("ctrl-l".to_string(), json!("explorer::Second")),
+        ]);
+
+        // Written back as the object it was read from, repeats included.
+        assert_eq!(
+            serde_json::to_string(&sections[0]).unwrap(),
+            r#"{"context":"explorer","bindings":{"ctrl-l":"explorer::First","backspace":null,"ctrl-l":"explorer::Second"}}"#
+        );
+    }
+
+    #[test]
+    fn bindings_must_be_an_object() {
+        assert!(
+            serde_json::from_str::<Vec<KeymapSection>>(r#"[{ "bindings": ["ctrl-l"] }]"#).is_err()
+        );
+        let sections: Vec<KeymapSection> = serde_json::from_str(r#"[{}]"#).unwrap();
+        assert!(sections[0].bindings.is_empty());
+    }
+
+    #[test]
+    fn contexts_resolve_case_insensitively() {
+        assert_eq!(resolve_context(Some("Explorer")), Ok(Some("Explorer")));
+        assert_eq!(resolve_context(Some("PREVIEW")), Ok(Some("Preview")));
+        assert_eq!(resolve_context(Some("global")), Ok(None));
+        assert_eq!(resolve_context(None), Ok(None));
+    }
+
+    #[test]
+    fn unknown_contexts_list_the_known_ones() {
+        let err = resolve_context(Some("sidebar")).unwrap_err();
+        assert!(err.contains("\"sidebar\""), "{err}");
+        for (name, _) in CONTEXTS {
+            assert!(err.contains(name), "{err}");
+        }
+    }
+}
@@ -15,7 +15,12 @@ use gpui_component::{
 use serde::Deserialize;
 
 pub mod app_menus;
+pub mod channel;
 pub mod components;
+pub mod config_file;
+pub mod keymap;
+pub mod session_store;
+pub mod settings_store;
 mod title_bar;
 
 pub use components::file_list;
@@ -50,7 +55,14 @@ pub struct SelectRadius(usize);
 
 pub fn init(cx: &mut App) {
     gpui_component::init(cx);
+    settings_store::init(cx);
+    session_store::init(cx);
     themes::init(cx);
+    components::history::init(cx);
+    components::transfers::init(cx);
+    components::pane::init(cx);
+    components::split_view::init(cx);
+    crate::pages::explorer::init(cx);
     cx.bind_keys([
         KeyBinding::new("/", ToggleSearch, None),
         #[cfg(target_os = "macos")]
@@ -62,6 +74,8 @@ pub fn init(cx: &mut App) {
         #[cfg(not(target_os = "macos"))]
         KeyBinding::new("alt-f4", Quit, None),
     ]);
+    // Last, so it sees every default binding.
+    keymap::init(cx);
 
     cx.on_action(|_: &Quit, cx: &mut App| {
         cx.quit();
@@ -110,11 +124,33 @@ pub fn create_new_window_with_size<F, E>(
         window_size.height = window_size.height.min(display_size.height * 0.85);
     }
     let window_bounds = Bounds::centered(None, window_size, cx);
+    create_new_window_with_bounds(
+        title,
+        Some(WindowBounds::Windowed(window_bounds)),
+        crate_view_fn,
+        cx,
+    );
+}
+
+/// Opens a window at `window_bounds`, or at the default size and position when `None`.
+pub fn create_new_window_with_bounds<F, E>(
+    title: &str,
+    window_bounds: Option<WindowBounds>,
+    crate_view_fn: F,
+    cx: &mut App,
+) where
+    E: Into<AnyView>,
+    F: FnOnce(&mut Window, &mut App) -> E + Send + 'static,
+{
+    let Some(window_bounds) = window_bounds else {
+        create_new_window_with_size(title, None, crate_view_fn, cx);
+        return;
+    };
     let title = SharedString::from(title.to_string());
 
     cx.spawn(async move |cx| {
         let options = WindowOptions {
-            window_bounds: Some(WindowBounds::Windowed(window_bounds)),
+            window_bounds: Some(window_bounds),
             titlebar: Some(TitleBar::title_bar_options()),
             window_min_size: Some(gpui::Size { width: px(480.), height: px(320.) }),
             kind: WindowKind::Normal,
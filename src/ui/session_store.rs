@@ -0,0 +1,166 @@
+use std::{path::PathBuf, time::Duration};
+
+use gpui::{
+    px, App, AppContext, Bounds, Context, Entity, Global, Pixels, Point, Size, Subscription, Task,
+    WeakEntity, WindowBounds,
+};
+
+use crate::{
+    core::paths::data_dir,
+    services::session::{self, Session, WindowGeometry, WindowSession, WindowState},
+    ui::{create_new_window, create_new_window_with_bounds, NohrsApp},
+};
+
+const SESSION_FILE: &str = "session.json";
+/// Changes are written at most this often; quitting writes whatever is left.
+const SAVE_DELAY: Duration = Duration::from_millis(500);
+
+struct GlobalSessionStore(Entity<SessionStore>);
+
+impl Global for GlobalSessionStore {}
+
+/// Loads the last session. Call before [`open_windows`].
+pub fn init(cx: &mut App) {
+    let store = cx.new(SessionStore::new);
+    cx.set_global(GlobalSessionStore(store));
+}
+
+/// Opens the windows of the last session where they were, or one new window if there were none.
+pub fn open_windows(cx: &mut App) {
+    let windows = SessionStore::global(cx).update(cx, |store, _| store.take_restored());
+    if windows.is_empty() {
+        create_new_window("Nohrs", NohrsApp::view, cx);
+        return;
+    }
+    for window in windows {
+        let bounds = window.geometry.and_then(|geometry| window_bounds(geometry, cx));
+        create_new_window_with_bounds(
+            "Nohrs",
+            bounds,
+            move |window_handle, cx| NohrsApp::restore(window, window_handle, cx),
+            cx,
+        );
+    }
+}
+
+/// The session file and the windows whose state goes into it.
+pub struct SessionStore {
+    path: Option<PathBuf>,
+    /// Windows from the last session that have not been opened yet.
+    restored: Vec<WindowSession>,
+    windows: Vec<WeakEntity<NohrsApp>>,
+    last_saved: Option<Session>,
+    save_task: Option<Task<()>>,
+    _quit_subscription: Subscription,
+}
+
+impl SessionStore {
+    fn new(cx: &mut Context<Self>) -> Self {
+        let path = data_dir().map(|dir| dir.join(SESSION_FILE));
+        let last_saved = path.as_ref().and_then(|path| match session::load(path) {
+            Ok(session) => Some(session),
+            Err(err) => {
+                tracing::warn!("Session not restored: {}", err);
+                None
+            },
+        });
+        let quit_subscription = cx.on_app_quit(|this, cx| {
+            this.save(cx);
+            async {}
+        });
+        Self {
+            path,
+            restored: last_saved.as_ref().map(|s| s.windows.clone()).unwrap_or_default(),
+            windows: Vec::new(),
+            last_saved,
+            save_task: None,
+            _quit_subscription: quit_subscription,
+        }
+    }
+
+    pub fn global(cx: &App) -> Entity<SessionStore> {
+        cx.global::<GlobalSessionStore>().0.clone()
+    }
+
+    fn take_restored(&mut self) -> Vec<WindowSession> {
+        std::mem::take(&mut self.restored)
+    }
+
+    /// Includes the window in saved sessions for as long as it is open.
+    pub fn register(&mut self, app: WeakEntity<NohrsApp>) {
+        self.windows.push(app);
+    }
+
+    /// Saves the session shortly, batching the changes made until then.
+    pub fn schedule_save(&mut self, cx: &mut Context<Self>) {
+        if self.save_task.is_some() {
+            return;
+        }
+        self.save_task = Some(cx.spawn(async move |this, cx| {
+            cx.background_executor().timer(SAVE_DELAY).await;
+            let _ = this.update(cx, |this, cx| {
+                this.save_task = None;
+                this.save(cx);
+            });
+        }));
+    }
+
+    fn save(&mut self, cx: &mut Context<Self>) {
+        let Some(path) = self.path.clone() else {
+            return;
+        };
+        self.windows.retain(|app| app.upgrade().is_some());
+        let windows: Vec<WindowSession> = self
+            .windows
+            .iter()
+            .filter_map(|app| app.upgrade())
+            .map(|app| app.read(cx).session(cx))
+            .collect();
+        // Closing the last window keeps it for next time instead of forgetting every window.
+        if windows.is_empty() {
+            return;
+        }
+        let session = Session { version: session::SESSION_VERSION, windows };
+        if self.last_saved.as_ref() == Some(&session) {
+            return;
+        }
+        match session::save(&path, &session) {
+            Ok(()) => self.last_saved = Some(session),
+            Err(err) => tracing::warn!("Failed to save session {}: {}", path.display(), err),
+        }
+    }
+}
+
+impl From<WindowBounds> for WindowGeometry {
+    fn from(bounds: WindowBounds) -> Self {
+        let (state, bounds) = match bounds {
+            WindowBounds::Windowed(bounds) => (WindowState::Windowed, bounds),
+            WindowBounds::Maximized(bounds) => (WindowState::Maximized, bounds),
+            WindowBounds::Fullscreen(bounds) => (WindowState::Fullscreen, bounds),
+        };
+        WindowGeometry {
+            x: f32::from(bounds.origin.x),
+            y: f32::from(bounds.origin.y),
+            width: f32::from(bounds.size.width),
+            height: f32::from(bounds.size.height),
+            state,
+        }
+    }
+}
+
+/// Where to reopen a saved window, unless it would land off every display (one was unplugged,
+/// say), in which case it opens centered.
+fn window_bounds(geometry: WindowGeometry, cx: &App) -> Option<WindowBounds> {
+    let bounds: Bounds<Pixels> = Bounds::new(
+        Point::new(px(geometry.x), px(geometry.y)),
+        Size::new(px(geometry.width), px(geometry.height)),
+    );
+    if !cx.displays().iter().any(|display| display.bounds().intersects(&bounds)) {
+        return None;
+    }
+    Some(match geometry.state {
+        WindowState::Windowed => WindowBounds::Windowed(bounds),
+        WindowState::Maximized => WindowBounds::Maximized(bounds),
+        WindowState::Fullscreen => WindowBounds::Fullscreen(bounds),
+    })
+}
@@ -0,0 +1,267 @@
+use std::{fs, path::PathBuf};
+
+use gpui::{App, AppContext, Context, Entity, Global, SharedString, Task};
+use gpui_component::{
+    notification::Notification, scroll::ScrollbarShow, ActiveTheme, Theme, ThemeMode,
+    ThemeRegistry, WindowExt as _,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    core::{errors::Error, paths::config_dir},
+    pages::settings::AppSettings,
+    services::settings::{self, SETTINGS_VERSION},
+    ui::config_file::watch_config_file,
+};
+
+const SETTINGS_FILE: &str = "settings.json";
+/// Where theme state was kept before there was a settings file, relative to the working folder.
+const LEGACY_STATE_FILE: &str = "target/state.json";
+
+/// Theme and scrollbar state, kept in the [`Theme`] global while running.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Appearance {
+    pub theme: SharedString,
+    pub mode: ThemeMode,
+    pub scrollbar_show: Option<ScrollbarShow>,
+}
+
+impl Default for Appearance {
+    fn default() -> Self {
+        Self { theme: "Default Light".into(), mode: ThemeMode::Light, scrollbar_show: None }
+    }
+}
+
+/// The settings file as of [`SETTINGS_VERSION`].
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+struct SettingsFile {
+    version: u64,
+    appearance: Appearance,
+    general: AppSettings,
+}
+
+struct GlobalSettingsStore(Entity<SettingsStore>);
+
+impl Global for GlobalSettingsStore {}
+
+/// Loads the settings file into [`AppSettings`] and saves every later change back, including
+/// theme changes. Call before the themes are loaded so they can pick up the saved theme.
+pub fn init(cx: &mut App) {
+    let store = cx.new(SettingsStore::new);
+    cx.set_global(GlobalSettingsStore(store.clone()));
+    store.update(cx, |store, cx| store.load(cx));
+
+    cx.observe_global::<AppSettings>(|cx| {
+        SettingsStore::global(cx).update(cx, |store, cx| store.save(cx));
+    })
+    .detach();
+    cx.observe_global::<Theme>(|cx| {
+        SettingsStore::global(cx).update(cx, |store, cx| store.save(cx));
+    })
+    .detach();
+}
+
+/// The persisted settings file: where it lives, whether it could be read, and what was last
+/// written to it.
+pub struct SettingsStore {
+    path: Option<PathBuf>,
+    /// The appearance to restore; the theme is applied once the theme registry has loaded.
+    appearance: Appearance,
+    themes_loaded: bool,
+    /// Why the file on disk could not be used. Nothing is saved while this is set, so a typo in
+    /// the file doesn't get it overwritten with defaults.
+    error: Option<String>,
+    /// The JSON last read or written, to skip saves that would not change anything (including
+    /// the ones caused by applying a reloaded file).
+    last_saved: Option<String>,
+    _watch_task: Option<Task<()>>,
+}
+
+impl SettingsStore {
+    fn new(cx: &mut Context<Self>) -> Self {
+        let path = config_dir().map(|dir| dir.join(SETTINGS_FILE));
+        let watch_task = path.clone().and_then(|path| {
+            watch_config_file(path, cx, |this: &mut Self, cx| {
+                this.load(cx);
+                this.report_error(cx);
+            })
+        });
+        Self {
+            path,
+            appearance: Appearance::default(),
+            themes_loaded: false,
+            error: None,
+            last_saved: None,
+            _watch_task: watch_task,
+        }
+    }
+
+    pub fn global(cx: &App) -> Entity<SettingsStore> {
+        cx.global::<GlobalSettingsStore>().0.clone()
+    }
+
+    pub fn path(&self) -> Option<&PathBuf> {
+        self.path.as_ref()
+    }
+
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// Reads the settings file (or the legacy theme state on first run) and applies it. On
+    /// failure the current settings stay and the error is kept for display.
+    fn load(&mut self, cx: &mut Context<Self>) {
+        let Some(path) = self.path.clone() else {
+            cx.set_global(AppSettings::default());
+            return;
+        };
+
+        let (value, first_run) = match settings::load(&path) {
+            Ok(None) => {
+                let legacy = settings::load(LEGACY_STATE_FILE.as_ref()).unwrap_or_else(|err| {
+                    tracing::warn!("Ignoring old theme state: {}", err);
+                    None
+                });
+                (Ok(legacy), true)
+            },
+            value => (value, false),
+        };
+        let parsed = value.and_then(|value| match value {
+            Some(value) => serde_json::from_value::<SettingsFile>(value)
+                .map_err(|err| Error::Parse(format!("{}: {}", path.display(), err))),
+            None => Ok(SettingsFile::default()),
+        });
+        let mut file = match parsed {
+            Ok(file) => file,
+            Err(err) => {
+                tracing::warn!("Settings not loaded: {}", err);
+                self.error = Some(err.to_string());
+                if !cx.has_global::<AppSettings>() {
+                    cx.set_global(AppSettings::default());
+                }
+                cx.notify();
+                return;
+            },
+        };
+        file.version = SETTINGS_VERSION;
+        self.error = None;
+
+        let json = to_json(&file);
+        if self.last_saved.as_ref() == Some(&json) {
+            return;
+        }
+        self.last_saved = Some(json);
+
+        self.appearance = file.appearance;
+        self.apply_appearance(cx);
+        if cx.try_global::<AppSettings>() != Some(&file.general) {
+            cx.set_global(file.general);
+        }
+        if first_run {
+            // Create the file right away so there is something to edit.
+            self.last_saved = None;
+            self.save(cx);
+        }
+        cx.refresh_windows();
+        cx.notify();
+    }
+
+    /// Called by the theme registry once it has (re)loaded the theme folder.
+    pub fn themes_loaded(&mut self, cx: &mut Context<Self>) {
+        self.themes_loaded = true;
+        self.apply_appearance(cx);
+        cx.refresh_windows();
+    }
+
+    fn apply_appearance(&mut self, cx: &mut App) {
+        if self.themes_loaded {
+            let registered =
+                ThemeRegistry::global(cx).themes().get(&self.appearance.theme).cloned();
+            match registered {
+                Some(theme) if cx.theme().theme_name() != &self.appearance.theme => {
+                    Theme::global_mut(cx).apply_config(&theme);
+                },
+                Some(_) => {},
+                None if cx.theme().mode != self.appearance.mode => {
+                    Theme::change(self.appearance.mode, None, cx);
+                },
+                None => {},
+            }
+        }
+        if let Some(scrollbar_show) = self.appearance.scrollbar_show {
+            if cx.theme().scrollbar_show != scrollbar_show {
+                Theme::global_mut(cx).scrollbar_show = scrollbar_show;
+            }
+        }
+    }
+
+    fn save(&mut self, cx: &mut Context<Self>) {
+        let Some(path) = self.path.clone() else {
+            return;
+        };
+        if self.error.is_some() {
+            return;
+        }
+        let Some(general) = cx.try_global::<AppSettings>().cloned() else {
+            return;
+        };
+        // Until the theme folder is loaded the active theme is only the built-in default.
+        let appearance = Appearance {
+            theme: if self.themes_loaded {
+                cx.theme().theme_name().clone()
+            } else {
+                self.appearance.theme.clone()
+            },
+            mode: cx.theme().mode,
+            scrollbar_show: Some(cx.theme().scrollbar_show),
+        };
+        let file = SettingsFile { version: SETTINGS_VERSION, appearance, general };
+        let json = to_json(&file);
+        if self.last_saved.as_ref() == Some(&json) {
+            return;
+        }
+        match settings::save(&path, &json) {
+            Ok(()) => {
+                self.appearance = file.appearance;
+                self.last_saved = Some(json);
+            },
+            Err(err) => tracing::warn!("Failed to save settings {}: {}", path.display(), err),
+        }
+    }
+
+    /// Sets the unreadable settings file aside as `settings.json.bak` and writes the current
+    /// settings in its place.
+    pub fn replace_invalid_file(&mut self, cx: &mut Context<Self>) {
+        let Some(path) = self.path.clone() else {
+            return;
+        };
+        if let Err(err) = fs::rename(&path, path.with_extension("json.bak")) {
+            tracing::warn!("Failed to back up {}: {}", path.display(), err);
+        }
+        self.error = None;
+        self.last_saved = None;
+        self.save(cx);
+        cx.notify();
+    }
+
+    /// Shows the load error, if any, in the active window.
+    pub fn report_error(&self, cx: &mut App) {
+        let Some(error) = self.error.clone() else {
+            return;
+        };
+        if let Some(window) = cx.active_window() {
+            cx.defer(move |cx| {
+                let _ = window.update(cx, |_, window, cx| {
+                    let message = format!("Settings not loaded: {error}");
+                    window.push_notification(Notification::error(message), cx);
+                });
+            });
+        }
+    }
+}
+
+fn to_json(file: &SettingsFile) -> String {
+    serde_json::to_string_pretty(file).unwrap_or_default()
+}
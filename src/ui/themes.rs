@@ -1,54 +1,20 @@
 use std::path::PathBuf;
 
 use gpui::{Action, App, SharedString};
-use gpui_component::{scroll::ScrollbarShow, ActiveTheme, Theme, ThemeMode, ThemeRegistry};
-use serde::{Deserialize, Serialize};
+use gpui_component::{Theme, ThemeMode, ThemeRegistry};
 
-const STATE_FILE: &str = "target/state.json";
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-struct State {
-    theme: SharedString,
-    scrollbar_show: Option<ScrollbarShow>,
-}
-
-impl Default for State {
-    fn default() -> Self {
-        Self { theme: "Default Light".into(), scrollbar_show: None }
-    }
-}
+use crate::ui::settings_store::SettingsStore;
 
+/// Loads the theme folder and applies the theme saved in the settings. The settings store saves
+/// theme changes itself, so it must be initialized first.
 pub fn init(cx: &mut App) {
-    // Load last theme state
-    let json = std::fs::read_to_string(STATE_FILE).unwrap_or_default();
     tracing::info!("Load themes...");
-    let state = serde_json::from_str::<State>(&json).unwrap_or_default();
-    if let Err(err) = ThemeRegistry::watch_dir(PathBuf::from("./themes"), cx, move |cx| {
-        if let Some(theme) = ThemeRegistry::global(cx).themes().get(&state.theme).cloned() {
-            Theme::global_mut(cx).apply_config(&theme);
-        }
+    if let Err(err) = ThemeRegistry::watch_dir(PathBuf::from("./themes"), cx, |cx| {
+        SettingsStore::global(cx).update(cx, |store, cx| store.themes_loaded(cx));
     }) {
         tracing::error!("Failed to watch themes directory: {}", err);
     }
 
-    if let Some(scrollbar_show) = state.scrollbar_show {
-        Theme::global_mut(cx).scrollbar_show = scrollbar_show;
-    }
-    cx.refresh_windows();
-
-    cx.observe_global::<Theme>(|cx| {
-        let state = State {
-            theme: cx.theme().theme_name().clone(),
-            scrollbar_show: Some(cx.theme().scrollbar_show),
-        };
-
-        if let Ok(json) = serde_json::to_string_pretty(&state) {
-            // Ignore write errors - if STATE_FILE doesn't exist or can't be written, do nothing
-            let _ = std::fs::write(STATE_FILE, json);
-        }
-    })
-    .detach();
-
     cx.on_action(|switch: &SwitchTheme, cx| {
         let theme_name = switch.0.clone();
         if let Some(theme_config) = ThemeRegistry::global(cx).themes().get(&theme_name).cloned() {